@@ -15,7 +15,6 @@ extern crate log;
 extern crate stderrlog;
 
 use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
-use goblin::elf::program_header::pt_to_str;
 use goblin::Object;
 use std::fs::File;
 use std::io;
@@ -28,10 +27,12 @@ mod trace;
 use crate::semihost::get_semihost_func;
 use crate::trace::format_trace_entry;
 
-use std::cmp;
 use std::collections::HashMap;
 use tabwriter::TabWriter;
+use zmu_cortex_m::bus::Bus;
+use zmu_cortex_m::loader::elf::{elf_load_range, load_elf};
 use zmu_cortex_m::memory::map::MemoryMapConfig;
+use zmu_cortex_m::semihosting::host_io::StdHostIo;
 use zmu_cortex_m::Processor;
 
 use zmu_cortex_m::system::simulation::simulate_trace;
@@ -46,8 +47,18 @@ use crate::errors::*;
 use error_chain::State;
 
 impl From<SimulationError> for errors::Error {
-    fn from(_error: SimulationError) -> Self {
-        errors::Error(ErrorKind::Msg("trap".to_string()), State::default())
+    fn from(error: SimulationError) -> Self {
+        let message = match error {
+            SimulationError::FaultTrap => "trap".to_string(),
+            SimulationError::Lockup { reason } => reason.to_string(),
+            SimulationError::BreakpointHit { address, imm } => {
+                format!("breakpoint hit: bkpt #{} at 0x{:08x}", imm, address)
+            }
+            SimulationError::DebugHalt { address } => {
+                format!("debug halt at 0x{:08x}", address)
+            }
+        };
+        errors::Error(ErrorKind::Msg(message), State::default())
     }
 }
 
@@ -56,6 +67,8 @@ fn run_bin(
     trace: bool,
     option_trace_start: Option<u64>,
     itm_file: Option<Box<dyn io::Write + 'static>>,
+    load_offset: u32,
+    cmdline: String,
 ) -> Result<()> {
     let res = Object::parse(buffer).unwrap();
 
@@ -68,56 +81,25 @@ fn run_bin(
 
     debug!("Detected ELF file.");
 
-    // auto detection of required flash size:
-    // loop 1: determine lower bound and upper bound
-
-    let mut min_address = 0xffff_ffff;
-    let mut max_address = 0;
-    debug!("Determining ELF code sections");
-    for ph in &elf.program_headers {
-        if ph.p_type == goblin::elf::program_header::PT_LOAD && ph.p_filesz > 0 {
-            let dst_addr = ph.p_paddr as usize;
-            let dst_end_addr = (ph.p_paddr + ph.p_filesz) as usize;
-
-            debug!(
-                "PT_LOAD section at 0x{:08x} - 0x{:08x} (size = {} bytes)",
-                dst_addr, dst_end_addr, ph.p_filesz
-            );
-            min_address = cmp::min(dst_addr, min_address);
-            max_address = cmp::max(dst_end_addr, max_address);
-        } else {
-            debug!(
-                "ignoring section : {} (size = {} bytes)",
-                pt_to_str(ph.p_type),
-                ph.p_filesz
-            );
-        }
-    }
-
-    let flash_start_address = min_address as u32;
-    let flash_size = (max_address - min_address) as usize;
+    // auto detection of required flash size, the vector table is expected
+    // at the start of the (relocated) image
+    let load_range = elf_load_range(buffer, load_offset).map_err(|e| format!("{:?}", e))?;
+    let flash_start_address = load_range.start;
+    let flash_size = (load_range.end - load_range.start) as usize;
     info!(
         "Auto configuring flash: address space is 0x{:x}..0x{:x}, size= {} bytes",
-        flash_start_address, max_address, flash_size
+        flash_start_address, load_range.end, flash_size
     );
-    let mut flash_mem = vec![0; flash_size];
-
-    // loop 2: load data by offset
-    for ph in &elf.program_headers {
-        if ph.p_type == goblin::elf::program_header::PT_LOAD && ph.p_filesz > 0 {
-            let dst_addr = (ph.p_paddr - u64::from(flash_start_address)) as usize;
-            let dst_end_addr =
-                ((ph.p_paddr + ph.p_filesz) - u64::from(flash_start_address)) as usize;
 
-            let src_addr = ph.p_offset as usize;
-            let src_end_addr = (ph.p_offset + ph.p_filesz) as usize;
-
-            flash_mem[dst_addr..dst_end_addr].copy_from_slice(&buffer[src_addr..src_end_addr]);
-        }
-    }
+    let mut loader = Processor::new();
+    loader.flash_memory_at(flash_start_address, flash_size, &[]);
+    load_elf(&mut loader, buffer, load_offset).map_err(|e| format!("{:?}", e))?;
+    let flash_mem = loader
+        .read_slice(flash_start_address, flash_size)
+        .map_err(|e| format!("{:?}", e))?;
 
     let trace_start = option_trace_start.unwrap_or(0);
-    let semihost_func = Box::new(get_semihost_func(Instant::now()));
+    let semihost_func = Box::new(get_semihost_func(Instant::now(), StdHostIo, cmdline));
 
     let statistics = if trace {
         debug!("Configuring tracing.");
@@ -130,7 +112,7 @@ fn run_bin(
                 if let Some(maybe_name) = elf.strtab.get(sym.st_name) {
                     let name = maybe_name.unwrap_or("unknown");
                     let mut count = 0;
-                    let mut pos = sym.st_value as u32;
+                    let mut pos = (sym.st_value as u32).wrapping_add(load_offset);
                     while count <= sym.st_size {
                         // Align addresses to 2 byte alignment
                         symboltable.insert(pos & 0xffff_fffe, name);
@@ -161,6 +143,7 @@ fn run_bin(
                 None
             },
             flash_size,
+            flash_start_address,
         )?
     } else {
         debug!("Starting simulation.");
@@ -174,6 +157,7 @@ fn run_bin(
                 None
             },
             flash_size,
+            flash_start_address,
         )?
     };
 
@@ -193,6 +177,13 @@ fn run_bin(
         cycles_per_sec,
         cycles_per_sec / 1_000_000.0,
     );
+
+    if let Some(exit_code) = statistics.exit_code {
+        info!("program exited with code {}", exit_code);
+        if exit_code != 0 {
+            ::std::process::exit(exit_code as i32);
+        }
+    }
     Ok(())
 }
 
@@ -205,6 +196,14 @@ fn open_itm_file(filename: &str) -> Option<Box<dyn io::Write + 'static>> {
     }
 }
 
+fn parse_address(value: &str) -> std::result::Result<u32, std::num::ParseIntError> {
+    if value.starts_with("0x") || value.starts_with("0X") {
+        u32::from_str_radix(&value[2..], 16)
+    } else {
+        value.parse::<u32>()
+    }
+}
+
 fn run(args: &ArgMatches) -> Result<()> {
     match args.subcommand() {
         ("run", Some(run_matches)) => {
@@ -226,6 +225,21 @@ fn run(args: &ArgMatches) -> Result<()> {
                 None => None,
             };
 
+            let load_offset = match run_matches.value_of("load-offset") {
+                Some(offset) => parse_address(offset).chain_err(|| "invalid load offset")?,
+                None => 0,
+            };
+
+            // the command line seen by the program: executable followed by
+            // the free arguments
+            let mut cmdline = filename.to_string();
+            if let Some(args) = run_matches.values_of("ARGS") {
+                for arg in args {
+                    cmdline.push(' ');
+                    cmdline.push_str(arg);
+                }
+            }
+
             let buffer = {
                 let mut v = Vec::new();
                 let mut f = File::open(&filename).chain_err(|| "unable to open file")?;
@@ -238,6 +252,8 @@ fn run(args: &ArgMatches) -> Result<()> {
                 run_matches.is_present("trace"),
                 trace_start,
                 itm_output,
+                load_offset,
+                cmdline,
             )?;
         }
         ("", None) => bail!("No sub command found"),
@@ -279,6 +295,12 @@ fn main() {
                         .help("Name of file to which itm trace data is written to. ")
                         .takes_value(true),
                 )
+                .arg(
+                    Arg::with_name("load-offset")
+                        .long("load-offset")
+                        .help("Offset added to the ELF load addresses, relocating the image and its vector table")
+                        .takes_value(true),
+                )
                 .arg(
                     Arg::with_name("EXECUTABLE")
                         .index(1)
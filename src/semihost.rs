@@ -1,14 +1,18 @@
 use std::cmp::min;
-use std::io;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
 use std::io::prelude::*;
+use std::io::SeekFrom;
 use std::time::Instant;
 
-use zmu_cortex_m::semihosting::{SemihostingCommand, SemihostingResponse, SysExceptionReason};
+use zmu_cortex_m::semihosting::host_io::HostIo;
+use zmu_cortex_m::semihosting::{
+    SemihostingCommand, SemihostingResponse, SysExceptionReason, TT_HANDLE_STDERR, TT_HANDLE_STDIN,
+    TT_HANDLE_STDOUT,
+};
 
-const TT_HANDLE_STDIN: u32 = 1;
-const TT_HANDLE_STDOUT: u32 = 2;
-const TT_HANDLE_STDERR: u32 = 3;
 const SEMIHOST_FEATURES_HANDLE: u32 = 4;
+const FIRST_FILE_HANDLE: u32 = 5;
 
 /*
  byte 0: SHFB_MAGIC_0 0x53
@@ -19,8 +23,32 @@ const SEMIHOST_FEATURES_HANDLE: u32 = 4;
 */
 static FEATURE_DATA: [u8; 5] = [0x53, 0x48, 0x46, 0x42, 3];
 
-pub fn get_semihost_func(start: Instant) -> impl FnMut(&SemihostingCommand) -> SemihostingResponse {
+///
+/// Map a semihosting open mode to the matching fopen() mode options.
+///
+/// Modes come in groups of four: "r", "w" and "a", each in text, binary,
+/// update and binary update variants.
+///
+fn open_options(mode: u32) -> Option<OpenOptions> {
+    let mut options = OpenOptions::new();
+    let update = mode & 2 != 0;
+    match mode >> 2 {
+        0 => options.read(true).write(update),
+        1 => options.write(true).read(update).create(true).truncate(true),
+        2 => options.append(true).read(update).create(true),
+        _ => return None,
+    };
+    Some(options)
+}
+
+pub fn get_semihost_func<H: HostIo>(
+    start: Instant,
+    mut host_io: H,
+    cmdline: String,
+) -> impl FnMut(&SemihostingCommand) -> SemihostingResponse {
     let mut semihost_features_position: u32 = 0;
+    let mut files: HashMap<u32, File> = HashMap::new();
+    let mut next_file_handle = FIRST_FILE_HANDLE;
 
     move |semihost_cmd: &SemihostingCommand| -> SemihostingResponse {
         match semihost_cmd {
@@ -46,7 +74,15 @@ pub fn get_semihost_func(start: Instant) -> impl FnMut(&SemihostingCommand) -> S
                         result: Ok(SEMIHOST_FEATURES_HANDLE),
                     }
                 } else {
-                    SemihostingResponse::SysOpen { result: Err(-1) }
+                    match open_options(*mode).map(|options| options.open(name)) {
+                        Some(Ok(file)) => {
+                            let handle = next_file_handle;
+                            next_file_handle += 1;
+                            files.insert(handle, file);
+                            SemihostingResponse::SysOpen { result: Ok(handle) }
+                        }
+                        _ => SemihostingResponse::SysOpen { result: Err(-1) },
+                    }
                 }
             }
             SemihostingCommand::SysClose { handle } => {
@@ -55,7 +91,8 @@ pub fn get_semihost_func(start: Instant) -> impl FnMut(&SemihostingCommand) -> S
                     semihost_features_position = 0;
                 }
 
-                SemihostingResponse::SysClose { success: true }
+                let success = *handle < FIRST_FILE_HANDLE || files.remove(handle).is_some();
+                SemihostingResponse::SysClose { success }
             }
             SemihostingCommand::SysFlen { handle } => {
                 // println!("filelen for handle '{}'", handle);
@@ -64,6 +101,13 @@ pub fn get_semihost_func(start: Instant) -> impl FnMut(&SemihostingCommand) -> S
                     SemihostingResponse::SysFlen { result: Ok(0) }
                 } else if *handle == SEMIHOST_FEATURES_HANDLE {
                     SemihostingResponse::SysFlen { result: Ok(5) }
+                } else if let Some(file) = files.get(handle) {
+                    match file.metadata() {
+                        Ok(metadata) => SemihostingResponse::SysFlen {
+                            result: Ok(metadata.len() as u32),
+                        },
+                        Err(_) => SemihostingResponse::SysFlen { result: Err(-1) },
+                    }
                 } else {
                     SemihostingResponse::SysFlen { result: Err(-1) }
                 }
@@ -84,13 +128,24 @@ pub fn get_semihost_func(start: Instant) -> impl FnMut(&SemihostingCommand) -> S
             }
             SemihostingCommand::SysWrite { handle, ref data } => {
                 // println!("write: handle={}, data={:?}", handle, data);
-                if *handle == TT_HANDLE_STDOUT {
-                    let text = &**data;
-                    print!("{}", String::from_utf8_lossy(text));
-                    io::stdout().flush().expect("Could not flush stdout");
-                    SemihostingResponse::SysWrite { result: Ok(0) }
+                // console writes go to the semihosting console of the processor
+                if let Some(file) = files.get_mut(handle) {
+                    match file.write(data) {
+                        Ok(count) if count == data.len() => SemihostingResponse::SysWrite {
+                            result: Ok(data.len() as u32),
+                        },
+                        Ok(count) => SemihostingResponse::SysWrite {
+                            result: Err((data.len() - count) as i32),
+                        },
+                        Err(_) => SemihostingResponse::SysWrite {
+                            result: Err(data.len() as i32),
+                        },
+                    }
                 } else {
-                    SemihostingResponse::SysWrite { result: Err(-1) }
+                    // nothing was written
+                    SemihostingResponse::SysWrite {
+                        result: Err(data.len() as i32),
+                    }
                 }
             }
             SemihostingCommand::SysRead {
@@ -122,10 +177,34 @@ pub fn get_semihost_func(start: Instant) -> impl FnMut(&SemihostingCommand) -> S
                     SemihostingResponse::SysRead {
                         result: Ok((*memoryptr, data, diff)),
                     }
+                } else if *handle == TT_HANDLE_STDIN || files.contains_key(handle) {
+                    let mut data = vec![0; *len as usize];
+                    let read = match files.get_mut(handle) {
+                        Some(file) => file.read(&mut data),
+                        None => host_io.read_stdin(&mut data),
+                    };
+                    match read {
+                        Ok(count) => {
+                            data.truncate(count);
+                            SemihostingResponse::SysRead {
+                                result: Ok((*memoryptr, data, *len - count as u32)),
+                            }
+                        }
+                        Err(_) => SemihostingResponse::SysRead { result: Err(-1) },
+                    }
                 } else {
                     SemihostingResponse::SysRead { result: Err(-1) }
                 }
             }
+            SemihostingCommand::SysReadc => {
+                let mut character = [0; 1];
+                match host_io.read_stdin(&mut character) {
+                    Ok(1) => SemihostingResponse::SysReadc {
+                        result: Ok(character[0]),
+                    },
+                    _ => SemihostingResponse::SysReadc { result: Err(-1) },
+                }
+            }
             SemihostingCommand::SysSeek { handle, position } => {
                 /*println!("seek: handle={}, position={}", handle, position);*/
 
@@ -136,6 +215,10 @@ pub fn get_semihost_func(start: Instant) -> impl FnMut(&SemihostingCommand) -> S
                     } else {
                         SemihostingResponse::SysSeek { success: false }
                     }
+                } else if let Some(file) = files.get_mut(handle) {
+                    SemihostingResponse::SysSeek {
+                        success: file.seek(SeekFrom::Start(u64::from(*position))).is_ok(),
+                    }
                 } else {
                     SemihostingResponse::SysSeek { success: false }
                 }
@@ -176,6 +259,138 @@ pub fn get_semihost_func(start: Instant) -> impl FnMut(&SemihostingCommand) -> S
 
                 SemihostingResponse::SysErrno { result: 0 }
             }
+            SemihostingCommand::SysGetCmdline => SemihostingResponse::SysGetCmdline {
+                result: Ok(cmdline.clone()),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::fs;
+    use std::rc::Rc;
+    use zmu_cortex_m::semihosting::host_io::CapturingHostIo;
+
+    #[test]
+    fn test_console_input_goes_through_host_io() {
+        // Arrange
+        let host_io = CapturingHostIo {
+            stdin: Rc::new(RefCell::new(b"x".to_vec())),
+            ..Default::default()
+        };
+        let mut semihost = get_semihost_func(Instant::now(), host_io, String::new());
+
+        // Act
+        let first = semihost(&SemihostingCommand::SysReadc);
+        let second = semihost(&SemihostingCommand::SysReadc);
+
+        // Assert
+        assert_eq!(first, SemihostingResponse::SysReadc { result: Ok(b'x') });
+        assert_eq!(second, SemihostingResponse::SysReadc { result: Err(-1) });
+    }
+
+    #[test]
+    fn test_open_write_close_file() {
+        // Arrange
+        let path = std::env::temp_dir().join(format!("zmu_semihost_{}.txt", std::process::id()));
+        let mut semihost =
+            get_semihost_func(Instant::now(), CapturingHostIo::default(), String::new());
+
+        // Act
+        let handle = match semihost(&SemihostingCommand::SysOpen {
+            name: path.to_str().unwrap().to_string(),
+            mode: 4,
+        }) {
+            SemihostingResponse::SysOpen { result } => result.unwrap(),
+            _ => panic!("unexpected response"),
+        };
+        let written = match semihost(&SemihostingCommand::SysWrite {
+            handle,
+            data: b"hello file".to_vec(),
+        }) {
+            SemihostingResponse::SysWrite { result } => result,
+            _ => panic!("unexpected response"),
+        };
+        let closed = match semihost(&SemihostingCommand::SysClose { handle }) {
+            SemihostingResponse::SysClose { success } => success,
+            _ => panic!("unexpected response"),
+        };
+        let closed_again = match semihost(&SemihostingCommand::SysClose { handle }) {
+            SemihostingResponse::SysClose { success } => success,
+            _ => panic!("unexpected response"),
+        };
+        let contents = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        // Assert
+        assert!(handle >= FIRST_FILE_HANDLE);
+        assert_eq!(written, Ok(10));
+        assert!(closed);
+        assert!(!closed_again);
+        assert_eq!(contents, b"hello file");
+    }
+
+    #[test]
+    fn test_seek_and_flen_file() {
+        // Arrange
+        let path =
+            std::env::temp_dir().join(format!("zmu_semihost_seek_{}.txt", std::process::id()));
+        fs::write(&path, b"0123456789").unwrap();
+        let mut semihost =
+            get_semihost_func(Instant::now(), CapturingHostIo::default(), String::new());
+        let handle = match semihost(&SemihostingCommand::SysOpen {
+            name: path.to_str().unwrap().to_string(),
+            mode: 1,
+        }) {
+            SemihostingResponse::SysOpen { result } => result.unwrap(),
+            _ => panic!("unexpected response"),
+        };
+
+        // Act
+        let length = semihost(&SemihostingCommand::SysFlen { handle });
+        let seek = semihost(&SemihostingCommand::SysSeek {
+            handle,
+            position: 6,
+        });
+        let read = semihost(&SemihostingCommand::SysRead {
+            handle,
+            memoryptr: 0x2000_0000,
+            len: 8,
+        });
+        semihost(&SemihostingCommand::SysClose { handle });
+        fs::remove_file(&path).unwrap();
+
+        // Assert
+        assert_eq!(length, SemihostingResponse::SysFlen { result: Ok(10) });
+        assert_eq!(seek, SemihostingResponse::SysSeek { success: true });
+        assert_eq!(
+            read,
+            SemihostingResponse::SysRead {
+                result: Ok((0x2000_0000, b"6789".to_vec(), 4)),
+            }
+        );
+    }
+
+    #[test]
+    fn test_open_missing_file_for_reading_fails() {
+        // Arrange
+        let path = std::env::temp_dir().join("zmu_semihost_does_not_exist.txt");
+        let mut semihost =
+            get_semihost_func(Instant::now(), CapturingHostIo::default(), String::new());
+
+        // Act
+        let response = semihost(&SemihostingCommand::SysOpen {
+            name: path.to_str().unwrap().to_string(),
+            mode: 0,
+        });
+
+        // Assert
+        match response {
+            SemihostingResponse::SysOpen { result } => assert_eq!(result, Err(-1)),
+            _ => panic!("unexpected response"),
         }
     }
 }
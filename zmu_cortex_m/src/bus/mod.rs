@@ -5,12 +5,18 @@
 use crate::Processor;
 
 use crate::core::fault::Fault;
+use crate::core::trace::TraceEvent;
 use crate::memory::map::MapMemory;
 use crate::peripheral::dwt::Dwt;
 use crate::peripheral::itm::InstrumentationTraceMacrocell;
+use crate::peripheral::mpu::{Mpu, MpuAccess};
 use crate::peripheral::nvic::NVIC;
-use crate::peripheral::scb::SystemControlBlock;
+use crate::peripheral::scb::{SystemControlBlock, DEMCR_TRCENA};
 use crate::peripheral::systick::SysTick;
+use crate::peripheral::test_exit::TestExit;
+use std::cell::RefCell;
+use std::ops::Range;
+use std::rc::Rc;
 
 ///
 /// Trait for reading and writing via a memory bus.
@@ -43,10 +49,278 @@ pub trait Bus {
     /// Checks if given address can be reached via the bus.
     ///
     fn in_range(&self, addr: u32) -> bool;
+
+    /// Reads `len` consecutive bytes starting from the given address.
+    ///
+    fn read_slice(&self, addr: u32, len: usize) -> Result<Vec<u8>, Fault> {
+        (0..len)
+            .map(|i| self.read8(addr.wrapping_add(i as u32)))
+            .collect()
+    }
+
+    /// Writes the bytes of `data` to consecutive addresses starting from the given address.
+    ///
+    fn write_slice(&mut self, addr: u32, data: &[u8]) -> Result<(), Fault> {
+        for (i, byte) in data.iter().enumerate() {
+            self.write8(addr.wrapping_add(i as u32), *byte)?;
+        }
+        Ok(())
+    }
 }
 
-impl Bus for Processor {
-    fn read8(&self, bus_addr: u32) -> Result<u8, Fault> {
+///
+/// Trait for memory mapped peripherals that can be registered to the
+/// processor bus. Addresses are given as offsets from the start of the
+/// registered address range.
+///
+pub trait Peripheral {
+    /// Reads a 32 bit value from the given offset.
+    ///
+    fn read32(&mut self, offset: u32) -> Result<u32, Fault>;
+
+    /// Reads a 16 bit value from the given offset.
+    ///
+    fn read16(&self, offset: u32) -> Result<u16, Fault>;
+
+    /// Reads a 8 bit value from the given offset.
+    ///
+    fn read8(&self, offset: u32) -> Result<u8, Fault>;
+
+    /// Writes a 32 bit value to the given offset.
+    ///
+    fn write32(&mut self, offset: u32, value: u32) -> Result<(), Fault>;
+
+    /// Writes a 16 bit value to the given offset.
+    ///
+    fn write16(&mut self, offset: u32, value: u16) -> Result<(), Fault>;
+
+    /// Writes a 8 bit value to the given offset.
+    ///
+    fn write8(&mut self, offset: u32, value: u8) -> Result<(), Fault>;
+
+    /// Advances the peripheral by the given number of core clock cycles.
+    /// Peripherals that do not keep time can use the default, which does
+    /// nothing.
+    ///
+    fn tick(&mut self, _cycles: u64) {}
+
+    /// Returns the peripheral to its reset state on a system reset.
+    /// Peripherals that keep their state over a reset can use the default,
+    /// which does nothing.
+    ///
+    fn reset(&mut self) {}
+
+    /// Takes the next request the peripheral makes to the processor, if
+    /// any. Called after every `tick`.
+    ///
+    fn take_request(&mut self) -> Option<PeripheralRequest> {
+        None
+    }
+}
+
+///
+/// Request a peripheral makes to the processor it is registered to
+///
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PeripheralRequest {
+    /// Pend external interrupt `n` in the NVIC
+    Interrupt(usize),
+    /// Reset the system when the current instruction completes
+    SystemReset,
+}
+
+///
+/// A peripheral shared with the host, so that the host can inspect and
+/// drive it while it is registered to the bus
+///
+impl<T: Peripheral> Peripheral for Rc<RefCell<T>> {
+    fn read32(&mut self, offset: u32) -> Result<u32, Fault> {
+        self.borrow_mut().read32(offset)
+    }
+
+    fn read16(&self, offset: u32) -> Result<u16, Fault> {
+        self.borrow().read16(offset)
+    }
+
+    fn read8(&self, offset: u32) -> Result<u8, Fault> {
+        self.borrow().read8(offset)
+    }
+
+    fn write32(&mut self, offset: u32, value: u32) -> Result<(), Fault> {
+        self.borrow_mut().write32(offset, value)
+    }
+
+    fn write16(&mut self, offset: u32, value: u16) -> Result<(), Fault> {
+        self.borrow_mut().write16(offset, value)
+    }
+
+    fn write8(&mut self, offset: u32, value: u8) -> Result<(), Fault> {
+        self.borrow_mut().write8(offset, value)
+    }
+
+    fn tick(&mut self, cycles: u64) {
+        self.borrow_mut().tick(cycles);
+    }
+
+    fn reset(&mut self) {
+        self.borrow_mut().reset();
+    }
+
+    fn take_request(&mut self) -> Option<PeripheralRequest> {
+        self.borrow_mut().take_request()
+    }
+}
+
+///
+/// Report bus errors with the address seen by the processor instead of the
+/// mapped address.
+///
+fn unmap_fault(fault: Fault, bus_addr: u32) -> Fault {
+    match fault {
+        Fault::PreciseErr { .. } => Fault::PreciseErr { address: bus_addr },
+        _ => fault,
+    }
+}
+
+///
+/// Callback for memory watchpoints, called with the address, access size in
+/// bytes, accessed value and a flag telling if the access was a write.
+/// Reads may happen via shared references, so the hook is `Fn`; use
+/// interior mutability to collect state.
+///
+pub type WatchpointHook = Box<dyn Fn(u32, usize, u32, bool)>;
+
+///
+/// Watchpoint on a range of memory addresses
+///
+pub struct Watchpoint {
+    range: Range<u32>,
+    read: bool,
+    write: bool,
+    hook: WatchpointHook,
+}
+
+impl Watchpoint {
+    ///
+    /// Create watchpoint calling `hook` on reads and/or writes in `range`
+    ///
+    pub fn new(range: Range<u32>, read: bool, write: bool, hook: WatchpointHook) -> Self {
+        Self {
+            range,
+            read,
+            write,
+            hook,
+        }
+    }
+}
+
+///
+/// Byte order of data accesses. Instruction fetches and the private
+/// peripheral bus are always little endian.
+///
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Endianness {
+    /// Least significant byte at the lowest address
+    Little,
+    /// Most significant byte at the lowest address, BE-8
+    Big,
+}
+
+trait BusHelper {
+    fn bus_read32(&mut self, bus_addr: u32) -> Result<u32, Fault>;
+    fn bus_read16(&self, bus_addr: u32) -> Result<u16, Fault>;
+    fn bus_read8(&self, bus_addr: u32) -> Result<u8, Fault>;
+    fn bus_write32(&mut self, bus_addr: u32, value: u32) -> Result<(), Fault>;
+    fn bus_write16(&mut self, bus_addr: u32, value: u16) -> Result<(), Fault>;
+    fn bus_write8(&mut self, bus_addr: u32, value: u8) -> Result<(), Fault>;
+    fn notify_watchpoints(&self, addr: u32, size: usize, value: u32, is_write: bool);
+    fn watch_access(&self, addr: u32, size: usize, value: u32, is_write: bool);
+    fn dwt_access(&self, addr: u32, size: usize, is_write: bool);
+    fn trace_device_access(&self, addr: u32, size: usize, value: u32, is_write: bool);
+    fn add_wait_states(&self, bus_addr: u32);
+    fn data_u16(&self, bus_addr: u32, value: u16) -> u16;
+    fn data_u32(&self, bus_addr: u32, value: u32) -> u32;
+    fn is_big_endian(&self, bus_addr: u32) -> bool;
+}
+
+impl Processor {
+    ///
+    /// Wait states of a memory access to the given address
+    ///
+    pub(crate) fn memory_wait_states(&self, bus_addr: u32) -> u32 {
+        if self.code.wait_states() == 0 && self.sram.wait_states() == 0 {
+            return 0;
+        }
+        let addr = self.map_address(bus_addr);
+        if self
+            .peripherals
+            .iter()
+            .any(|(range, _)| range.contains(&addr))
+        {
+            0
+        } else if self.sram.in_range(addr) {
+            self.sram.wait_states()
+        } else if self.code.in_range(addr) {
+            self.code.wait_states()
+        } else {
+            0
+        }
+    }
+
+    ///
+    /// Fetch an instruction halfword, which is little endian regardless of
+    /// the data endianness
+    ///
+    pub(crate) fn fetch16(&self, bus_addr: u32) -> Result<u16, Fault> {
+        let value = self.bus_read16(bus_addr)?;
+        self.add_wait_states(bus_addr);
+        Ok(value)
+    }
+
+    ///
+    /// Read a vector table entry. Vector reads are not checked by the MPU.
+    ///
+    pub(crate) fn read_vector(&mut self, bus_addr: u32) -> Result<u32, Fault> {
+        let value = self.bus_read32(bus_addr)?;
+        self.add_wait_states(bus_addr);
+        Ok(self.data_u32(bus_addr, value))
+    }
+
+    ///
+    /// Read a word of an exception stack frame. Stacking is checked by the
+    /// MPU but is not reported to the memory watchpoints.
+    ///
+    pub(crate) fn stack_read32(&mut self, bus_addr: u32) -> Result<u32, Fault> {
+        self.mpu_check(bus_addr, 4, MpuAccess::Read)?;
+        let value = self.bus_read32(bus_addr)?;
+        let value = self.data_u32(bus_addr, value);
+        self.add_wait_states(bus_addr);
+        self.dwt_access(bus_addr, 4, false);
+        Ok(value)
+    }
+
+    ///
+    /// Write a word of an exception stack frame, see `stack_read32`
+    ///
+    pub(crate) fn stack_write32(&mut self, bus_addr: u32, value: u32) -> Result<(), Fault> {
+        self.mpu_check(bus_addr, 4, MpuAccess::Write)?;
+        self.bus_write32(bus_addr, self.data_u32(bus_addr, value))?;
+        self.add_wait_states(bus_addr);
+        self.invalidate_decode_cache(bus_addr, 4);
+        self.dwt_access(bus_addr, 4, true);
+        Ok(())
+    }
+
+    ///
+    /// Check that instructions could be fetched from an address
+    ///
+    pub(crate) fn is_executable(&self, bus_addr: u32) -> bool {
+        self.bus_read16(bus_addr).is_ok()
+    }
+}
+
+impl BusHelper for Processor {
+    fn bus_read8(&self, bus_addr: u32) -> Result<u8, Fault> {
         let addr = self.map_address(bus_addr);
 
         let result = match addr {
@@ -61,21 +335,37 @@ impl Bus for Processor {
             0xE000_ED20..=0xE000_ED23 => self.read_shpr3_u8((addr - 0xE000_ED20) as usize),
 
             _ => {
-                if self.sram.in_range(addr) {
+                if let Some((range, peripheral)) = self
+                    .peripherals
+                    .iter()
+                    .find(|(range, _)| range.contains(&addr))
+                {
+                    return peripheral
+                        .read8(addr - range.start)
+                        .map_err(|fault| unmap_fault(fault, bus_addr));
+                } else if self.sram.in_range(addr) {
                     return self.sram.read8(addr);
                 } else if self.code.in_range(addr) {
-                    return self.code.read8(addr);
+                    return self
+                        .code
+                        .read8(addr)
+                        .map_err(|fault| unmap_fault(fault, bus_addr));
                 } else if self.device.in_range(addr) {
-                    return self.device.read8(addr);
+                    let value = self
+                        .device
+                        .read8(addr)
+                        .map_err(|fault| unmap_fault(fault, bus_addr))?;
+                    self.trace_device_access(addr, 1, u32::from(value), false);
+                    return Ok(value);
                 } else {
-                    return Err(Fault::DAccViol);
+                    return Err(Fault::PreciseErr { address: bus_addr });
                 }
             }
         };
         Ok(result)
     }
 
-    fn read16(&self, bus_addr: u32) -> Result<u16, Fault> {
+    fn bus_read16(&self, bus_addr: u32) -> Result<u16, Fault> {
         let addr = self.map_address(bus_addr);
         match addr {
             #[cfg(any(armv7m, armv7em))]
@@ -95,26 +385,45 @@ impl Bus for Processor {
             }
 
             _ => {
-                if self.sram.in_range(addr) {
+                if let Some((range, peripheral)) = self
+                    .peripherals
+                    .iter()
+                    .find(|(range, _)| range.contains(&addr))
+                {
+                    peripheral
+                        .read16(addr - range.start)
+                        .map_err(|fault| unmap_fault(fault, bus_addr))
+                } else if self.sram.in_range(addr) {
                     self.sram.read16(addr)
                 } else if self.code.in_range(addr) {
-                    self.code.read16(addr)
+                    self.code
+                        .read16(addr)
+                        .map_err(|fault| unmap_fault(fault, bus_addr))
                 } else if self.device.in_range(addr) {
-                    self.device.read16(addr)
+                    let value = self
+                        .device
+                        .read16(addr)
+                        .map_err(|fault| unmap_fault(fault, bus_addr))?;
+                    self.trace_device_access(addr, 2, u32::from(value), false);
+                    Ok(value)
                 } else {
-                    Err(Fault::DAccViol)
+                    Err(Fault::PreciseErr { address: bus_addr })
                 }
             }
         }
     }
 
-    fn read32(&mut self, bus_addr: u32) -> Result<u32, Fault> {
+    fn bus_read32(&mut self, bus_addr: u32) -> Result<u32, Fault> {
         let addr = self.map_address(bus_addr);
 
         let result = match addr {
             0xE000_0000 => self.read_stim0(),
+            0xE000_0E00 => self.itm_ter,
+            0xE000_0E40 => self.itm_tpr,
+            0xE000_0E80 => self.itm_tcr,
 
             0xE000_1004 => self.dwt_cyccnt,
+            0xE000_1020..=0xE000_105C => self.dwt_read_comparator(addr - 0xE000_1020),
 
             0xE000_E004 => self.ictr,
             0xE000_E008 => self.actlr,
@@ -132,7 +441,7 @@ impl Bus for Processor {
             0xE000_ED00 => self.cpuid,
             0xE000_ED04 => self.read_icsr(),
             0xE000_ED08 => self.read_vtor(),
-            0xE000_ED0C => self.aircr,
+            0xE000_ED0C => self.read_aircr(),
             0xE000_ED10 => self.read_scr(),
             0xE000_ED14 => self.ccr,
             #[cfg(any(armv7m, armv7em))]
@@ -151,6 +460,12 @@ impl Bus for Processor {
 
             0xE000_ED88 => self.cpacr,
 
+            0xE000_ED90 => self.mpu_read_type(),
+            0xE000_ED94 => self.mpu_ctrl,
+            0xE000_ED98 => self.mpu_rnr,
+            0xE000_ED9C => self.mpu_read_rbar(),
+            0xE000_EDA0 => self.mpu_read_rasr(),
+
             0xE000_EF34 => self.fpccr,
             0xE000_EF38 => self.fpcar,
             0xE000_EF3C => self.fpdscr,
@@ -159,26 +474,46 @@ impl Bus for Processor {
             0xE000_EF44 => self.mvfr1,
             0xE000_EF48 => self.mvfr2,
 
+            0xE000_EDF0 => self.read_dhcsr(),
             0xE000_EDFC => self.read_demcr(),
 
             // DWT
             0xE000_1000 => self.dwt_ctrl,
             _ => {
-                if self.sram.in_range(addr) {
+                if self.test_exit_at(addr) {
+                    // the exit register is write only
+                    0
+                } else if let Some((range, peripheral)) = self
+                    .peripherals
+                    .iter_mut()
+                    .find(|(range, _)| range.contains(&addr))
+                {
+                    peripheral
+                        .read32(addr - range.start)
+                        .map_err(|fault| unmap_fault(fault, bus_addr))?
+                } else if self.sram.in_range(addr) {
                     self.sram.read32(addr)?
                 } else if self.code.in_range(addr) {
-                    self.code.read32(addr)?
+                    self.code
+                        .read32(addr)
+                        .map_err(|fault| unmap_fault(fault, bus_addr))?
                 } else if self.device.in_range(addr) {
-                    self.device.read32(addr)?
+                    let value = self
+                        .device
+                        .read32(addr)
+                        .map_err(|fault| unmap_fault(fault, bus_addr))?;
+                    self.trace_device_access(addr, 4, value, false);
+                    value
                 } else {
-                    return Err(Fault::DAccViol);
+                    return Err(Fault::PreciseErr { address: bus_addr });
                 }
             }
         };
         Ok(result)
     }
 
-    fn write32(&mut self, addr: u32, value: u32) -> Result<(), Fault> {
+    fn bus_write32(&mut self, bus_addr: u32, value: u32) -> Result<(), Fault> {
+        let addr = self.map_address(bus_addr);
         match addr {
             0xE000_0000..=0xE000_007C => {
                 self.write_stim_u32(((addr - 0xE000_0000) >> 2) as u8, value)
@@ -186,19 +521,51 @@ impl Bus for Processor {
 
             0xE000_1000 => self.dwt_write_ctrl(value),
             0xE000_1004 => self.dwt_write_cyccnt(value),
+            0xE000_1020..=0xE000_105C => self.dwt_write_comparator(addr - 0xE000_1020, value),
+
+            0xE000_0E00 => self.itm_write_ter(value),
+            0xE000_0E40 => self.itm_write_tpr(value),
+            0xE000_0E80 => self.itm_write_tcr(value),
 
             0xE000_1FB0 => self.itm_write_lar_u32(value),
 
             0xE000_ED04 => self.write_icsr(value),
             0xE000_ED08 => self.write_vtor(value),
+            0xE000_ED0C => self.write_aircr(value),
             0xE000_ED10 => self.write_scr(value),
+            0xE000_ED14 => self.write_ccr(value),
             #[cfg(any(armv7m, armv7em))]
             0xE000_ED18 => self.write_shpr1(value),
             #[cfg(any(armv7m, armv7em))]
             0xE000_ED1C => self.write_shpr2(value),
             #[cfg(any(armv7m, armv7em))]
             0xE000_ED20 => self.write_shpr3(value),
+            #[cfg(any(armv7m, armv7em))]
+            0xE000_ED24 => self.write_shcsr(value),
+            #[cfg(any(armv7m, armv7em))]
+            0xE000_ED28 => self.write_cfsr(value),
+            #[cfg(any(armv7m, armv7em))]
+            0xE000_ED2C => self.write_hfsr(value),
+            #[cfg(any(armv7m, armv7em))]
+            0xE000_ED34 => self.mmfar = value,
+            #[cfg(any(armv7m, armv7em))]
+            0xE000_ED38 => self.bfar = value,
+
+            0xE000_ED94 => self.mpu_write_ctrl(value),
+            0xE000_ED98 => self.mpu_write_rnr(value),
+            0xE000_ED9C => self.mpu_write_rbar(value),
+            0xE000_EDA0 => self.mpu_write_rasr(value),
+
+            // only the CP10 and CP11 (floating point) access bits are implemented
+            0xE000_ED88 => self.cpacr = value & 0x00f0_0000,
+
+            // ASPEN, LSPEN and LSPACT
+            0xE000_EF34 => self.fpccr = value & 0xc000_0001,
+            0xE000_EF38 => self.fpcar = value & 0xffff_fff8,
+            // default AHP, DN, FZ and RMode
+            0xE000_EF3C => self.fpdscr = value & 0x07c0_0000,
 
+            0xE000_EDF0 => self.write_dhcsr(value),
             0xE000_EDFC => self.write_demcr(value),
 
             0xE000_E010 => self.syst_write_csr(value),
@@ -223,21 +590,39 @@ impl Bus for Processor {
             #[cfg(any(armv7m, armv7em))]
             0xE000_EF00 => self.write_stir(value),
             _ => {
-                if self.sram.in_range(addr) {
+                if self.test_exit_at(addr) {
+                    self.test_exit_write(value);
+                } else if let Some((range, peripheral)) = self
+                    .peripherals
+                    .iter_mut()
+                    .find(|(range, _)| range.contains(&addr))
+                {
+                    return peripheral
+                        .write32(addr - range.start, value)
+                        .map_err(|fault| unmap_fault(fault, bus_addr));
+                } else if self.sram.in_range(addr) {
                     return self.sram.write32(addr, value);
                 } else if self.code.in_range(addr) {
-                    return self.code.write32(addr, value);
+                    return self
+                        .code
+                        .write32(addr, value)
+                        .map_err(|fault| unmap_fault(fault, bus_addr));
                 } else if self.device.in_range(addr) {
-                    return self.device.write32(addr, value);
+                    self.trace_device_access(addr, 4, value, true);
+                    return self
+                        .device
+                        .write32(addr, value)
+                        .map_err(|fault| unmap_fault(fault, bus_addr));
                 } else {
-                    return Err(Fault::DAccViol);
+                    return Err(Fault::PreciseErr { address: bus_addr });
                 }
             }
         }
         Ok(())
     }
 
-    fn write16(&mut self, addr: u32, value: u16) -> Result<(), Fault> {
+    fn bus_write16(&mut self, bus_addr: u32, value: u16) -> Result<(), Fault> {
+        let addr = self.map_address(bus_addr);
         match addr {
             0xE000_0000..=0xE000_007C => {
                 self.write_stim_u16(((addr - 0xE000_0000) >> 2) as u8, value)
@@ -258,21 +643,37 @@ impl Bus for Processor {
                 self.nvic_write_ipr_u16(((addr - 0xE000_E400) >> 1) as usize, value)
             }
             _ => {
-                if self.sram.in_range(addr) {
+                if let Some((range, peripheral)) = self
+                    .peripherals
+                    .iter_mut()
+                    .find(|(range, _)| range.contains(&addr))
+                {
+                    return peripheral
+                        .write16(addr - range.start, value)
+                        .map_err(|fault| unmap_fault(fault, bus_addr));
+                } else if self.sram.in_range(addr) {
                     return self.sram.write16(addr, value);
                 } else if self.code.in_range(addr) {
-                    return self.code.write16(addr, value);
+                    return self
+                        .code
+                        .write16(addr, value)
+                        .map_err(|fault| unmap_fault(fault, bus_addr));
                 } else if self.device.in_range(addr) {
-                    return self.device.write16(addr, value);
+                    self.trace_device_access(addr, 2, u32::from(value), true);
+                    return self
+                        .device
+                        .write16(addr, value)
+                        .map_err(|fault| unmap_fault(fault, bus_addr));
                 } else {
-                    return Err(Fault::DAccViol);
+                    return Err(Fault::PreciseErr { address: bus_addr });
                 }
             }
         }
         Ok(())
     }
 
-    fn write8(&mut self, addr: u32, value: u8) -> Result<(), Fault> {
+    fn bus_write8(&mut self, bus_addr: u32, value: u8) -> Result<(), Fault> {
+        let addr = self.map_address(bus_addr);
         match addr {
             0xE000_0000..=0xE000_007C => {
                 self.write_stim_u8(((addr - 0xE000_0000) >> 2) as u8, value)
@@ -288,22 +689,438 @@ impl Bus for Processor {
             0xE000_ED20..=0xE000_ED23 => self.write_shpr3_u8((addr - 0xE000_ED20) as usize, value),
 
             _ => {
-                if self.sram.in_range(addr) {
+                if let Some((range, peripheral)) = self
+                    .peripherals
+                    .iter_mut()
+                    .find(|(range, _)| range.contains(&addr))
+                {
+                    return peripheral
+                        .write8(addr - range.start, value)
+                        .map_err(|fault| unmap_fault(fault, bus_addr));
+                } else if self.sram.in_range(addr) {
                     return self.sram.write8(addr, value);
                 } else if self.code.in_range(addr) {
-                    return self.code.write8(addr, value);
+                    return self
+                        .code
+                        .write8(addr, value)
+                        .map_err(|fault| unmap_fault(fault, bus_addr));
                 } else if self.device.in_range(addr) {
-                    return self.device.write8(addr, value);
+                    self.trace_device_access(addr, 1, u32::from(value), true);
+                    return self
+                        .device
+                        .write8(addr, value)
+                        .map_err(|fault| unmap_fault(fault, bus_addr));
                 } else {
-                    return Err(Fault::DAccViol);
+                    return Err(Fault::PreciseErr { address: bus_addr });
                 }
             }
         }
         Ok(())
     }
 
+    fn trace_device_access(&self, addr: u32, size: usize, value: u32, is_write: bool) {
+        self.trace(&TraceEvent::DeviceAccess {
+            address: addr,
+            size,
+            value,
+            is_write,
+        });
+    }
+
+    fn notify_watchpoints(&self, addr: u32, size: usize, value: u32, is_write: bool) {
+        for watchpoint in &self.watchpoints {
+            let enabled = if is_write {
+                watchpoint.write
+            } else {
+                watchpoint.read
+            };
+            // the access overlaps the watched range
+            if enabled
+                && addr < watchpoint.range.end
+                && watchpoint.range.start < addr.saturating_add(size as u32)
+            {
+                (watchpoint.hook)(addr, size, value, is_write);
+            }
+        }
+    }
+
+    fn watch_access(&self, addr: u32, size: usize, value: u32, is_write: bool) {
+        if !self.watchpoints.is_empty() {
+            self.notify_watchpoints(addr, size, value, is_write);
+        }
+        self.dwt_access(addr, size, is_write);
+    }
+
+    fn dwt_access(&self, addr: u32, size: usize, is_write: bool) {
+        if self.demcr & DEMCR_TRCENA != 0 {
+            self.dwt_watch_access(addr, size, is_write);
+        }
+    }
+
+    fn add_wait_states(&self, bus_addr: u32) {
+        let wait_states = self.memory_wait_states(bus_addr);
+        if wait_states > 0 {
+            self.wait_states.set(self.wait_states.get() + wait_states);
+        }
+    }
+
+    fn data_u16(&self, bus_addr: u32, value: u16) -> u16 {
+        if self.is_big_endian(bus_addr) {
+            value.swap_bytes()
+        } else {
+            value
+        }
+    }
+
+    fn data_u32(&self, bus_addr: u32, value: u32) -> u32 {
+        if self.is_big_endian(bus_addr) {
+            value.swap_bytes()
+        } else {
+            value
+        }
+    }
+
+    fn is_big_endian(&self, bus_addr: u32) -> bool {
+        // the private peripheral bus is always little endian
+        self.endianness == Endianness::Big && !(0xE000_0000..0xE010_0000).contains(&bus_addr)
+    }
+}
+
+impl Bus for Processor {
+    fn read8(&self, bus_addr: u32) -> Result<u8, Fault> {
+        self.mpu_check(bus_addr, 1, MpuAccess::Read)?;
+        let value = self.bus_read8(bus_addr)?;
+        self.add_wait_states(bus_addr);
+        self.watch_access(bus_addr, 1, u32::from(value), false);
+        Ok(value)
+    }
+
+    fn read16(&self, bus_addr: u32) -> Result<u16, Fault> {
+        self.mpu_check(bus_addr, 2, MpuAccess::Read)?;
+        let value = self.data_u16(bus_addr, self.bus_read16(bus_addr)?);
+        self.add_wait_states(bus_addr);
+        self.watch_access(bus_addr, 2, u32::from(value), false);
+        Ok(value)
+    }
+
+    fn read32(&mut self, bus_addr: u32) -> Result<u32, Fault> {
+        self.mpu_check(bus_addr, 4, MpuAccess::Read)?;
+        let value = self.bus_read32(bus_addr)?;
+        let value = self.data_u32(bus_addr, value);
+        self.add_wait_states(bus_addr);
+        self.watch_access(bus_addr, 4, value, false);
+        Ok(value)
+    }
+
+    fn write32(&mut self, bus_addr: u32, value: u32) -> Result<(), Fault> {
+        self.mpu_check(bus_addr, 4, MpuAccess::Write)?;
+        self.bus_write32(bus_addr, self.data_u32(bus_addr, value))?;
+        self.add_wait_states(bus_addr);
+        self.invalidate_decode_cache(bus_addr, 4);
+        self.watch_access(bus_addr, 4, value, true);
+        Ok(())
+    }
+
+    fn write16(&mut self, bus_addr: u32, value: u16) -> Result<(), Fault> {
+        self.mpu_check(bus_addr, 2, MpuAccess::Write)?;
+        self.bus_write16(bus_addr, self.data_u16(bus_addr, value))?;
+        self.add_wait_states(bus_addr);
+        self.invalidate_decode_cache(bus_addr, 2);
+        self.watch_access(bus_addr, 2, u32::from(value), true);
+        Ok(())
+    }
+
+    fn write8(&mut self, bus_addr: u32, value: u8) -> Result<(), Fault> {
+        self.mpu_check(bus_addr, 1, MpuAccess::Write)?;
+        self.bus_write8(bus_addr, value)?;
+        self.add_wait_states(bus_addr);
+        self.invalidate_decode_cache(bus_addr, 1);
+        self.watch_access(bus_addr, 1, u32::from(value), true);
+        Ok(())
+    }
+
     #[allow(unused)]
     fn in_range(&self, addr: u32) -> bool {
-        self.code.in_range(addr) || self.sram.in_range(addr) || self.device.in_range(addr)
+        self.peripherals
+            .iter()
+            .any(|(range, _)| range.contains(&addr))
+            || self.code.in_range(addr)
+            || self.sram.in_range(addr)
+            || self.device.in_range(addr)
+    }
+
+    // slices are for test fixtures and debuggers, they do not burn wait
+    // states or trigger watchpoints
+    fn read_slice(&self, addr: u32, len: usize) -> Result<Vec<u8>, Fault> {
+        (0..len)
+            .map(|i| self.bus_read8(addr.wrapping_add(i as u32)))
+            .collect()
+    }
+
+    fn write_slice(&mut self, addr: u32, data: &[u8]) -> Result<(), Fault> {
+        for (i, byte) in data.iter().enumerate() {
+            let address = addr.wrapping_add(i as u32);
+            self.bus_write8(address, *byte)?;
+            self.invalidate_decode_cache(address, 1);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::bits::Bits;
+    use crate::core::exception::{Exception, ExceptionHandling};
+    use crate::core::executor::Executor;
+    use crate::core::register::{BaseReg, Reg};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    struct ToyPeripheral {
+        writes: Rc<RefCell<Vec<(u32, u32)>>>,
+    }
+
+    impl Peripheral for ToyPeripheral {
+        fn read32(&mut self, offset: u32) -> Result<u32, Fault> {
+            // the last word of the block answers with a bus error
+            if offset == 0x3fc {
+                return Err(Fault::PreciseErr { address: offset });
+            }
+            Ok(0x1000 + offset)
+        }
+        fn read16(&self, offset: u32) -> Result<u16, Fault> {
+            Ok(0x100 + offset as u16)
+        }
+        fn read8(&self, offset: u32) -> Result<u8, Fault> {
+            Ok(0x10 + offset as u8)
+        }
+        fn write32(&mut self, offset: u32, value: u32) -> Result<(), Fault> {
+            self.writes.borrow_mut().push((offset, value));
+            Ok(())
+        }
+        fn write16(&mut self, offset: u32, value: u16) -> Result<(), Fault> {
+            self.writes.borrow_mut().push((offset, u32::from(value)));
+            Ok(())
+        }
+        fn write8(&mut self, offset: u32, value: u8) -> Result<(), Fault> {
+            self.writes.borrow_mut().push((offset, u32::from(value)));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_register_peripheral() {
+        // Arrange
+        let writes = Rc::new(RefCell::new(Vec::new()));
+        let mut processor = Processor::new();
+        processor.register_peripheral(
+            0x4000_1000..0x4000_1400,
+            Box::new(ToyPeripheral {
+                writes: writes.clone(),
+            }),
+        );
+
+        // Act
+        processor.write32(0x4000_1004, 42).unwrap();
+        processor.write16(0x4000_1008, 43).unwrap();
+        processor.write8(0x4000_100c, 44).unwrap();
+
+        // Assert
+        assert_eq!(*writes.borrow(), vec![(4, 42), (8, 43), (0xc, 44)]);
+        assert_eq!(processor.read32(0x4000_1010).unwrap(), 0x1010);
+        assert_eq!(processor.read16(0x4000_1002).unwrap(), 0x102);
+        assert_eq!(processor.read8(0x4000_1001).unwrap(), 0x11);
+        assert!(processor.in_range(0x4000_13ff));
+
+        // accesses outside of the range fall through to the other regions
+        processor.write32(0x2000_0000, 7).unwrap();
+        assert_eq!(processor.read32(0x2000_0000).unwrap(), 7);
+        assert_eq!(writes.borrow().len(), 3);
+    }
+
+    #[test]
+    fn test_peripheral_fault_reports_bus_address() {
+        // Arrange
+        let mut processor = Processor::new();
+        processor.register_peripheral(
+            0x4000_1000..0x4000_1400,
+            Box::new(ToyPeripheral {
+                writes: Rc::new(RefCell::new(Vec::new())),
+            }),
+        );
+
+        // Act
+        let result = processor.read32(0x4000_13fc);
+
+        // Assert
+        assert_eq!(
+            result,
+            Err(Fault::PreciseErr {
+                address: 0x4000_13fc
+            })
+        );
+    }
+
+    #[test]
+    fn test_read_write_slice() {
+        // Arrange
+        let mut processor = Processor::new();
+        processor.write32(0x2000_0000, 0).unwrap();
+        processor.write32(0x2000_0004, 0).unwrap();
+
+        // Act
+        processor
+            .write_slice(0x2000_0002, &[0x11, 0x22, 0x33, 0x44])
+            .unwrap();
+
+        // Assert
+        // the slice spans two words, little endian like the 32 bit accesses
+        assert_eq!(processor.read32(0x2000_0000).unwrap(), 0x2211_0000);
+        assert_eq!(processor.read32(0x2000_0004).unwrap(), 0x0000_4433);
+        assert_eq!(
+            processor.read_slice(0x2000_0001, 6).unwrap(),
+            vec![0, 0x11, 0x22, 0x33, 0x44, 0]
+        );
+    }
+
+    #[test]
+    fn test_slice_outside_memory_faults() {
+        // Arrange
+        let mut processor = Processor::new();
+
+        // Act & Assert
+        assert!(processor.read_slice(0x1000_0000, 4).is_err());
+        assert!(processor.write_slice(0x1000_0000, &[1]).is_err());
+        // slices that run past the top of the address space wrap around
+        assert!(processor.read_slice(0xffff_fffe, 4).is_err());
+        assert!(processor.write_slice(0xffff_fffe, &[1, 2, 3, 4]).is_err());
+    }
+
+    #[test]
+    fn test_big_endian_data_access() {
+        // Arrange
+        let mut little = Processor::new();
+        let mut big = Processor::new();
+        big.endianness(Endianness::Big);
+        for processor in &mut [&mut little, &mut big] {
+            processor
+                .write_slice(0x2000_0000, &[0x11, 0x22, 0x33, 0x44])
+                .unwrap();
+        }
+
+        // Act & Assert
+        assert_eq!(little.read32(0x2000_0000).unwrap(), 0x4433_2211);
+        assert_eq!(big.read32(0x2000_0000).unwrap(), 0x1122_3344);
+        assert_eq!(little.read16(0x2000_0002).unwrap(), 0x4433);
+        assert_eq!(big.read16(0x2000_0002).unwrap(), 0x3344);
+        assert_eq!(big.read8(0x2000_0001).unwrap(), 0x22);
+
+        big.write32(0x2000_0004, 0xaabb_ccdd).unwrap();
+        big.write16(0x2000_0008, 0x1234).unwrap();
+        assert_eq!(
+            big.read_slice(0x2000_0004, 6).unwrap(),
+            vec![0xaa, 0xbb, 0xcc, 0xdd, 0x12, 0x34]
+        );
+    }
+
+    #[test]
+    fn test_big_endian_fetches_little_endian_instructions() {
+        // Arrange
+        let code = [
+            0x01, 0x48, // ldr r0, [pc, #4]
+            0xfe, 0xe7, // b .
+            0xfe, 0xe7, // b .
+            0xfe, 0xe7, // b .
+            0x11, 0x22, 0x33, 0x44, // .word, big endian data
+        ];
+        let mut processor = Processor::new();
+        processor.flash_memory(code.len(), &code);
+        processor.endianness(Endianness::Big);
+        processor.set_pc(0);
+
+        // Act
+        processor.step_instruction();
+
+        // Assert
+        assert_eq!(processor.get_r(Reg::R0), 0x1122_3344);
+        assert!(processor.read32(0xE000_ED0C).unwrap().get_bit(15));
+    }
+
+    #[test]
+    fn test_write_watchpoint() {
+        // Arrange
+        let hits = Rc::new(RefCell::new(Vec::new()));
+        let recorded = hits.clone();
+        let mut processor = Processor::new();
+        processor.add_watchpoint(Watchpoint::new(
+            0x2000_0100..0x2000_0108,
+            false,
+            true,
+            Box::new(move |address, size, value, is_write| {
+                recorded.borrow_mut().push((address, size, value, is_write))
+            }),
+        ));
+
+        // Act
+        processor.write32(0x2000_00fc, 1).unwrap();
+        processor.write32(0x2000_0104, 0xdead_beef).unwrap();
+        processor.read32(0x2000_0104).unwrap();
+
+        // Assert
+        assert_eq!(*hits.borrow(), vec![(0x2000_0104, 4, 0xdead_beef, true)]);
+    }
+
+    #[test]
+    fn test_watchpoint_matches_overlapping_access() {
+        // Arrange
+        let hits = Rc::new(RefCell::new(Vec::new()));
+        let recorded = hits.clone();
+        let mut processor = Processor::new();
+        processor.add_watchpoint(Watchpoint::new(
+            0x2000_0102..0x2000_0103,
+            true,
+            true,
+            Box::new(move |address, size, _, _| recorded.borrow_mut().push((address, size))),
+        ));
+
+        // Act
+        processor.write16(0x2000_0100, 1).unwrap();
+        processor.write16(0x2000_0102, 2).unwrap();
+        processor.write32(0x2000_0100, 3).unwrap();
+        processor.read8(0x2000_0103).unwrap();
+
+        // Assert
+        assert_eq!(*hits.borrow(), vec![(0x2000_0102, 2), (0x2000_0100, 4)]);
+    }
+
+    #[test]
+    fn test_watchpoint_ignores_fetch_and_stacking() {
+        // Arrange
+        let hits = Rc::new(RefCell::new(0));
+        let recorded = hits.clone();
+        let mut code = vec![0_u8; 0x100];
+        code[14 * 4..14 * 4 + 4].copy_from_slice(&0x0000_0081_u32.to_le_bytes()); // PendSV
+        code[0x40..0x42].copy_from_slice(&0xbf00_u16.to_le_bytes()); // nop
+        code[0x80..0x82].copy_from_slice(&0xe7fe_u16.to_le_bytes()); // b .
+        let mut processor = Processor::new();
+        processor.flash_memory(code.len(), &code);
+        processor.set_msp(0x2000_1000);
+        processor.set_pc(0x40);
+        processor.execution_priority = processor.get_execution_priority();
+        processor.add_watchpoint(Watchpoint::new(
+            0x0000_0000..0x2000_1000,
+            true,
+            true,
+            Box::new(move |_, _, _, _| *recorded.borrow_mut() += 1),
+        ));
+
+        // Act
+        processor.set_exception_pending(Exception::PendSV);
+        processor.step_instruction();
+
+        // Assert
+        assert_eq!(processor.get_pc(), 0x80);
+        assert_eq!(*hits.borrow(), 0);
     }
 }
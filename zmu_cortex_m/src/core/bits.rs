@@ -13,6 +13,13 @@ pub trait Bits {
     ///
     fn get_bits(&self, range: Range<usize>) -> Self;
     ///
+    /// Get a range of bits from source, `None` if the range is empty or
+    /// does not fit in the type width
+    ///
+    fn try_get_bits(&self, range: Range<usize>) -> Option<Self>
+    where
+        Self: Sized;
+    ///
     /// Get value of single bit in source word
     ///
     fn get_bit(&self, bit: usize) -> bool;
@@ -24,11 +31,14 @@ pub trait Bits {
     ///
     /// Set range of bits to a value.
     ///
+    /// Bits of `value` above the range width are ignored.
+    ///
     /// # Algorithm
     ///
     /// 1. count = (range.end - range.start)
-    /// 2. set lowest count bits to 1
-    ///   (1 << count) - 1                               |  0000 1111
+    /// 2. set lowest count bits to 1, shifting all ones right so that
+    ///    a full width range does not overflow
+    ///   MAX >> (width - count)                         |  0000 1111
     /// 3. left shift by range.start                     |  0001 1110
     /// 4. invert                                        |  1110 0001
     fn set_bits(&mut self, range: Range<usize>, value: Self);
@@ -37,20 +47,36 @@ pub trait Bits {
 impl Bits for u32 {
     #[inline(always)]
     fn get_bits(&self, range: Range<usize>) -> Self {
+        debug_assert!(
+            range.start < range.end && range.end <= 32,
+            "bit range {:?} out of bounds for u32",
+            range
+        );
         let bits = *self << (32 - range.end) >> (32 - range.end);
         bits >> range.start
     }
     #[inline(always)]
+    fn try_get_bits(&self, range: Range<usize>) -> Option<Self> {
+        if range.start < range.end && range.end <= 32 {
+            Some(self.get_bits(range))
+        } else {
+            None
+        }
+    }
+    #[inline(always)]
     fn get_bit(&self, bit: usize) -> bool {
         (*self & 1 << bit) == 1 << bit
     }
 
     #[inline(always)]
     fn set_bits(&mut self, range: Range<usize>, value: Self) {
-        let mask: Self = !(((1 << (range.end - range.start)) - 1) << range.start);
+        let ones = Self::MAX
+            .checked_shr((32 - (range.end - range.start)) as u32)
+            .unwrap_or(0);
+        let mask = ones.wrapping_shl(range.start as u32);
 
-        *self &= mask;
-        *self |= value << range.start;
+        *self &= !mask;
+        *self |= (value & ones).wrapping_shl(range.start as u32);
     }
 
     #[inline(always)]
@@ -63,18 +89,35 @@ impl Bits for u32 {
 impl Bits for u64 {
     #[inline(always)]
     fn get_bits(&self, range: Range<usize>) -> Self {
+        debug_assert!(
+            range.start < range.end && range.end <= 64,
+            "bit range {:?} out of bounds for u64",
+            range
+        );
         let bits = *self << (64 - range.end) >> (64 - range.end);
         bits >> range.start
     }
     #[inline(always)]
+    fn try_get_bits(&self, range: Range<usize>) -> Option<Self> {
+        if range.start < range.end && range.end <= 64 {
+            Some(self.get_bits(range))
+        } else {
+            None
+        }
+    }
+    #[inline(always)]
     fn get_bit(&self, bit: usize) -> bool {
         (*self & 1 << bit) == 1 << bit
     }
     #[inline(always)]
     fn set_bits(&mut self, range: Range<usize>, value: Self) {
-        let mask: Self = !(((1 << (range.end - range.start)) - 1) << range.start);
-        *self &= mask;
-        *self |= value << range.start;
+        let ones = Self::MAX
+            .checked_shr((64 - (range.end - range.start)) as u32)
+            .unwrap_or(0);
+        let mask = ones.wrapping_shl(range.start as u32);
+
+        *self &= !mask;
+        *self |= (value & ones).wrapping_shl(range.start as u32);
     }
     #[inline(always)]
     fn set_bit(&mut self, bit: usize, value: bool) {
@@ -86,19 +129,35 @@ impl Bits for u64 {
 impl Bits for u16 {
     #[inline(always)]
     fn get_bits(&self, range: Range<usize>) -> Self {
+        debug_assert!(
+            range.start < range.end && range.end <= 16,
+            "bit range {:?} out of bounds for u16",
+            range
+        );
         let bits = *self << (16 - range.end) >> (16 - range.end);
         bits >> range.start
     }
     #[inline(always)]
+    fn try_get_bits(&self, range: Range<usize>) -> Option<Self> {
+        if range.start < range.end && range.end <= 16 {
+            Some(self.get_bits(range))
+        } else {
+            None
+        }
+    }
+    #[inline(always)]
     fn get_bit(&self, bit: usize) -> bool {
         (*self & 1 << bit) == 1 << bit
     }
     #[inline(always)]
     fn set_bits(&mut self, range: Range<usize>, value: Self) {
-        let mask: Self = !(((1 << (range.end - range.start)) - 1) << range.start);
+        let ones = Self::MAX
+            .checked_shr((16 - (range.end - range.start)) as u32)
+            .unwrap_or(0);
+        let mask = ones.wrapping_shl(range.start as u32);
 
-        *self &= mask;
-        *self |= value << range.start;
+        *self &= !mask;
+        *self |= (value & ones).wrapping_shl(range.start as u32);
     }
     #[inline(always)]
     fn set_bit(&mut self, bit: usize, value: bool) {
@@ -110,19 +169,35 @@ impl Bits for u16 {
 impl Bits for u8 {
     #[inline(always)]
     fn get_bits(&self, range: Range<usize>) -> Self {
+        debug_assert!(
+            range.start < range.end && range.end <= 8,
+            "bit range {:?} out of bounds for u8",
+            range
+        );
         let bits = *self << (8 - range.end) >> (8 - range.end);
         bits >> range.start
     }
     #[inline(always)]
+    fn try_get_bits(&self, range: Range<usize>) -> Option<Self> {
+        if range.start < range.end && range.end <= 8 {
+            Some(self.get_bits(range))
+        } else {
+            None
+        }
+    }
+    #[inline(always)]
     fn get_bit(&self, bit: usize) -> bool {
         (*self & 1 << bit) == 1 << bit
     }
     #[inline(always)]
     fn set_bits(&mut self, range: Range<usize>, value: Self) {
-        let mask: Self = !(((1 << (range.end - range.start)) - 1) << range.start);
+        let ones = Self::MAX
+            .checked_shr((8 - (range.end - range.start)) as u32)
+            .unwrap_or(0);
+        let mask = ones.wrapping_shl(range.start as u32);
 
-        *self &= mask;
-        *self |= value << range.start;
+        *self &= !mask;
+        *self |= (value & ones).wrapping_shl(range.start as u32);
     }
     #[inline(always)]
     fn set_bit(&mut self, bit: usize, value: bool) {
@@ -172,4 +247,68 @@ mod tests {
             assert_eq!(o1, 0b1111_1111_1111_1111_1111_1111_1111_1111_u32);
         }
     }
+
+    #[test]
+    fn test_try_get_bits() {
+        let input: u32 = 0xdead_beef;
+        assert_eq!(input.try_get_bits(0..32), Some(0xdead_beef));
+        assert_eq!(input.try_get_bits(28..32), Some(0xd));
+        assert_eq!(input.try_get_bits(0..33), None);
+        assert_eq!(input.try_get_bits(4..4), None);
+
+        let input: u8 = 0xa5;
+        assert_eq!(input.try_get_bits(4..8), Some(0xa));
+        assert_eq!(input.try_get_bits(4..9), None);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "out of bounds")]
+    fn test_get_bits_out_of_range() {
+        let input: u16 = 0xffff;
+        input.get_bits(8..17);
+    }
+
+    #[test]
+    fn test_set_bits_full_width() {
+        let mut value: u32 = 0x1234_5678;
+        value.set_bits(0..32, 0xdead_beef);
+        assert_eq!(value, 0xdead_beef);
+
+        let mut value: u8 = 0x5a;
+        value.set_bits(0..8, 0xa5);
+        assert_eq!(value, 0xa5);
+
+        let mut value: u64 = 0;
+        value.set_bits(0..64, u64::MAX);
+        assert_eq!(value, u64::MAX);
+    }
+
+    #[test]
+    fn test_set_bits_partial() {
+        let mut value: u16 = 0xffff;
+        value.set_bits(4..8, 0b0101);
+        assert_eq!(value, 0xff5f);
+
+        // bits of value above the range width are ignored
+        let mut value: u32 = 0;
+        value.set_bits(8..12, 0xff);
+        assert_eq!(value, 0x0000_0f00);
+    }
+
+    #[test]
+    fn test_set_msb() {
+        let mut value: u32 = 0;
+        value.set_bit(31, true);
+        assert_eq!(value, 0x8000_0000);
+        assert!(value.get_bit(31));
+
+        let mut value: u8 = 0;
+        value.set_bits(7..8, 1);
+        assert_eq!(value, 0x80);
+        assert!(value.get_bit(7));
+
+        value.set_bit(7, false);
+        assert_eq!(value, 0);
+    }
 }
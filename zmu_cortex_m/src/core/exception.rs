@@ -3,14 +3,18 @@
 //!
 //!
 
-use crate::bus::Bus;
 use crate::core::bits::Bits;
 use crate::core::fault::Fault;
 use crate::core::register::{BaseReg, Ipsr, Reg};
 use crate::core::reset::Reset;
+use crate::core::trace::TraceEvent;
 use crate::peripheral::nvic::NVIC;
+#[cfg(any(armv7m, armv7em))]
+use crate::peripheral::scb::SystemControlBlock;
+use crate::peripheral::scb::CCR_STKALIGN;
 use crate::Processor;
 use crate::ProcessorMode;
+use std::fmt;
 
 #[derive(Debug, Eq, Ord, PartialEq, PartialOrd, Copy, Clone)]
 ///
@@ -84,6 +88,11 @@ pub trait ExceptionHandling {
     ///
     fn exception_active(&self, exception: Exception) -> bool;
 
+    ///
+    /// Check if given exception is currently pending
+    ///
+    fn exception_pending(&self, exception: Exception) -> bool;
+
     ///
     /// Set priority of an exception. Smaller priority number has higher urgency.
     ///
@@ -103,6 +112,25 @@ pub trait ExceptionHandling {
     ///
     ///
     fn check_exceptions(&mut self);
+
+    ///
+    /// Resolve the exception that handles given fault. Configurable faults
+    /// (`UsageFault`, `BusFault`, `MemoryManagementFault`) escalate to `HardFault`
+    /// when their handler is disabled or cannot preempt the current execution.
+    ///
+    fn fault_exception(&self, fault: Fault) -> Exception;
+
+    ///
+    /// Save the floating point context to the extended frame reserved by a
+    /// lazy stacking exception entry, if one is pending (FPCCR.LSPACT).
+    ///
+    fn preserve_fp_state(&mut self) -> Result<(), Fault>;
+
+    ///
+    /// Enter the lockup state. The processor stops executing instructions
+    /// until it is reset.
+    ///
+    fn enter_lockup(&mut self, reason: Lockup);
 }
 
 trait ExceptionHandlingHelpers {
@@ -116,7 +144,10 @@ trait ExceptionHandlingHelpers {
     fn return_address(&self, exception_type: Exception, return_address: u32) -> u32;
     fn push_stack(&mut self, exception_type: Exception, return_address: u32) -> Result<(), Fault>;
     fn pop_stack(&mut self, frameptr: u32, exc_return: u32) -> Result<(), Fault>;
+    fn push_fp_state(&mut self, address: u32) -> Result<(), Fault>;
+    fn stacking_fault(&mut self, fault: Fault) -> Result<(), Fault>;
     fn exception_active_bit_count(&self) -> usize;
+    fn group_priority(&self, priority: i16) -> i16;
 }
 
 #[derive(PartialEq, Debug, Copy, Clone)]
@@ -166,9 +197,52 @@ pub enum Exception {
     },
 }
 
+#[derive(PartialEq, Debug, Copy, Clone)]
+///
+/// Reason for the processor to enter the lockup state
+///
+pub enum Lockup {
+    /// The vector of a taken exception is zero, does not point to mapped
+    /// memory or could not be read at all.
+    InvalidVector {
+        /// exception being taken
+        exception: Exception,
+        /// vector table entry, `None` if it could not be read
+        vector: Option<u32>,
+    },
+    /// A fault escalated to hard fault while the execution priority was
+    /// negative, in the hard fault or NMI handler or with FAULTMASK set.
+    NestedFault {
+        /// the fault raised
+        fault: Fault,
+    },
+}
+
+/// Value of PC in the lockup state
+pub const LOCKUP_ADDRESS: u32 = 0xEFFF_FFFE;
+
+impl fmt::Display for Lockup {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::InvalidVector {
+                exception,
+                vector: Some(vector),
+            } => write!(f, "lockup: invalid {} vector 0x{:08x}", exception, vector),
+            Self::InvalidVector {
+                exception,
+                vector: None,
+            } => write!(f, "lockup: {} vector could not be read", exception),
+            Self::NestedFault { fault } => {
+                write!(f, "lockup: {:?} fault at negative priority", fault)
+            }
+        }
+    }
+}
+
 impl ExceptionHandlingHelpers for Processor {
     fn exception_taken(&mut self, exception: Exception) -> Result<(), Fault> {
         self.control.sp_sel = false;
+        self.control.fpca = false;
         self.mode = ProcessorMode::HandlerMode;
         self.psr.set_isr_number(exception.into());
         self.exceptions.get_mut(&exception.into()).unwrap().active = true;
@@ -179,8 +253,19 @@ impl ExceptionHandlingHelpers for Processor {
         // InstructionSynchronizationBarrier();
         let vtor = self.vtor;
         let offset: u32 = usize::from(exception) as u32 * 4;
-        let start = self.read32(vtor + offset)?;
-        self.blx_write_pc(start);
+        let vector = self.read_vector(vtor + offset).ok();
+        match vector {
+            Some(start) if start != 0 && self.is_executable(start & !1) => {
+                self.blx_write_pc(start);
+            }
+            _ => {
+                #[cfg(any(armv7m, armv7em))]
+                if vector.is_none() {
+                    self.set_fault_status(Fault::VectorTable, Exception::HardFault);
+                }
+                self.enter_lockup(Lockup::InvalidVector { exception, vector });
+            }
+        }
         Ok(())
     }
 
@@ -216,6 +301,19 @@ impl ExceptionHandlingHelpers for Processor {
             .filter(|&(_, exp)| exp.active)
             .fold(0, |acc, _| acc + 1)
     }
+
+    ///
+    /// Priority with the subpriority bits selected by AIRCR.PRIGROUP cleared.
+    ///
+    fn group_priority(&self, priority: i16) -> i16 {
+        // Reset, NMI and HardFault have fixed priorities without subpriority
+        if priority < 0 {
+            return priority;
+        }
+        let subgroupshift = self.aircr.get_bits(8..11);
+        let groupvalue = 2 << subgroupshift;
+        priority - priority % groupvalue
+    }
     fn return_address(&self, exception_type: Exception, return_address: u32) -> u32 {
         match exception_type {
             Exception::NMI
@@ -226,28 +324,54 @@ impl ExceptionHandlingHelpers for Processor {
             | Exception::DebugMonitor
             | Exception::PendSV
             | Exception::SysTick
+            | Exception::UsageFault
             | Exception::Interrupt { .. } => return_address,
-            Exception::UsageFault => return_address - 4,
             _ => panic!("unsupported exception"),
         }
     }
+    fn push_fp_state(&mut self, address: u32) -> Result<(), Fault> {
+        // S0-S15 followed by FPSCR
+        for i in 0..16 {
+            self.stack_write32(address.wrapping_add(4 * i as u32), self.fp_regs[i])?;
+        }
+        self.stack_write32(address.wrapping_add(0x40), self.fpscr)
+    }
+
+    fn stacking_fault(&mut self, fault: Fault) -> Result<(), Fault> {
+        // a bus error or MPU violation while stacking is a derived fault,
+        // reported as MSTKERR or STKERR without a fault address
+        let fault = match fault {
+            Fault::DAccViol { .. } => Fault::Mstkerr,
+            Fault::PreciseErr { .. } => Fault::Stkerr,
+            _ => fault,
+        };
+        let exception = self.fault_exception(fault);
+        if exception == Exception::HardFault && self.execution_priority < 0 {
+            return Err(fault);
+        }
+        #[cfg(any(armv7m, armv7em))]
+        self.set_fault_status(fault, exception);
+        self.set_exception_pending(exception);
+        Ok(())
+    }
+
     fn push_stack(&mut self, exception_type: Exception, return_address: u32) -> Result<(), Fault> {
-        const FRAME_SIZE: u32 = 0x20;
+        // extended frame reserves space for S0-S15, FPSCR and a reserved word
+        let frame_size: u32 = if self.control.fpca { 0x68 } else { 0x20 };
 
-        //TODO FP extensions
-        //TODO forcealign
-        // forces 8 byte alignment on the stack
-        let forcealign = true;
+        // CCR.STKALIGN forces 8 byte alignment of the frame, stacked xPSR
+        // bit 9 records whether padding was inserted
+        let forcealign = self.ccr & CCR_STKALIGN != 0;
         let spmask = ((forcealign as u32) << 2) ^ 0xFFFF_FFFF;
 
         let (frameptr, frameptralign) =
             if self.control.sp_sel && self.mode == ProcessorMode::ThreadMode {
                 let align = (self.psp.get_bit(2) & forcealign) as u32;
-                self.set_psp((self.psp.wrapping_sub(FRAME_SIZE)) & spmask);
+                self.set_psp((self.psp.wrapping_sub(frame_size)) & spmask);
                 (self.psp, align)
             } else {
-                let align = self.msp.get_bit(2) as u32;
-                self.set_msp((self.msp.wrapping_sub(FRAME_SIZE)) & spmask);
+                let align = (self.msp.get_bit(2) & forcealign) as u32;
+                self.set_msp((self.msp.wrapping_sub(frame_size)) & spmask);
                 (self.msp, align)
             };
 
@@ -260,16 +384,38 @@ impl ExceptionHandlingHelpers for Processor {
 
         let ret_addr = self.return_address(exception_type, return_address);
 
-        self.write32(frameptr, r0)?;
-        self.write32(frameptr.wrapping_add(0x4), r1)?;
-        self.write32(frameptr.wrapping_add(0x8), r2)?;
-        self.write32(frameptr.wrapping_add(0xc), r3)?;
-        self.write32(frameptr.wrapping_add(0x10), r12)?;
-        self.write32(frameptr.wrapping_add(0x14), lr)?;
-        self.write32(frameptr.wrapping_add(0x18), ret_addr)?;
-        let xpsr = (self.psr.value & 0b1111_1111_1111_1111_1111_1101_1111_1111)
-            | (frameptralign << 9) as u32;
-        self.write32(frameptr.wrapping_add(0x1c), xpsr)?;
+        let stack_limit = self.get_stack_limit().filter(|&limit| frameptr < limit);
+        if let Some(limit) = stack_limit {
+            // as on ARMv8-M, nothing is stacked below the limit: the stack
+            // pointer stops at the limit and the overflow fault is pended
+            self.set_r(Reg::SP, limit);
+            let fault_exception = self.fault_exception(Fault::StkOf);
+            #[cfg(any(armv7m, armv7em))]
+            self.set_fault_status(Fault::StkOf, fault_exception);
+            self.set_exception_pending(fault_exception);
+        } else {
+            self.stack_write32(frameptr, r0)?;
+            self.stack_write32(frameptr.wrapping_add(0x4), r1)?;
+            self.stack_write32(frameptr.wrapping_add(0x8), r2)?;
+            self.stack_write32(frameptr.wrapping_add(0xc), r3)?;
+            self.stack_write32(frameptr.wrapping_add(0x10), r12)?;
+            self.stack_write32(frameptr.wrapping_add(0x14), lr)?;
+            self.stack_write32(frameptr.wrapping_add(0x18), ret_addr)?;
+            let xpsr = (self.psr.value & 0b1111_1111_1111_1111_1111_1101_1111_1111)
+                | (frameptralign << 9) as u32;
+            self.stack_write32(frameptr.wrapping_add(0x1c), xpsr)?;
+        }
+
+        if self.control.fpca && stack_limit.is_none() {
+            if self.fpccr.get_bit(30) {
+                // FPCCR.LSPEN: reserve the space, the state is saved by the
+                // first floating point instruction of the handler
+                self.fpcar = frameptr.wrapping_add(0x20);
+                self.fpccr.set_bit(0, true); // LSPACT
+            } else {
+                self.push_fp_state(frameptr.wrapping_add(0x20))?;
+            }
+        }
 
         if self.mode == ProcessorMode::HandlerMode {
             self.lr = 0xFFFF_FFF1;
@@ -278,44 +424,58 @@ impl ExceptionHandlingHelpers for Processor {
         } else {
             self.lr = 0xFFFF_FFF9;
         }
+        // EXC_RETURN bit 4 is cleared for the extended frame
+        self.lr.set_bit(4, !self.control.fpca);
         Ok(())
     }
 
     fn pop_stack(&mut self, frameptr: u32, exc_return: u32) -> Result<(), Fault> {
-        //TODO: fp extensions
+        let extended_frame = !exc_return.get_bit(4);
+        let frame_size: u32 = if extended_frame { 0x68 } else { 0x20 };
 
-        const FRAME_SIZE: u32 = 0x20;
+        let forcealign = self.ccr & CCR_STKALIGN != 0;
 
-        //let forcealign = ccr.stkalign;
-        let forcealign = true;
-
-        let r0 = self.read32(frameptr)?;
+        let r0 = self.stack_read32(frameptr)?;
         self.set_r(Reg::R0, r0);
-        let r1 = self.read32(frameptr.wrapping_add(0x4))?;
+        let r1 = self.stack_read32(frameptr.wrapping_add(0x4))?;
         self.set_r(Reg::R1, r1);
-        let r2 = self.read32(frameptr.wrapping_add(0x8))?;
+        let r2 = self.stack_read32(frameptr.wrapping_add(0x8))?;
         self.set_r(Reg::R2, r2);
-        let r3 = self.read32(frameptr.wrapping_add(0xc))?;
+        let r3 = self.stack_read32(frameptr.wrapping_add(0xc))?;
         self.set_r(Reg::R3, r3);
-        let r12 = self.read32(frameptr.wrapping_add(0x10))?;
+        let r12 = self.stack_read32(frameptr.wrapping_add(0x10))?;
         self.set_r(Reg::R12, r12);
-        let lr = self.read32(frameptr.wrapping_add(0x14))?;
+        let lr = self.stack_read32(frameptr.wrapping_add(0x14))?;
         self.set_r(Reg::LR, lr);
-        let pc = self.read32(frameptr.wrapping_add(0x18))?;
-        let psr = self.read32(frameptr.wrapping_add(0x1c))?;
+        let pc = self.stack_read32(frameptr.wrapping_add(0x18))?;
+        let psr = self.stack_read32(frameptr.wrapping_add(0x1c))?;
 
         self.branch_write_pc(pc);
 
+        if extended_frame {
+            if self.fpccr.get_bit(0) {
+                // state was never saved, it is still in the registers
+                self.fpccr.set_bit(0, false);
+            } else {
+                for i in 0..16 {
+                    self.fp_regs[i] =
+                        self.stack_read32(frameptr.wrapping_add(0x20 + 4 * i as u32))?;
+                }
+                self.fpscr = self.stack_read32(frameptr.wrapping_add(0x60))?;
+            }
+        }
+        self.control.fpca = extended_frame;
+
         let spmask = ((psr.get_bit(9) && forcealign) as u32) << 2;
 
         match exc_return.get_bits(0..4) {
             0b0001 | 0b1001 => {
                 let msp = self.get_msp();
-                self.set_msp((msp.wrapping_add(FRAME_SIZE)) | spmask);
+                self.set_msp((msp.wrapping_add(frame_size)) | spmask);
             }
             0b1101 => {
                 let psp = self.get_psp();
-                self.set_psp((psp.wrapping_add(FRAME_SIZE)) | spmask);
+                self.set_psp((psp.wrapping_add(frame_size)) | spmask);
             }
             _ => {
                 panic!("wrong exc return");
@@ -339,11 +499,16 @@ impl ExceptionHandling for Processor {
                 exception.priority = 0;
             }
         }
+        self.pending_exception_count = 0;
     }
     fn exception_active(&self, exception: Exception) -> bool {
         self.exceptions[&usize::from(exception)].active
     }
 
+    fn exception_pending(&self, exception: Exception) -> bool {
+        self.exceptions[&usize::from(exception)].pending
+    }
+
     fn set_exception_priority(&mut self, exception: Exception, priority: u8) {
         self.exceptions.get_mut(&exception.into()).unwrap().priority = i16::from(priority);
     }
@@ -355,20 +520,14 @@ impl ExceptionHandling for Processor {
     fn get_execution_priority(&self) -> i16 {
         let mut highestpri: i16 = 256;
         let mut boostedpri: i16 = 256;
-        let subgroupshift = self.aircr.get_bits(8..11);
-        let groupvalue = 2 << subgroupshift;
 
         for (_, exp) in self.exceptions.iter().filter(|&(_, e)| e.active) {
             if exp.priority < highestpri {
-                highestpri = exp.priority;
-                let subgroupvalue = highestpri % groupvalue;
-                highestpri -= subgroupvalue;
+                highestpri = self.group_priority(exp.priority);
             }
         }
         if self.basepri != 0 {
-            boostedpri = i16::from(self.basepri);
-            let subgroupvalue = boostedpri % groupvalue;
-            boostedpri -= subgroupvalue;
+            boostedpri = self.group_priority(i16::from(self.basepri));
         }
         if self.primask {
             boostedpri = 0;
@@ -401,11 +560,15 @@ impl ExceptionHandling for Processor {
             let mut possible_exceptions: Vec<ExceptionState> = self
                 .exceptions
                 .iter()
-                .filter(|&(_, e)| e.pending && e.priority < self.execution_priority)
+                .filter(|&(_, e)| {
+                    e.pending && self.group_priority(e.priority) < self.execution_priority
+                })
                 .map(|(&_, &e)| e)
                 .collect();
 
             if !possible_exceptions.is_empty() {
+                // the full priority value orders by group priority first and
+                // by subpriority within a group
                 possible_exceptions.sort_by(|a, b| {
                     if a.priority == b.priority {
                         a.exception_number.cmp(&b.exception_number)
@@ -434,8 +597,17 @@ impl ExceptionHandling for Processor {
             if let Exception::Interrupt { n } = exception {
                 self.nvic_unpend_interrupt(n);
             }
-            self.push_stack(exception, return_address)?;
-            self.exception_taken(exception)
+            if let Err(fault) = self.push_stack(exception, return_address) {
+                // the exception is still taken, the derived fault stays
+                // pending until its priority allows it to preempt
+                self.stacking_fault(fault)?;
+            }
+            self.exception_taken(exception)?;
+            self.trace(&TraceEvent::ExceptionEntered {
+                exception,
+                return_address,
+            });
+            Ok(())
         }
     }
 
@@ -520,9 +692,91 @@ impl ExceptionHandling for Processor {
             self.state.set_bit(1, false); // sleeping == false
             self.clear_pending_exception(exception);
             let pc = self.get_pc();
-            // TODO: handle failure to enter exception
-            self.exception_entry(exception, pc)
-                .expect("error handling on exception entry not implemented");
+            if let Err(fault) = self.exception_entry(exception, pc) {
+                self.enter_lockup(Lockup::NestedFault { fault });
+            }
+        }
+    }
+
+    fn preserve_fp_state(&mut self) -> Result<(), Fault> {
+        if self.fpccr.get_bit(0) {
+            let fpcar = self.fpcar;
+            self.push_fp_state(fpcar)?;
+            self.fpccr.set_bit(0, false);
+        }
+        Ok(())
+    }
+
+    fn enter_lockup(&mut self, reason: Lockup) {
+        self.lockup = Some(reason);
+        self.set_pc(LOCKUP_ADDRESS);
+        self.state.set_bit(0, false); // running == false
+    }
+
+    #[cfg(armv6m)]
+    fn fault_exception(&self, _fault: Fault) -> Exception {
+        // all faults are mapped to hardfaults on armv6m
+        Exception::HardFault
+    }
+
+    #[cfg(any(armv7m, armv7em))]
+    fn fault_exception(&self, fault: Fault) -> Exception {
+        let exception = Exception::from(fault);
+        let enabled = match exception {
+            Exception::MemoryManagementFault => self.shcsr.get_bit(16),
+            Exception::BusFault => self.shcsr.get_bit(17),
+            Exception::UsageFault => self.shcsr.get_bit(18),
+            _ => return exception,
+        };
+
+        if enabled && self.get_exception_priority(exception) < self.execution_priority {
+            exception
+        } else {
+            Exception::HardFault
+        }
+    }
+}
+
+impl From<Fault> for Exception {
+    fn from(value: Fault) -> Self {
+        match value {
+            Fault::VectorTable | Fault::Forced => Self::HardFault,
+            Fault::IAccViol | Fault::DAccViol { .. } | Fault::Mstkerr | Fault::Msunskerr => {
+                Self::MemoryManagementFault
+            }
+            Fault::Stkerr | Fault::PreciseErr { .. } => Self::BusFault,
+            Fault::UndefInstr
+            | Fault::Invstate
+            | Fault::InvPc
+            | Fault::Unaligned
+            | Fault::DivByZero
+            | Fault::NoCp
+            | Fault::StkOf => Self::UsageFault,
+        }
+    }
+}
+
+impl fmt::Display for Exception {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::Reset => write!(f, "Reset"),
+            Self::NMI => write!(f, "NMI"),
+            Self::HardFault => write!(f, "HardFault"),
+            Self::MemoryManagementFault => write!(f, "MemManage"),
+            Self::BusFault => write!(f, "BusFault"),
+            Self::UsageFault => write!(f, "UsageFault"),
+            Self::Reserved4
+            | Self::Reserved5
+            | Self::Reserved6
+            | Self::Reserved8
+            | Self::Reserved9 => {
+                write!(f, "Reserved{}", usize::from(*self))
+            }
+            Self::DebugMonitor => write!(f, "DebugMonitor"),
+            Self::SVCall => write!(f, "SVCall"),
+            Self::PendSV => write!(f, "PendSV"),
+            Self::SysTick => write!(f, "SysTick"),
+            Self::Interrupt { n } => write!(f, "IRQ{}", n),
         }
     }
 }
@@ -539,9 +793,9 @@ impl From<Exception> for usize {
             Exception::Reserved4 => 7,
             Exception::Reserved5 => 8,
             Exception::Reserved6 => 9,
-            Exception::DebugMonitor => 10,
+            Exception::Reserved8 => 10,
             Exception::SVCall => 11,
-            Exception::Reserved8 => 12,
+            Exception::DebugMonitor => 12,
             Exception::Reserved9 => 13,
             Exception::PendSV => 14,
             Exception::SysTick => 15,
@@ -562,9 +816,9 @@ impl From<usize> for Exception {
             7 => Self::Reserved4,
             8 => Self::Reserved5,
             9 => Self::Reserved6,
-            10 => Self::DebugMonitor,
+            10 => Self::Reserved8,
             11 => Self::SVCall,
-            12 => Self::Reserved8,
+            12 => Self::DebugMonitor,
             13 => Self::Reserved9,
             14 => Self::PendSV,
             15 => Self::SysTick,
@@ -580,10 +834,14 @@ mod tests {
     #[cfg(any(armv7m, armv7em))]
     use crate::core::exception::Exception;
     use crate::core::exception::ExceptionHandling;
-    #[cfg(any(armv7m, armv7em))]
     use crate::core::executor::Executor;
+    use crate::core::executor::{RunExit, StepResult};
     #[cfg(any(armv7m, armv7em))]
     use crate::core::instruction::Instruction;
+    #[cfg(any(armv7m, armv7em))]
+    use crate::core::register::SingleReg;
+    use crate::test_fixture::handler_test_processor;
+    use std::collections::HashSet;
 
     #[test]
     fn test_push_stack() {
@@ -722,4 +980,796 @@ mod tests {
         // Assert
         assert_eq!(processor.nvic_read_ispr(0), 0);
     }
+
+    #[test]
+    fn test_exception_entry_uses_vtor() {
+        // Arrange
+        let mut processor = Processor::new();
+        processor.reset().unwrap();
+        processor.set_msp(0x2000_1000);
+
+        // relocated table in RAM with SVCall vector pointing to 0x400
+        processor
+            .write32(0x2000_0200 + 11 * 4, 0x0000_0401)
+            .unwrap();
+
+        // Act
+        processor.write32(0xE000_ED08, 0x2000_0200).unwrap();
+        processor.exception_entry(Exception::SVCall, 0x100).unwrap();
+
+        // Assert
+        assert_eq!(processor.read32(0xE000_ED08).unwrap(), 0x2000_0200);
+        assert_eq!(processor.get_pc(), 0x400);
+    }
+
+    #[test]
+    fn test_reset_uses_vtor() {
+        // Arrange
+        let mut processor = Processor::new();
+        processor.write32(0x2000_0200, 0x2000_0800).unwrap();
+        processor.write32(0x2000_0204, 0x0000_0101).unwrap();
+
+        // Act
+        processor.write32(0xE000_ED08, 0x2000_0200).unwrap();
+        processor.reset().unwrap();
+
+        // Assert
+        assert_eq!(processor.msp, 0x2000_0800);
+        assert_eq!(processor.get_pc(), 0x100);
+    }
+
+    #[test]
+    fn test_vtor_tbloff_alignment() {
+        // Arrange
+        let mut processor = Processor::new();
+
+        // Act
+        processor.write32(0xE000_ED08, 0x0000_1234).unwrap();
+
+        // Assert
+        assert_eq!(processor.read32(0xE000_ED08).unwrap(), 0x0000_1200);
+    }
+
+    const FP_STACK_START: u32 = 0x2000_1000;
+    const FP_FRAME: u32 = FP_STACK_START - 0x68;
+
+    fn fp_context_test_processor(fpccr: u32) -> Processor {
+        let mut processor = Processor::new();
+        processor.reset().unwrap();
+        processor.set_msp(FP_STACK_START);
+        processor.write32(0xE000_ED88, 0b1111 << 20).unwrap();
+        processor.write32(0xE000_EF34, fpccr).unwrap();
+
+        // thread has used the FPU
+        processor.control.fpca = true;
+        for (i, reg) in processor.fp_regs.iter_mut().enumerate() {
+            *reg = 100 + i as u32;
+        }
+        processor.fpscr = 0x0300_0000;
+        processor
+    }
+
+    #[test]
+    fn test_exception_with_fpca_stacks_fp_frame() {
+        // Arrange
+        let mut processor = fp_context_test_processor(0);
+
+        // Act
+        processor.exception_entry(Exception::SVCall, 0x100).unwrap();
+
+        // Assert
+        assert_eq!(processor.get_msp(), FP_FRAME);
+        assert_eq!(processor.get_r(Reg::LR), 0xFFFF_FFE9);
+        assert_eq!(processor.control.fpca, false);
+        for i in 0..16 {
+            assert_eq!(processor.read32(FP_FRAME + 0x20 + 4 * i).unwrap(), 100 + i);
+        }
+        assert_eq!(processor.read32(FP_FRAME + 0x60).unwrap(), 0x0300_0000);
+
+        // Act: handler clobbers the caller saved FP registers
+        processor.fp_regs[0] = 0;
+        processor.fp_regs[15] = 0;
+        processor.fpscr = 0;
+        let exc_return = processor.get_r(Reg::LR);
+        processor.exception_return(exc_return).unwrap();
+
+        // Assert
+        assert_eq!(processor.mode, ProcessorMode::ThreadMode);
+        assert_eq!(processor.get_msp(), FP_STACK_START);
+        assert_eq!(processor.fp_regs[0], 100);
+        assert_eq!(processor.fp_regs[15], 115);
+        assert_eq!(processor.fpscr, 0x0300_0000);
+        assert_eq!(processor.control.fpca, true);
+    }
+
+    #[test]
+    fn test_exception_without_fpca_uses_basic_frame() {
+        // Arrange
+        let mut processor = fp_context_test_processor(0);
+        processor.control.fpca = false;
+
+        // Act
+        processor.exception_entry(Exception::SVCall, 0x100).unwrap();
+
+        // Assert
+        assert_eq!(processor.get_msp(), FP_STACK_START - 0x20);
+        assert_eq!(processor.get_r(Reg::LR), 0xFFFF_FFF9);
+    }
+
+    #[cfg(any(armv7m, armv7em))]
+    #[test]
+    fn test_lazy_fp_stacking_preserves_on_first_fp_instruction() {
+        // Arrange
+        let mut processor = fp_context_test_processor(0xc000_0000); // ASPEN, LSPEN
+
+        // Act
+        processor.exception_entry(Exception::SVCall, 0x100).unwrap();
+
+        // Assert: space is reserved but nothing saved yet
+        assert_eq!(processor.get_msp(), FP_FRAME);
+        assert_eq!(processor.fpcar, FP_FRAME + 0x20);
+        assert_eq!(processor.fpccr.get_bit(0), true);
+        assert_ne!(processor.read32(FP_FRAME + 0x20).unwrap(), 100);
+
+        // Act: handler uses the FPU
+        processor.execute(
+            &Instruction::VADD_f32 {
+                sd: SingleReg::S0,
+                sn: SingleReg::S1,
+                sm: SingleReg::S2,
+            },
+            4,
+        );
+
+        // Assert
+        assert_eq!(processor.fpccr.get_bit(0), false);
+        assert_eq!(processor.control.fpca, true);
+        assert_eq!(processor.read32(FP_FRAME + 0x20).unwrap(), 100);
+        assert_eq!(processor.read32(FP_FRAME + 0x60).unwrap(), 0x0300_0000);
+        assert_ne!(processor.fp_regs[0], 100);
+
+        // Act
+        let exc_return = processor.get_r(Reg::LR);
+        processor.exception_return(exc_return).unwrap();
+
+        // Assert
+        assert_eq!(processor.get_msp(), FP_STACK_START);
+        assert_eq!(processor.fp_regs[0], 100);
+        assert_eq!(processor.fpscr, 0x0300_0000);
+    }
+
+    #[test]
+    fn test_lazy_fp_stacking_without_fp_use() {
+        // Arrange
+        let mut processor = fp_context_test_processor(0xc000_0000); // ASPEN, LSPEN
+
+        // Act
+        processor.exception_entry(Exception::SVCall, 0x100).unwrap();
+        let exc_return = processor.get_r(Reg::LR);
+        processor.exception_return(exc_return).unwrap();
+
+        // Assert: registers were never saved nor restored
+        assert_eq!(processor.fpccr.get_bit(0), false);
+        assert_eq!(processor.get_msp(), FP_STACK_START);
+        assert_eq!(processor.fp_regs[0], 100);
+        assert_eq!(processor.control.fpca, true);
+    }
+
+    ///
+    /// Processor running `program` from 0x100, with NMI, IRQ0 and IRQ1
+    /// handlers at 0x200 and IRQ0 enabled and pending
+    ///
+    fn masking_test_processor(program: &[u16]) -> Processor {
+        let mut processor = handler_test_processor(
+            program,
+            &[0xe7fe], // b .
+            &[2, 3, 16, 17],
+            0x2000_1000,
+        );
+        processor.nvic_write_iser(0, 0b11);
+        processor
+    }
+
+    ///
+    /// Processor with an unaligned MSP, running NOPs from 0x100 with IRQ0
+    /// pending and a handler that returns right away
+    ///
+    fn stack_align_test_processor() -> Processor {
+        let mut processor = handler_test_processor(
+            &[0xbf00, 0xbf00], // nop, nop
+            &[0x4770],         // bx lr
+            &[16],
+            0x2000_0ffc,
+        );
+        processor.nvic_write_iser(0, 1);
+        processor.nvic_write_ispr(0, 1);
+        processor
+    }
+
+    #[test]
+    fn test_stkalign_pads_unaligned_frame() {
+        // Arrange
+        let mut processor = stack_align_test_processor();
+
+        // Act
+        processor.step_instruction();
+
+        // Assert
+        assert_eq!(processor.get_pc(), 0x200);
+        assert_eq!(processor.get_msp(), 0x2000_0fd8);
+        assert!(processor.read32(0x2000_0fd8 + 0x1c).unwrap().get_bit(9));
+
+        // Act
+        processor.step_instruction();
+
+        // Assert
+        assert_eq!(processor.get_pc(), 0x102);
+        assert_eq!(processor.get_msp(), 0x2000_0ffc);
+        assert_eq!(processor.psr.value.get_bit(9), false);
+    }
+
+    #[cfg(any(armv7m, armv7em))]
+    #[test]
+    fn test_stack_frame_without_stkalign() {
+        // Arrange
+        let mut processor = stack_align_test_processor();
+        processor.write32(0xE000_ED14, 0).unwrap();
+
+        // Act
+        processor.step_instruction();
+
+        // Assert
+        assert_eq!(processor.get_msp(), 0x2000_0fdc);
+        assert_eq!(
+            processor.read32(0x2000_0fdc + 0x1c).unwrap().get_bit(9),
+            false
+        );
+
+        // Act
+        processor.step_instruction();
+
+        // Assert
+        assert_eq!(processor.get_pc(), 0x102);
+        assert_eq!(processor.get_msp(), 0x2000_0ffc);
+    }
+
+    #[test]
+    fn test_primask_blocks_interrupts_until_cpsie() {
+        // Arrange
+        let mut processor = masking_test_processor(&[
+            0xb672, // cpsid i
+            0xbf00, // nop
+            0xb662, // cpsie i
+            0xbf00, // nop
+        ]);
+        processor.step_instruction();
+
+        // Act
+        processor.nvic_write_ispr(0, 1);
+        processor.step_instruction();
+
+        // Assert
+        assert_eq!(processor.psr.get_isr_number(), 0);
+        assert_eq!(processor.get_pc(), 0x104);
+
+        // Act
+        processor.step_instruction();
+
+        // Assert
+        assert_eq!(processor.psr.get_isr_number(), 16);
+        assert_eq!(processor.get_pc(), 0x200);
+        assert_eq!(processor.read32(0x2000_1000 - 0x20 + 0x18).unwrap(), 0x106);
+    }
+
+    #[test]
+    fn test_nmi_pended_through_icsr_ignores_primask() {
+        // Arrange
+        let mut processor = masking_test_processor(&[
+            0xb672, // cpsid i
+            0xbf00, // nop
+        ]);
+        processor.step_instruction();
+
+        // Act
+        processor.write32(0xE000_ED04, 1 << 31).unwrap(); // NMIPENDSET
+        processor.step_instruction();
+
+        // Assert
+        assert_eq!(processor.psr.get_isr_number(), Exception::NMI.into());
+        assert_eq!(processor.get_pc(), 0x200);
+        assert_eq!(processor.read32(0xE000_ED04).unwrap().get_bit(31), false);
+    }
+
+    #[test]
+    fn test_nmi_preempts_hardfault_handler() {
+        // Arrange
+        let mut processor = masking_test_processor(&[0xbf00]);
+        processor.set_exception_pending(Exception::HardFault);
+        processor.check_exceptions();
+
+        // Act
+        processor.set_exception_pending(Exception::NMI);
+        processor.check_exceptions();
+
+        // Assert
+        assert_eq!(processor.psr.get_isr_number(), Exception::NMI.into());
+        assert_eq!(processor.exception_active(Exception::HardFault), true);
+        assert_eq!(processor.get_r(Reg::LR), 0xFFFF_FFF1);
+    }
+
+    #[cfg(any(armv7m, armv7em))]
+    #[test]
+    fn test_faultmask_blocks_all_but_nmi() {
+        // Arrange
+        let mut processor = masking_test_processor(&[
+            0xb671, // cpsid f
+            0xbf00, // nop
+            0xbf00, // nop
+        ]);
+        processor.step_instruction();
+
+        // Act
+        processor.nvic_write_ispr(0, 1);
+        processor.set_exception_pending(Exception::HardFault);
+        processor.step_instruction();
+
+        // Assert
+        assert_eq!(processor.psr.get_isr_number(), 0);
+
+        // Act
+        processor.set_exception_pending(Exception::NMI);
+        processor.step_instruction();
+
+        // Assert
+        assert_eq!(processor.psr.get_isr_number(), Exception::NMI.into());
+    }
+
+    #[cfg(any(armv7m, armv7em))]
+    #[test]
+    fn test_basepri_blocks_lower_priorities() {
+        // Arrange
+        let mut processor = masking_test_processor(&[
+            0x2040, // movs r0, #0x40
+            0xf380, 0x8811, // msr basepri, r0
+            0xbf00, // nop
+        ]);
+        processor.set_exception_priority(Exception::Interrupt { n: 0 }, 0x40);
+        processor.set_exception_priority(Exception::Interrupt { n: 1 }, 0x20);
+        processor.step_instruction();
+        processor.step_instruction();
+
+        // Act
+        processor.nvic_write_ispr(0, 1);
+        processor.step_instruction();
+
+        // Assert
+        assert_eq!(processor.psr.get_isr_number(), 0);
+
+        // Act
+        processor.nvic_write_ispr(0, 0b10);
+        processor.check_exceptions();
+
+        // Assert
+        assert_eq!(processor.psr.get_isr_number(), 17);
+    }
+
+    #[cfg(any(armv7m, armv7em))]
+    fn prigroup_test_processor(prigroup: u32) -> Processor {
+        let mut processor = masking_test_processor(&[0xbf00, 0xbf00]);
+        processor.write32(0xE000_ED0C, 0x05fa_0000 | (prigroup << 8)).unwrap();
+        // same group priority under PRIGROUP 6, IRQ1 has the higher subpriority
+        processor.set_exception_priority(Exception::Interrupt { n: 0 }, 0x40);
+        processor.set_exception_priority(Exception::Interrupt { n: 1 }, 0x20);
+        processor
+    }
+
+    #[cfg(any(armv7m, armv7em))]
+    #[test]
+    fn test_prigroup_subpriority_does_not_preempt() {
+        // Arrange
+        let mut processor = prigroup_test_processor(6);
+        processor.nvic_write_ispr(0, 0b01);
+        processor.step_instruction();
+        assert_eq!(processor.psr.get_isr_number(), 16);
+
+        // Act
+        processor.nvic_write_ispr(0, 0b10);
+        processor.step_instruction();
+
+        // Assert
+        assert_eq!(processor.psr.get_isr_number(), 16);
+        assert!(processor.exception_pending(Exception::Interrupt { n: 1 }));
+    }
+
+    #[cfg(any(armv7m, armv7em))]
+    #[test]
+    fn test_prigroup_zero_preempts_on_priority() {
+        // Arrange
+        let mut processor = prigroup_test_processor(0);
+        processor.nvic_write_ispr(0, 0b01);
+        processor.step_instruction();
+        assert_eq!(processor.psr.get_isr_number(), 16);
+
+        // Act
+        processor.nvic_write_ispr(0, 0b10);
+        processor.step_instruction();
+
+        // Assert
+        assert_eq!(processor.psr.get_isr_number(), 17);
+    }
+
+    #[cfg(any(armv7m, armv7em))]
+    #[test]
+    fn test_prigroup_subpriority_breaks_ties() {
+        // Arrange
+        let mut processor = prigroup_test_processor(6);
+
+        // Act
+        processor.nvic_write_ispr(0, 0b11);
+        processor.step_instruction();
+
+        // Assert
+        assert_eq!(processor.psr.get_isr_number(), 17);
+
+        // Act
+        processor.step_instruction();
+
+        // Assert
+        assert_eq!(processor.psr.get_isr_number(), 17);
+        assert!(processor.exception_pending(Exception::Interrupt { n: 0 }));
+    }
+
+    fn rtos_test_processor() -> Processor {
+        let mut processor = handler_test_processor(&[], &[], &[], 0x2000_1000);
+
+        // common RTOS setup: SysTick and PendSV both at the lowest priority
+        processor.set_exception_priority(Exception::PendSV, 0xff);
+        processor.set_exception_priority(Exception::SysTick, 0xff);
+        processor
+    }
+
+    #[test]
+    fn test_systick_pends_pendsv_tail_chain() {
+        // Arrange
+        let mut processor = rtos_test_processor();
+        processor.set_exception_pending(Exception::SysTick);
+
+        // Act: tick interrupt taken from thread mode
+        processor.check_exceptions();
+
+        // Assert
+        assert_eq!(processor.psr.get_isr_number(), Exception::SysTick.into());
+
+        // Act: tick handler requests a context switch, PendSV must not preempt
+        processor.write32(0xE000_ED04, 1 << 28).unwrap();
+        processor.check_exceptions();
+
+        // Assert
+        assert_eq!(processor.psr.get_isr_number(), Exception::SysTick.into());
+        assert_eq!(
+            processor.get_pending_exception(),
+            None,
+            "same priority must not preempt"
+        );
+
+        // Act: return from tick handler, PendSV is chained right after
+        let exc_return = processor.get_r(Reg::LR);
+        processor.exception_return(exc_return).unwrap();
+        processor.check_exceptions();
+
+        // Assert
+        assert_eq!(processor.psr.get_isr_number(), Exception::PendSV.into());
+        assert_eq!(processor.exception_active(Exception::SysTick), false);
+
+        // Act: return from context switch
+        let exc_return = processor.get_r(Reg::LR);
+        processor.exception_return(exc_return).unwrap();
+        processor.check_exceptions();
+
+        // Assert
+        assert_eq!(processor.mode, ProcessorMode::ThreadMode);
+        assert_eq!(processor.psr.get_isr_number(), 0);
+        assert_eq!(processor.get_msp(), 0x2000_1000);
+    }
+
+    #[test]
+    fn test_pendsv_pended_in_higher_priority_handler_runs_after_return() {
+        // Arrange
+        let mut processor = rtos_test_processor();
+        processor.set_exception_pending(Exception::SVCall);
+        processor.check_exceptions();
+        assert_eq!(processor.psr.get_isr_number(), Exception::SVCall.into());
+
+        // Act: handler requests a context switch
+        processor.write32(0xE000_ED04, 1 << 28).unwrap();
+        processor.check_exceptions();
+
+        // Assert: PendSV waits for the handler to finish
+        assert_eq!(processor.psr.get_isr_number(), Exception::SVCall.into());
+        assert_eq!(processor.read32(0xE000_ED04).unwrap().get_bit(28), true);
+
+        // Act
+        let exc_return = processor.get_r(Reg::LR);
+        processor.exception_return(exc_return).unwrap();
+        processor.check_exceptions();
+
+        // Assert
+        assert_eq!(processor.psr.get_isr_number(), Exception::PendSV.into());
+        assert_eq!(processor.exception_active(Exception::SVCall), false);
+        assert_eq!(processor.read32(0xE000_ED04).unwrap().get_bit(28), false);
+    }
+
+    #[test]
+    fn test_systick_and_pendsv_pending_together() {
+        // Arrange
+        let mut processor = rtos_test_processor();
+        processor.set_exception_pending(Exception::SysTick);
+        processor.set_exception_pending(Exception::PendSV);
+
+        // Act
+        processor.check_exceptions();
+
+        // Assert: equal priority, lower exception number (PendSV = 14) wins
+        assert_eq!(processor.psr.get_isr_number(), Exception::PendSV.into());
+
+        // Act
+        let exc_return = processor.get_r(Reg::LR);
+        processor.exception_return(exc_return).unwrap();
+        processor.check_exceptions();
+
+        // Assert
+        assert_eq!(processor.psr.get_isr_number(), Exception::SysTick.into());
+    }
+
+    #[test]
+    fn test_push_below_stack_limit_faults() {
+        // Arrange
+        let mut processor = masking_test_processor(&[
+            0xb403, // push {r0, r1}
+            0xb5ff, // push {r0-r7, lr}
+        ]);
+        processor.stack_limits(Some(0x2000_0fd8), None);
+        processor.step_instruction();
+
+        // Act
+        processor.step_instruction();
+
+        // Assert
+        assert_eq!(processor.psr.get_isr_number(), Exception::HardFault.into());
+        assert_eq!(processor.get_pc(), 0x200);
+        // the push is not done, the fault frame still fits above the limit
+        assert_eq!(processor.get_msp(), 0x2000_0ff8 - 0x20);
+        assert_eq!(processor.read32(0x2000_0fd8 + 0x18).unwrap(), 0x102);
+        #[cfg(any(armv7m, armv7em))]
+        assert_eq!(processor.cfsr.get_bit(20), true);
+    }
+
+    #[test]
+    fn test_sub_sp_below_stack_limit_faults() {
+        // Arrange
+        let mut processor = masking_test_processor(&[
+            0xb084, // sub sp, #16
+            0xf5ad, 0x7d80, // sub.w sp, sp, #256
+        ]);
+        processor.stack_limits(Some(0x2000_0f00), None);
+        processor.step_instruction();
+
+        // Act
+        processor.step_instruction();
+
+        // Assert
+        assert_eq!(processor.psr.get_isr_number(), Exception::HardFault.into());
+        assert_eq!(processor.read32(0x2000_0ff0 - 0x20 + 0x18).unwrap(), 0x102);
+        #[cfg(any(armv7m, armv7em))]
+        assert_eq!(processor.cfsr.get_bit(20), true);
+    }
+
+    #[test]
+    fn test_push_without_stack_limit() {
+        // Arrange
+        let mut processor = masking_test_processor(&[
+            0xb403, // push {r0, r1}
+            0xb5ff, // push {r0-r7, lr}
+        ]);
+
+        // Act
+        processor.step_instruction();
+        processor.step_instruction();
+
+        // Assert
+        assert_eq!(processor.psr.get_isr_number(), 0);
+        assert_eq!(processor.get_msp(), 0x2000_0ff8 - 0x24);
+    }
+
+    #[test]
+    fn test_exception_stacking_below_stack_limit_pends_fault() {
+        // Arrange
+        let mut processor = masking_test_processor(&[0xbf00]);
+        processor.stack_limits(Some(0x2000_0ff0), None);
+
+        // Act
+        processor.nvic_write_ispr(0, 1);
+        processor.step_instruction();
+
+        // Assert
+        assert_eq!(processor.psr.get_isr_number(), 16);
+        assert_eq!(processor.get_pc(), 0x200);
+        assert_eq!(processor.get_msp(), 0x2000_0ff0);
+        assert_eq!(processor.exception_pending(Exception::HardFault), true);
+        #[cfg(any(armv7m, armv7em))]
+        assert_eq!(processor.cfsr.get_bit(20), true);
+    }
+
+    #[test]
+    fn test_mrs_ipsr_reports_active_interrupt() {
+        // Arrange
+        let mut processor = handler_test_processor(
+            &[
+                0xbf00, // nop
+                0xf3ef, 0x8105, // mrs r1, ipsr
+                0xe7fe, // b .
+            ],
+            &[
+                0xf3ef, 0x8005, // mrs r0, ipsr
+                0x4770, // bx lr
+            ],
+            &[19],
+            0x2000_1000,
+        );
+        processor.set_r(Reg::R1, 0xff);
+        processor.nvic_write_iser(0, 1 << 3);
+
+        // Act
+        processor.nvic_write_ispr(0, 1 << 3);
+        processor.step_instruction();
+        processor.step_instruction();
+
+        // Assert
+        assert_eq!(processor.get_pc(), 0x204);
+        assert_eq!(processor.get_r(Reg::R0), 16 + 3);
+
+        // Act
+        processor.step_instruction();
+        processor.step_instruction();
+
+        // Assert
+        assert_eq!(processor.mode, ProcessorMode::ThreadMode);
+        assert_eq!(processor.get_pc(), 0x106);
+        assert_eq!(processor.get_r(Reg::R1), 0);
+    }
+
+    #[test]
+    fn test_zero_vector_enters_lockup() {
+        // Arrange
+        let mut processor = masking_test_processor(&[0xbf00, 0xbf00]);
+        processor.nvic_write_iser(0, 0b100);
+        processor.execution_priority = processor.get_execution_priority();
+
+        // Act
+        processor.nvic_write_ispr(0, 0b100);
+        let result = processor.step_instruction();
+
+        // Assert
+        let reason = Lockup::InvalidVector {
+            exception: Exception::Interrupt { n: 2 },
+            vector: Some(0),
+        };
+        assert_eq!(result, StepResult::Halted { cycles: 1 });
+        assert_eq!(processor.lockup(), Some(reason));
+        assert_eq!(processor.get_pc(), LOCKUP_ADDRESS);
+        assert_eq!(reason.to_string(), "lockup: invalid IRQ2 vector 0x00000000");
+
+        // Act
+        let result = processor.step_instruction();
+
+        // Assert
+        assert_eq!(result, StepResult::Halted { cycles: 0 });
+        assert_eq!(processor.get_pc(), LOCKUP_ADDRESS);
+    }
+
+    #[test]
+    fn test_fault_in_hardfault_handler_enters_lockup() {
+        // Arrange
+        let mut code = vec![0_u8; 0x300];
+        code[12..16].copy_from_slice(&0x0000_0201_u32.to_le_bytes());
+        code[0x100..0x102].copy_from_slice(&0xde00_u16.to_le_bytes()); // udf #0
+        code[0x200..0x202].copy_from_slice(&0xde00_u16.to_le_bytes()); // udf #0
+        let mut processor = Processor::new();
+        processor.flash_memory(code.len(), &code);
+        processor.set_msp(0x2000_1000);
+        processor.set_pc(0x100);
+        processor.execution_priority = processor.get_execution_priority();
+
+        // Act
+        let exit = processor.run(100, &HashSet::new());
+
+        // Assert
+        assert_eq!(
+            exit,
+            RunExit::Fault {
+                fault: Fault::UndefInstr
+            }
+        );
+        assert_eq!(processor.get_pc(), 0x200);
+
+        // Act
+        let exit = processor.run(100, &HashSet::new());
+
+        // Assert
+        let reason = Lockup::NestedFault {
+            fault: Fault::UndefInstr,
+        };
+        assert_eq!(exit, RunExit::Lockup { reason });
+        assert_eq!(processor.get_pc(), LOCKUP_ADDRESS);
+        assert_eq!(processor.exception_active(Exception::HardFault), true);
+        assert_eq!(
+            reason.to_string(),
+            "lockup: UndefInstr fault at negative priority"
+        );
+    }
+
+    #[test]
+    fn test_stacking_bus_error_is_derived_fault() {
+        // Arrange
+        let mut processor = masking_test_processor(&[0xbf00, 0xbf00]);
+        processor.set_msp(0x6000_1000);
+        processor.execution_priority = processor.get_execution_priority();
+
+        // Act
+        processor.nvic_write_ispr(0, 1);
+        processor.step_instruction();
+
+        // Assert: the interrupt is taken, the escalated stacking error pends
+        assert_eq!(processor.lockup(), None);
+        assert_eq!(processor.psr.get_isr_number(), 16);
+        assert_eq!(processor.get_pc(), 0x200);
+        assert_eq!(processor.exception_pending(Exception::HardFault), true);
+        #[cfg(any(armv7m, armv7em))]
+        {
+            assert_eq!(processor.cfsr.get_bit(12), true); // STKERR
+            assert_eq!(processor.cfsr.get_bit(15), false); // BFARVALID
+            assert_eq!(processor.hfsr.get_bit(30), true); // FORCED
+        }
+    }
+
+    #[cfg(any(armv7m, armv7em))]
+    #[test]
+    fn test_stacking_bus_error_taken_as_bus_fault() {
+        // Arrange
+        let mut processor = masking_test_processor(&[0xbf00, 0xbf00]);
+        processor.set_msp(0x6000_1000);
+        processor.shcsr.set_bit(17, true); // BUSFAULTENA
+        processor.execution_priority = processor.get_execution_priority();
+
+        // Act
+        processor.nvic_write_ispr(0, 1);
+        processor.step_instruction();
+
+        // Assert
+        assert_eq!(processor.lockup(), None);
+        assert_eq!(processor.exception_pending(Exception::BusFault), true);
+        assert_eq!(processor.exception_pending(Exception::HardFault), false);
+        assert_eq!(processor.cfsr.get_bit(12), true);
+        assert_eq!(processor.hfsr.get_bit(30), false);
+    }
+
+    #[test]
+    fn test_stacking_error_in_hardfault_handler_enters_lockup() {
+        // Arrange
+        let mut processor = masking_test_processor(&[0xbf00]);
+        processor.set_exception_pending(Exception::HardFault);
+        processor.check_exceptions();
+        processor.set_msp(0x6000_1000);
+
+        // Act
+        processor.set_exception_pending(Exception::NMI);
+        processor.check_exceptions();
+
+        // Assert
+        let reason = Lockup::NestedFault {
+            fault: Fault::Stkerr,
+        };
+        assert_eq!(processor.lockup(), Some(reason));
+        assert_eq!(processor.get_pc(), LOCKUP_ADDRESS);
+    }
 }
@@ -5,23 +5,34 @@
 use crate::bus::Bus;
 use crate::core::bits::Bits;
 use crate::core::condition::Condition;
-use crate::core::exception::Exception;
-use crate::core::exception::ExceptionHandling;
+use crate::core::exception::{Exception, ExceptionHandling, Lockup};
 use crate::core::fault::Fault;
-use crate::core::instruction::{Imm32Carry, Instruction, SRType, SetFlags};
+use crate::core::fetch::Fetch;
+use crate::core::fpu::{fp_add, fp_div, fp_mul, fp_sub, fp_to_int, int_to_fp};
+use crate::core::instruction::{instruction_size, Imm32Carry, Instruction, SRType, SetFlags};
 use crate::core::monitor::Monitor;
 use crate::core::operation::condition_test;
 use crate::core::operation::{
     add_with_carry, ror, shift, shift_c, sign_extend, zero_extend, zero_extend_u16,
 };
 use crate::core::register::{Apsr, BaseReg, Reg};
+use crate::core::reset::Reset;
+use crate::core::timing::{DefaultTiming, TimingModel};
+use crate::core::trace::TraceEvent;
+use crate::decoder::Decoder;
 
 use super::register::{ExtensionReg, ExtensionRegOperations};
-use crate::peripheral::{dwt::Dwt, systick::SysTick};
+use crate::peripheral::clock::Clocked;
+#[cfg(any(armv7m, armv7em))]
+use crate::peripheral::scb::SystemControlBlock;
+use crate::peripheral::mpu::{Mpu, MpuAccess, MPU_CTRL_ENABLE};
+use crate::peripheral::scb::{DEMCR_MON_EN, DHCSR_C_HALT, DHCSR_C_STEP};
+use crate::semihosting::console_write;
 use crate::semihosting::decode_semihostcmd;
 use crate::semihosting::semihost_return;
 use crate::Processor;
 use crate::{memory::map::MapMemory, ProcessorMode};
+use std::collections::HashSet;
 
 ///
 /// Stepping processor with instructions
@@ -41,6 +52,112 @@ pub trait Executor {
     /// Execute given instruction. Returns number of clock cycles burn.
     ///
     fn execute(&mut self, instruction: &Instruction, instruction_size: usize) -> u32;
+
+    ///
+    /// Run processor forward one instruction like `step` and report what
+    /// it did. A sleeping core advances one cycle, a stopped one none.
+    ///
+    fn step_instruction(&mut self) -> StepResult;
+
+    ///
+    /// Run until `max_cycles` have been simulated, PC hits one of the
+    /// `breakpoints`, the simulation is halted or a fault occurs.
+    ///
+    /// Breakpoints are checked before the instruction at PC executes, so a
+    /// breakpoint at the starting PC stops the run at once. Resuming with
+    /// PC still at the breakpoint the previous run stopped at executes that
+    /// instruction first.
+    ///
+    fn run(&mut self, max_cycles: u64, breakpoints: &HashSet<u32>) -> RunExit;
+}
+
+#[derive(PartialEq, Debug, Copy, Clone)]
+///
+/// Reason for `Executor::run` to return
+///
+pub enum RunExit {
+    /// The cycle budget was used
+    CycleBudget,
+    /// PC reached a breakpoint address
+    Breakpoint {
+        /// the breakpoint address
+        address: u32,
+    },
+    /// The simulation was halted
+    Halted,
+    /// The program exited via semihosting
+    Exited {
+        /// the exit code reported by the program
+        code: u32,
+    },
+    /// An instruction faulted, the fault exception was entered
+    Fault {
+        /// the fault raised
+        fault: Fault,
+    },
+    /// The processor entered the lockup state
+    Lockup {
+        /// why the processor locked up
+        reason: Lockup,
+    },
+    /// The core was halted for debugging, see `Processor::halt`
+    DebugHalt,
+    /// A BKPT instruction other than a semihosting call halted the processor
+    BreakpointHit {
+        /// address of the BKPT instruction
+        address: u32,
+        /// the BKPT immediate
+        imm: u8,
+    },
+}
+
+#[derive(PartialEq, Debug, Copy, Clone)]
+///
+/// Outcome of stepping a single instruction
+///
+pub enum StepResult {
+    /// The instruction was executed, or skipped as its condition did not pass
+    Executed {
+        /// number of clock cycles burn
+        cycles: u32,
+    },
+    /// The instruction branched to a new address
+    Branched {
+        /// number of clock cycles burn
+        cycles: u32,
+    },
+    /// The instruction faulted and the fault exception was entered
+    Faulted {
+        /// the fault raised
+        fault: Fault,
+        /// number of clock cycles burn
+        cycles: u32,
+    },
+    /// The simulation was halted, for example by a semihosting exit request
+    Halted {
+        /// number of clock cycles burn
+        cycles: u32,
+    },
+    /// The core was sleeping and no instruction was executed
+    Sleeping {
+        /// number of clock cycles burn
+        cycles: u32,
+    },
+}
+
+impl StepResult {
+    ///
+    /// Number of clock cycles burn by the step
+    ///
+    pub fn cycles(&self) -> u32 {
+        match *self {
+            Self::Executed { cycles }
+            | Self::Branched { cycles }
+            | Self::Faulted { cycles, .. }
+            | Self::Halted { cycles }
+            | Self::Sleeping { cycles } => cycles,
+        }
+    }
 }
 
 trait ExecutorHelper {
@@ -51,22 +168,72 @@ trait ExecutorHelper {
     fn it_advance(&mut self);
     fn in_it_block(&self) -> bool;
     fn last_in_it_block(&self) -> bool;
+    fn check_aligned_access(&self, address: u32, size: u32) -> Result<(), Fault>;
+    fn check_unaligned_access(&self, address: u32, size: u32) -> Result<(), Fault>;
+    fn execute_fp_check(&mut self) -> Result<(), Fault>;
+    fn literal_address(&self, imm32: u32, add: bool) -> u32;
     fn execute_internal(&mut self, instruction: &Instruction) -> Result<ExecuteResult, Fault>;
+    fn execute_step(&mut self, instruction: &Instruction, instruction_size: usize) -> StepResult;
+    fn enter_fault(&mut self, fault: Fault);
+    fn debug_step_halt(&mut self);
+    fn fetch_decode(&mut self, pc: u32) -> Result<(Instruction, usize), Fault>;
 }
 
 #[derive(PartialEq, Debug, Copy, Clone)]
-enum ExecuteResult {
+///
+/// Outcome of executing a single instruction
+///
+/// The cycle counts are the executor's own estimates. The cycles a step
+/// reports come from the timing model of the processor.
+///
+pub enum ExecuteResult {
     /// The instruction was taken normally
-    Taken { cycles: u32 },
+    Taken {
+        /// number of clock cycles burn
+        cycles: u32,
+    },
     /// The instruction was not taken as the condition did not pass
     NotTaken,
     /// The execution branched to a new address, pc was set accordingly
-    Branched { cycles: u32 },
+    Branched {
+        /// number of clock cycles burn
+        cycles: u32,
+    },
+    /// The instruction raised a fault, to be handled by the exception machinery
+    Fault {
+        /// the fault raised
+        fault: Fault,
+    },
 }
 
+///
+/// Custom instruction handler, called before the built-in executor.
+///
+/// Returning `Some` result skips the built-in execution of the instruction,
+/// `None` lets the instruction execute normally.
+///
+pub type InstructionHook = Box<dyn FnMut(&mut Processor, &Instruction) -> Option<ExecuteResult>>;
+
+///
+/// Trace handler, called after each executed instruction with the address
+/// of the instruction, the instruction and the number of cycles it burn.
+///
+pub type TraceHook = Box<dyn FnMut(u32, &Instruction, u32)>;
+
+///
+/// Supervisor call handler, called with the immediate of an executed `SVC`
+/// instruction. The handler emulates the call on the host side, in place of
+/// the `SVCall` exception handler of the firmware.
+///
+pub type SvcHook = Box<dyn FnMut(&mut Processor, u8)>;
+
 #[inline(always)]
 fn resolve_addressing(rn: u32, imm32: u32, add: bool, index: bool) -> (u32, u32) {
-    let offset_address = if add { rn + imm32 } else { rn - imm32 };
+    let offset_address = if add {
+        rn.wrapping_add(imm32)
+    } else {
+        rn.wrapping_sub(imm32)
+    };
     let address = if index { offset_address } else { rn };
     (address, offset_address)
 }
@@ -92,6 +259,15 @@ fn conditional_setflags(setflags: SetFlags, in_it_block: bool) -> bool {
     }
 }
 
+impl Processor {
+    ///
+    /// Check if the current execution is privileged, handler mode always is
+    ///
+    pub(crate) fn current_mode_is_privileged(&self) -> bool {
+        self.mode == ProcessorMode::HandlerMode || !self.control.n_priv
+    }
+}
+
 impl ExecutorHelper for Processor {
     fn set_itstate(&mut self, state: u8) {
         self.itstate = state;
@@ -119,6 +295,54 @@ impl ExecutorHelper for Processor {
         true
     }
 
+    // MemA accesses (LDM, STM, LDRD, LDREX, ...) must always be aligned
+    fn check_aligned_access(&self, address: u32, size: u32) -> Result<(), Fault> {
+        if address & (size - 1) == 0 {
+            Ok(())
+        } else {
+            Err(Fault::Unaligned)
+        }
+    }
+
+    // MemU accesses (LDR, LDRH, STR, STRH, ...) can be unaligned unless
+    // CCR.UNALIGN_TRP is set. ARMv6-M does not support unaligned accesses.
+    fn check_unaligned_access(&self, address: u32, size: u32) -> Result<(), Fault> {
+        #[cfg(any(armv7m, armv7em))]
+        {
+            if !self.ccr.get_bit(3) {
+                return Ok(());
+            }
+        }
+        self.check_aligned_access(address, size)
+    }
+
+    fn literal_address(&self, imm32: u32, add: bool) -> u32 {
+        let base = self.get_r(Reg::PC) & 0xffff_fffc;
+        if add {
+            base.wrapping_add(imm32)
+        } else {
+            base.wrapping_sub(imm32)
+        }
+    }
+
+    fn execute_fp_check(&mut self) -> Result<(), Fault> {
+        // CPACR access rights of CP10, CP11 is required to match it
+        match self.cpacr.get_bits(20..22) {
+            0b11 => (),
+            0b01 if self.current_mode_is_privileged() => (),
+            _ => return Err(Fault::NoCp),
+        }
+
+        self.preserve_fp_state()?;
+
+        // FPCCR.ASPEN: first floating point instruction of a context
+        if self.fpccr.get_bit(31) && !self.control.fpca {
+            self.fpscr.set_bits(22..27, self.fpdscr.get_bits(22..27));
+            self.control.fpca = true;
+        }
+        Ok(())
+    }
+
     fn condition_passed(&mut self) -> bool {
         let itstate = self.itstate;
 
@@ -329,12 +553,11 @@ impl ExecutorHelper for Processor {
             }
             #[cfg(armv6m)]
             Instruction::CPS { im } => {
-                if *im {
-                    self.primask = true;
-                } else {
-                    self.primask = false;
+                // ignored when unprivileged
+                if self.current_mode_is_privileged() {
+                    self.primask = *im;
+                    self.execution_priority = self.get_execution_priority();
                 }
-                self.execution_priority = self.get_execution_priority();
                 Ok(ExecuteResult::Taken { cycles: 1 })
             }
 
@@ -344,6 +567,10 @@ impl ExecutorHelper for Processor {
                 affect_pri,
                 affect_fault,
             } => {
+                // ignored when unprivileged
+                if !self.current_mode_is_privileged() {
+                    return Ok(ExecuteResult::Taken { cycles: 1 });
+                }
                 if *im {
                     if *affect_pri {
                         self.primask = true;
@@ -363,6 +590,7 @@ impl ExecutorHelper for Processor {
                 Ok(ExecuteResult::Taken { cycles: 1 })
             }
             Instruction::CBZ { rn, nonzero, imm32 } => {
+                // CBZ (nonzero == false) branches on zero, CBNZ on non-zero
                 if nonzero ^ (self.get_r(*rn) == 0) {
                     let pc = self.get_r(Reg::PC);
                     self.branch_write_pc(pc + imm32);
@@ -438,9 +666,9 @@ impl ExecutorHelper for Processor {
                                 value.set_bit(0, self.faultmask);
                             }
                             0b100 => {
-                                //let ctrl = u8::from(self.control) as u32;
-                                //value.set_bits(0..2, ctrl);
-                                panic!("unimplemented CONTROL");
+                                value.set_bit(0, self.control.n_priv);
+                                value.set_bit(1, self.control.sp_sel);
+                                value.set_bit(2, self.control.fpca);
                             }
                             _ => (),
                         },
@@ -501,6 +729,7 @@ impl ExecutorHelper for Processor {
                                 if self.mode == ProcessorMode::ThreadMode {
                                     self.control.sp_sel = r_n.get_bit(1);
                                 }
+                                self.control.fpca = r_n.get_bit(2);
                             }
                             _ => (),
                         },
@@ -657,7 +886,7 @@ impl ExecutorHelper for Processor {
                 if self.condition_passed() {
                     let pc = self.get_r(Reg::PC);
                     self.set_r(Reg::LR, pc | 0x01);
-                    let target = ((pc as i32) + imm32) as u32;
+                    let target = pc.wrapping_add(*imm32 as u32);
                     self.branch_write_pc(target);
                     return Ok(ExecuteResult::Branched { cycles: 4 });
                 }
@@ -670,16 +899,47 @@ impl ExecutorHelper for Processor {
                     let r0 = self.get_r(Reg::R0);
                     let r1 = self.get_r(Reg::R1);
                     let semihost_cmd = decode_semihostcmd(r0, r1, self)?;
+                    self.trace(&TraceEvent::Semihosting {
+                        command: &semihost_cmd,
+                    });
 
-                    if let Some(sh_func) = &mut self.semihost_func {
-                        let semihost_response = (sh_func)(&semihost_cmd);
+                    let console_response = self
+                        .semihost_console
+                        .as_mut()
+                        .and_then(|console| console_write(console.as_mut(), &semihost_cmd));
+                    let semihost_response = match console_response {
+                        Some(response) => Some(response),
+                        None => self
+                            .semihost_func
+                            .as_mut()
+                            .map(|sh_func| sh_func(&semihost_cmd)),
+                    };
+                    if let Some(semihost_response) = semihost_response {
                         semihost_return(self, &semihost_response);
+                        if self.state & 1 == 0 {
+                            self.exit_code = semihost_cmd.exit_code();
+                        }
                     }
+                    return Ok(ExecuteResult::Taken { cycles: 1 });
                 }
-                Ok(ExecuteResult::Taken { cycles: 1 })
+
+                // the debug monitor returns to the BKPT instruction itself
+                self.dfsr.set_bit(1, true); // BKPT
+                if self.demcr & DEMCR_MON_EN != 0 {
+                    self.set_exception_pending(Exception::DebugMonitor);
+                } else {
+                    self.breakpoint_hit = Some(*imm32 as u8);
+                    self.state.set_bit(0, false);
+                }
+                Ok(ExecuteResult::Branched { cycles: 1 })
             }
 
-            Instruction::NOP { .. } => Ok(ExecuteResult::Taken { cycles: 1 }),
+            Instruction::NOP { .. } => {
+                if self.condition_passed() {
+                    return Ok(ExecuteResult::Taken { cycles: 1 });
+                }
+                Ok(ExecuteResult::NotTaken)
+            }
 
             Instruction::MUL {
                 rd,
@@ -981,12 +1241,14 @@ impl ExecutorHelper for Processor {
             Instruction::LDM {
                 registers,
                 rn,
+                wback,
                 thumb32,
             } => {
                 if self.condition_passed() {
                     let regs_size = 4 * (registers.len() as u32);
 
                     let mut address = self.get_r(*rn);
+                    self.check_aligned_access(address, 4)?;
 
                     let mut branched = false;
                     for reg in registers.iter() {
@@ -997,10 +1259,13 @@ impl ExecutorHelper for Processor {
                         } else {
                             self.set_r(reg, value);
                         }
-                        address += 4;
+                        address = address.wrapping_add(4);
                     }
 
-                    if !registers.contains(rn) {
+                    // With rn in the list the loaded value is kept. The 16 bit
+                    // encoding has no writeback then and for the 32 bit
+                    // encodings writeback would be UNPREDICTABLE.
+                    if *wback && !registers.contains(rn) {
                         self.add_r(*rn, regs_size);
                     }
                     let cc = 1 + registers.len() as u32;
@@ -1011,6 +1276,40 @@ impl ExecutorHelper for Processor {
                 }
                 Ok(ExecuteResult::NotTaken)
             }
+            Instruction::LDMDB {
+                registers,
+                rn,
+                wback,
+            } => {
+                if self.condition_passed() {
+                    let regs_size = 4 * (registers.len() as u32);
+
+                    let mut address = self.get_r(*rn).wrapping_sub(regs_size);
+                    self.check_aligned_access(address, 4)?;
+
+                    let mut branched = false;
+                    for reg in registers {
+                        let value = self.read32(address)?;
+                        if reg == Reg::PC {
+                            self.load_write_pc(value)?;
+                            branched = true;
+                        } else {
+                            self.set_r(reg, value);
+                        }
+                        address = address.wrapping_add(4);
+                    }
+
+                    if *wback && !registers.contains(rn) {
+                        self.sub_r(*rn, regs_size);
+                    }
+                    let cc = 1 + registers.len() as u32;
+                    if branched {
+                        return Ok(ExecuteResult::Branched { cycles: cc });
+                    }
+                    return Ok(ExecuteResult::Taken { cycles: cc });
+                }
+                Ok(ExecuteResult::NotTaken)
+            }
             Instruction::MOV_imm {
                 rd,
                 imm32,
@@ -1179,15 +1478,17 @@ impl ExecutorHelper for Processor {
                 if self.condition_passed() {
                     let regs_size = 4 * (registers.len() as u32);
                     let sp = self.get_r(Reg::SP);
-                    let mut address = sp - regs_size;
+                    let mut address = sp.wrapping_sub(regs_size);
+                    self.check_aligned_access(address, 4)?;
+                    self.check_stack_limit(address)?;
 
                     for reg in registers.iter() {
                         let value = self.get_r(reg);
                         self.write32(address, value)?;
-                        address += 4;
+                        address = address.wrapping_add(4);
                     }
 
-                    self.set_r(Reg::SP, sp - regs_size);
+                    self.set_r(Reg::SP, sp.wrapping_sub(regs_size));
                     return Ok(ExecuteResult::Taken {
                         cycles: 1 + registers.len() as u32,
                     });
@@ -1200,8 +1501,9 @@ impl ExecutorHelper for Processor {
                     let regs_size = 4 * (registers.len() as u32);
                     let sp = self.get_r(Reg::SP);
                     let mut address = sp;
+                    self.check_aligned_access(address, 4)?;
 
-                    self.set_r(Reg::SP, sp + regs_size);
+                    self.set_r(Reg::SP, sp.wrapping_add(regs_size));
 
                     for reg in registers.iter() {
                         let val = self.read32(address)?;
@@ -1210,7 +1512,7 @@ impl ExecutorHelper for Processor {
                         } else {
                             self.set_r(reg, val);
                         }
-                        address += 4;
+                        address = address.wrapping_add(4);
                     }
 
                     if registers.contains(&Reg::PC) {
@@ -1263,6 +1565,7 @@ impl ExecutorHelper for Processor {
                 if self.condition_passed() {
                     let (address, offset_address) =
                         resolve_addressing(self.get_r(*rn), *imm32, *add, *index);
+                    self.check_unaligned_access(address, 4)?;
 
                     let data = self.read32(address)?;
                     if *wback {
@@ -1282,6 +1585,7 @@ impl ExecutorHelper for Processor {
             Instruction::LDREX { rt, rn, imm32 } => {
                 if self.condition_passed() {
                     let (address, _) = resolve_addressing(self.get_r(*rn), *imm32, true, true);
+                    self.check_aligned_access(address, 4)?;
 
                     self.set_exclusive_monitors(address, 4);
 
@@ -1312,6 +1616,7 @@ impl ExecutorHelper for Processor {
             Instruction::LDREXH { rt, rn } => {
                 if self.condition_passed() {
                     let address = self.get_r(*rn);
+                    self.check_aligned_access(address, 2)?;
                     self.set_exclusive_monitors(address, 2);
 
                     let data = self.read16(address)?;
@@ -1337,6 +1642,7 @@ impl ExecutorHelper for Processor {
                 if self.condition_passed() {
                     let (address, offset_address) =
                         resolve_addressing(self.get_r(*rn), *imm32, *add, *index);
+                    self.check_unaligned_access(address, 2)?;
 
                     let data = self.read16(address)?;
                     if *wback {
@@ -1389,6 +1695,7 @@ impl ExecutorHelper for Processor {
 
                     let (address, offset_address) =
                         resolve_addressing(self.get_r(*rn), offset, *add, *index);
+                    self.check_unaligned_access(address, 4)?;
 
                     let data = self.read32(address)?;
                     if *wback {
@@ -1472,6 +1779,7 @@ impl ExecutorHelper for Processor {
                 if self.condition_passed() {
                     let (address, offset_address) =
                         resolve_addressing(self.get_r(*rn), *imm32, *add, *index);
+                    self.check_unaligned_access(address, 2)?;
 
                     let data = self.read16(address)?;
                     if *wback {
@@ -1501,6 +1809,7 @@ impl ExecutorHelper for Processor {
 
                     let (address, offset_address) =
                         resolve_addressing(self.get_r(*rn), offset, *add, *index);
+                    self.check_unaligned_access(address, 2)?;
 
                     let data = u32::from(self.read16(address)?);
                     if *wback {
@@ -1530,6 +1839,7 @@ impl ExecutorHelper for Processor {
 
                     let (address, offset_address) =
                         resolve_addressing(self.get_r(*rn), offset, *add, *index);
+                    self.check_unaligned_access(address, 2)?;
 
                     let data = u32::from(self.read16(address)?);
                     if *wback {
@@ -1637,11 +1947,16 @@ impl ExecutorHelper for Processor {
                     let regs_size = 4 * (registers.len() as u32);
 
                     let mut address = self.get_r(*rn);
+                    self.check_aligned_access(address, 4)?;
 
+                    // Writeback happens after all the stores, so rn in the list
+                    // stores its original value. That is the architected value
+                    // when rn is the lowest register. Otherwise the stored
+                    // value is UNKNOWN and the original value is used as well.
                     for reg in registers.iter() {
                         let r = self.get_r(reg);
                         self.write32(address, r)?;
-                        address += 4;
+                        address = address.wrapping_add(4);
                     }
 
                     if *wback {
@@ -1661,12 +1976,16 @@ impl ExecutorHelper for Processor {
                 if self.condition_passed() {
                     let regs_size = 4 * (registers.len() as u32);
 
-                    let mut address = self.get_r(*rn) - regs_size;
+                    let mut address = self.get_r(*rn).wrapping_sub(regs_size);
+                    self.check_aligned_access(address, 4)?;
+                    if *wback && *rn == Reg::SP {
+                        self.check_stack_limit(address)?;
+                    }
 
                     for reg in registers.iter() {
                         let r = self.get_r(reg);
                         self.write32(address, r)?;
-                        address += 4;
+                        address = address.wrapping_add(4);
                     }
 
                     if *wback {
@@ -1691,6 +2010,7 @@ impl ExecutorHelper for Processor {
                 if self.condition_passed() {
                     let (address, offset_address) =
                         resolve_addressing(self.get_r(*rn), *imm32, *add, *index);
+                    self.check_unaligned_access(address, 4)?;
 
                     let value = self.get_r(*rt);
                     if *wback {
@@ -1706,6 +2026,7 @@ impl ExecutorHelper for Processor {
             Instruction::STREX { rd, rt, rn, imm32 } => {
                 if self.condition_passed() {
                     let (address, _) = resolve_addressing(self.get_r(*rn), *imm32, true, true);
+                    self.check_aligned_access(address, 4)?;
 
                     if self.exclusive_monitors_pass(address, 4) {
                         self.write32(address, self.get_r(*rt))?;
@@ -1738,6 +2059,7 @@ impl ExecutorHelper for Processor {
             Instruction::STREXH { rd, rt, rn } => {
                 if self.condition_passed() {
                     let address = self.get_r(*rn);
+                    self.check_aligned_access(address, 2)?;
 
                     if self.exclusive_monitors_pass(address, 2) {
                         self.write16(address, self.get_r(*rt) as u16)?;
@@ -1763,11 +2085,12 @@ impl ExecutorHelper for Processor {
                 if self.condition_passed() {
                     let (address, offset_address) =
                         resolve_addressing(self.get_r(*rn), *imm32, *add, *index);
+                    self.check_aligned_access(address, 4)?;
 
                     let value1 = self.get_r(*rt);
                     self.write32(address, value1)?;
                     let value2 = self.get_r(*rt2);
-                    self.write32(address + 4, value2)?;
+                    self.write32(address.wrapping_add(4), value2)?;
 
                     if *wback {
                         self.set_r(*rn, offset_address);
@@ -1789,10 +2112,11 @@ impl ExecutorHelper for Processor {
                 if self.condition_passed() {
                     let (address, offset_address) =
                         resolve_addressing(self.get_r(*rn), *imm32, *add, *index);
+                    self.check_aligned_access(address, 4)?;
 
                     let data = self.read32(address)?;
                     self.set_r(*rt, data);
-                    let data2 = self.read32(address + 4)?;
+                    let data2 = self.read32(address.wrapping_add(4))?;
                     self.set_r(*rt2, data2);
 
                     if *wback {
@@ -1818,7 +2142,8 @@ impl ExecutorHelper for Processor {
                 if self.condition_passed() {
                     let c = self.psr.get_c();
                     let offset = shift(self.get_r(*rm), *shift_t, *shift_n as usize, c);
-                    let address = self.get_r(*rn) + offset;
+                    let address = self.get_r(*rn).wrapping_add(offset);
+                    self.check_unaligned_access(address, 4)?;
                     let value = self.get_r(*rt);
                     self.write32(address, value)?;
 
@@ -1841,7 +2166,7 @@ impl ExecutorHelper for Processor {
                 if self.condition_passed() {
                     let c = self.psr.get_c();
                     let offset = shift(self.get_r(*rm), *shift_t, *shift_n as usize, c);
-                    let address = self.get_r(*rn) + offset;
+                    let address = self.get_r(*rn).wrapping_add(offset);
                     let rt: u32 = self.get_r(*rt);
                     let value = rt.get_bits(0..8);
                     self.write8(address, value as u8)?;
@@ -1887,6 +2212,7 @@ impl ExecutorHelper for Processor {
                 if self.condition_passed() {
                     let (address, offset_address) =
                         resolve_addressing(self.get_r(*rn), *imm32, *add, *index);
+                    self.check_unaligned_access(address, 2)?;
 
                     let value = self.get_r(*rt);
                     self.write16(address, value.get_bits(0..16) as u16)?;
@@ -1914,7 +2240,8 @@ impl ExecutorHelper for Processor {
                 if self.condition_passed() {
                     let c = self.psr.get_c();
                     let offset = shift(self.get_r(*rm), *shift_t, *shift_n as usize, c);
-                    let address = self.get_r(*rn) + offset;
+                    let address = self.get_r(*rn).wrapping_add(offset);
+                    self.check_unaligned_access(address, 2)?;
                     let value = self.get_r(*rt).get_bits(0..16);
                     self.write16(address, value as u16)?;
                     return Ok(ExecuteResult::Taken { cycles: 2 });
@@ -1929,8 +2256,8 @@ impl ExecutorHelper for Processor {
                 thumb32,
             } => {
                 if self.condition_passed() {
-                    let base = self.get_r(Reg::PC) & 0xffff_fffc;
-                    let address = if *add { base + imm32 } else { base - imm32 };
+                    let address = self.literal_address(*imm32, *add);
+                    self.check_unaligned_access(address, 4)?;
                     let data = self.read32(address)?;
 
                     if rt == &Reg::PC {
@@ -1943,6 +2270,44 @@ impl ExecutorHelper for Processor {
                 }
                 Ok(ExecuteResult::NotTaken)
             }
+            Instruction::LDRB_lit { rt, imm32, add } => {
+                if self.condition_passed() {
+                    let address = self.literal_address(*imm32, *add);
+                    let data = self.read8(address)?;
+                    self.set_r(*rt, u32::from(data));
+                    return Ok(ExecuteResult::Taken { cycles: 2 });
+                }
+                Ok(ExecuteResult::NotTaken)
+            }
+            Instruction::LDRSB_lit { rt, imm32, add } => {
+                if self.condition_passed() {
+                    let address = self.literal_address(*imm32, *add);
+                    let data = self.read8(address)?;
+                    self.set_r(*rt, sign_extend(u32::from(data), 7, 32) as u32);
+                    return Ok(ExecuteResult::Taken { cycles: 2 });
+                }
+                Ok(ExecuteResult::NotTaken)
+            }
+            Instruction::LDRH_lit { rt, imm32, add } => {
+                if self.condition_passed() {
+                    let address = self.literal_address(*imm32, *add);
+                    self.check_unaligned_access(address, 2)?;
+                    let data = self.read16(address)?;
+                    self.set_r(*rt, u32::from(data));
+                    return Ok(ExecuteResult::Taken { cycles: 2 });
+                }
+                Ok(ExecuteResult::NotTaken)
+            }
+            Instruction::LDRSH_lit { rt, imm32, add } => {
+                if self.condition_passed() {
+                    let address = self.literal_address(*imm32, *add);
+                    self.check_unaligned_access(address, 2)?;
+                    let data = self.read16(address)?;
+                    self.set_r(*rt, sign_extend(u32::from(data), 15, 32) as u32);
+                    return Ok(ExecuteResult::Taken { cycles: 2 });
+                }
+                Ok(ExecuteResult::NotTaken)
+            }
 
             Instruction::ADD_reg {
                 rd,
@@ -2031,9 +2396,14 @@ impl ExecutorHelper for Processor {
                 Ok(ExecuteResult::NotTaken)
             }
 
-            Instruction::ADR { rd, imm32, thumb32 } => {
+            Instruction::ADR {
+                rd,
+                imm32,
+                add,
+                thumb32,
+            } => {
                 if self.condition_passed() {
-                    let result = (self.get_r(Reg::PC) & 0xffff_fffc) + imm32;
+                    let result = self.literal_address(*imm32, *add);
                     self.set_r(*rd, result);
                     return Ok(ExecuteResult::Taken { cycles: 1 });
                 }
@@ -2129,6 +2499,16 @@ impl ExecutorHelper for Processor {
                     let r_n = self.get_r(*rn);
                     let (result, carry, overflow) = add_with_carry(r_n, imm32 ^ 0xFFFF_FFFF, true);
 
+                    // Rd == PC is UNPREDICTABLE (CMP when setting flags),
+                    // branch like ADD (register) does
+                    if rd == &Reg::PC {
+                        self.branch_write_pc(result);
+                        return Ok(ExecuteResult::Branched { cycles: 3 });
+                    }
+                    if rd == &Reg::SP {
+                        // SUB SP, SP, #imm allocates the stack frame
+                        self.check_stack_limit(result)?;
+                    }
                     if conditional_setflags(*setflags, self.in_it_block()) {
                         self.psr.set_n(result);
                         self.psr.set_z(result);
@@ -2153,12 +2533,19 @@ impl ExecutorHelper for Processor {
             } => {
                 if self.condition_passed() {
                     let r_n = self.get_r(*rn);
-                    let r_m = self.get_r(*rm);
                     let c = self.psr.get_c();
                     let shifted = shift(self.get_r(*rm), *shift_t, *shift_n as usize, c);
 
                     let (result, carry, overflow) =
                         add_with_carry(r_n, shifted ^ 0xFFFF_FFFF, true);
+                    // UNPREDICTABLE, see SUB (immediate)
+                    if rd == &Reg::PC {
+                        self.branch_write_pc(result);
+                        return Ok(ExecuteResult::Branched { cycles: 3 });
+                    }
+                    if rd == &Reg::SP {
+                        self.check_stack_limit(result)?;
+                    }
                     self.set_r(*rd, result);
 
                     if conditional_setflags(*setflags, self.in_it_block()) {
@@ -2173,10 +2560,12 @@ impl ExecutorHelper for Processor {
             }
             Instruction::TBB { rn, rm } => {
                 if self.condition_passed() {
+                    // with rn == PC the table follows this (32-bit) instruction,
+                    // get_r(PC) already includes the +4 read offset
                     let r_n = self.get_r(*rn);
                     let r_m = self.get_r(*rm);
                     let pc = self.get_r(Reg::PC);
-                    let halfwords = u32::from(self.read8(r_n + r_m)?);
+                    let halfwords = u32::from(self.read8(r_n.wrapping_add(r_m))?);
 
                     self.branch_write_pc(pc + 2 * halfwords);
 
@@ -2189,7 +2578,9 @@ impl ExecutorHelper for Processor {
                     let r_n = self.get_r(*rn);
                     let r_m = self.get_r(*rm);
                     let pc = self.get_r(Reg::PC);
-                    let halfwords = u32::from(self.read16(r_n + (r_m << 1))?);
+                    let address = r_n.wrapping_add(r_m << 1);
+                    self.check_unaligned_access(address, 2)?;
+                    let halfwords = u32::from(self.read16(address)?);
 
                     self.branch_write_pc(pc + 2 * halfwords);
 
@@ -2287,7 +2678,9 @@ impl ExecutorHelper for Processor {
                         let data = self.get_r(*rn).get_bits(*lsb..upper);
                         self.set_r(*rd, data);
                     } else {
-                        panic!();
+                        return Ok(ExecuteResult::Fault {
+                            fault: Fault::UndefInstr,
+                        });
                     }
 
                     return Ok(ExecuteResult::Taken { cycles: 1 });
@@ -2445,14 +2838,18 @@ impl ExecutorHelper for Processor {
             }
             Instruction::SVC { imm32 } => {
                 if self.condition_passed() {
-                    println!("SVC {}", imm32);
+                    if let Some(mut hook) = self.svc_hook.take() {
+                        hook(self, *imm32 as u8);
+                        self.svc_hook = Some(hook);
+                    } else {
+                        self.set_exception_pending(Exception::SVCall);
+                    }
                     return Ok(ExecuteResult::Taken { cycles: 1 });
                 }
                 Ok(ExecuteResult::NotTaken)
             }
             Instruction::SEV { .. } => {
                 if self.condition_passed() {
-                    println!("SEV");
                     return Ok(ExecuteResult::Taken { cycles: 1 });
                 }
                 Ok(ExecuteResult::NotTaken)
@@ -2475,40 +2872,15 @@ impl ExecutorHelper for Processor {
             }
 
             // ARMv7-M
-            Instruction::MCR {
-                rt,
-                coproc,
-                opc1,
-                opc2,
-                crn,
-                crm,
-            } => unimplemented!(),
-
-            // ARMv7-M
-            Instruction::MCR2 {
-                rt,
-                coproc,
-                opc1,
-                opc2,
-                crn,
-                crm,
-            } => unimplemented!(),
-
-            // ARMv7-M
-            Instruction::LDC_imm {
-                coproc,
-                imm32,
-                crd,
-                rn,
-            } => unimplemented!(),
-
-            // ARMv7-M
-            Instruction::LDC2_imm {
-                coproc,
-                imm32,
-                crd,
-                rn,
-            } => unimplemented!(),
+            Instruction::MCR { .. }
+            | Instruction::MCR2 { .. }
+            | Instruction::LDC_imm { .. }
+            | Instruction::LDC2_imm { .. } => {
+                if self.condition_passed() {
+                    return Ok(ExecuteResult::Fault { fault: Fault::NoCp });
+                }
+                Ok(ExecuteResult::NotTaken)
+            }
 
             // ARMv7-M
             Instruction::UDIV { rd, rn, rm } => {
@@ -2686,12 +3058,60 @@ impl ExecutorHelper for Processor {
             }
 
             // ARMv7-M
-            Instruction::SMLAL { rdlo, rdhi, rn, rm } => unimplemented!(),
-
-            Instruction::UDF { imm32, opcode, .. } => {
-                println!("UDF {}, {}", imm32, opcode);
-                panic!("undefined");
-                //Some(Fault::UndefinedInstruction)
+            Instruction::SMLAL { .. } => Ok(ExecuteResult::Fault {
+                fault: Fault::UndefInstr,
+            }),
+
+            Instruction::UDF { .. } => Ok(ExecuteResult::Fault {
+                fault: Fault::UndefInstr,
+            }),
+            Instruction::VADD_f32 { sd, sn, sm } => {
+                if self.condition_passed() {
+                    self.execute_fp_check()?;
+                    let (result, exceptions) =
+                        fp_add(self.get_sr(*sn), self.get_sr(*sm), self.fpscr);
+                    self.set_sr(*sd, result);
+                    self.fpscr |= exceptions;
+                    return Ok(ExecuteResult::Taken { cycles: 1 });
+                }
+                Ok(ExecuteResult::NotTaken)
+            }
+            Instruction::VCVT_f32_to_int {
+                sd,
+                sm,
+                unsigned,
+                round_zero,
+            } => {
+                if self.condition_passed() {
+                    self.execute_fp_check()?;
+                    let (result, exceptions) =
+                        fp_to_int(self.get_sr(*sm), *unsigned, *round_zero, self.fpscr);
+                    self.set_sr(*sd, result);
+                    self.fpscr |= exceptions;
+                    return Ok(ExecuteResult::Taken { cycles: 1 });
+                }
+                Ok(ExecuteResult::NotTaken)
+            }
+            Instruction::VCVT_int_to_f32 { sd, sm, unsigned } => {
+                if self.condition_passed() {
+                    self.execute_fp_check()?;
+                    let (result, exceptions) = int_to_fp(self.get_sr(*sm), *unsigned, self.fpscr);
+                    self.set_sr(*sd, result);
+                    self.fpscr |= exceptions;
+                    return Ok(ExecuteResult::Taken { cycles: 1 });
+                }
+                Ok(ExecuteResult::NotTaken)
+            }
+            Instruction::VDIV_f32 { sd, sn, sm } => {
+                if self.condition_passed() {
+                    self.execute_fp_check()?;
+                    let (result, exceptions) =
+                        fp_div(self.get_sr(*sn), self.get_sr(*sm), self.fpscr);
+                    self.set_sr(*sd, result);
+                    self.fpscr |= exceptions;
+                    return Ok(ExecuteResult::Taken { cycles: 14 });
+                }
+                Ok(ExecuteResult::NotTaken)
             }
             Instruction::VLDR {
                 dd,
@@ -2701,14 +3121,19 @@ impl ExecutorHelper for Processor {
                 single_reg,
             } => {
                 if self.condition_passed() {
-                    //self.execute_fp_check();
+                    self.execute_fp_check()?;
 
                     let base = match *rn {
                         Reg::PC => self.get_r(Reg::PC) & 0xffff_fffc,
                         _ => self.get_r(*rn),
                     };
 
-                    let address = if *add { base + imm32 } else { base - imm32 };
+                    let address = if *add {
+                        base.wrapping_add(*imm32)
+                    } else {
+                        base.wrapping_sub(*imm32)
+                    };
+                    self.check_aligned_access(address, 4)?;
                     match *dd {
                         ExtensionReg::Single { reg } => {
                             let data = self.read32(address)?;
@@ -2716,11 +3141,81 @@ impl ExecutorHelper for Processor {
                         }
                         ExtensionReg::Double { reg } => {
                             let word1 = self.read32(address)?;
-                            let word2 = self.read32(address + 4)?;
+                            let word2 = self.read32(address.wrapping_add(4))?;
                             self.set_dr(reg, word1, word2);
                         }
                     }
 
+                    return Ok(ExecuteResult::Taken {
+                        cycles: if *single_reg { 2 } else { 3 },
+                    });
+                }
+                Ok(ExecuteResult::NotTaken)
+            }
+            Instruction::VMOV_cr_sp {
+                to_arm_register,
+                rt,
+                sn,
+            } => {
+                if self.condition_passed() {
+                    self.execute_fp_check()?;
+                    if *to_arm_register {
+                        let value = self.get_sr(*sn);
+                        self.set_r(*rt, value);
+                    } else {
+                        let value = self.get_r(*rt);
+                        self.set_sr(*sn, value);
+                    }
+                    return Ok(ExecuteResult::Taken { cycles: 1 });
+                }
+                Ok(ExecuteResult::NotTaken)
+            }
+            Instruction::VMOV_cr2_dp {
+                to_arm_registers,
+                rt,
+                rt2,
+                dm,
+            } => {
+                if self.condition_passed() {
+                    self.execute_fp_check()?;
+                    if *to_arm_registers {
+                        let (low_word, high_word) = self.get_dr(*dm);
+                        self.set_r(*rt, low_word);
+                        self.set_r(*rt2, high_word);
+                    } else {
+                        let low_word = self.get_r(*rt);
+                        let high_word = self.get_r(*rt2);
+                        self.set_dr(*dm, low_word, high_word);
+                    }
+                    return Ok(ExecuteResult::Taken { cycles: 2 });
+                }
+                Ok(ExecuteResult::NotTaken)
+            }
+            Instruction::VMOV_reg_f32 { sd, sm } => {
+                if self.condition_passed() {
+                    self.execute_fp_check()?;
+                    let value = self.get_sr(*sm);
+                    self.set_sr(*sd, value);
+                    return Ok(ExecuteResult::Taken { cycles: 1 });
+                }
+                Ok(ExecuteResult::NotTaken)
+            }
+            Instruction::VMOV_reg_f64 { dd, dm } => {
+                if self.condition_passed() {
+                    self.execute_fp_check()?;
+                    let (low_word, high_word) = self.get_dr(*dm);
+                    self.set_dr(*dd, low_word, high_word);
+                    return Ok(ExecuteResult::Taken { cycles: 1 });
+                }
+                Ok(ExecuteResult::NotTaken)
+            }
+            Instruction::VMUL_f32 { sd, sn, sm } => {
+                if self.condition_passed() {
+                    self.execute_fp_check()?;
+                    let (result, exceptions) =
+                        fp_mul(self.get_sr(*sn), self.get_sr(*sm), self.fpscr);
+                    self.set_sr(*sd, result);
+                    self.fpscr |= exceptions;
                     return Ok(ExecuteResult::Taken { cycles: 1 });
                 }
                 Ok(ExecuteResult::NotTaken)
@@ -2733,11 +3228,16 @@ impl ExecutorHelper for Processor {
                 single_reg,
             } => {
                 if self.condition_passed() {
-                    //self.execute_fp_check();
+                    self.execute_fp_check()?;
 
                     let base = self.get_r(*rn);
 
-                    let address = if *add { base + imm32 } else { base - imm32 };
+                    let address = if *add {
+                        base.wrapping_add(*imm32)
+                    } else {
+                        base.wrapping_sub(*imm32)
+                    };
+                    self.check_aligned_access(address, 4)?;
                     match *dd {
                         ExtensionReg::Single { reg } => {
                             let value = self.get_sr(reg);
@@ -2746,75 +3246,288 @@ impl ExecutorHelper for Processor {
                         ExtensionReg::Double { reg } => {
                             let (low_word, high_word) = self.get_dr(reg);
                             self.write32(address, low_word)?;
-                            self.write32(address + 4, high_word)?;
+                            self.write32(address.wrapping_add(4), high_word)?;
                         }
                     }
 
+                    return Ok(ExecuteResult::Taken {
+                        cycles: if *single_reg { 2 } else { 3 },
+                    });
+                }
+                Ok(ExecuteResult::NotTaken)
+            }
+            Instruction::VSUB_f32 { sd, sn, sm } => {
+                if self.condition_passed() {
+                    self.execute_fp_check()?;
+                    let (result, exceptions) =
+                        fp_sub(self.get_sr(*sn), self.get_sr(*sm), self.fpscr);
+                    self.set_sr(*sd, result);
+                    self.fpscr |= exceptions;
                     return Ok(ExecuteResult::Taken { cycles: 1 });
                 }
                 Ok(ExecuteResult::NotTaken)
             }
         }
     }
-}
-
-impl Executor for Processor {
     #[inline(always)]
-    fn step_sleep(&mut self) {
-        self.syst_step(1);
-        self.check_exceptions();
-        self.dwt_tick(1);
+    fn debug_step_halt(&mut self) {
+        // a single step request halts the core again after one instruction,
+        // which may have entered an exception handler
+        if self.dhcsr & DHCSR_C_STEP != 0 && self.dhcsr & DHCSR_C_HALT == 0 {
+            self.dhcsr |= DHCSR_C_HALT;
+            self.dfsr.set_bit(0, true); // HALTED
+        }
     }
 
-    #[inline(always)]
-    fn step(&mut self) {
-        let pc = self.get_pc();
-        let mapped_pc = (self.map_address(pc) >> 1) as usize;
-        let (instruction, instruction_size) = self.instruction_cache[mapped_pc];
-        let count = self.execute(&instruction, instruction_size);
-        self.cycle_count += u64::from(count);
-        self.dwt_tick(count);
-        self.syst_step(count);
-        self.check_exceptions();
-        //TODO exception entry also burns cycles that should be accounted for
-        //DWT and SYST ticking
+    fn enter_fault(&mut self, fault: Fault) {
+        let new_pc = self.get_pc();
+
+        let exception = self.fault_exception(fault);
+        if exception == Exception::HardFault && self.execution_priority < 0 {
+            // hard fault cannot preempt itself, NMI or FAULTMASK
+            self.enter_lockup(Lockup::NestedFault { fault });
+            return;
+        }
+        #[cfg(any(armv7m, armv7em))]
+        self.set_fault_status(fault, exception);
+
+        //TODO: cycles not correctly accumulated yet for exception entry
+        if let Err(fault) = self.exception_entry(exception, new_pc) {
+            self.enter_lockup(Lockup::NestedFault { fault });
+        }
     }
 
     #[inline(always)]
-    fn execute(&mut self, instruction: &Instruction, instruction_size: usize) -> u32 {
-        self.instruction_count += 1;
-
-        let in_it_block = self.in_it_block();
+    fn fetch_decode(&mut self, pc: u32) -> Result<(Instruction, usize), Fault> {
+        let mpu_enabled = self.mpu_ctrl & MPU_CTRL_ENABLE != 0;
+        if mpu_enabled {
+            self.mpu_check(pc, 2, MpuAccess::Execute)?;
+        }
 
-        match self.execute_internal(&instruction) {
-            Err(_fault) => {
-                // all faults are mapped to hardfaults on armv6m
-                let new_pc = self.get_pc();
+        // the code region starts at address zero, one entry per halfword
+        let index = (self.map_address(pc) >> 1) as usize;
+        let cached = self
+            .decode_cache
+            .as_ref()
+            .and_then(|cache| cache.get(index).copied().flatten());
 
-                //TODO: map to correct exception
-                //TODO: cycles not correctly accumulated yet for exception entry
-                self.exception_entry(Exception::HardFault, new_pc)
-                    .expect("error handling on exception entry not implemented");
+        let decoded = if let Some(decoded) = cached {
+            decoded
+        } else {
+            let instruction = self.decode(self.fetch(pc)?);
+            let decoded = (instruction, instruction_size(&instruction));
+            if let Some(entry) = self
+                .decode_cache
+                .as_mut()
+                .and_then(|cache| cache.get_mut(index))
+            {
+                *entry = Some(decoded);
+            }
+            decoded
+        };
+
+        // the second halfword of a 32-bit instruction may be in another region
+        if mpu_enabled && decoded.1 == 4 {
+            self.mpu_check(pc.wrapping_add(2), 2, MpuAccess::Execute)?;
+        }
+        Ok(decoded)
+    }
+
+    #[inline(always)]
+    fn execute_step(&mut self, instruction: &Instruction, instruction_size: usize) -> StepResult {
+        self.instruction_count += 1;
+
+        let pc = self.get_pc();
+        // instruction fetch, bus reads of the instruction add to this
+        self.wait_states.set(self.memory_wait_states(pc));
+
+        let in_it_block = self.in_it_block();
+
+        let hook_result = if let Some(mut hook) = self.instruction_hook.take() {
+            let result = hook(self, instruction);
+            self.instruction_hook = Some(hook);
+            result
+        } else {
+            None
+        };
+
+        let hooked = hook_result.is_some();
+        let result = match hook_result {
+            Some(result) => Ok(result),
+            None => self.execute_internal(&instruction),
+        };
+
+        let wait_states = self.wait_states.get();
+        let cycles = match (&result, &self.timing_model) {
+            (Ok(result), Some(model)) => model.cycles(instruction, result, wait_states),
+            // the default timing does not know about instructions implemented by hooks
+            (Ok(ExecuteResult::Taken { cycles } | ExecuteResult::Branched { cycles }), None)
+                if hooked =>
+            {
+                cycles + wait_states
+            }
+            (Ok(result), None) => DefaultTiming::default().cycles(instruction, result, wait_states),
+            (Err(_), _) => 0,
+        };
+
+        let step_result = match result {
+            Err(fault) | Ok(ExecuteResult::Fault { fault }) => {
+                self.enter_fault(fault);
                 //TODO: proper amount of cycles calcuation
-                12
+                StepResult::Faulted { fault, cycles: 12 }
             }
             Ok(ExecuteResult::NotTaken) => {
                 self.add_pc(instruction_size as u32);
                 if in_it_block {
                     self.it_advance();
                 }
-                1
+                StepResult::Executed { cycles }
+            }
+            Ok(ExecuteResult::Branched { .. }) => {
+                // a branch may only be the last instruction of an IT block
+                if in_it_block {
+                    self.it_advance();
+                }
+                StepResult::Branched { cycles }
             }
-            Ok(ExecuteResult::Branched { cycles }) => cycles,
-            Ok(ExecuteResult::Taken { cycles }) => {
+            Ok(ExecuteResult::Taken { .. }) => {
                 self.add_pc(instruction_size as u32);
 
                 if in_it_block {
                     self.it_advance();
                 }
-                cycles
+                StepResult::Executed { cycles }
+            }
+        };
+
+        if let Some(trace) = self.trace_hook.as_mut() {
+            trace(pc, instruction, step_result.cycles());
+        }
+        if let (true, StepResult::Executed { cycles } | StepResult::Branched { cycles }) =
+            (self.trace_sink.is_some(), step_result)
+        {
+            self.trace(&TraceEvent::InstructionRetired {
+                address: pc,
+                instruction,
+                cycles,
+            });
+        }
+        step_result
+    }
+}
+
+impl Executor for Processor {
+    #[inline(always)]
+    fn step_sleep(&mut self) {
+        if self.is_halted() {
+            return;
+        }
+        self.tick(1);
+        self.check_reset_request();
+        self.check_exceptions();
+    }
+
+    #[inline(always)]
+    fn step(&mut self) {
+        if self.is_halted() {
+            return;
+        }
+        let pc = self.get_pc();
+        let count = match self.fetch_decode(pc) {
+            Ok((instruction, instruction_size)) => self.execute(&instruction, instruction_size),
+            Err(fault) => {
+                self.enter_fault(fault);
+                12
+            }
+        };
+        self.tick(u64::from(count));
+        self.check_reset_request();
+        self.check_exceptions();
+        self.debug_step_halt();
+        //TODO exception entry also burns cycles that should be accounted for
+        //DWT and SYST ticking
+    }
+
+    #[inline(always)]
+    fn execute(&mut self, instruction: &Instruction, instruction_size: usize) -> u32 {
+        self.execute_step(instruction, instruction_size).cycles()
+    }
+
+    fn step_instruction(&mut self) -> StepResult {
+        if self.lockup.is_some() || self.is_halted() || !self.state.get_bit(0) {
+            return StepResult::Halted { cycles: 0 };
+        }
+        if self.state.get_bit(1) {
+            // sleeping until an exception wakes the core up
+            self.step_sleep();
+            return StepResult::Sleeping { cycles: 1 };
+        }
+        let pc = self.get_pc();
+        self.breakpoint_hit = None;
+
+        let result = match self.fetch_decode(pc) {
+            Ok((instruction, instruction_size)) => {
+                self.execute_step(&instruction, instruction_size)
+            }
+            Err(fault) => {
+                self.enter_fault(fault);
+                StepResult::Faulted { fault, cycles: 12 }
+            }
+        };
+
+        let count = result.cycles();
+        self.tick(u64::from(count));
+        self.check_reset_request();
+        self.check_exceptions();
+        self.debug_step_halt();
+
+        if self.state.get_bit(0) {
+            result
+        } else {
+            StepResult::Halted { cycles: count }
+        }
+    }
+
+    fn run(&mut self, max_cycles: u64, breakpoints: &HashSet<u32>) -> RunExit {
+        let end = self.cycle_count.saturating_add(max_cycles);
+        let mut step_over = self.run_breakpoint.take();
+
+        while self.cycle_count < end {
+            if self.is_halted() {
+                return RunExit::DebugHalt;
+            }
+            if self.state == 0b11 {
+                //running, sleeping
+                self.step_sleep();
+                continue;
+            }
+
+            if !breakpoints.is_empty() {
+                let pc = self.get_pc();
+                if step_over.take() != Some(pc) && breakpoints.contains(&pc) {
+                    self.run_breakpoint = Some(pc);
+                    return RunExit::Breakpoint { address: pc };
+                }
+            }
+
+            match self.step_instruction() {
+                StepResult::Halted { .. } => {
+                    return match (self.exit_code, self.lockup, self.breakpoint_hit) {
+                        (Some(code), _, _) => RunExit::Exited { code },
+                        (None, Some(reason), _) => RunExit::Lockup { reason },
+                        (None, None, Some(imm)) => RunExit::BreakpointHit {
+                            address: self.get_pc(),
+                            imm,
+                        },
+                        (None, None, None) => RunExit::Halted,
+                    }
+                }
+                StepResult::Faulted { fault, .. } => return RunExit::Fault { fault },
+                _ => {}
             }
+            step_over = None;
         }
+        RunExit::CycleBudget
     }
 }
 
@@ -2822,8 +3535,18 @@ impl Executor for Processor {
 mod tests {
     use super::*;
     use crate::core::condition::Condition;
+    use crate::core::exception::Exception;
+    use crate::core::fpu::{FPSCR_DZC, FPSCR_IOC, FPSCR_IXC};
     use crate::core::instruction::instruction_size;
     use crate::core::instruction::{ITCondition, SetFlags};
+    use crate::core::register::Ipsr;
+    use crate::core::thumb::ThumbCode;
+    use crate::decoder::{decode_16, decode_32};
+    use crate::peripheral::scb::{DHCSR_C_DEBUGEN, DHCSR_DBGKEY, DHCSR_S_HALT};
+    use crate::semihosting::{SemihostingCommand, SemihostingResponse};
+    use crate::test_fixture::handler_test_processor;
+    use std::cell::RefCell;
+    use std::rc::Rc;
 
     #[test]
     fn test_udiv() {
@@ -3026,4 +3749,2018 @@ mod tests {
 
         assert_eq!(core.get_r(Reg::R12), 0xFFD4F24B);
     }
+
+    fn fault_test_processor() -> Processor {
+        // hardfault, busfault and usagefault handlers at 0x200
+        handler_test_processor(&[], &[], &[3, 5, 6], 0x2000_0400)
+    }
+
+    #[test]
+    fn test_udf_usagefault_disabled_escalates_to_hardfault() {
+        // arrange
+        let mut core = fault_test_processor();
+        let instruction = Instruction::UDF {
+            imm32: 0,
+            opcode: ThumbCode::from(0xde00_u16),
+            thumb32: false,
+        };
+
+        // act
+        core.execute(&instruction, instruction_size(&instruction));
+
+        // assert
+        assert_eq!(core.get_pc(), 0x200);
+        assert_eq!(core.psr.get_isr_number(), Exception::HardFault.into());
+        #[cfg(any(armv7m, armv7em))]
+        assert!(core.hfsr.get_bit(30));
+    }
+
+    #[cfg(any(armv7m, armv7em))]
+    #[test]
+    fn test_udf_usagefault_enabled() {
+        // arrange
+        let mut core = fault_test_processor();
+        core.write32(0xE000_ED24, 1 << 18).unwrap();
+        let instruction = Instruction::UDF {
+            imm32: 0,
+            opcode: ThumbCode::from(0xde00_u16),
+            thumb32: false,
+        };
+
+        // act
+        core.execute(&instruction, instruction_size(&instruction));
+
+        // assert
+        assert_eq!(core.get_pc(), 0x200);
+        assert_eq!(core.psr.get_isr_number(), Exception::UsageFault.into());
+        assert!(!core.hfsr.get_bit(30));
+        // UFSR.UNDEFINSTR
+        assert_eq!(core.read32(0xE000_ED28).unwrap(), 1 << 16);
+        // stacked return address points to the faulting instruction
+        assert_eq!(core.read32(0x2000_0400 - 0x20 + 0x18).unwrap(), 0x100);
+    }
+
+    #[cfg(any(armv7m, armv7em))]
+    #[test]
+    fn test_blx_imm_usagefault() {
+        // arrange
+        let mut core = fault_test_processor();
+        core.write32(0xE000_ED24, 1 << 18).unwrap();
+        core.set_r(Reg::LR, 0x1234_5679);
+        let instruction = decode_32(0xf7ff_efbe); // blx (immediate)
+
+        // act
+        core.execute(&instruction, instruction_size(&instruction));
+
+        // assert
+        assert_eq!(core.get_pc(), 0x200);
+        assert_eq!(core.psr.get_isr_number(), Exception::UsageFault.into());
+        // UFSR.UNDEFINSTR
+        assert_eq!(core.read32(0xE000_ED28).unwrap(), 1 << 16);
+        // stacked LR was not modified by the instruction
+        assert_eq!(core.read32(0x2000_0400 - 0x20 + 0x14).unwrap(), 0x1234_5679);
+    }
+
+    #[test]
+    fn test_bl_backward_from_high_address() {
+        // arrange
+        let mut core = Processor::new();
+        core.set_pc(0x8000_0100);
+        let instruction = decode_32(0xf400_d000); // bl -16777216
+
+        // act
+        let result = core.execute_internal(&instruction);
+
+        // assert
+        assert_eq!(result, Ok(ExecuteResult::Branched { cycles: 4 }));
+        assert_eq!(core.get_pc(), 0x8000_0104 - 0x0100_0000);
+        assert_eq!(core.get_r(Reg::LR), 0x8000_0105);
+    }
+
+    #[test]
+    fn test_stmdb_ldmia_round_trip() {
+        // arrange
+        let mut core = Processor::new();
+        core.set_r(Reg::R0, 0x2000_0100);
+        core.set_r(Reg::R1, 0x1111_1111);
+        core.set_r(Reg::R2, 0x2222_2222);
+        core.set_r(Reg::R3, 0x3333_3333);
+        let store = decode_32(0xe920_000e); // stmdb r0!, {r1-r3}
+        let load = decode_32(0xe8b0_0070); // ldmia.w r0!, {r4-r6}
+
+        // act
+        core.execute_internal(&store).unwrap();
+
+        // assert
+        assert_eq!(core.get_r(Reg::R0), 0x2000_00f4);
+        assert_eq!(core.read32(0x2000_00f4).unwrap(), 0x1111_1111);
+        assert_eq!(core.read32(0x2000_00fc).unwrap(), 0x3333_3333);
+
+        // act
+        core.execute_internal(&load).unwrap();
+
+        // assert
+        assert_eq!(core.get_r(Reg::R0), 0x2000_0100);
+        assert_eq!(core.get_r(Reg::R4), 0x1111_1111);
+        assert_eq!(core.get_r(Reg::R5), 0x2222_2222);
+        assert_eq!(core.get_r(Reg::R6), 0x3333_3333);
+    }
+
+    #[test]
+    fn test_ldmdb_addressing() {
+        // arrange
+        let mut core = Processor::new();
+        core.set_r(Reg::R0, 0x2000_0100);
+        core.write32(0x2000_00f8, 0xaaaa_aaaa).unwrap();
+        core.write32(0x2000_00fc, 0xbbbb_bbbb).unwrap();
+        let load = decode_32(0xe910_0030); // ldmdb r0, {r4, r5}
+        let load_wback = decode_32(0xe930_0180); // ldmdb r0!, {r7, r8}
+
+        // act
+        core.execute_internal(&load).unwrap();
+
+        // assert
+        assert_eq!(core.get_r(Reg::R0), 0x2000_0100);
+        assert_eq!(core.get_r(Reg::R4), 0xaaaa_aaaa);
+        assert_eq!(core.get_r(Reg::R5), 0xbbbb_bbbb);
+
+        // act
+        core.execute_internal(&load_wback).unwrap();
+
+        // assert
+        assert_eq!(core.get_r(Reg::R0), 0x2000_00f8);
+        assert_eq!(core.get_r(Reg::R7), 0xaaaa_aaaa);
+        assert_eq!(core.get_r(Reg::R8), 0xbbbb_bbbb);
+    }
+
+    #[test]
+    fn test_ldm_w_without_writeback() {
+        // arrange
+        let mut core = Processor::new();
+        core.set_r(Reg::R0, 0x2000_0100);
+        core.write32(0x2000_0100, 0x1234_5678).unwrap();
+        let load = decode_32(0xe890_0002); // ldm.w r0, {r1}
+
+        // act
+        core.execute_internal(&load).unwrap();
+
+        // assert
+        assert_eq!(core.get_r(Reg::R0), 0x2000_0100);
+        assert_eq!(core.get_r(Reg::R1), 0x1234_5678);
+    }
+
+    #[test]
+    fn test_ldm_rn_in_list_keeps_loaded_value() {
+        // arrange
+        let mut core = Processor::new();
+        core.set_r(Reg::R0, 0x2000_0100);
+        core.write32(0x2000_0100, 0x1111_1111).unwrap();
+        core.write32(0x2000_0104, 0x2222_2222).unwrap();
+        let narrow = decode_16(0xc803); // ldm r0, {r0, r1}
+        let wide = decode_32(0xe8b1_0006); // ldm.w r1!, {r1, r2}
+
+        // act
+        core.execute_internal(&narrow).unwrap();
+
+        // assert
+        assert_eq!(core.get_r(Reg::R0), 0x1111_1111);
+        assert_eq!(core.get_r(Reg::R1), 0x2222_2222);
+
+        // act
+        core.set_r(Reg::R1, 0x2000_0100);
+        core.execute_internal(&wide).unwrap();
+
+        // assert
+        assert_eq!(core.get_r(Reg::R1), 0x1111_1111);
+        assert_eq!(core.get_r(Reg::R2), 0x2222_2222);
+    }
+
+    #[test]
+    fn test_stm_rn_in_list_stores_original_value() {
+        // arrange
+        let mut core = Processor::new();
+        core.set_r(Reg::R1, 0x2000_0100);
+        core.set_r(Reg::R2, 0x2222_2222);
+        let lowest = decode_16(0xc106); // stm r1!, {r1, r2}
+        let not_lowest = decode_16(0xc206); // stm r2!, {r1, r2}
+
+        // act
+        core.execute_internal(&lowest).unwrap();
+
+        // assert
+        assert_eq!(core.read32(0x2000_0100).unwrap(), 0x2000_0100);
+        assert_eq!(core.read32(0x2000_0104).unwrap(), 0x2222_2222);
+        assert_eq!(core.get_r(Reg::R1), 0x2000_0108);
+
+        // act
+        core.set_r(Reg::R2, 0x2000_0200);
+        core.execute_internal(&not_lowest).unwrap();
+
+        // assert
+        assert_eq!(core.read32(0x2000_0200).unwrap(), 0x2000_0108);
+        assert_eq!(core.read32(0x2000_0204).unwrap(), 0x2000_0200);
+        assert_eq!(core.get_r(Reg::R2), 0x2000_0208);
+    }
+
+    #[test]
+    fn test_stm_narrow_stores_r7() {
+        // arrange
+        let mut core = Processor::new();
+        core.set_r(Reg::R0, 0x2000_0100);
+        core.set_r(Reg::R7, 0x7777_7777);
+        let store = decode_16(0xc080); // stm r0!, {r7}
+
+        // act
+        core.execute_internal(&store).unwrap();
+
+        // assert
+        assert_eq!(core.read32(0x2000_0100).unwrap(), 0x7777_7777);
+        assert_eq!(core.get_r(Reg::R0), 0x2000_0104);
+    }
+
+    #[test]
+    fn test_sp_adjust_round_trip() {
+        // arrange
+        let mut core = Processor::new();
+        core.set_r(Reg::SP, 0x2000_0100);
+
+        // act
+        core.execute_internal(&decode_16(0xb084)).unwrap(); // sub sp, #16
+
+        // assert
+        assert_eq!(core.get_r(Reg::SP), 0x2000_00f0);
+
+        // act
+        core.execute_internal(&decode_16(0xb004)).unwrap(); // add sp, #16
+
+        // assert
+        assert_eq!(core.get_r(Reg::SP), 0x2000_0100);
+    }
+
+    #[test]
+    fn test_sp_relative_addresses() {
+        // arrange
+        let mut core = Processor::new();
+        core.set_r(Reg::SP, 0x2000_0100);
+        core.set_r(Reg::R1, 3);
+
+        // act
+        core.execute_internal(&decode_16(0xa804)).unwrap(); // add r0, sp, #16
+        core.execute_internal(&decode_32(0xeb0d_0281)).unwrap(); // add.w r2, sp, r1, lsl #2
+        core.execute_internal(&decode_16(0x446b)).unwrap(); // add r3, sp, r3
+
+        // assert
+        assert_eq!(core.get_r(Reg::R0), 0x2000_0110);
+        assert_eq!(core.get_r(Reg::R2), 0x2000_010c);
+        assert_eq!(core.get_r(Reg::R3), 0x2000_0100);
+        assert_eq!(core.get_r(Reg::SP), 0x2000_0100);
+    }
+
+    #[test]
+    fn test_narrow_literal_loads() {
+        // arrange
+        let code = [
+            0x9f, 0xf8, 0x0c, 0x00, // 0x00: ldrb.w r0, [pc, #12]
+            0x3f, 0xf8, 0x04, 0x10, // 0x04: ldrh.w r1, [pc, #-4]
+            0x9f, 0xf9, 0x04, 0x20, // 0x08: ldrsb.w r2, [pc, #4]
+            0xbf, 0xf9, 0x00, 0x30, // 0x0c: ldrsh.w r3, [pc]
+            0xfe, 0x81, 0xff, 0xff, // 0x10: .word 0xffff81fe
+        ];
+        let mut core = Processor::new();
+        core.flash_memory(code.len(), &code);
+        core.set_pc(0);
+
+        // act
+        for _ in 0..4 {
+            core.step_instruction();
+        }
+
+        // assert
+        assert_eq!(core.get_r(Reg::R0), 0xfe);
+        assert_eq!(core.get_r(Reg::R1), 0xf83f);
+        assert_eq!(core.get_r(Reg::R2), 0xffff_fffe);
+        assert_eq!(core.get_r(Reg::R3), 0xffff_81fe);
+    }
+
+    #[test]
+    fn test_add_shifted_register() {
+        // arrange
+        let mut core = Processor::new();
+        core.set_r(Reg::R1, 0x100);
+        core.set_r(Reg::R2, 5);
+
+        // act
+        core.execute_internal(&decode_32(0xeb01_00c2)).unwrap(); // add.w r0, r1, r2, lsl #3
+
+        // assert
+        assert_eq!(core.get_r(Reg::R0), 0x128);
+    }
+
+    #[test]
+    fn test_orrs_shifted_register_sets_carry_from_shift() {
+        // arrange
+        let mut core = Processor::new();
+        core.set_r(Reg::R1, 0x8000_0000);
+        core.set_r(Reg::R2, 3);
+        core.psr.set_c(false);
+
+        // act
+        core.execute_internal(&decode_32(0xea51_0052)).unwrap(); // orrs.w r0, r1, r2, lsr #1
+
+        // assert
+        assert_eq!(core.get_r(Reg::R0), 0x8000_0001);
+        assert!(core.psr.get_c());
+        assert!(core.psr.get_n());
+        assert!(!core.psr.get_z());
+    }
+
+    #[test]
+    fn test_subs_shifted_register() {
+        // arrange
+        let mut core = Processor::new();
+        core.set_r(Reg::R1, 0x10);
+        core.set_r(Reg::R2, 0xffff_ffc0);
+
+        // act
+        core.execute_internal(&decode_32(0xebb1_00a2)).unwrap(); // subs.w r0, r1, r2, asr #2
+
+        // assert
+        assert_eq!(core.get_r(Reg::R0), 0x20);
+        assert!(!core.psr.get_c());
+    }
+
+    #[test]
+    fn test_pc_advances_by_instruction_length() {
+        // arrange
+        let code = [
+            0x01, 0x20, // 0x00: movs r0, #1
+            0x00, 0xf1, 0x02, 0x00, // 0x02: add.w r0, r0, #2
+            0x03, 0x30, // 0x06: adds r0, #3
+            0x4f, 0xf4, 0x80, 0x71, // 0x08: mov.w r1, #0x100
+            0x00, 0xbf, // 0x0c: nop
+            0xfe, 0xe7, // 0x0e: b .
+        ];
+        let mut core = Processor::new();
+        core.flash_memory(code.len(), &code);
+        core.set_pc(0);
+
+        // act
+        let mut pcs = Vec::new();
+        for _ in 0..6 {
+            core.step_instruction();
+            pcs.push(core.get_pc());
+        }
+
+        // assert
+        assert_eq!(pcs, vec![0x02, 0x06, 0x08, 0x0c, 0x0e, 0x0e]);
+        assert_eq!(core.get_r(Reg::R0), 6);
+        assert_eq!(core.get_r(Reg::R1), 0x100);
+    }
+
+    #[test]
+    fn test_pc_advances_by_instruction_length_when_cached() {
+        // arrange
+        let code = [
+            0x4f, 0xf4, 0x80, 0x71, // 0x00: mov.w r1, #0x100
+            0x01, 0x20, // 0x04: movs r0, #1
+            0x00, 0xf1, 0x02, 0x00, // 0x06: add.w r0, r0, #2
+            0xfe, 0xe7, // 0x0a: b .
+        ];
+        let mut core = Processor::new();
+        core.flash_memory(code.len(), &code);
+        core.cache_instructions();
+        core.set_pc(0);
+
+        // act
+        let mut pcs = Vec::new();
+        for _ in 0..4 {
+            core.step();
+            pcs.push(core.get_pc());
+        }
+
+        // assert
+        assert_eq!(pcs, vec![0x04, 0x06, 0x0a, 0x0a]);
+        assert_eq!(core.get_r(Reg::R0), 3);
+    }
+
+    #[test]
+    fn test_svc_hook_receives_immediate() {
+        // arrange
+        let code = [
+            0x03, 0xdf, // svc #3
+            0xfe, 0xe7, // b .
+        ];
+        let mut core = Processor::new();
+        core.flash_memory(code.len(), &code);
+        core.set_pc(0);
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let hook_calls = Rc::clone(&calls);
+        core.svc_hook(Some(Box::new(move |core, number| {
+            hook_calls.borrow_mut().push(number);
+            core.set_r(Reg::R0, 42);
+        })));
+
+        // act
+        core.step_instruction();
+
+        // assert
+        assert_eq!(*calls.borrow(), vec![3]);
+        assert_eq!(core.get_r(Reg::R0), 42);
+        assert_eq!(core.get_pc(), 2);
+        assert!(!core.exception_pending(Exception::SVCall));
+    }
+
+    #[test]
+    fn test_svc_takes_svcall_exception() {
+        // arrange
+        let mut code = vec![0_u8; 0x204];
+        code[44..48].copy_from_slice(&0x201_u32.to_le_bytes()); // SVCall
+        code[0x100..0x102].copy_from_slice(&0xdf03_u16.to_le_bytes()); // svc #3
+        code[0x200..0x202].copy_from_slice(&0xe7fe_u16.to_le_bytes()); // b .
+        let mut core = Processor::new();
+        core.flash_memory(code.len(), &code);
+        core.set_msp(0x2000_1000);
+        core.set_pc(0x100);
+        core.execution_priority = core.get_execution_priority();
+
+        // act
+        core.step_instruction();
+
+        // assert
+        assert_eq!(core.get_pc(), 0x200);
+        assert_eq!(core.psr.get_isr_number(), 11);
+        // return address is the instruction after svc
+        assert_eq!(core.read32(0x2000_1000 - 0x20 + 0x18).unwrap(), 0x102);
+    }
+
+    #[test]
+    fn test_cmn_shifted_register() {
+        // arrange
+        let mut core = Processor::new();
+        core.set_r(Reg::R0, 0x8000_0000);
+        core.set_r(Reg::R1, 0x4000_0000);
+        let instruction = decode_32(0xeb10_0f41); // cmn.w r0, r1, lsl #1
+
+        // act
+        let result = core.execute_internal(&instruction);
+
+        // assert
+        assert_eq!(result, Ok(ExecuteResult::Taken { cycles: 1 }));
+        // 0x8000_0000 + 0x8000_0000 wraps to zero with carry and overflow
+        assert!(!core.psr.get_n());
+        assert!(core.psr.get_z());
+        assert!(core.psr.get_c());
+        assert!(core.psr.get_v());
+    }
+
+    #[test]
+    fn test_add_reg_reads_unaligned_pc() {
+        // arrange
+        let mut core = Processor::new();
+        core.set_pc(0x102);
+        core.set_r(Reg::R1, 0x10);
+        let instruction = decode_16(0x4479); // add r1, pc
+
+        // act
+        core.execute_internal(&instruction).unwrap();
+
+        // assert: unlike ADR, ADD (register) uses PC + 4 without alignment
+        assert_eq!(core.get_r(Reg::R1), 0x102 + 4 + 0x10);
+        assert_eq!(core.get_pc(), 0x102);
+    }
+
+    #[test]
+    fn test_sub_reg_to_pc_branches() {
+        // arrange
+        let mut core = Processor::new();
+        core.set_pc(0x100);
+        core.set_r(Reg::R1, 0x2001);
+        core.set_r(Reg::R2, 0x1000);
+        // UNPREDICTABLE, with S set the same encoding is CMP
+        let instruction = decode_32(0xeba1_0f02); // sub.w pc, r1, r2
+
+        // act
+        let result = core.execute_internal(&instruction).unwrap();
+
+        // assert
+        assert_eq!(result, ExecuteResult::Branched { cycles: 3 });
+        assert_eq!(core.get_pc(), 0x1000);
+    }
+
+    #[test]
+    fn test_movs_rotated_immediate_sets_carry() {
+        // arrange
+        let mut core = Processor::new();
+        core.psr.value = 0;
+
+        // act
+        let instruction = decode_32(0xf05f_4000); // movs.w r0, #0x80000000
+        core.execute_internal(&instruction).unwrap();
+
+        // assert
+        assert_eq!(core.get_r(Reg::R0), 0x8000_0000);
+        assert!(core.psr.get_n());
+        assert!(core.psr.get_c());
+
+        // act: an immediate without rotation leaves the carry alone
+        let instruction = decode_32(0xf05f_01ff); // movs.w r1, #0xff
+        core.execute_internal(&instruction).unwrap();
+
+        // assert
+        assert_eq!(core.get_r(Reg::R1), 0xff);
+        assert!(!core.psr.get_n());
+        assert!(core.psr.get_c());
+    }
+
+    #[test]
+    fn test_adr_add_and_sub() {
+        // arrange
+        let mut core = Processor::new();
+        core.set_pc(0x102);
+
+        // act
+        core.execute_internal(&decode_16(0xa002)).unwrap(); // adr r0, #8
+        core.execute_internal(&decode_32(0xf2af_0110)).unwrap(); // sub r1, pc, #0x10
+
+        // assert: both use the word aligned PC, 0x104
+        assert_eq!(core.get_r(Reg::R0), 0x10c);
+        assert_eq!(core.get_r(Reg::R1), 0xf4);
+    }
+
+    #[test]
+    fn test_pc_relative_narrow_and_wide() {
+        // arrange
+        let code = [
+            0x00, 0xbf, // 0x00: nop
+            0x05, 0x48, // 0x02: ldr r0, [pc, #20]
+            0x00, 0xbf, // 0x04: nop
+            0xdf, 0xf8, 0x14, 0x10, // 0x06: ldr.w r1, [pc, #20]
+            0x02, 0xa2, // 0x0a: adr r2, #8
+            0x00, 0xbf, // 0x0c: nop
+            0x0f, 0xf2, 0x08, 0x03, // 0x0e: adr.w r3, #8
+            0x00, 0xbf, // 0x12: nop
+            0x00, 0xbf, // 0x14: nop
+            0x00, 0xbf, // 0x16: nop
+            0x44, 0x33, 0x22, 0x11, // 0x18: .word 0x11223344
+            0x88, 0x77, 0x66, 0x55, // 0x1c: .word 0x55667788
+        ];
+        let mut core = Processor::new();
+        core.flash_memory(code.len(), &code);
+        core.set_pc(0);
+
+        // act
+        for _ in 0..7 {
+            core.step_instruction();
+        }
+
+        // assert
+        assert_eq!(core.get_pc(), 0x12);
+        assert_eq!(core.get_r(Reg::R0), 0x1122_3344);
+        assert_eq!(core.get_r(Reg::R1), 0x5566_7788);
+        assert_eq!(core.get_r(Reg::R2), 0x14);
+        assert_eq!(core.get_r(Reg::R3), 0x18);
+    }
+
+    #[test]
+    fn test_pc_reads_as_current_instruction_plus_4() {
+        // arrange
+        let mut core = Processor::new();
+
+        // act
+        core.set_pc(0x102);
+        let pc = core.get_r(Reg::PC);
+        core.set_pc(0xffff_fffe);
+        let wrapped_pc = core.get_r(Reg::PC);
+
+        // assert
+        assert_eq!(pc, 0x106);
+        assert_eq!(wrapped_pc, 0x2);
+    }
+
+    #[test]
+    fn test_cps_interrupt_mask() {
+        // arrange
+        let mut core = Processor::new();
+
+        // act
+        core.execute_internal(&decode_16(0xb672)).unwrap(); // cpsid i
+        let disabled = core.primask;
+        core.execute_internal(&decode_16(0xb662)).unwrap(); // cpsie i
+
+        // assert
+        assert_eq!(disabled, true);
+        assert_eq!(core.primask, false);
+    }
+
+    #[cfg(any(armv7m, armv7em))]
+    #[test]
+    fn test_cps_fault_mask() {
+        // arrange
+        let mut core = Processor::new();
+
+        // act
+        core.execute_internal(&decode_16(0xb671)).unwrap(); // cpsid f
+        let (primask, faultmask) = (core.primask, core.faultmask);
+        core.execute_internal(&decode_16(0xb661)).unwrap(); // cpsie f
+
+        // assert
+        assert_eq!(primask, false);
+        assert_eq!(faultmask, true);
+        assert_eq!(core.execution_priority, 256);
+        assert_eq!(core.faultmask, false);
+
+        // act
+        core.execute_internal(&decode_16(0xb673)).unwrap(); // cpsid if
+
+        // assert
+        assert_eq!(core.primask, true);
+        assert_eq!(core.faultmask, true);
+        assert_eq!(core.execution_priority, -1);
+    }
+
+    #[test]
+    fn test_cps_is_ignored_when_unprivileged() {
+        // arrange
+        let mut core = Processor::new();
+        core.control.n_priv = true;
+
+        // act
+        let result = core.execute_internal(&decode_16(0xb672)); // cpsid i
+
+        // assert
+        assert_eq!(result, Ok(ExecuteResult::Taken { cycles: 1 }));
+        assert_eq!(core.primask, false);
+    }
+
+    fn fp_enabled_processor() -> Processor {
+        let mut core = Processor::new();
+        core.write32(0xE000_ED88, 0b1111 << 20).unwrap(); // CP10, CP11 full access
+        core
+    }
+
+    #[test]
+    fn test_vmov_core_register_to_single_and_back() {
+        // arrange
+        let mut core = fp_enabled_processor();
+        core.set_r(Reg::R1, 0x3fc0_0000); // 1.5
+        let to_fp = decode_32(0xee01_1a90); // vmov s3, r1
+        let from_fp = decode_32(0xee11_2a90); // vmov r2, s3
+
+        // act
+        let to_fp_result = core.execute_internal(&to_fp);
+        let from_fp_result = core.execute_internal(&from_fp);
+
+        // assert
+        assert_eq!(to_fp_result, Ok(ExecuteResult::Taken { cycles: 1 }));
+        assert_eq!(from_fp_result, Ok(ExecuteResult::Taken { cycles: 1 }));
+        assert_eq!(core.fp_regs[3], 0x3fc0_0000);
+        assert_eq!(core.get_r(Reg::R2), 0x3fc0_0000);
+    }
+
+    #[test]
+    fn test_vmov_between_fp_registers() {
+        // arrange
+        let mut core = fp_enabled_processor();
+        core.set_r(Reg::R2, 0x1111_1111);
+        core.set_r(Reg::R3, 0x2222_2222);
+
+        // act
+        core.execute_internal(&decode_32(0xec43_2b11)).unwrap(); // vmov d1, r2, r3
+        core.execute_internal(&decode_32(0xeeb0_2b41)).unwrap(); // vmov.f64 d2, d1
+        core.execute_internal(&decode_32(0xeef0_2a61)).unwrap(); // vmov.f32 s5, s3
+        core.execute_internal(&decode_32(0xec55_4b12)).unwrap(); // vmov r4, r5, d2
+
+        // assert
+        // d1 aliases s2 and s3, d2 aliases s4 and s5
+        assert_eq!(
+            &core.fp_regs[2..6],
+            &[0x1111_1111, 0x2222_2222, 0x1111_1111, 0x2222_2222]
+        );
+        assert_eq!(core.get_r(Reg::R4), 0x1111_1111);
+        assert_eq!(core.get_r(Reg::R5), 0x2222_2222);
+    }
+
+    #[test]
+    fn test_vstr_vldr_single_round_trip() {
+        // arrange
+        let mut core = fp_enabled_processor();
+        core.set_r(Reg::SP, 0x2000_0100);
+        core.fp_regs[3] = (-1.25_f32).to_bits();
+        let store = decode_32(0xed4d_1a02); // vstr s3, [sp, #-8]
+        let load = decode_32(0xed1d_3a02); // vldr s6, [sp, #-8]
+
+        // act
+        let store_result = core.execute_internal(&store);
+        let load_result = core.execute_internal(&load);
+
+        // assert
+        assert_eq!(store_result, Ok(ExecuteResult::Taken { cycles: 2 }));
+        assert_eq!(load_result, Ok(ExecuteResult::Taken { cycles: 2 }));
+        assert_eq!(core.read32(0x2000_00f8).unwrap(), (-1.25_f32).to_bits());
+        assert_eq!(f32::from_bits(core.fp_regs[6]), -1.25);
+    }
+
+    #[test]
+    fn test_vstr_vldr_double_round_trip() {
+        // arrange
+        let mut core = fp_enabled_processor();
+        core.set_r(Reg::R0, 0x2000_0200);
+        let bits = 2.5_f64.to_bits();
+        core.fp_regs[4] = bits as u32;
+        core.fp_regs[5] = (bits >> 32) as u32;
+        let store = decode_32(0xed80_2b02); // vstr d2, [r0, #8]
+        let load = decode_32(0xed90_5b02); // vldr d5, [r0, #8]
+
+        // act
+        let store_result = core.execute_internal(&store);
+        let load_result = core.execute_internal(&load);
+
+        // assert
+        assert_eq!(store_result, Ok(ExecuteResult::Taken { cycles: 3 }));
+        assert_eq!(load_result, Ok(ExecuteResult::Taken { cycles: 3 }));
+        assert_eq!(core.read32(0x2000_0208).unwrap(), bits as u32);
+        assert_eq!(core.read32(0x2000_020c).unwrap(), (bits >> 32) as u32);
+        let loaded = u64::from(core.fp_regs[10]) | (u64::from(core.fp_regs[11]) << 32);
+        assert_eq!(f64::from_bits(loaded), 2.5);
+    }
+
+    #[test]
+    fn test_vfp_arithmetic() {
+        // arrange
+        let mut core = fp_enabled_processor();
+        core.fp_regs[0] = 1.5_f32.to_bits();
+        core.fp_regs[1] = 2.0_f32.to_bits();
+        core.fp_regs[2] = 0.25_f32.to_bits();
+        core.fp_regs[4] = 6.0_f32.to_bits();
+        core.fp_regs[5] = 4.0_f32.to_bits();
+
+        // act
+        core.execute_internal(&decode_32(0xee30_0a81)).unwrap(); // vadd.f32 s0, s1, s2
+        core.execute_internal(&decode_32(0xee72_1a62)).unwrap(); // vsub.f32 s3, s4, s5
+        core.execute_internal(&decode_32(0xee20_0a20)).unwrap(); // vmul.f32 s0, s0, s1
+        let result = core.execute_internal(&decode_32(0xee80_1a20)); // vdiv.f32 s2, s0, s1
+
+        // assert
+        assert_eq!(result, Ok(ExecuteResult::Taken { cycles: 14 }));
+        assert_eq!(f32::from_bits(core.fp_regs[3]), 2.0);
+        assert_eq!(f32::from_bits(core.fp_regs[0]), 4.5);
+        assert_eq!(f32::from_bits(core.fp_regs[2]), 2.25);
+        assert_eq!(core.fpscr, 0);
+    }
+
+    #[test]
+    fn test_vdiv_by_zero_yields_infinity() {
+        // arrange
+        let mut core = fp_enabled_processor();
+        core.fp_regs[0] = (-3.0_f32).to_bits();
+        core.fp_regs[1] = 0.0_f32.to_bits();
+
+        // act
+        core.execute_internal(&decode_32(0xee80_1a20)).unwrap(); // vdiv.f32 s2, s0, s1
+
+        // assert
+        assert_eq!(f32::from_bits(core.fp_regs[2]), f32::NEG_INFINITY);
+        assert_eq!(core.fpscr, FPSCR_DZC);
+    }
+
+    #[test]
+    fn test_vfp_arithmetic_propagates_nan() {
+        // arrange
+        let mut core = fp_enabled_processor();
+        core.fp_regs[0] = 0x7fc0_0123; // quiet NaN
+        core.fp_regs[1] = 1.0_f32.to_bits();
+        core.fp_regs[4] = f32::INFINITY.to_bits();
+        core.fp_regs[5] = f32::INFINITY.to_bits();
+
+        // act
+        core.execute_internal(&decode_32(0xee20_0a20)).unwrap(); // vmul.f32 s0, s0, s1
+        let quiet_flags = core.fpscr;
+        core.execute_internal(&decode_32(0xee72_1a62)).unwrap(); // vsub.f32 s3, s4, s5
+
+        // assert
+        assert_eq!(core.fp_regs[0], 0x7fc0_0123);
+        assert_eq!(quiet_flags, 0);
+        assert!(f32::from_bits(core.fp_regs[3]).is_nan());
+        assert_eq!(core.fpscr, FPSCR_IOC);
+    }
+
+    #[test]
+    fn test_vcvt_round_trip() {
+        // arrange
+        let mut core = fp_enabled_processor();
+        core.fp_regs[1] = (-1234.75_f32).to_bits();
+
+        // act
+        core.execute_internal(&decode_32(0xeebd_0ae0)).unwrap(); // vcvt.s32.f32 s0, s1
+        core.execute_internal(&decode_32(0xeeb8_1ac0)).unwrap(); // vcvt.f32.s32 s2, s0
+
+        // assert
+        assert_eq!(core.fp_regs[0], -1234_i32 as u32);
+        assert_eq!(f32::from_bits(core.fp_regs[2]), -1234.0);
+        assert_eq!(core.fpscr, FPSCR_IXC);
+    }
+
+    #[test]
+    fn test_vcvt_boundary_values() {
+        // arrange
+        let mut core = fp_enabled_processor();
+        core.fp_regs[1] = 2_147_483_648.0_f32.to_bits(); // 2^31
+        core.fp_regs[5] = 2_147_483_648.0_f32.to_bits();
+
+        // act
+        core.execute_internal(&decode_32(0xeebd_0ae0)).unwrap(); // vcvt.s32.f32 s0, s1
+        let signed_flags = core.fpscr;
+        core.execute_internal(&decode_32(0xeebc_2ae2)).unwrap(); // vcvt.u32.f32 s4, s5
+        core.execute_internal(&decode_32(0xeeb8_3a42)).unwrap(); // vcvt.f32.u32 s6, s4
+
+        // assert
+        assert_eq!(core.fp_regs[0], 0x7fff_ffff);
+        assert_eq!(signed_flags, FPSCR_IOC);
+        assert_eq!(core.fp_regs[4], 0x8000_0000);
+        assert_eq!(f32::from_bits(core.fp_regs[6]), 2_147_483_648.0);
+    }
+
+    #[test]
+    fn test_vcvtr_uses_fpscr_rounding_mode() {
+        // arrange
+        let mut core = fp_enabled_processor();
+        core.fp_regs[1] = 2.5_f32.to_bits();
+        let vcvtr = decode_32(0xeebd_0a60); // vcvtr.s32.f32 s0, s1
+
+        // act
+        core.execute_internal(&vcvtr).unwrap();
+        let nearest = core.fp_regs[0];
+        core.fpscr = 0b01 << 22; // round towards plus infinity
+        core.execute_internal(&vcvtr).unwrap();
+
+        // assert
+        assert_eq!(nearest, 2);
+        assert_eq!(core.fp_regs[0], 3);
+    }
+
+    #[test]
+    fn test_vldr_with_fp_disabled_yields_nocp_fault() {
+        // arrange
+        let mut core = Processor::new();
+        core.set_r(Reg::R0, 0x2000_0200);
+        let instruction = decode_32(0xed90_5b02); // vldr d5, [r0, #8]
+
+        // act
+        let result = core.execute_internal(&instruction);
+
+        // assert
+        assert_eq!(result, Err(Fault::NoCp));
+        assert_eq!(core.fp_regs[10], 0);
+    }
+
+    #[test]
+    fn test_vmov_with_fp_disabled_yields_nocp_fault() {
+        // arrange
+        let mut core = Processor::new();
+        let instruction = decode_32(0xee01_1a90); // vmov s3, r1
+
+        // act
+        let result = core.execute_internal(&instruction);
+
+        // assert
+        assert_eq!(result, Err(Fault::NoCp));
+    }
+
+    #[test]
+    fn test_vmov_unprivileged_with_privileged_only_access_yields_nocp_fault() {
+        // arrange
+        let mut core = Processor::new();
+        core.write32(0xE000_ED88, 0b0101 << 20).unwrap(); // privileged access only
+        let instruction = decode_32(0xee01_1a90); // vmov s3, r1
+
+        // act
+        let privileged = core.execute_internal(&instruction);
+        core.control.n_priv = true;
+        let unprivileged = core.execute_internal(&instruction);
+
+        // assert
+        assert_eq!(privileged, Ok(ExecuteResult::Taken { cycles: 1 }));
+        assert_eq!(unprivileged, Err(Fault::NoCp));
+    }
+
+    #[cfg(any(armv7m, armv7em))]
+    #[test]
+    fn test_vmov_with_fp_disabled_raises_usagefault() {
+        // arrange
+        let mut core = fault_test_processor();
+        core.write32(0xE000_ED24, 1 << 18).unwrap();
+        let instruction = decode_32(0xee01_1a90); // vmov s3, r1
+
+        // act
+        core.execute(&instruction, instruction_size(&instruction));
+
+        // assert
+        assert_eq!(core.psr.get_isr_number(), Exception::UsageFault.into());
+        // UFSR.NOCP
+        assert_eq!(core.read32(0xE000_ED28).unwrap(), 1 << 19);
+    }
+
+    fn cbz_branch_taken(nonzero: bool, value: u32) -> bool {
+        let mut core = Processor::new();
+        core.set_pc(0x100);
+        core.set_r(Reg::R2, value);
+
+        let instruction = Instruction::CBZ {
+            rn: Reg::R2,
+            nonzero,
+            imm32: 0x10,
+        };
+
+        match core.execute_internal(&instruction).unwrap() {
+            ExecuteResult::Branched { .. } => {
+                assert_eq!(core.get_pc(), 0x100 + 4 + 0x10);
+                true
+            }
+            _ => {
+                assert_eq!(core.get_pc(), 0x100);
+                false
+            }
+        }
+    }
+
+    #[test]
+    fn test_cbz_zero_branches() {
+        assert!(cbz_branch_taken(false, 0));
+    }
+
+    #[test]
+    fn test_cbz_nonzero_falls_through() {
+        assert!(!cbz_branch_taken(false, 1));
+    }
+
+    #[test]
+    fn test_cbnz_zero_falls_through() {
+        assert!(!cbz_branch_taken(true, 0));
+    }
+
+    #[test]
+    fn test_cbnz_nonzero_branches() {
+        assert!(cbz_branch_taken(true, 0x8000_0000));
+    }
+
+    #[test]
+    fn test_instruction_hook_intercepts_mcr() {
+        // arrange
+        let mut core = Processor::new();
+        core.set_pc(0x100);
+        core.instruction_hook(Some(Box::new(|core, instruction| match instruction {
+            Instruction::MCR { rt, .. } => {
+                let value = core.get_r(*rt);
+                core.set_r(Reg::R0, value.wrapping_mul(2));
+                Some(ExecuteResult::Taken { cycles: 3 })
+            }
+            _ => None,
+        })));
+        core.set_r(Reg::R1, 21);
+
+        let instruction = Instruction::MCR {
+            rt: Reg::R1,
+            coproc: 0,
+            opc1: 0,
+            opc2: 0,
+            crn: 0,
+            crm: 0,
+        };
+
+        // act
+        let cycles = core.execute(&instruction, 4);
+
+        // assert
+        assert_eq!(cycles, 3);
+        assert_eq!(core.get_r(Reg::R0), 42);
+        assert_eq!(core.get_pc(), 0x104);
+    }
+
+    #[test]
+    fn test_trace_hook_sees_executed_instructions() {
+        // arrange
+        let code = [
+            0x01, 0x20, // movs r0, #1
+            0x01, 0xe0, // b.n 0x8
+            0x00, 0xbf, // nop (skipped)
+            0x00, 0xbf, // nop (skipped)
+            0x02, 0x21, // movs r1, #2
+        ];
+        let trace = Rc::new(RefCell::new(Vec::new()));
+        let sink = trace.clone();
+        let mut core = Processor::new();
+        core.flash_memory(code.len(), &code);
+        core.set_pc(0);
+        core.trace_hook(Some(Box::new(move |pc, instruction, cycles| {
+            sink.borrow_mut().push((pc, *instruction, cycles));
+        })));
+
+        // act
+        for _ in 0..3 {
+            core.step_instruction();
+        }
+
+        // assert
+        let pcs: Vec<u32> = trace.borrow().iter().map(|(pc, _, _)| *pc).collect();
+        assert_eq!(pcs, vec![0, 2, 8]);
+        assert!(matches!(trace.borrow()[0].1, Instruction::MOV_imm { .. }));
+        assert!(trace.borrow().iter().all(|(_, _, cycles)| *cycles > 0));
+    }
+
+    #[test]
+    #[cfg(any(armv7m, armv7em))]
+    fn test_ittee_block_predication() {
+        // arrange
+        let code = [
+            0x01, 0x20, // movs r0, #1
+            0x01, 0x28, // cmp r0, #1
+            0x07, 0xbf, // ittee eq
+            0x01, 0x21, // moveq r1, #1
+            0x02, 0x22, // moveq r2, #2
+            0x03, 0x23, // movne r3, #3
+            0x04, 0x24, // movne r4, #4
+            0x05, 0x25, // movs r5, #5
+        ];
+        let mut core = Processor::new();
+        core.flash_memory(code.len(), &code);
+        core.set_pc(0);
+
+        // act
+        for _ in 0..8 {
+            core.step_instruction();
+        }
+
+        // assert
+        assert_eq!(core.get_r(Reg::R1), 1);
+        assert_eq!(core.get_r(Reg::R2), 2);
+        assert_eq!(core.get_r(Reg::R3), 0);
+        assert_eq!(core.get_r(Reg::R4), 0);
+        assert_eq!(core.get_r(Reg::R5), 5);
+        assert_eq!(core.itstate, 0);
+        assert_eq!(core.get_pc(), 16);
+    }
+
+    #[test]
+    #[cfg(any(armv7m, armv7em))]
+    fn test_skipped_it_lane_leaves_state_unchanged() {
+        // arrange
+        let code = [
+            0x01, 0x20, // movs r0, #1
+            0x01, 0x28, // cmp r0, #1
+            0x0c, 0xbf, // ite eq
+            0x01, 0x21, // moveq r1, #1
+            0x02, 0x22, // movne r2, #2
+            0x18, 0xbf, // it ne
+            0x00, 0x28, // cmpne r0, #0
+        ];
+        let mut core = Processor::new();
+        core.flash_memory(code.len(), &code);
+        core.set_pc(0);
+        core.set_r(Reg::R2, 0x55);
+
+        // act
+        let results: Vec<StepResult> = (0..7).map(|_| core.step_instruction()).collect();
+
+        // assert
+        assert_eq!(core.get_r(Reg::R1), 1);
+        assert_eq!(core.get_r(Reg::R2), 0x55);
+        assert_eq!(results[4], StepResult::Executed { cycles: 1 });
+        assert!(core.psr.get_z());
+        assert!(core.psr.get_c());
+        assert_eq!(core.get_pc(), 14);
+    }
+
+    #[test]
+    #[cfg(any(armv7m, armv7em))]
+    fn test_branch_ends_it_block() {
+        // arrange
+        let code = [
+            0x01, 0x28, // cmp r0, #1
+            0x18, 0xbf, // it ne
+            0x00, 0xe0, // bne.n 0x8
+            0x00, 0xbf, // nop
+            0x00, 0x29, // cmp r1, #0
+            0x08, 0xbf, // it eq
+            0x02, 0x22, // moveq r2, #2
+        ];
+        let mut core = Processor::new();
+        core.flash_memory(code.len(), &code);
+        core.set_pc(0);
+
+        // act
+        for _ in 0..3 {
+            core.step_instruction();
+        }
+
+        // assert: branch was taken and the IT block closed
+        assert_eq!(core.get_pc(), 8);
+        assert_eq!(core.itstate, 0);
+
+        // act
+        for _ in 0..3 {
+            core.step_instruction();
+        }
+
+        // assert
+        assert_eq!(core.get_r(Reg::R2), 2);
+    }
+
+    #[test]
+    #[cfg(any(armv7m, armv7em))]
+    fn test_it_predicated_ldr_and_add() {
+        // arrange
+        let code = [
+            0x01, 0x20, // movs r0, #1
+            0x01, 0x28, // cmp r0, #1
+            0x14, 0xbf, // ite ne
+            0x32, 0x68, // ldrne r2, [r6]
+            0x05, 0x33, // addeq r3, #5
+            0x08, 0xbf, // it eq
+            0x0c, 0x68, // ldreq r4, [r1]
+            0x18, 0xbf, // it ne
+            0x07, 0x35, // addne r5, #7
+        ];
+        let mut core = Processor::new();
+        core.flash_memory(code.len(), &code);
+        core.set_pc(0);
+        core.write32(0x2000_0000, 0x1234_5678).unwrap();
+        core.set_r(Reg::R1, 0x2000_0000);
+        core.set_r(Reg::R3, 1);
+        core.set_r(Reg::R5, 1);
+        // unmapped, the skipped load must not fault
+        core.set_r(Reg::R6, 0x1000_0000);
+
+        // act
+        let results: Vec<StepResult> = (0..9).map(|_| core.step_instruction()).collect();
+
+        // assert
+        assert!(results
+            .iter()
+            .all(|result| !matches!(result, StepResult::Faulted { .. })));
+        assert_eq!(core.get_r(Reg::R2), 0);
+        assert_eq!(core.get_r(Reg::R3), 6);
+        assert_eq!(core.get_r(Reg::R4), 0x1234_5678);
+        assert_eq!(core.get_r(Reg::R5), 1);
+        // adds inside an IT block do not set flags
+        assert!(core.psr.get_z());
+        assert_eq!(core.get_pc(), 18);
+        assert_eq!(core.itstate, 0);
+    }
+
+    #[test]
+    #[cfg(any(armv7m, armv7em))]
+    fn test_it_skipped_coprocessor_instruction_does_not_fault() {
+        // arrange
+        let mut core = Processor::new();
+        core.psr.set_z(0);
+        core.set_itstate(0x18); // it ne
+        let instruction = Instruction::MCR {
+            rt: Reg::R1,
+            coproc: 0,
+            opc1: 0,
+            opc2: 0,
+            crn: 0,
+            crm: 0,
+        };
+
+        // act
+        let result = core.execute_internal(&instruction);
+
+        // assert
+        assert_eq!(result, Ok(ExecuteResult::NotTaken));
+    }
+
+    #[test]
+    fn test_udf_yields_undefined_instruction_fault() {
+        // arrange
+        let mut core = Processor::new();
+        let instruction = Instruction::UDF {
+            imm32: 0,
+            opcode: ThumbCode::from(0xde00_u16),
+            thumb32: false,
+        };
+
+        // act
+        let result = core.execute_internal(&instruction);
+
+        // assert
+        assert_eq!(
+            result,
+            Ok(ExecuteResult::Fault {
+                fault: Fault::UndefInstr
+            })
+        );
+    }
+
+    #[test]
+    fn test_mcr_without_coprocessor_yields_nocp_fault() {
+        // arrange
+        let mut core = Processor::new();
+        let instruction = Instruction::MCR {
+            rt: Reg::R1,
+            coproc: 0,
+            opc1: 0,
+            opc2: 0,
+            crn: 0,
+            crm: 0,
+        };
+
+        // act
+        let result = core.execute_internal(&instruction);
+
+        // assert
+        assert_eq!(result, Ok(ExecuteResult::Fault { fault: Fault::NoCp }));
+    }
+
+    #[test]
+    fn test_ldr_aligned_access() {
+        // arrange
+        let mut core = Processor::new();
+        core.ccr.set_bit(3, true);
+        core.set_r(Reg::R1, 0x2000_0000);
+        core.write32(0x2000_0004, 0x1234_5678).unwrap();
+
+        let instruction = Instruction::LDR_imm {
+            rt: Reg::R0,
+            rn: Reg::R1,
+            imm32: 4,
+            index: true,
+            add: true,
+            wback: false,
+            thumb32: false,
+        };
+
+        // act
+        let result = core.execute_internal(&instruction);
+
+        // assert
+        assert_eq!(result, Ok(ExecuteResult::Taken { cycles: 2 }));
+        assert_eq!(core.get_r(Reg::R0), 0x1234_5678);
+    }
+
+    #[test]
+    fn test_ldr_unaligned_access_traps() {
+        // arrange
+        let mut core = Processor::new();
+        core.ccr.set_bit(3, true);
+        core.set_r(Reg::R0, 0);
+        core.set_r(Reg::R1, 0x2000_0001);
+
+        let instruction = Instruction::LDR_imm {
+            rt: Reg::R0,
+            rn: Reg::R1,
+            imm32: 0,
+            index: true,
+            add: true,
+            wback: true,
+            thumb32: true,
+        };
+
+        // act
+        let result = core.execute_internal(&instruction);
+
+        // assert
+        assert_eq!(result, Err(Fault::Unaligned));
+        assert_eq!(core.get_r(Reg::R0), 0);
+        assert_eq!(core.get_r(Reg::R1), 0x2000_0001);
+    }
+
+    #[cfg(any(armv7m, armv7em))]
+    #[test]
+    fn test_ldr_unaligned_access_without_trap() {
+        // arrange
+        let mut core = Processor::new();
+        core.set_r(Reg::R1, 0x2000_0001);
+        core.write32(0x2000_0000, 0x4433_2211).unwrap();
+        core.write32(0x2000_0004, 0x8877_6655).unwrap();
+
+        let instruction = Instruction::LDR_imm {
+            rt: Reg::R0,
+            rn: Reg::R1,
+            imm32: 0,
+            index: true,
+            add: true,
+            wback: false,
+            thumb32: false,
+        };
+
+        // act
+        let result = core.execute_internal(&instruction);
+
+        // assert
+        assert_eq!(result, Ok(ExecuteResult::Taken { cycles: 2 }));
+        assert_eq!(core.get_r(Reg::R0), 0x5544_3322);
+    }
+
+    #[test]
+    fn test_tbb_pc_relative_table() {
+        // arrange
+        let mut core = Processor::new();
+        core.set_pc(0x2000_0000);
+        // tbb [pc, r1] followed by the byte table
+        core.write8(0x2000_0004, 0x02).unwrap();
+        core.write8(0x2000_0005, 0x05).unwrap();
+        core.set_r(Reg::R1, 1);
+
+        let instruction = Instruction::TBB {
+            rn: Reg::PC,
+            rm: Reg::R1,
+        };
+
+        // act
+        let result = core.execute_internal(&instruction);
+
+        // assert
+        assert_eq!(result, Ok(ExecuteResult::Branched { cycles: 2 }));
+        assert_eq!(core.get_pc(), 0x2000_0004 + 2 * 5);
+    }
+
+    #[test]
+    fn test_tbh_pc_relative_table() {
+        // arrange
+        let mut core = Processor::new();
+        core.set_pc(0x2000_0000);
+        // tbh [pc, r1, lsl #1] followed by the halfword table
+        core.write16(0x2000_0004, 0x0003).unwrap();
+        core.write16(0x2000_0006, 0x0010).unwrap();
+        core.set_r(Reg::R1, 1);
+
+        let instruction = Instruction::TBH {
+            rn: Reg::PC,
+            rm: Reg::R1,
+        };
+
+        // act
+        let result = core.execute_internal(&instruction);
+
+        // assert
+        assert_eq!(result, Ok(ExecuteResult::Branched { cycles: 1 }));
+        assert_eq!(core.get_pc(), 0x2000_0004 + 2 * 0x10);
+    }
+
+    #[test]
+    fn test_ldr_address_wraps_around() {
+        // arrange
+        let mut code = [0u8; 16];
+        code[4..8].copy_from_slice(&0xcafe_babe_u32.to_le_bytes());
+
+        let mut core = Processor::new();
+        core.flash_memory(code.len(), &code);
+        core.set_r(Reg::R1, 0xffff_fffc);
+
+        let instruction = Instruction::LDR_imm {
+            rt: Reg::R0,
+            rn: Reg::R1,
+            imm32: 8,
+            index: true,
+            add: true,
+            wback: true,
+            thumb32: true,
+        };
+
+        // act
+        let result = core.execute_internal(&instruction);
+
+        // assert
+        assert_eq!(result, Ok(ExecuteResult::Taken { cycles: 2 }));
+        assert_eq!(core.get_r(Reg::R0), 0xcafe_babe);
+        assert_eq!(core.get_r(Reg::R1), 0x0000_0004);
+    }
+
+    #[test]
+    fn test_str_address_wraps_below_zero() {
+        // arrange
+        let mut core = Processor::new();
+        core.set_r(Reg::R0, 0x1234_5678);
+        core.set_r(Reg::R1, 0x0000_0004);
+
+        let instruction = Instruction::STR_imm {
+            rt: Reg::R0,
+            rn: Reg::R1,
+            imm32: 8,
+            index: true,
+            add: false,
+            wback: false,
+            thumb32: true,
+        };
+
+        // act
+        let result = core.execute_internal(&instruction);
+
+        // assert: 0xffff_fffc is unmapped, so a fault is raised instead of a panic
+        assert_eq!(
+            result,
+            Err(Fault::PreciseErr {
+                address: 0xffff_fffc
+            })
+        );
+    }
+
+    #[test]
+    fn test_ldr_unmapped_address_bus_fault() {
+        // arrange
+        let mut core = Processor::new();
+        core.set_r(Reg::R1, 0x6000_0000);
+
+        let instruction = Instruction::LDR_imm {
+            rt: Reg::R0,
+            rn: Reg::R1,
+            imm32: 0,
+            index: true,
+            add: true,
+            wback: false,
+            thumb32: false,
+        };
+
+        // act
+        let result = core.execute_internal(&instruction);
+
+        // assert
+        assert_eq!(
+            result,
+            Err(Fault::PreciseErr {
+                address: 0x6000_0000
+            })
+        );
+        assert_eq!(Exception::from(result.unwrap_err()), Exception::BusFault);
+    }
+
+    #[cfg(any(armv7m, armv7em))]
+    #[test]
+    fn test_ldr_unmapped_address_busfault_enabled() {
+        // arrange
+        let mut core = fault_test_processor();
+        core.write32(0xE000_ED24, 1 << 17).unwrap();
+        core.set_r(Reg::R1, 0x6000_0000);
+        let instruction = Instruction::LDR_imm {
+            rt: Reg::R0,
+            rn: Reg::R1,
+            imm32: 0,
+            index: true,
+            add: true,
+            wback: false,
+            thumb32: false,
+        };
+
+        // act
+        core.execute(&instruction, instruction_size(&instruction));
+
+        // assert
+        assert_eq!(core.get_pc(), 0x200);
+        assert_eq!(core.psr.get_isr_number(), Exception::BusFault.into());
+        // BFSR.PRECISERR and BFSR.BFARVALID
+        assert_eq!(core.read32(0xE000_ED28).unwrap(), (1 << 9) | (1 << 15));
+        assert_eq!(core.read32(0xE000_ED38).unwrap(), 0x6000_0000);
+    }
+
+    #[test]
+    fn test_msr_apsr_g_leaves_nzcvq() {
+        // arrange
+        let mut core = Processor::new();
+        core.psr.value = 0xf800_0000;
+        core.set_r(Reg::R0, 0x000f_0000);
+
+        // msr APSR_g, r0
+        let instruction = Instruction::MSR_reg {
+            rn: Reg::R0,
+            sysm: 0,
+            mask: 0b01,
+        };
+
+        // act
+        core.execute_internal(&instruction).unwrap();
+
+        // assert
+        assert_eq!(core.psr.value, 0xf80f_0000);
+    }
+
+    #[test]
+    fn test_msr_apsr_nzcvq_leaves_ge() {
+        // arrange
+        let mut core = Processor::new();
+        core.psr.value = 0x000a_0000;
+        core.set_r(Reg::R0, 0xffff_ffff);
+
+        // msr APSR_nzcvq, r0
+        let instruction = Instruction::MSR_reg {
+            rn: Reg::R0,
+            sysm: 0,
+            mask: 0b10,
+        };
+
+        // act
+        core.execute_internal(&instruction).unwrap();
+
+        // assert
+        assert_eq!(core.psr.value, 0xf80a_0000);
+    }
+
+    #[test]
+    fn test_step_instruction() {
+        // Arrange
+        let code = [
+            0x01, 0x20, // movs r0, #1
+            0x4f, 0xf0, 0x02, 0x01, // mov.w r1, #2
+            0xfe, 0xe7, // b .
+        ];
+        let mut core = Processor::new();
+        core.flash_memory(code.len(), &code);
+        core.set_pc(0);
+
+        // Act & Assert
+        assert_eq!(core.step_instruction(), StepResult::Executed { cycles: 1 });
+        assert_eq!(core.get_pc(), 2);
+        assert_eq!(core.get_r(Reg::R0), 1);
+
+        assert_eq!(core.step_instruction(), StepResult::Executed { cycles: 1 });
+        assert_eq!(core.get_pc(), 6);
+        assert_eq!(core.get_r(Reg::R1), 2);
+
+        assert!(matches!(
+            core.step_instruction(),
+            StepResult::Branched { .. }
+        ));
+        assert_eq!(core.get_pc(), 6);
+        assert_eq!(core.instruction_count, 3);
+    }
+
+    #[test]
+    fn test_step_instruction_while_sleeping() {
+        // Arrange
+        let code = [
+            0x30, 0xbf, // wfi
+            0x01, 0x20, // movs r0, #1
+            0xfe, 0xe7, // b .
+        ];
+        let mut core = Processor::new();
+        core.flash_memory(code.len(), &code);
+        core.set_pc(0);
+        core.step_instruction();
+
+        // Act
+        let result = core.step_instruction();
+
+        // Assert
+        assert_eq!(result, StepResult::Sleeping { cycles: 1 });
+        assert_eq!(core.get_pc(), 2);
+        assert_eq!(core.get_r(Reg::R0), 0);
+        assert_eq!(core.instruction_count, 1);
+    }
+
+    #[test]
+    fn test_step_instruction_after_exit() {
+        // Arrange
+        let code = [
+            0x20, 0x20, // movs r0, #0x20 (SYS_EXIT_EXTENDED)
+            0xab, 0xbe, // bkpt 0xab
+            0x01, 0x21, // movs r1, #1
+        ];
+        let mut core = Processor::new();
+        core.flash_memory(code.len(), &code);
+        core.semihost(Some(Box::new(|_: &SemihostingCommand| {
+            SemihostingResponse::SysExitExtended {
+                success: true,
+                stop: true,
+            }
+        })));
+        core.set_pc(0);
+        core.step_instruction();
+        core.step_instruction();
+
+        // Act
+        let result = core.step_instruction();
+
+        // Assert
+        assert_eq!(result, StepResult::Halted { cycles: 0 });
+        assert_eq!(core.get_r(Reg::R1), 0);
+    }
+
+    #[test]
+    fn test_run_until_breakpoint() {
+        // Arrange
+        let code = [
+            0x01, 0x20, // movs r0, #1
+            0x02, 0x21, // movs r1, #2
+            0x03, 0x22, // movs r2, #3
+            0xfe, 0xe7, // b .
+        ];
+        let mut core = Processor::new();
+        core.flash_memory(code.len(), &code);
+        core.set_pc(0);
+        let breakpoints: HashSet<u32> = [4].iter().cloned().collect();
+
+        // Act
+        let exit = core.run(1000, &breakpoints);
+
+        // Assert
+        assert_eq!(exit, RunExit::Breakpoint { address: 4 });
+        assert_eq!(core.get_r(Reg::R1), 2);
+        assert_eq!(core.get_r(Reg::R2), 0);
+
+        // Act: continue without breakpoints until the budget is used
+        let exit = core.run(100, &HashSet::new());
+
+        // Assert
+        assert_eq!(exit, RunExit::CycleBudget);
+        assert_eq!(core.get_pc(), 6);
+    }
+
+    #[test]
+    fn test_run_breakpoint_at_start() {
+        // Arrange
+        let code = [
+            0x01, 0x20, // movs r0, #1
+            0x02, 0x21, // movs r1, #2
+            0xfc, 0xe7, // b 0
+        ];
+        let mut core = Processor::new();
+        core.flash_memory(code.len(), &code);
+        core.set_pc(0);
+        let breakpoints: HashSet<u32> = [0].iter().cloned().collect();
+
+        // Act
+        let exit = core.run(1000, &breakpoints);
+
+        // Assert
+        assert_eq!(exit, RunExit::Breakpoint { address: 0 });
+        assert_eq!(core.get_r(Reg::R0), 0);
+
+        // Act: resume, stepping off the breakpoint once around the loop
+        let exit = core.run(1000, &breakpoints);
+
+        // Assert
+        assert_eq!(exit, RunExit::Breakpoint { address: 0 });
+        assert_eq!(core.get_r(Reg::R0), 1);
+        assert_eq!(core.get_r(Reg::R1), 2);
+        assert_eq!(core.instruction_count, 3);
+    }
+
+    fn run_sum_loop(decode_cache: bool) -> Processor {
+        let code = [
+            0x64, 0x20, // movs r0, #100
+            0x00, 0x21, // movs r1, #0
+            0x09, 0x18, // loop: adds r1, r1, r0
+            0x01, 0x38, // subs r0, #1
+            0xfc, 0xd1, // bne loop
+            0xfe, 0xe7, // b .
+        ];
+        let mut core = Processor::new();
+        core.flash_memory(code.len(), &code);
+        core.decode_cache(decode_cache);
+        core.set_pc(0);
+        core.run(2000, &HashSet::new());
+        core
+    }
+
+    #[test]
+    fn test_decode_cache_gives_identical_results() {
+        // Arrange & Act
+        let uncached = run_sum_loop(false);
+        let cached = run_sum_loop(true);
+
+        // Assert
+        assert_eq!(cached.get_r(Reg::R1), 5050);
+        assert_eq!(cached.r0_12, uncached.r0_12);
+        assert_eq!(cached.pc, uncached.pc);
+        assert_eq!(cached.psr.value, uncached.psr.value);
+        assert_eq!(cached.cycle_count, uncached.cycle_count);
+        assert_eq!(cached.instruction_count, uncached.instruction_count);
+        // every instruction of the program was decoded exactly once
+        let decoded = cached.decode_cache.as_ref().unwrap();
+        assert_eq!(decoded.iter().filter(|entry| entry.is_some()).count(), 6);
+    }
+
+    fn step_store_loop(decode_cache: bool) -> Processor {
+        let code: [u16; 11] = [
+            0x2032, // 0x00: movs r0, #50
+            0x2120, // 0x02: movs r1, #0x20
+            0x0609, // 0x04: lsls r1, r1, #24
+            0x6008, // 0x06: loop: str r0, [r1, #0]
+            0x3104, // 0x08: adds r1, #4
+            0xf04f, 0x0201, // 0x0a: mov.w r2, #1
+            0x181b, // 0x0e: adds r3, r3, r0
+            0x3801, // 0x10: subs r0, #1
+            0xd1f8, // 0x12: bne loop
+            0xe7fe, // 0x14: b .
+        ];
+        let mut core = Processor::new();
+        core.flash_memory(0x100, &[]).flash_writable(true);
+        for (i, opcode) in code.iter().enumerate() {
+            core.write16(2 * i as u32, *opcode).unwrap();
+        }
+        core.decode_cache(decode_cache);
+        core.set_pc(0);
+        for _ in 0..400 {
+            core.step();
+        }
+        core
+    }
+
+    #[test]
+    fn test_step_with_decode_cache_gives_identical_results() {
+        // Arrange & Act
+        let mut uncached = step_store_loop(false);
+        let mut cached = step_store_loop(true);
+
+        // Assert
+        assert_eq!(cached.get_r(Reg::R3), 1275);
+        assert_eq!(cached.get_pc(), 0x14);
+        assert_eq!(cached.r0_12, uncached.r0_12);
+        assert_eq!(cached.psr.value, uncached.psr.value);
+        assert_eq!(cached.cycle_count, uncached.cycle_count);
+        assert_eq!(cached.instruction_count, uncached.instruction_count);
+        assert_eq!(
+            cached.read_slice(0x2000_0000, 200).unwrap(),
+            uncached.read_slice(0x2000_0000, 200).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_self_modifying_code_executes_new_instruction() {
+        for &decode_cache in &[false, true] {
+            // arrange
+            let code: [u16; 6] = [
+                0x2001, // 0x00: movs r0, #1 (rewritten to movs r0, #2)
+                0x2802, // 0x02: cmp r0, #2
+                0xd001, // 0x04: beq 0x0a
+                0x8025, // 0x06: strh r5, [r4, #0]
+                0xe7fa, // 0x08: b 0x00
+                0xe7fe, // 0x0a: b .
+            ];
+            let mut core = Processor::new();
+            core.flash_memory(0x100, &[]).flash_writable(true);
+            for (i, opcode) in code.iter().enumerate() {
+                core.write16(2 * i as u32, *opcode).unwrap();
+            }
+            core.decode_cache(decode_cache);
+            core.set_r(Reg::R4, 0);
+            core.set_r(Reg::R5, 0x2002); // movs r0, #2
+            core.set_pc(0);
+
+            // act
+            for _ in 0..9 {
+                core.step_instruction();
+            }
+
+            // assert
+            assert_eq!(core.get_r(Reg::R0), 2);
+            assert_eq!(core.get_pc(), 0x0a);
+        }
+    }
+
+    #[test]
+    fn test_step_executes_rewritten_instruction() {
+        // arrange: the same program as above, stepped like the simulation
+        // loop does with cached instructions
+        let code = [
+            0x01, 0x20, // 0x00: movs r0, #1 (rewritten to movs r0, #2)
+            0x02, 0x28, // 0x02: cmp r0, #2
+            0x01, 0xd0, // 0x04: beq 0x0a
+            0x25, 0x80, // 0x06: strh r5, [r4, #0]
+            0xfa, 0xe7, // 0x08: b 0x00
+            0xfe, 0xe7, // 0x0a: b .
+        ];
+        let mut core = Processor::new();
+        core.flash_memory(0x100, &code).flash_writable(true);
+        core.cache_instructions();
+        core.set_r(Reg::R4, 0);
+        core.set_r(Reg::R5, 0x2002); // movs r0, #2
+        core.set_pc(0);
+
+        // act
+        for _ in 0..9 {
+            core.step();
+        }
+
+        // assert
+        assert_eq!(core.get_r(Reg::R0), 2);
+        assert_eq!(core.get_pc(), 0x0a);
+    }
+
+    #[test]
+    fn test_write_invalidates_overlapping_thumb32_decode() {
+        // arrange
+        let mut core = Processor::new();
+        core.flash_memory(0x100, &[]).flash_writable(true);
+        core.decode_cache(true);
+        core.write16(0, 0xf04f).unwrap(); // mov.w r0, #1
+        core.write16(2, 0x0001).unwrap();
+        core.set_pc(0);
+        core.step_instruction();
+
+        // act
+        core.write16(2, 0x0002).unwrap(); // mov.w r0, #2
+        core.set_pc(0);
+        core.step_instruction();
+
+        // assert
+        assert_eq!(core.get_r(Reg::R0), 2);
+    }
+
+    #[test]
+    fn test_run_until_semihosting_exit() {
+        // Arrange
+        let code = [
+            0x20, 0x20, // movs r0, #0x20 (SYS_EXIT_EXTENDED)
+            0xab, 0xbe, // bkpt 0xab
+            0xfe, 0xe7, // b .
+        ];
+        let mut core = Processor::new();
+        core.flash_memory(code.len(), &code);
+        core.semihost(Some(Box::new(|cmd: &SemihostingCommand| {
+            assert!(matches!(cmd, SemihostingCommand::SysExitExtended { .. }));
+            SemihostingResponse::SysExitExtended {
+                success: true,
+                stop: true,
+            }
+        })));
+        core.write32(0x2000_0000, 0x20026).unwrap(); // ADP_Stopped_ApplicationExit
+        core.write32(0x2000_0004, 3).unwrap();
+        core.set_r(Reg::R1, 0x2000_0000);
+        core.set_pc(0);
+
+        // Act
+        let exit = core.run(1000, &HashSet::new());
+
+        // Assert
+        assert_eq!(exit, RunExit::Exited { code: 3 });
+        assert_eq!(core.exit_code(), Some(3));
+        assert_eq!(core.get_pc(), 4);
+    }
+
+    #[test]
+    fn test_run_until_bkpt_instruction() {
+        // Arrange
+        let code = [
+            0x01, 0x20, // movs r0, #1
+            0x00, 0xbe, // bkpt #0
+            0x02, 0x20, // movs r0, #2
+            0xfe, 0xe7, // b .
+        ];
+        let mut core = Processor::new();
+        core.flash_memory(code.len(), &code);
+        core.set_pc(0);
+
+        // Act
+        let exit = core.run(1000, &HashSet::new());
+
+        // Assert
+        assert_eq!(exit, RunExit::BreakpointHit { address: 2, imm: 0 });
+        assert_eq!(core.breakpoint_hit(), Some(0));
+        assert_eq!(core.get_r(Reg::R0), 1);
+        assert_eq!(core.get_pc(), 2);
+        assert!(core.dfsr.get_bit(1));
+    }
+
+    #[test]
+    #[cfg(any(armv7m, armv7em))]
+    fn test_bkpt_instruction_enters_debug_monitor() {
+        // Arrange
+        let mut code = vec![0_u8; 0x110];
+        code[0x30..0x34].copy_from_slice(&0x109_u32.to_le_bytes()); // DebugMonitor
+        code[0x100..0x102].copy_from_slice(&0xbe07_u16.to_le_bytes()); // bkpt #7
+        code[0x108..0x10a].copy_from_slice(&0xe7fe_u16.to_le_bytes()); // b .
+        let mut core = Processor::new();
+        core.flash_memory(code.len(), &code);
+        core.set_msp(0x2000_1000);
+        core.set_pc(0x100);
+        core.execution_priority = core.get_execution_priority();
+        core.write32(0xE000_EDFC, DEMCR_MON_EN).unwrap();
+
+        // Act
+        let result = core.step_instruction();
+
+        // Assert
+        assert!(matches!(result, StepResult::Branched { .. }));
+        assert_eq!(core.breakpoint_hit(), None);
+        assert_eq!(core.get_pc(), 0x108);
+        assert_eq!(core.psr.get_isr_number(), 12);
+        // the stacked return address is the BKPT instruction
+        assert_eq!(core.read32(0x2000_1000 - 32 + 24).unwrap(), 0x100);
+    }
+
+    #[test]
+    fn test_run_stops_while_halted() {
+        // Arrange
+        let code = [
+            0x01, 0x20, // movs r0, #1
+            0x02, 0x21, // movs r1, #2
+            0x03, 0x22, // movs r2, #3
+            0xfe, 0xe7, // b .
+        ];
+        let mut core = Processor::new();
+        core.flash_memory(code.len(), &code);
+        core.set_pc(0);
+        // halt like a debugger once movs r1 has executed
+        core.instruction_hook(Some(Box::new(|processor: &mut Processor, _| {
+            if processor.get_pc() == 2 {
+                processor.halt();
+            }
+            None
+        })));
+
+        // Act
+        let exit = core.run(1000, &HashSet::new());
+
+        // Assert
+        assert_eq!(exit, RunExit::DebugHalt);
+        assert!(core.is_halted());
+        assert_eq!(core.get_r(Reg::R1), 2);
+        assert_eq!(core.get_pc(), 4);
+        assert_eq!(
+            core.read32(0xE000_EDF0).unwrap() & (DHCSR_S_HALT | DHCSR_C_HALT),
+            DHCSR_S_HALT | DHCSR_C_HALT
+        );
+
+        // Act: nothing executes while halted
+        let cycles = core.cycle_count;
+        let exit = core.run(1000, &HashSet::new());
+        let result = core.step_instruction();
+
+        // Assert
+        assert_eq!(exit, RunExit::DebugHalt);
+        assert_eq!(result, StepResult::Halted { cycles: 0 });
+        assert_eq!(core.get_r(Reg::R2), 0);
+        assert_eq!(core.cycle_count, cycles);
+
+        // Act
+        core.instruction_hook(None);
+        core.resume();
+        let exit = core.run(100, &HashSet::new());
+
+        // Assert
+        assert_eq!(exit, RunExit::CycleBudget);
+        assert!(!core.is_halted());
+        assert_eq!(core.get_r(Reg::R2), 3);
+        assert_eq!(core.read32(0xE000_EDF0).unwrap() & DHCSR_S_HALT, 0);
+    }
+
+    #[test]
+    fn test_program_cannot_enable_halting_debug() {
+        // Arrange
+        let code = [
+            0x01, 0x48, // ldr r0, [pc, #4]
+            0x02, 0x49, // ldr r1, [pc, #8]
+            0x08, 0x60, // str r0, [r1]
+            0xfe, 0xe7, // b .
+            0x03, 0x00, 0x5f, 0xa0, // DBGKEY | C_HALT | C_DEBUGEN
+            0xf0, 0xed, 0x00, 0xe0, // DHCSR
+        ];
+        let mut core = Processor::new();
+        core.flash_memory(code.len(), &code);
+        core.set_pc(0);
+
+        // Act
+        let exit = core.run(100, &HashSet::new());
+
+        // Assert
+        assert_eq!(exit, RunExit::CycleBudget);
+        assert!(!core.is_halted());
+        assert_eq!(core.read32(0xE000_EDF0).unwrap() & DHCSR_C_DEBUGEN, 0);
+    }
+
+    #[test]
+    fn test_debug_single_step() {
+        // Arrange
+        let code = [
+            0x01, 0x20, // movs r0, #1
+            0x02, 0x21, // movs r1, #2
+            0x03, 0x22, // movs r2, #3
+            0xfe, 0xe7, // b .
+        ];
+        let mut core = Processor::new();
+        core.flash_memory(code.len(), &code);
+        core.set_pc(0);
+        core.halt();
+        core.dfsr = 0;
+
+        // Act: resume with single stepping enabled
+        core.write32(0xE000_EDF0, DHCSR_DBGKEY | DHCSR_C_DEBUGEN | DHCSR_C_STEP)
+            .unwrap();
+        let exit = core.run(1000, &HashSet::new());
+
+        // Assert
+        assert_eq!(exit, RunExit::DebugHalt);
+        assert_eq!(core.get_r(Reg::R0), 1);
+        assert_eq!(core.get_r(Reg::R1), 0);
+        assert_eq!(core.get_pc(), 2);
+        assert!(core.dfsr.get_bit(0));
+        assert_ne!(core.read32(0xE000_EDF0).unwrap() & DHCSR_S_HALT, 0);
+
+        // Act: step again, then run freely
+        core.write32(0xE000_EDF0, DHCSR_DBGKEY | DHCSR_C_DEBUGEN | DHCSR_C_STEP)
+            .unwrap();
+        let result = core.step_instruction();
+        let halted = core.is_halted();
+        core.write32(0xE000_EDF0, DHCSR_DBGKEY | DHCSR_C_DEBUGEN)
+            .unwrap();
+        let exit = core.run(100, &HashSet::new());
+
+        // Assert
+        assert!(matches!(result, StepResult::Executed { .. }));
+        assert!(halted);
+        assert_eq!(core.get_r(Reg::R1), 2);
+        assert_eq!(exit, RunExit::CycleBudget);
+        assert_eq!(core.get_r(Reg::R2), 3);
+    }
 }
@@ -17,13 +17,16 @@ pub enum Fault {
     ///
     Forced,
     ///
-    ///
+    /// Instruction fetch violated the MPU region permissions.
     ///
     IAccViol,
     ///
+    /// Data access violated the MPU region permissions.
     ///
-    ///
-    DAccViol,
+    DAccViol {
+        /// address of the faulting access
+        address: u32,
+    },
     ///
     ///
     ///
@@ -37,6 +40,13 @@ pub enum Fault {
     ///
     Stkerr,
     ///
+    /// Precise data bus error, for example an access to an unmapped address.
+    ///
+    PreciseErr {
+        /// address of the faulting access
+        address: u32,
+    },
+    ///
     /// Unknown instruction was tried to be executed.
     ///
     UndefInstr,
@@ -49,4 +59,12 @@ pub enum Fault {
     ///
     ///
     DivByZero,
+    ///
+    /// Coprocessor instruction was executed without a coprocessor present.
+    ///
+    NoCp,
+    ///
+    /// Stack pointer was moved below the configured stack limit.
+    ///
+    StkOf,
 }
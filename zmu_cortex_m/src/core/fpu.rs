@@ -0,0 +1,439 @@
+//!
+//! Floating point arithmetic helpers for the FP extension
+//!
+//! Operands and results are raw single precision bit patterns, as stored in
+//! the extension register bank. Each operation returns the result together
+//! with the cumulative exception bits it raised, ready to be or'ed to FPSCR.
+//!
+//! Conversions between integers and floating point values honor the rounding
+//! mode selected with FPSCR.RMode, arithmetic always rounds to nearest.
+//! Flush-to-zero mode is ignored. Underflow is detected after rounding.
+//!
+//! Floating point values are compared exactly on purpose: the helpers detect
+//! rounding by comparing against exactly computed results.
+//!
+#![allow(clippy::float_cmp)]
+
+use crate::core::bits::Bits;
+
+/// FPSCR Invalid Operation cumulative exception bit
+pub const FPSCR_IOC: u32 = 1 << 0;
+/// FPSCR Division by Zero cumulative exception bit
+pub const FPSCR_DZC: u32 = 1 << 1;
+/// FPSCR Overflow cumulative exception bit
+pub const FPSCR_OFC: u32 = 1 << 2;
+/// FPSCR Underflow cumulative exception bit
+pub const FPSCR_UFC: u32 = 1 << 3;
+/// FPSCR Inexact cumulative exception bit
+pub const FPSCR_IXC: u32 = 1 << 4;
+
+/// FPSCR Default NaN mode control bit position
+const FPSCR_DN_BIT: usize = 25;
+
+///
+/// Rounding modes selectable with FPSCR.RMode
+///
+#[derive(PartialEq, Debug, Copy, Clone)]
+enum FPRounding {
+    TieEven,
+    PosInf,
+    NegInf,
+    Zero,
+}
+
+fn rounding_mode(fpscr: u32) -> FPRounding {
+    match fpscr.get_bits(22..24) {
+        0b00 => FPRounding::TieEven,
+        0b01 => FPRounding::PosInf,
+        0b10 => FPRounding::NegInf,
+        _ => FPRounding::Zero,
+    }
+}
+
+/// Default NaN of single precision
+const DEFAULT_NAN: u32 = 0x7fc0_0000;
+
+/// Quiet bit of a single precision NaN
+const QUIET_BIT: usize = 22;
+
+fn is_nan(value: u32) -> bool {
+    f32::from_bits(value).is_nan()
+}
+
+fn is_signaling_nan(value: u32) -> bool {
+    is_nan(value) && !value.get_bit(QUIET_BIT)
+}
+
+///
+/// Pick the NaN result of a two operand operation, as `FPProcessNaNs` does.
+///
+/// Signaling NaNs take precedence over quiet NaNs and the first operand
+/// over the second one. Returns `None` if neither operand is a NaN.
+///
+fn process_nans(op1: u32, op2: u32, fpscr: u32) -> Option<(u32, u32)> {
+    let (mut value, exceptions) = if is_signaling_nan(op1) {
+        (op1, FPSCR_IOC)
+    } else if is_signaling_nan(op2) {
+        (op2, FPSCR_IOC)
+    } else if is_nan(op1) {
+        (op1, 0)
+    } else if is_nan(op2) {
+        (op2, 0)
+    } else {
+        return None;
+    };
+
+    if fpscr.get_bit(FPSCR_DN_BIT) {
+        value = DEFAULT_NAN;
+    } else {
+        value.set_bit(QUIET_BIT, true);
+    }
+    Some((value, exceptions))
+}
+
+///
+/// Compute the exceptions of a rounded, non NaN result
+///
+fn round_exceptions(result: f32, finite_operands: bool, inexact: bool) -> u32 {
+    if result.is_infinite() && finite_operands {
+        FPSCR_OFC | FPSCR_IXC
+    } else if inexact && (result == 0.0 || result.is_subnormal()) {
+        FPSCR_UFC | FPSCR_IXC
+    } else if inexact {
+        FPSCR_IXC
+    } else {
+        0
+    }
+}
+
+///
+/// Single precision addition
+///
+/// return tuple of (result, exceptions)
+///
+pub fn fp_add(op1: u32, op2: u32, fpscr: u32) -> (u32, u32) {
+    if let Some(nan) = process_nans(op1, op2, fpscr) {
+        return nan;
+    }
+    let (a, b) = (f32::from_bits(op1), f32::from_bits(op2));
+    let sum = a + b;
+    if sum.is_nan() {
+        // inf + -inf
+        return (DEFAULT_NAN, FPSCR_IOC);
+    }
+
+    // error of the rounded sum, exact as long as the sum did not overflow
+    let b_virtual = sum - a;
+    let error = (a - (sum - b_virtual)) + (b - b_virtual);
+    let inexact = sum.is_finite() && error != 0.0;
+
+    (
+        sum.to_bits(),
+        round_exceptions(sum, a.is_finite() && b.is_finite(), inexact),
+    )
+}
+
+///
+/// Single precision subtraction
+///
+/// return tuple of (result, exceptions)
+///
+pub fn fp_sub(op1: u32, op2: u32, fpscr: u32) -> (u32, u32) {
+    // NaN operands are propagated with their original sign
+    if let Some(nan) = process_nans(op1, op2, fpscr) {
+        return nan;
+    }
+    fp_add(op1, op2 ^ 0x8000_0000, fpscr)
+}
+
+///
+/// Single precision multiplication
+///
+/// return tuple of (result, exceptions)
+///
+pub fn fp_mul(op1: u32, op2: u32, fpscr: u32) -> (u32, u32) {
+    if let Some(nan) = process_nans(op1, op2, fpscr) {
+        return nan;
+    }
+    let (a, b) = (f32::from_bits(op1), f32::from_bits(op2));
+    let product = a * b;
+    if product.is_nan() {
+        // zero * inf
+        return (DEFAULT_NAN, FPSCR_IOC);
+    }
+
+    // the product of two single precision values is exact in double precision
+    let exact = f64::from(a) * f64::from(b);
+    let inexact = product.is_finite() && f64::from(product) != exact;
+
+    (
+        product.to_bits(),
+        round_exceptions(product, a.is_finite() && b.is_finite(), inexact),
+    )
+}
+
+///
+/// Single precision division
+///
+/// return tuple of (result, exceptions)
+///
+pub fn fp_div(op1: u32, op2: u32, fpscr: u32) -> (u32, u32) {
+    if let Some(nan) = process_nans(op1, op2, fpscr) {
+        return nan;
+    }
+    let (a, b) = (f32::from_bits(op1), f32::from_bits(op2));
+    let quotient = a / b;
+    if quotient.is_nan() {
+        // zero / zero, inf / inf
+        return (DEFAULT_NAN, FPSCR_IOC);
+    }
+    if b == 0.0 && a.is_finite() {
+        return (quotient.to_bits(), FPSCR_DZC);
+    }
+
+    // quotient * divisor is exact in double precision
+    let inexact = quotient.is_finite() && f64::from(quotient) * f64::from(b) != f64::from(a);
+
+    (
+        quotient.to_bits(),
+        round_exceptions(quotient, a.is_finite() && b.is_finite(), inexact),
+    )
+}
+
+///
+/// Round a value to an integral value with given rounding mode
+///
+fn round_integral(value: f64, rounding: FPRounding) -> f64 {
+    match rounding {
+        FPRounding::TieEven => {
+            let rounded = value.round();
+            if (rounded - value).abs() == 0.5 && rounded % 2.0 != 0.0 {
+                rounded - value.signum()
+            } else {
+                rounded
+            }
+        }
+        FPRounding::PosInf => value.ceil(),
+        FPRounding::NegInf => value.floor(),
+        FPRounding::Zero => value.trunc(),
+    }
+}
+
+///
+/// Convert single precision value to a 32-bit integer, as `FPToFixed` does.
+///
+/// Rounds toward zero if `round_zero` is set, otherwise with FPSCR.RMode.
+/// NaNs convert to zero and out of range values saturate, both raising
+/// Invalid Operation.
+///
+/// return tuple of (result, exceptions)
+///
+pub fn fp_to_int(op: u32, unsigned: bool, round_zero: bool, fpscr: u32) -> (u32, u32) {
+    let value = f32::from_bits(op);
+    if value.is_nan() {
+        return (0, FPSCR_IOC);
+    }
+    let rounding = if round_zero {
+        FPRounding::Zero
+    } else {
+        rounding_mode(fpscr)
+    };
+    let value = f64::from(value);
+    let rounded = round_integral(value, rounding);
+    let (min, max) = if unsigned {
+        (0.0, f64::from(u32::MAX))
+    } else {
+        (f64::from(i32::MIN), f64::from(i32::MAX))
+    };
+
+    if rounded < min {
+        (min as i64 as u32, FPSCR_IOC)
+    } else if rounded > max {
+        (max as i64 as u32, FPSCR_IOC)
+    } else {
+        let exceptions = if rounded == value { 0 } else { FPSCR_IXC };
+        (rounded as i64 as u32, exceptions)
+    }
+}
+
+///
+/// Bit pattern of the next representable value above (`up`) or below a
+/// non zero value
+///
+fn next_toward(value: f32, up: bool) -> u32 {
+    if (value > 0.0) == up {
+        value.to_bits() + 1
+    } else {
+        value.to_bits() - 1
+    }
+}
+
+///
+/// Convert a 32-bit integer to single precision value, rounding with
+/// FPSCR.RMode.
+///
+/// return tuple of (result, exceptions)
+///
+#[allow(clippy::cast_precision_loss)]
+pub fn int_to_fp(op: u32, unsigned: bool, fpscr: u32) -> (u32, u32) {
+    let exact = if unsigned {
+        i64::from(op)
+    } else {
+        i64::from(op as i32)
+    };
+    // `as` rounds to nearest, ties to even
+    let nearest = exact as f32;
+    let error = (exact as f64) - f64::from(nearest);
+    if error == 0.0 {
+        return (nearest.to_bits(), 0);
+    }
+
+    let result = match rounding_mode(fpscr) {
+        FPRounding::PosInf if error > 0.0 => next_toward(nearest, true),
+        FPRounding::NegInf if error < 0.0 => next_toward(nearest, false),
+        FPRounding::Zero if (error > 0.0) != (exact > 0) => next_toward(nearest, exact < 0),
+        _ => nearest.to_bits(),
+    };
+    (result, FPSCR_IXC)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bits(value: f32) -> u32 {
+        value.to_bits()
+    }
+
+    #[test]
+    fn test_fp_add_exact_and_inexact() {
+        assert_eq!(fp_add(bits(1.5), bits(2.25), 0), (bits(3.75), 0));
+        assert_eq!(fp_sub(bits(1.5), bits(2.25), 0), (bits(-0.75), 0));
+        assert_eq!(fp_add(bits(1.0), bits(1.0e-10), 0), (bits(1.0), FPSCR_IXC));
+        assert_eq!(
+            fp_add(bits(f32::INFINITY), bits(f32::NEG_INFINITY), 0),
+            (DEFAULT_NAN, FPSCR_IOC)
+        );
+    }
+
+    #[test]
+    fn test_fp_mul_overflow_and_underflow() {
+        assert_eq!(fp_mul(bits(3.0), bits(-0.5), 0), (bits(-1.5), 0));
+        assert_eq!(
+            fp_mul(bits(f32::MAX), bits(2.0), 0),
+            (bits(f32::INFINITY), FPSCR_OFC | FPSCR_IXC)
+        );
+        assert_eq!(
+            fp_mul(bits(1.0e-30), bits(1.0e-30), 0),
+            (bits(0.0), FPSCR_UFC | FPSCR_IXC)
+        );
+        assert_eq!(
+            fp_mul(bits(0.0), bits(f32::INFINITY), 0),
+            (DEFAULT_NAN, FPSCR_IOC)
+        );
+    }
+
+    #[test]
+    fn test_fp_div() {
+        assert_eq!(fp_div(bits(1.0), bits(4.0), 0), (bits(0.25), 0));
+        assert_eq!(
+            fp_div(bits(1.0), bits(3.0), 0),
+            (bits(1.0 / 3.0), FPSCR_IXC)
+        );
+        assert_eq!(
+            fp_div(bits(-2.0), bits(0.0), 0),
+            (bits(f32::NEG_INFINITY), FPSCR_DZC)
+        );
+        assert_eq!(fp_div(bits(0.0), bits(0.0), 0), (DEFAULT_NAN, FPSCR_IOC));
+        assert_eq!(
+            fp_div(bits(f32::INFINITY), bits(0.0), 0),
+            (bits(f32::INFINITY), 0)
+        );
+    }
+
+    #[test]
+    fn test_fp_to_int_rounding_and_saturation() {
+        assert_eq!(
+            fp_to_int(bits(-2.75), false, true, 0),
+            (-2_i32 as u32, FPSCR_IXC)
+        );
+        assert_eq!(fp_to_int(bits(2.5), false, false, 0), (2, FPSCR_IXC));
+        assert_eq!(fp_to_int(bits(3.5), false, false, 0), (4, FPSCR_IXC));
+        assert_eq!(
+            fp_to_int(bits(2.25), true, false, 0b01 << 22),
+            (3, FPSCR_IXC)
+        );
+        assert_eq!(
+            fp_to_int(bits(-2.25), false, false, 0b10 << 22),
+            (-3_i32 as u32, FPSCR_IXC)
+        );
+        assert_eq!(
+            fp_to_int(bits(2_147_483_648.0), false, true, 0),
+            (0x7fff_ffff, FPSCR_IOC)
+        );
+        assert_eq!(
+            fp_to_int(bits(2_147_483_648.0), true, true, 0),
+            (0x8000_0000, 0)
+        );
+        assert_eq!(
+            fp_to_int(bits(-2_147_483_648.0), false, true, 0),
+            (0x8000_0000, 0)
+        );
+        assert_eq!(fp_to_int(bits(-1.0), true, true, 0), (0, FPSCR_IOC));
+        assert_eq!(
+            fp_to_int(bits(f32::INFINITY), true, true, 0),
+            (0xffff_ffff, FPSCR_IOC)
+        );
+        assert_eq!(fp_to_int(DEFAULT_NAN, false, true, 0), (0, FPSCR_IOC));
+    }
+
+    #[test]
+    fn test_int_to_fp_rounding() {
+        assert_eq!(int_to_fp(-7_i32 as u32, false, 0), (bits(-7.0), 0));
+        assert_eq!(
+            int_to_fp(0x8000_0000, false, 0),
+            (bits(-2_147_483_648.0), 0)
+        );
+        assert_eq!(int_to_fp(0x8000_0000, true, 0), (bits(2_147_483_648.0), 0));
+        // 2^24 + 1 is not representable
+        assert_eq!(
+            int_to_fp(16_777_217, false, 0),
+            (bits(16_777_216.0), FPSCR_IXC)
+        );
+        assert_eq!(
+            int_to_fp(16_777_217, false, 0b01 << 22),
+            (bits(16_777_218.0), FPSCR_IXC)
+        );
+        assert_eq!(
+            int_to_fp(-16_777_217_i32 as u32, false, 0b11 << 22),
+            (bits(-16_777_216.0), FPSCR_IXC)
+        );
+        assert_eq!(
+            int_to_fp(-16_777_217_i32 as u32, false, 0b10 << 22),
+            (bits(-16_777_218.0), FPSCR_IXC)
+        );
+        // 2^32 - 1 rounds up to 2^32 by default, down toward zero
+        assert_eq!(
+            int_to_fp(u32::MAX, true, 0),
+            (bits(4_294_967_296.0), FPSCR_IXC)
+        );
+        assert_eq!(
+            int_to_fp(u32::MAX, true, 0b11 << 22),
+            (bits(4_294_967_040.0), FPSCR_IXC)
+        );
+    }
+
+    #[test]
+    fn test_nan_propagation() {
+        let quiet = 0x7fc0_1234;
+        let signaling = 0xff80_0042;
+
+        // first quiet NaN operand is propagated
+        assert_eq!(fp_add(quiet, bits(1.0), 0), (quiet, 0));
+        assert_eq!(fp_mul(bits(1.0), quiet, 0), (quiet, 0));
+        // signaling NaN wins over quiet NaN and is quieted
+        assert_eq!(fp_sub(quiet, signaling, 0), (0xffc0_0042, FPSCR_IOC));
+        // default NaN mode
+        assert_eq!(fp_div(quiet, bits(1.0), 1 << 25), (DEFAULT_NAN, 0));
+    }
+}
@@ -3,7 +3,7 @@
 //!
 
 use crate::core::condition::Condition;
-use crate::core::register::{ExtensionReg, Reg};
+use crate::core::register::{DoubleReg, ExtensionReg, Reg, SingleReg};
 use crate::core::thumb::ThumbCode;
 use enum_set::EnumSet;
 
@@ -23,6 +23,18 @@ pub enum SRType {
     ROR,
 }
 
+impl fmt::Display for SRType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::LSL => write!(f, "lsl"),
+            Self::LSR => write!(f, "lsr"),
+            Self::ASR => write!(f, "asr"),
+            Self::RRX => write!(f, "rrx"),
+            Self::ROR => write!(f, "ror"),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Copy, Clone)]
 /// IT instruction conditions
 pub enum ITCondition {
@@ -113,6 +125,7 @@ pub enum Instruction {
     ADR {
         rd: Reg,
         imm32: u32,
+        add: bool,
         thumb32: bool,
     },
     AND_reg {
@@ -280,8 +293,14 @@ pub enum Instruction {
     LDM {
         rn: Reg,
         registers: EnumSet<Reg>,
+        wback: bool,
         thumb32: bool,
     },
+    LDMDB {
+        rn: Reg,
+        registers: EnumSet<Reg>,
+        wback: bool,
+    },
     LDR_imm {
         rt: Reg,
         rn: Reg,
@@ -317,6 +336,11 @@ pub enum Instruction {
         wback: bool,
         thumb32: bool,
     },
+    LDRB_lit {
+        rt: Reg,
+        imm32: u32,
+        add: bool,
+    },
     LDRB_reg {
         rt: Reg,
         rn: Reg,
@@ -337,6 +361,11 @@ pub enum Instruction {
         wback: bool,
         thumb32: bool,
     },
+    LDRH_lit {
+        rt: Reg,
+        imm32: u32,
+        add: bool,
+    },
     LDRH_reg {
         rt: Reg,
         rn: Reg,
@@ -368,6 +397,11 @@ pub enum Instruction {
         wback: bool,
         thumb32: bool,
     },
+    LDRSB_lit {
+        rt: Reg,
+        imm32: u32,
+        add: bool,
+    },
 
     LDRSH_reg {
         rt: Reg,
@@ -390,6 +424,11 @@ pub enum Instruction {
         wback: bool,
         thumb32: bool,
     },
+    LDRSH_lit {
+        rt: Reg,
+        imm32: u32,
+        add: bool,
+    },
 
     LDREX {
         rt: Reg,
@@ -877,6 +916,27 @@ pub enum Instruction {
         rm: Reg,
         rotation: usize,
     },
+    VADD_f32 {
+        sd: SingleReg,
+        sn: SingleReg,
+        sm: SingleReg,
+    },
+    VCVT_f32_to_int {
+        sd: SingleReg,
+        sm: SingleReg,
+        unsigned: bool,
+        round_zero: bool,
+    },
+    VCVT_int_to_f32 {
+        sd: SingleReg,
+        sm: SingleReg,
+        unsigned: bool,
+    },
+    VDIV_f32 {
+        sd: SingleReg,
+        sn: SingleReg,
+        sm: SingleReg,
+    },
     VLDR {
         dd: ExtensionReg,
         rn: Reg,
@@ -884,6 +944,30 @@ pub enum Instruction {
         imm32: u32,
         single_reg: bool,
     },
+    VMOV_cr_sp {
+        to_arm_register: bool,
+        rt: Reg,
+        sn: SingleReg,
+    },
+    VMOV_cr2_dp {
+        to_arm_registers: bool,
+        rt: Reg,
+        rt2: Reg,
+        dm: DoubleReg,
+    },
+    VMOV_reg_f32 {
+        sd: SingleReg,
+        sm: SingleReg,
+    },
+    VMOV_reg_f64 {
+        dd: DoubleReg,
+        dm: DoubleReg,
+    },
+    VMUL_f32 {
+        sd: SingleReg,
+        sn: SingleReg,
+        sm: SingleReg,
+    },
     VSTR {
         dd: ExtensionReg,
         rn: Reg,
@@ -891,6 +975,11 @@ pub enum Instruction {
         imm32: u32,
         single_reg: bool,
     },
+    VSUB_f32 {
+        sd: SingleReg,
+        sn: SingleReg,
+        sm: SingleReg,
+    },
     WFE {
         thumb32: bool,
     },
@@ -921,24 +1010,24 @@ fn format_adressing_mode(
             // Pre-indexed
             write!(
                 f,
-                "{}{} {}, [{} , #{}{}]!",
+                "{}{} {}, [{}, #{}{}]!",
                 name,
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rt,
                 rn,
-                if add { "+" } else { "-" },
+                if add { "" } else { "-" },
                 imm32
             )
         } else {
             // Offset
             write!(
                 f,
-                "{}{} {}, [{} {{, #{}{}}}]",
+                "{}{} {}, [{}, #{}{}]",
                 name,
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rt,
                 rn,
-                if add { "+" } else { "-" },
+                if add { "" } else { "-" },
                 imm32
             )
         }
@@ -948,9 +1037,30 @@ fn format_adressing_mode(
             f,
             "{}{} {}, [{}], #{}{}",
             name,
-            if thumb32 { ".W" } else { "" },
+            if thumb32 { ".w" } else { "" },
             rt,
             rn,
+            if add { "" } else { "-" },
+            imm32
+        )
+    }
+}
+
+fn format_literal(
+    name: &str,
+    f: &mut fmt::Formatter,
+    rt: Reg,
+    imm32: u32,
+    add: bool,
+) -> fmt::Result {
+    if imm32 == 0 {
+        write!(f, "{} {}, [pc]", name, rt)
+    } else {
+        write!(
+            f,
+            "{} {}, [pc, #{}{}]",
+            name,
+            rt,
             if add { "+" } else { "-" },
             imm32
         )
@@ -975,26 +1085,26 @@ fn format_adressing_mode2(
             // Pre-indexed
             write!(
                 f,
-                "{}{} {}, {}, [{} , #{}{}]!",
+                "{}{} {}, {}, [{}, #{}{}]!",
                 name,
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rt,
                 rt2,
                 rn,
-                if add { "+" } else { "-" },
+                if add { "" } else { "-" },
                 imm32
             )
         } else {
             // Offset
             write!(
                 f,
-                "{}{} {}, {}, [{} {{, #{}{}}}]",
+                "{}{} {}, {}, [{}, #{}{}]",
                 name,
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rt,
                 rt2,
                 rn,
-                if add { "+" } else { "-" },
+                if add { "" } else { "-" },
                 imm32
             )
         }
@@ -1002,18 +1112,23 @@ fn format_adressing_mode2(
         // Post-indexed
         write!(
             f,
-            "{}{} {}, {},  [{}], #{}{}",
+            "{}{} {}, {}, [{}], #{}{}",
             name,
-            if thumb32 { ".W" } else { "" },
+            if thumb32 { ".w" } else { "" },
             rt,
             rt2,
             rn,
-            if add { "+" } else { "-" },
+            if add { "" } else { "-" },
             imm32
         )
     }
 }
 
+fn format_registers(registers: EnumSet<Reg>) -> String {
+    let names: Vec<String> = registers.iter().map(|reg| reg.to_string()).collect();
+    format!("{{{}}}", names.join(", "))
+}
+
 fn setflags_to_str(setflags: SetFlags) -> &'static str {
     match setflags {
         SetFlags::True => "s",
@@ -1021,12 +1136,21 @@ fn setflags_to_str(setflags: SetFlags) -> &'static str {
     }
 }
 
+impl Instruction {
+    ///
+    /// Length of the instruction encoding in bytes, 2 or 4
+    ///
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> u32 {
+        instruction_size(self) as u32
+    }
+}
+
 #[allow(clippy::cognitive_complexity)]
 #[allow(unused_variables)]
 #[allow(clippy::too_many_lines)]
 impl fmt::Display for Instruction {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // TODO: shift_t, shift_n formattings missing.
         // TODO: some of the wide instruction formattings missing.
         match *self {
             Self::ADD_imm {
@@ -1040,8 +1164,8 @@ impl fmt::Display for Instruction {
                     write!(
                         f,
                         "add{}{} {}, #{}",
-                        if thumb32 { ".W" } else { "" },
                         setflags_to_str(*setflags),
+                        if thumb32 { ".w" } else { "" },
                         rd,
                         imm32
                     )
@@ -1049,8 +1173,8 @@ impl fmt::Display for Instruction {
                     write!(
                         f,
                         "add{}{} {}, {}, #{}",
-                        if thumb32 { ".W" } else { "" },
                         setflags_to_str(*setflags),
+                        if thumb32 { ".w" } else { "" },
                         rd,
                         rn,
                         imm32
@@ -1082,12 +1206,12 @@ impl fmt::Display for Instruction {
                 f,
                 "add{}{} {}, {}, {}{}",
                 setflags_to_str(*setflags),
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rd,
                 rn,
                 rm,
                 if shift_n > 0 {
-                    format!(", {:?} {}", shift_t, shift_n)
+                    format!(", {} #{}", shift_t, shift_n)
                 } else {
                     "".to_string()
                 }
@@ -1103,11 +1227,11 @@ impl fmt::Display for Instruction {
                 f,
                 "add{}{} {}, SP, {}{}",
                 if setflags { "s" } else { "" },
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rd,
                 rm,
                 if shift_n > 0 {
-                    format!(", {:?} {}", shift_t, shift_n)
+                    format!(", {} #{}", shift_t, shift_n)
                 } else {
                     "".to_string()
                 }
@@ -1124,21 +1248,27 @@ impl fmt::Display for Instruction {
                 f,
                 "adc{}{} {}, {}, {}{}",
                 setflags_to_str(*setflags),
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rd,
                 rn,
                 rm,
                 if shift_n > 0 {
-                    format!(", {:?} {}", shift_t, shift_n)
+                    format!(", {} #{}", shift_t, shift_n)
                 } else {
                     "".to_string()
                 }
             ),
-            Self::ADR { rd, imm32, thumb32 } => write!(
+            Self::ADR {
+                rd,
+                imm32,
+                add,
+                thumb32,
+            } => write!(
                 f,
-                "adr{} {}, pc, 0x#{:x}",
-                if thumb32 { ".W" } else { "" },
+                "adr{} {}, pc, {}0x#{:x}",
+                if thumb32 { ".w" } else { "" },
                 rd,
+                if add { "" } else { "-" },
                 imm32
             ),
             Self::AND_reg {
@@ -1153,12 +1283,12 @@ impl fmt::Display for Instruction {
                 f,
                 "and{}{} {}, {}, {}{}",
                 setflags_to_str(*setflags),
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rd,
                 rn,
                 rm,
                 if shift_n > 0 {
-                    format!(", {:?} {}", shift_t, shift_n)
+                    format!(", {} #{}", shift_t, shift_n)
                 } else {
                     "".to_string()
                 }
@@ -1190,7 +1320,7 @@ impl fmt::Display for Instruction {
                 f,
                 "asr{}{} {}, {}, #{}",
                 setflags_to_str(*setflags),
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rd,
                 rm,
                 shift_n
@@ -1217,8 +1347,8 @@ impl fmt::Display for Instruction {
             } => write!(
                 f,
                 "asr{}{} {}, {}, {}",
-                if thumb32 { ".W" } else { "" },
                 setflags_to_str(*setflags),
+                if thumb32 { ".w" } else { "" },
                 rd,
                 rn,
                 rm
@@ -1235,12 +1365,12 @@ impl fmt::Display for Instruction {
                 f,
                 "bic{}{} {}, {}, {}{}",
                 setflags_to_str(*setflags),
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rd,
                 rn,
                 rm,
                 if shift_n > 0 {
-                    format!(", {:?} {}", shift_t, shift_n)
+                    format!(", {} #{}", shift_t, shift_n)
                 } else {
                     "".to_string()
                 }
@@ -1274,11 +1404,11 @@ impl fmt::Display for Instruction {
                 ref cond,
                 imm32,
                 thumb32,
-            } => write!(f, "b{}{} {}", cond, if thumb32 { ".W" } else { "" }, imm32),
+            } => write!(f, "b{}{} {}", cond, if thumb32 { ".w" } else { "" }, imm32),
             Self::B_t24 { imm32, thumb32 } => {
-                write!(f, "b{} {}", if thumb32 { ".W" } else { "" }, imm32)
+                write!(f, "b{} {}", if thumb32 { ".w" } else { "" }, imm32)
             }
-            Self::BL { imm32 } => write!(f, "bl 0x#{:x}", imm32),
+            Self::BL { imm32 } => write!(f, "bl {}", imm32),
             Self::BX { rm } => write!(f, "bx {}", rm),
             Self::BLX { rm } => write!(f, "blx {}", rm),
             Self::BKPT { imm32 } => write!(f, "bkpt #{}", imm32),
@@ -1305,11 +1435,11 @@ impl fmt::Display for Instruction {
             } => write!(
                 f,
                 "cmn{} {}, {}{}",
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rn,
                 rm,
                 if shift_n > 0 {
-                    format!(", {:?} {}", shift_t, shift_n)
+                    format!(", {} #{}", shift_t, shift_n)
                 } else {
                     "".to_string()
                 }
@@ -1326,7 +1456,7 @@ impl fmt::Display for Instruction {
             Self::CMP_imm { rn, imm32, thumb32 } => write!(
                 f,
                 "cmp{} {}, #{}",
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rn,
                 imm32
             ),
@@ -1339,11 +1469,11 @@ impl fmt::Display for Instruction {
             } => write!(
                 f,
                 "cmp{} {}, {}{}",
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rn,
                 rm,
                 if shift_n > 0 {
-                    format!(", {:?} {}", shift_t, shift_n)
+                    format!(", {} #{}", shift_t, shift_n)
                 } else {
                     "".to_string()
                 }
@@ -1377,12 +1507,12 @@ impl fmt::Display for Instruction {
                 f,
                 "eor{}{} {}, {}, {}{}",
                 setflags_to_str(*setflags),
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rd,
                 rn,
                 rm,
                 if shift_n > 0 {
-                    format!(", {:?} {}", shift_t, shift_n)
+                    format!(", {} #{}", shift_t, shift_n)
                 } else {
                     "".to_string()
                 }
@@ -1413,13 +1543,26 @@ impl fmt::Display for Instruction {
             Self::LDM {
                 rn,
                 registers,
+                wback,
                 thumb32,
             } => write!(
                 f,
-                "ldm{} {}, {{{:?}}}",
-                if thumb32 { ".W" } else { "" },
+                "ldm{} {}{}, {}",
+                if thumb32 { ".w" } else { "" },
                 rn,
-                registers
+                if wback { "!" } else { "" },
+                format_registers(registers)
+            ),
+            Self::LDMDB {
+                rn,
+                registers,
+                wback,
+            } => write!(
+                f,
+                "ldmdb {}{}, {}",
+                rn,
+                if wback { "!" } else { "" },
+                format_registers(registers)
             ),
             Self::LDR_reg {
                 rt,
@@ -1434,7 +1577,7 @@ impl fmt::Display for Instruction {
             } => write!(
                 f,
                 "ldr{} {}, [{}, {}]",
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rt,
                 rn,
                 rm
@@ -1455,18 +1598,22 @@ impl fmt::Display for Instruction {
                 add,
             } => {
                 if imm32 == 0 {
-                    write!(f, "ldr{} {}, [pc]", if thumb32 { ".W" } else { "" }, rt)
+                    write!(f, "ldr{} {}, [pc]", if thumb32 { ".w" } else { "" }, rt)
                 } else {
                     write!(
                         f,
                         "ldr{} {}, [pc, #{}{}]",
-                        if thumb32 { ".W" } else { "" },
+                        if thumb32 { ".w" } else { "" },
                         rt,
                         if add { "+" } else { "-" },
                         imm32
                     )
                 }
             }
+            Self::LDRB_lit { rt, imm32, add } => format_literal("ldrb", f, rt, imm32, add),
+            Self::LDRH_lit { rt, imm32, add } => format_literal("ldrh", f, rt, imm32, add),
+            Self::LDRSB_lit { rt, imm32, add } => format_literal("ldrsb", f, rt, imm32, add),
+            Self::LDRSH_lit { rt, imm32, add } => format_literal("ldrsh", f, rt, imm32, add),
             Self::LDREX { rt, rn, imm32 } => write!(f, "ldrex {}, {}, #{}", rt, rn, imm32),
             Self::LDREXB { rt, rn } => write!(f, "ldrexb {}, {}", rt, rn),
             Self::LDREXH { rt, rn } => write!(f, "ldrexh {}, {}", rt, rn),
@@ -1493,7 +1640,7 @@ impl fmt::Display for Instruction {
             } => write!(
                 f,
                 "ldrb{} {}, [{}, {}]",
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rt,
                 rn,
                 rm
@@ -1520,7 +1667,7 @@ impl fmt::Display for Instruction {
             } => write!(
                 f,
                 "ldrh{} {}, [{}, {}]",
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rt,
                 rn,
                 rm
@@ -1549,7 +1696,7 @@ impl fmt::Display for Instruction {
             } => write!(
                 f,
                 "ldrsh{} {}, [{}, {}]",
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rt,
                 rn,
                 rm
@@ -1564,7 +1711,7 @@ impl fmt::Display for Instruction {
                 f,
                 "lsl{}{} {}, {}, #{}",
                 setflags_to_str(*setflags),
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rd,
                 rm,
                 shift_n
@@ -1579,7 +1726,7 @@ impl fmt::Display for Instruction {
                 f,
                 "lsl{}{} {}, {}, {}",
                 setflags_to_str(*setflags),
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rd,
                 rn,
                 rm
@@ -1594,7 +1741,7 @@ impl fmt::Display for Instruction {
                 f,
                 "lsr{}{} {}, {}, {}",
                 setflags_to_str(*setflags),
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rd,
                 rn,
                 rm
@@ -1670,7 +1817,7 @@ impl fmt::Display for Instruction {
                 f,
                 "mov{}{} {}, {}",
                 if setflags { "s" } else { "" },
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rd,
                 rm
             ),
@@ -1683,7 +1830,7 @@ impl fmt::Display for Instruction {
                 f,
                 "mov{}{} {}, #{}",
                 setflags_to_str(*setflags),
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rd,
                 match *imm32 {
                     Imm32Carry::NoCarry { imm32 } => imm32,
@@ -1722,11 +1869,11 @@ impl fmt::Display for Instruction {
                 f,
                 "mvn{}{} {}, {}, {}",
                 setflags_to_str(*setflags),
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rd,
                 rm,
                 if shift_n > 0 {
-                    format!(", {:?} {}", shift_t, shift_n)
+                    format!(", {} #{}", shift_t, shift_n)
                 } else {
                     "".to_string()
                 }
@@ -1758,12 +1905,12 @@ impl fmt::Display for Instruction {
                 f,
                 "orr{}{} {}, {}, {}{}",
                 setflags_to_str(*setflags),
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rd,
                 rn,
                 rm,
                 if shift_n > 0 {
-                    format!(", {:?} {}", shift_t, shift_n)
+                    format!(", {} #{}", shift_t, shift_n)
                 } else {
                     "".to_string()
                 }
@@ -1799,7 +1946,7 @@ impl fmt::Display for Instruction {
                 rn,
                 rm,
                 if shift_n > 0 {
-                    format!(", {:?} {}", shift_t, shift_n)
+                    format!(", {} #{}", shift_t, shift_n)
                 } else {
                     "".to_string()
                 }
@@ -1820,12 +1967,18 @@ impl fmt::Display for Instruction {
                     Imm32Carry::Carry { imm32_c0, imm32_c1 } => imm32_c0.0,
                 }
             ),
-            Self::POP { registers, thumb32 } => {
-                write!(f, "pop{} {:?}", if thumb32 { ".W" } else { "" }, registers)
-            }
-            Self::PUSH { thumb32, registers } => {
-                write!(f, "push{} {:?}", if thumb32 { ".W" } else { "" }, registers)
-            }
+            Self::POP { registers, thumb32 } => write!(
+                f,
+                "pop{} {}",
+                if thumb32 { ".w" } else { "" },
+                format_registers(registers)
+            ),
+            Self::PUSH { thumb32, registers } => write!(
+                f,
+                "push{} {}",
+                if thumb32 { ".w" } else { "" },
+                format_registers(registers)
+            ),
             Self::PLD_imm { rn, imm32, add } => {
                 write!(f, "pld [{}, {}{}]", rn, if add { "+" } else { "-" }, imm32)
             }
@@ -1843,7 +1996,7 @@ impl fmt::Display for Instruction {
                 rn,
                 rm,
                 if shift_n > 0 {
-                    format!(", {:?} {}", shift_t, shift_n)
+                    format!(", {} #{}", shift_t, shift_n)
                 } else {
                     "".to_string()
                 }
@@ -1876,7 +2029,7 @@ impl fmt::Display for Instruction {
                 f,
                 "rsb{}{} {}, {}, #{}",
                 setflags_to_str(*setflags),
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rd,
                 rn,
                 imm32
@@ -1914,12 +2067,12 @@ impl fmt::Display for Instruction {
                 f,
                 "rsb{}{} {}, {}, {}{}",
                 if setflags { "s" } else { "" },
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rd,
                 rn,
                 rm,
                 if shift_n > 0 {
-                    format!(", {:?} {}", shift_t, shift_n)
+                    format!(", {} #{}", shift_t, shift_n)
                 } else {
                     "".to_string()
                 }
@@ -1938,7 +2091,7 @@ impl fmt::Display for Instruction {
                 f,
                 "sbc{}{} {}, {}, {}",
                 setflags_to_str(*setflags),
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rd,
                 rn,
                 rm
@@ -1950,11 +2103,11 @@ impl fmt::Display for Instruction {
                 thumb32,
             } => write!(
                 f,
-                "stm{} {}{}, {{{:?}}}",
-                if thumb32 { ".W" } else { "" },
+                "stm{} {}{}, {}",
+                if thumb32 { ".w" } else { "" },
                 rn,
                 if wback { "!" } else { "" },
-                registers
+                format_registers(registers)
             ),
             Self::STMDB {
                 rn,
@@ -1962,10 +2115,10 @@ impl fmt::Display for Instruction {
                 registers,
             } => write!(
                 f,
-                "stmdb {}{}, {{{:?}}}",
+                "stmdb {}{}, {}",
                 rn,
                 if wback { "!" } else { "" },
-                registers
+                format_registers(registers)
             ),
             Self::STR_imm {
                 rn,
@@ -2063,7 +2216,7 @@ impl fmt::Display for Instruction {
                         f,
                         "sub{}{} {}, #{}",
                         setflags_to_str(*setflags),
-                        if thumb32 { ".W" } else { "" },
+                        if thumb32 { ".w" } else { "" },
                         rd,
                         imm32
                     )
@@ -2072,7 +2225,7 @@ impl fmt::Display for Instruction {
                         f,
                         "sub{}{} {}, {}, #{}",
                         setflags_to_str(*setflags),
-                        if thumb32 { ".W" } else { "" },
+                        if thumb32 { ".w" } else { "" },
                         rd,
                         rn,
                         imm32
@@ -2091,7 +2244,7 @@ impl fmt::Display for Instruction {
                 f,
                 "sub{}{} {}, {}, {}",
                 setflags_to_str(*setflags),
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rd,
                 rn,
                 rm
@@ -2107,7 +2260,7 @@ impl fmt::Display for Instruction {
                 rn,
                 rm,
                 if shift_n > 0 {
-                    format!(", {:?} {}", shift_t, shift_n)
+                    format!(", {} #{}", shift_t, shift_n)
                 } else {
                     "".to_string()
                 }
@@ -2121,7 +2274,7 @@ impl fmt::Display for Instruction {
             } => write!(
                 f,
                 "sxth{} {}, {}{}",
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rd,
                 rm,
                 if rotation > 0 {
@@ -2139,7 +2292,7 @@ impl fmt::Display for Instruction {
             } => write!(
                 f,
                 "sxtb{} {}, {}{}",
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rd,
                 rm,
                 if rotation > 0 {
@@ -2159,11 +2312,11 @@ impl fmt::Display for Instruction {
             } => write!(
                 f,
                 "tst{} {}, {}{}",
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rn,
                 rm,
                 if shift_n > 0 {
-                    format!(", {:?} {}", shift_t, shift_n)
+                    format!(", {} #{}", shift_t, shift_n)
                 } else {
                     "".to_string()
                 }
@@ -2213,7 +2366,7 @@ impl fmt::Display for Instruction {
             } => write!(
                 f,
                 "uxtb{} {}, {}{}",
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rd,
                 rm,
                 if rotation > 0 {
@@ -2247,7 +2400,7 @@ impl fmt::Display for Instruction {
             } => write!(
                 f,
                 "uxth{} {}, {}{}",
-                if thumb32 { ".W" } else { "" },
+                if thumb32 { ".w" } else { "" },
                 rd,
                 rm,
                 if rotation > 0 {
@@ -2262,20 +2415,75 @@ impl fmt::Display for Instruction {
                 lsb,
                 widthminus1,
             } => write!(f, "ubfx {}, {}, #{}, #{}", rd, rn, lsb, widthminus1 + 1),
+            Self::VADD_f32 { sd, sn, sm } => write!(f, "vadd.f32 {}, {}, {}", sd, sn, sm),
+            Self::VCVT_f32_to_int {
+                sd,
+                sm,
+                unsigned,
+                round_zero,
+            } => write!(
+                f,
+                "vcvt{}.{}.f32 {}, {}",
+                if round_zero { "" } else { "r" },
+                if unsigned { "u32" } else { "s32" },
+                sd,
+                sm
+            ),
+            Self::VCVT_int_to_f32 { sd, sm, unsigned } => write!(
+                f,
+                "vcvt.f32.{} {}, {}",
+                if unsigned { "u32" } else { "s32" },
+                sd,
+                sm
+            ),
+            Self::VDIV_f32 { sd, sn, sm } => write!(f, "vdiv.f32 {}, {}, {}", sd, sn, sm),
             Self::VLDR {
+                dd, rn, add, imm32, ..
+            } => write!(
+                f,
+                "vldr {}, [{}, #{}{}]",
                 dd,
                 rn,
-                add,
-                imm32,
-                single_reg,
-            } => write!(f, "vldr {}, {}", dd, rn),
+                if add { "" } else { "-" },
+                imm32
+            ),
+            Self::VMOV_cr_sp {
+                to_arm_register,
+                rt,
+                sn,
+            } => {
+                if to_arm_register {
+                    write!(f, "vmov {}, {}", rt, sn)
+                } else {
+                    write!(f, "vmov {}, {}", sn, rt)
+                }
+            }
+            Self::VMOV_cr2_dp {
+                to_arm_registers,
+                rt,
+                rt2,
+                dm,
+            } => {
+                if to_arm_registers {
+                    write!(f, "vmov {}, {}, {}", rt, rt2, dm)
+                } else {
+                    write!(f, "vmov {}, {}, {}", dm, rt, rt2)
+                }
+            }
+            Self::VMOV_reg_f32 { sd, sm } => write!(f, "vmov.f32 {}, {}", sd, sm),
+            Self::VMOV_reg_f64 { dd, dm } => write!(f, "vmov.f64 {}, {}", dd, dm),
+            Self::VMUL_f32 { sd, sn, sm } => write!(f, "vmul.f32 {}, {}, {}", sd, sn, sm),
             Self::VSTR {
+                dd, rn, add, imm32, ..
+            } => write!(
+                f,
+                "vstr {}, [{}, #{}{}]",
                 dd,
                 rn,
-                add,
-                imm32,
-                single_reg,
-            } => write!(f, "vstr {}, {}", dd, rn),
+                if add { "" } else { "-" },
+                imm32
+            ),
+            Self::VSUB_f32 { sd, sn, sm } => write!(f, "vsub.f32 {}, {}, {}", sd, sn, sm),
 
             Self::WFE { .. } => write!(f, "wfe"),
             Self::WFI { .. } => write!(f, "wfi"),
@@ -2379,12 +2587,12 @@ pub fn instruction_size(instruction: &Instruction) -> usize {
         Instruction::LDC_imm { .. } => 4,
         Instruction::LDC2_imm { .. } => 4,
         Instruction::LDM { thumb32, .. } => isize_t(*thumb32),
-        //LDMDB
+        Instruction::LDMDB { .. } => 4,
         Instruction::LDR_imm { thumb32, .. } => isize_t(*thumb32),
         Instruction::LDR_lit { thumb32, .. } => isize_t(*thumb32),
         Instruction::LDR_reg { thumb32, .. } => isize_t(*thumb32),
         Instruction::LDRB_imm { thumb32, .. } => isize_t(*thumb32),
-        //LDRB_lit
+        Instruction::LDRB_lit { .. } => 4,
         Instruction::LDRB_reg { thumb32, .. } => isize_t(*thumb32),
         //LDRBT
         Instruction::LDRD_imm { .. } => 4,
@@ -2393,15 +2601,15 @@ pub fn instruction_size(instruction: &Instruction) -> usize {
         Instruction::LDREXB { .. } => 4,
         Instruction::LDREXH { .. } => 4,
         Instruction::LDRH_imm { thumb32, .. } => isize_t(*thumb32),
-        //LDRH_lit
+        Instruction::LDRH_lit { .. } => 4,
         Instruction::LDRH_reg { thumb32, .. } => isize_t(*thumb32),
         //LDRHT
         Instruction::LDRSB_imm { thumb32, .. } => isize_t(*thumb32),
-        //LDRSB_lit
+        Instruction::LDRSB_lit { .. } => 4,
         Instruction::LDRSB_reg { thumb32, .. } => isize_t(*thumb32),
         //LDRSBT
         Instruction::LDRSH_imm { thumb32, .. } => isize_t(*thumb32),
-        //LDRSH_lit
+        Instruction::LDRSH_lit { .. } => 4,
         Instruction::LDRSH_reg { thumb32, .. } => isize_t(*thumb32),
         Instruction::LSL_imm { thumb32, .. } => isize_t(*thumb32),
         Instruction::LSL_reg { thumb32, .. } => isize_t(*thumb32),
@@ -2563,13 +2771,14 @@ pub fn instruction_size(instruction: &Instruction) -> usize {
         Instruction::UXTH { thumb32, .. } => isize_t(*thumb32),
 
         //VABS
-        //VADD
+        Instruction::VADD_f32 { .. } => 4,
         //VCMP
         //VCVTX
-        //VCVT
+        Instruction::VCVT_f32_to_int { .. } => 4,
+        Instruction::VCVT_int_to_f32 { .. } => 4,
         //VCVTB
         //VCVTT
-        //VDIV
+        Instruction::VDIV_f32 { .. } => 4,
         //VFMA
         //VFMS
         //VFNMA
@@ -2580,11 +2789,10 @@ pub fn instruction_size(instruction: &Instruction) -> usize {
         //VMLA
         //VMLS
         //VMOV_imm
-        //VMON_reg
         //VMOVX
         //VMRS
         //VMSR
-        //VMUL
+        Instruction::VMUL_f32 { .. } => 4,
         //VNEG
         //VNMLA,VNMLS, VNMUL
         //VPOP
@@ -2596,11 +2804,15 @@ pub fn instruction_size(instruction: &Instruction) -> usize {
         //VSQRT
         //VSTM
         //VSTR
-        //VSUB
+        Instruction::VSUB_f32 { .. } => 4,
         Instruction::WFE { thumb32, .. } => isize_t(*thumb32),
         Instruction::WFI { thumb32, .. } => isize_t(*thumb32),
         Instruction::YIELD { thumb32, .. } => isize_t(*thumb32),
         Instruction::VLDR { .. } => 4,
+        Instruction::VMOV_cr_sp { .. } => 4,
+        Instruction::VMOV_cr2_dp { .. } => 4,
+        Instruction::VMOV_reg_f32 { .. } => 4,
+        Instruction::VMOV_reg_f64 { .. } => 4,
         Instruction::VSTR { .. } => 4,
     }
 }
@@ -2613,3 +2825,221 @@ fn isize_t(thumb32: bool) -> usize {
         2
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::condition::Condition;
+
+    #[test]
+    fn test_display_fp_load_store() {
+        assert_eq!(
+            Instruction::VLDR {
+                dd: ExtensionReg::Double { reg: DoubleReg::D7 },
+                rn: Reg::PC,
+                add: true,
+                imm32: 536,
+                single_reg: false,
+            }
+            .to_string(),
+            "vldr d7, [pc, #536]"
+        );
+        assert_eq!(
+            Instruction::VSTR {
+                dd: ExtensionReg::Single { reg: SingleReg::S3 },
+                rn: Reg::SP,
+                add: false,
+                imm32: 8,
+                single_reg: true,
+            }
+            .to_string(),
+            "vstr s3, [sp, #-8]"
+        );
+    }
+
+    #[test]
+    fn test_display_fp_arithmetic() {
+        assert_eq!(
+            Instruction::VDIV_f32 {
+                sd: SingleReg::S2,
+                sn: SingleReg::S0,
+                sm: SingleReg::S1,
+            }
+            .to_string(),
+            "vdiv.f32 s2, s0, s1"
+        );
+        assert_eq!(
+            Instruction::VCVT_f32_to_int {
+                sd: SingleReg::S0,
+                sm: SingleReg::S1,
+                unsigned: false,
+                round_zero: false,
+            }
+            .to_string(),
+            "vcvtr.s32.f32 s0, s1"
+        );
+        assert_eq!(
+            Instruction::VCVT_int_to_f32 {
+                sd: SingleReg::S6,
+                sm: SingleReg::S4,
+                unsigned: true,
+            }
+            .to_string(),
+            "vcvt.f32.u32 s6, s4"
+        );
+    }
+
+    #[test]
+    fn test_display_data_processing() {
+        assert_eq!(
+            Instruction::ADD_imm {
+                rd: Reg::R0,
+                rn: Reg::R1,
+                imm32: 4,
+                setflags: SetFlags::True,
+                thumb32: false,
+            }
+            .to_string(),
+            "adds r0, r1, #4"
+        );
+        assert_eq!(
+            Instruction::ADD_imm {
+                rd: Reg::R0,
+                rn: Reg::R0,
+                imm32: 4,
+                setflags: SetFlags::True,
+                thumb32: true,
+            }
+            .to_string(),
+            "adds.w r0, #4"
+        );
+        assert_eq!(
+            Instruction::ADD_reg {
+                rd: Reg::R0,
+                rn: Reg::R1,
+                rm: Reg::R2,
+                setflags: SetFlags::False,
+                shift_t: SRType::LSL,
+                shift_n: 2,
+                thumb32: true,
+            }
+            .to_string(),
+            "add.w r0, r1, r2, lsl #2"
+        );
+    }
+
+    #[test]
+    fn test_display_register_lists() {
+        assert_eq!(
+            Instruction::PUSH {
+                registers: [Reg::R4, Reg::R5, Reg::LR].iter().cloned().collect(),
+                thumb32: false,
+            }
+            .to_string(),
+            "push {r4, r5, lr}"
+        );
+        assert_eq!(
+            Instruction::POP {
+                registers: [Reg::R4, Reg::PC].iter().cloned().collect(),
+                thumb32: true,
+            }
+            .to_string(),
+            "pop.w {r4, pc}"
+        );
+        assert_eq!(
+            Instruction::LDM {
+                rn: Reg::R0,
+                registers: [Reg::R1, Reg::R2].iter().cloned().collect(),
+                wback: true,
+                thumb32: false,
+            }
+            .to_string(),
+            "ldm r0!, {r1, r2}"
+        );
+        assert_eq!(
+            Instruction::LDMDB {
+                rn: Reg::R0,
+                registers: [Reg::R1, Reg::R2].iter().cloned().collect(),
+                wback: false,
+            }
+            .to_string(),
+            "ldmdb r0, {r1, r2}"
+        );
+        assert_eq!(
+            Instruction::STM {
+                rn: Reg::R0,
+                registers: [Reg::R1, Reg::R2].iter().cloned().collect(),
+                wback: true,
+                thumb32: false,
+            }
+            .to_string(),
+            "stm r0!, {r1, r2}"
+        );
+    }
+
+    #[test]
+    fn test_display_branches() {
+        assert_eq!(
+            Instruction::B_t13 {
+                cond: Condition::NE,
+                imm32: -8,
+                thumb32: false,
+            }
+            .to_string(),
+            "bne -8"
+        );
+        assert_eq!(
+            Instruction::B_t13 {
+                cond: Condition::EQ,
+                imm32: 100,
+                thumb32: true,
+            }
+            .to_string(),
+            "beq.w 100"
+        );
+        assert_eq!(Instruction::BX { rm: Reg::LR }.to_string(), "bx lr");
+    }
+
+    #[test]
+    fn test_display_addressing_modes() {
+        assert_eq!(
+            Instruction::LDR_imm {
+                rt: Reg::R0,
+                rn: Reg::R1,
+                imm32: 4,
+                index: true,
+                add: true,
+                wback: false,
+                thumb32: false,
+            }
+            .to_string(),
+            "ldr r0, [r1, #4]"
+        );
+        assert_eq!(
+            Instruction::LDR_imm {
+                rt: Reg::R0,
+                rn: Reg::R1,
+                imm32: 4,
+                index: true,
+                add: false,
+                wback: true,
+                thumb32: true,
+            }
+            .to_string(),
+            "ldr.w r0, [r1, #-4]!"
+        );
+        assert_eq!(
+            Instruction::LDR_imm {
+                rt: Reg::R0,
+                rn: Reg::R1,
+                imm32: 4,
+                index: false,
+                add: true,
+                wback: true,
+                thumb32: true,
+            }
+            .to_string(),
+            "ldr.w r0, [r1], #4"
+        );
+    }
+}
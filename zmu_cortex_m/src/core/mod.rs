@@ -8,9 +8,14 @@ pub mod exception;
 pub mod executor;
 pub mod fault;
 pub mod fetch;
+pub mod fpu;
 pub mod instruction;
 pub mod monitor;
 pub mod operation;
 pub mod register;
 pub mod reset;
+pub mod snapshot;
 pub mod thumb;
+pub mod timing;
+pub mod trace;
+pub mod variant;
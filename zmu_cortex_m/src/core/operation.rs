@@ -86,16 +86,49 @@ pub fn sign_extend(word: u32, topbit: usize, size: usize) -> u64 {
 ///
 pub fn add_with_carry(x: u32, y: u32, carry_in: bool) -> (u32, bool, bool) {
     let unsigned_sum = u64::from(x) + u64::from(y) + (carry_in as u64);
-    let signed_sum = (x as i32)
-        .wrapping_add(y as i32)
-        .wrapping_add(carry_in as i32);
+    let signed_sum = i64::from(x as i32) + i64::from(y as i32) + i64::from(carry_in);
     let result = (unsigned_sum & 0xffff_ffff) as u32; // same value as signed_sum<N-1:0>
     let carry_out = u64::from(result) != unsigned_sum;
-    let overflow = (result as i32) != signed_sum;
+    let overflow = i64::from(result as i32) != signed_sum;
 
     (result, carry_out, overflow)
 }
 
+///
+/// Saturate a signed value to the range of an ```n``` bit signed integer
+///
+/// return tuple of (result, saturated)
+///
+pub fn signed_sat(i: i64, n: usize) -> (i32, bool) {
+    debug_assert!((1..=32).contains(&n));
+    let max = (1_i64 << (n - 1)) - 1;
+    let min = -(1_i64 << (n - 1));
+    if i > max {
+        (max as i32, true)
+    } else if i < min {
+        (min as i32, true)
+    } else {
+        (i as i32, false)
+    }
+}
+
+///
+/// Saturate a signed value to the range of an ```n``` bit unsigned integer
+///
+/// return tuple of (result, saturated)
+///
+pub fn unsigned_sat(i: i64, n: usize) -> (u32, bool) {
+    debug_assert!(n <= 32);
+    let max = (1_i64 << n) - 1;
+    if i > max {
+        (max as u32, true)
+    } else if i < 0 {
+        (0, true)
+    } else {
+        (i as u32, false)
+    }
+}
+
 ///
 /// This function performs the condition test for an instruction, based on:
 /// • the two Thumb conditional branch encodings, encodings T1 and T3 of the B instruction
@@ -415,6 +448,71 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_thumb_expand_imm_c() {
+        // i:imm3:imm8 = 0b0_000_1010_1011, no rotation keeps the carry
+        assert_eq!(
+            thumb_expand_imm_c(&[0, 0b000, 0xab], &[1, 3, 8], true),
+            (0xab, true)
+        );
+        assert_eq!(
+            thumb_expand_imm_c(&[0, 0b011, 0xab], &[1, 3, 8], false),
+            (0xabab_abab, false)
+        );
+        // 0x80 rotated right by 8, carry from bit 31
+        assert_eq!(
+            thumb_expand_imm_c(&[0, 0b100, 0x00], &[1, 3, 8], false),
+            (0x8000_0000, true)
+        );
+        // 0xff rotated right by 9
+        assert_eq!(
+            thumb_expand_imm_c(&[0, 0b100, 0xff], &[1, 3, 8], true),
+            (0x7f80_0000, false)
+        );
+    }
+
+    #[test]
+    fn test_condition_test_all_conditions() {
+        for flags in 0..16_u32 {
+            let psr = PSR { value: flags << 28 };
+            let (n, z, c, v) = (
+                flags.get_bit(3),
+                flags.get_bit(2),
+                flags.get_bit(1),
+                flags.get_bit(0),
+            );
+
+            for cond in 0..16_u16 {
+                // ConditionPassed(): cond<3:1> selects the test, cond<0>
+                // inverts it, except for the always encodings
+                let base = match cond >> 1 {
+                    0b000 => z,
+                    0b001 => c,
+                    0b010 => n,
+                    0b011 => v,
+                    0b100 => c && !z,
+                    0b101 => n == v,
+                    0b110 => n == v && !z,
+                    _ => true,
+                };
+                let expected = if cond & 1 == 1 && cond != 0b1111 {
+                    !base
+                } else {
+                    base
+                };
+
+                let condition = Condition::from_u16(cond).unwrap_or(Condition::AL);
+                assert_eq!(
+                    condition_test(condition, &psr),
+                    expected,
+                    "{:?} with NZCV {:04b}",
+                    condition,
+                    flags
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_add_with_carry() {
         let (result, carry, overflow) = add_with_carry(0x410, 4, false);
@@ -470,6 +568,35 @@ mod tests {
         assert_eq!(carry, true);
         assert_eq!(overflow, false);
     }
+    #[test]
+    fn test_signed_sat() {
+        assert_eq!(signed_sat(127, 8), (127, false));
+        assert_eq!(signed_sat(128, 8), (127, true));
+        assert_eq!(signed_sat(-128, 8), (-128, false));
+        assert_eq!(signed_sat(-129, 8), (-128, true));
+        assert_eq!(signed_sat(0x7fff, 16), (0x7fff, false));
+        assert_eq!(signed_sat(0x8000, 16), (0x7fff, true));
+        assert_eq!(signed_sat(-0x8001, 16), (-0x8000, true));
+        assert_eq!(signed_sat(1, 1), (0, true));
+        assert_eq!(signed_sat(-1, 1), (-1, false));
+        assert_eq!(signed_sat(i64::from(i32::MAX) + 1, 32), (i32::MAX, true));
+        assert_eq!(signed_sat(i64::from(i32::MIN) - 1, 32), (i32::MIN, true));
+        assert_eq!(signed_sat(i64::from(i32::MIN), 32), (i32::MIN, false));
+    }
+
+    #[test]
+    fn test_unsigned_sat() {
+        assert_eq!(unsigned_sat(255, 8), (255, false));
+        assert_eq!(unsigned_sat(256, 8), (255, true));
+        assert_eq!(unsigned_sat(0, 8), (0, false));
+        assert_eq!(unsigned_sat(-1, 8), (0, true));
+        assert_eq!(unsigned_sat(0x1_0000, 16), (0xffff, true));
+        assert_eq!(unsigned_sat(5, 0), (0, true));
+        assert_eq!(unsigned_sat(0x7fff_ffff, 31), (0x7fff_ffff, false));
+        assert_eq!(unsigned_sat(0x8000_0000, 31), (0x7fff_ffff, true));
+        assert_eq!(unsigned_sat(i64::from(u32::MAX) + 1, 32), (u32::MAX, true));
+    }
+
     #[test]
     fn test_build_imm_6_11() {
         assert_eq!(build_imm_6_11(0xF00080C4), 0xc4 << 1);
@@ -3,7 +3,7 @@
 //!
 
 use crate::core::bits::Bits;
-use crate::core::exception::ExceptionHandling;
+use crate::core::exception::{Exception, ExceptionHandling};
 use crate::core::fault::Fault;
 use crate::Processor;
 use crate::ProcessorMode;
@@ -38,6 +38,9 @@ pub trait BaseReg {
     ///
     /// Getter for registers
     ///
+    /// Reading PC returns the address of the current instruction + 4,
+    /// for both 16-bit and 32-bit instructions.
+    ///
     fn get_r(&self, r: Reg) -> u32;
 
     ///
@@ -64,6 +67,16 @@ pub trait BaseReg {
     ///
     fn get_psp(&self) -> u32;
 
+    ///
+    /// Lowest allowed value of the active stack pointer, if limited
+    ///
+    fn get_stack_limit(&self) -> Option<u32>;
+
+    ///
+    /// Check that the active stack pointer can be moved down to `sp`
+    ///
+    fn check_stack_limit(&self, sp: u32) -> Result<(), Fault>;
+
     ///
     /// Increment PC by a value
     ///
@@ -88,6 +101,35 @@ pub trait BaseReg {
     /// substract value from a register
     ///
     fn sub_r(&mut self, r: Reg, value: u32);
+
+    ///
+    /// Read a core register by name, as seen by a debugger
+    ///
+    /// Unlike `get_r`, reading PC returns the address of the current
+    /// instruction.
+    ///
+    fn read_register(&self, r: Reg) -> u32;
+
+    ///
+    /// Write a core register by name, as done by a debugger
+    ///
+    /// Unlike `set_r`, writing PC moves execution to `value` with no
+    /// interworking side effects.
+    ///
+    fn write_register(&mut self, r: Reg, value: u32);
+
+    ///
+    /// Read a special register by name
+    ///
+    fn read_special_register(&self, r: SpecialReg) -> u32;
+
+    ///
+    /// Write a special register by name
+    ///
+    /// Writes are not restricted by privilege level or processor mode.
+    /// Writes to the PSR views only update the bits of that view.
+    ///
+    fn write_special_register(&mut self, r: SpecialReg, value: u32);
 }
 
 ///
@@ -164,7 +206,7 @@ impl BaseReg for Processor {
                 }
             }
             Reg::LR => self.lr,
-            Reg::PC => self.pc + 4,
+            Reg::PC => self.pc.wrapping_add(4),
         }
     }
 
@@ -215,6 +257,21 @@ impl BaseReg for Processor {
         self.psp
     }
 
+    fn get_stack_limit(&self) -> Option<u32> {
+        if self.control.sp_sel {
+            self.psp_limit
+        } else {
+            self.msp_limit
+        }
+    }
+
+    fn check_stack_limit(&self, sp: u32) -> Result<(), Fault> {
+        match self.get_stack_limit() {
+            Some(limit) if sp < limit => Err(Fault::StkOf),
+            _ => Ok(()),
+        }
+    }
+
     fn add_pc(&mut self, value: u32) {
         self.pc += value;
     }
@@ -246,17 +303,17 @@ impl BaseReg for Processor {
             | Reg::R11
             | Reg::R12 => {
                 let reg: usize = From::from(r);
-                self.r0_12[reg] += value;
+                self.r0_12[reg] = self.r0_12[reg].wrapping_add(value);
             }
             Reg::SP => {
                 if self.control.sp_sel {
-                    self.psp += value
+                    self.psp = self.psp.wrapping_add(value)
                 } else {
-                    self.msp += value
+                    self.msp = self.msp.wrapping_add(value)
                 }
             }
-            Reg::LR => self.lr += value,
-            Reg::PC => self.pc += value,
+            Reg::LR => self.lr = self.lr.wrapping_add(value),
+            Reg::PC => self.pc = self.pc.wrapping_add(value),
         };
     }
     //
@@ -278,19 +335,74 @@ impl BaseReg for Processor {
             | Reg::R11
             | Reg::R12 => {
                 let reg: usize = From::from(r);
-                self.r0_12[reg] -= value;
+                self.r0_12[reg] = self.r0_12[reg].wrapping_sub(value);
             }
             Reg::SP => {
                 if self.control.sp_sel {
-                    self.psp -= value
+                    self.psp = self.psp.wrapping_sub(value)
                 } else {
-                    self.msp -= value
+                    self.msp = self.msp.wrapping_sub(value)
                 }
             }
-            Reg::LR => self.lr -= value,
-            Reg::PC => self.pc -= value,
+            Reg::LR => self.lr = self.lr.wrapping_sub(value),
+            Reg::PC => self.pc = self.pc.wrapping_sub(value),
         };
     }
+
+    fn read_register(&self, r: Reg) -> u32 {
+        match r {
+            Reg::PC => self.pc,
+            _ => self.get_r(r),
+        }
+    }
+
+    fn write_register(&mut self, r: Reg, value: u32) {
+        match r {
+            Reg::PC => self.set_pc(value),
+            _ => self.set_r(r, value),
+        }
+    }
+
+    fn read_special_register(&self, r: SpecialReg) -> u32 {
+        match r {
+            SpecialReg::MSP => self.msp,
+            SpecialReg::PSP => self.psp,
+            SpecialReg::PRIMASK => u32::from(self.primask),
+            #[cfg(any(armv7m, armv7em))]
+            SpecialReg::FAULTMASK => u32::from(self.faultmask),
+            #[cfg(armv6m)]
+            SpecialReg::FAULTMASK => 0,
+            SpecialReg::CONTROL => u32::from(u8::from(self.control)),
+            _ => self.psr.value & r.psr_mask(),
+        }
+    }
+
+    fn write_special_register(&mut self, r: SpecialReg, value: u32) {
+        match r {
+            SpecialReg::MSP => self.msp = value,
+            SpecialReg::PSP => self.psp = value,
+            SpecialReg::PRIMASK => {
+                self.primask = value.get_bit(0);
+                self.execution_priority = self.get_execution_priority();
+            }
+            #[cfg(any(armv7m, armv7em))]
+            SpecialReg::FAULTMASK => {
+                self.faultmask = value.get_bit(0);
+                self.execution_priority = self.get_execution_priority();
+            }
+            #[cfg(armv6m)]
+            SpecialReg::FAULTMASK => (),
+            SpecialReg::CONTROL => {
+                self.control.n_priv = value.get_bit(0);
+                self.control.sp_sel = value.get_bit(1);
+                self.control.fpca = value.get_bit(2);
+            }
+            _ => {
+                let mask = r.psr_mask();
+                self.psr.value = (self.psr.value & !mask) | (value & mask);
+            }
+        }
+    }
 }
 
 impl ExtensionRegOperations for Processor {
@@ -535,6 +647,34 @@ impl Ipsr for PSR {
     }
 }
 
+impl fmt::Display for PSR {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut itstate: u32 = 0;
+        itstate.set_bits(0..2, self.value.get_bits(25..27));
+        itstate.set_bits(2..8, self.value.get_bits(10..16));
+
+        let isr_number = self.get_isr_number();
+
+        write!(
+            f,
+            "{}{}{}{}{}, IT=0b{:08b}, T={}, ISR=",
+            if self.get_n() { 'N' } else { 'n' },
+            if self.get_z() { 'Z' } else { 'z' },
+            if self.get_c() { 'C' } else { 'c' },
+            if self.get_v() { 'V' } else { 'v' },
+            if self.get_q() { 'Q' } else { 'q' },
+            itstate,
+            u8::from(self.get_t()),
+        )?;
+
+        if isr_number == 0 {
+            write!(f, "Thread")
+        } else {
+            write!(f, "{}", Exception::from(isr_number))
+        }
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Debug)]
 #[repr(u32)]
 ///
@@ -915,6 +1055,23 @@ impl From<Reg> for usize {
 }
 
 impl SpecialReg {
+    /// bits of the PSR visible through this register, zero for non-PSR registers
+    pub fn psr_mask(self) -> u32 {
+        const APSR: u32 = 0xf80f_0000;
+        const IPSR: u32 = 0x0000_01ff;
+        const EPSR: u32 = 0x0700_fc00;
+        match self {
+            Self::APSR => APSR,
+            Self::IAPSR => IPSR | APSR,
+            Self::EAPSR => EPSR | APSR,
+            Self::XPSR => IPSR | EPSR | APSR,
+            Self::IPSR => IPSR,
+            Self::EPSR => EPSR,
+            Self::IEPSR => IPSR | EPSR,
+            _ => 0,
+        }
+    }
+
     /// decode 16 bit value to Special Register designator
     pub fn from_u16(n: u16) -> Option<Self> {
         match n {
@@ -1066,18 +1223,20 @@ impl fmt::Display for SpecialReg {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 /// CONTROL register parts
 pub struct Control {
     /// Thread mode priviledge level
     pub n_priv: bool,
     /// selection of current active stack pointer, true = PSP, false = MSP
     pub sp_sel: bool,
+    /// floating point context active, the FP state is stacked on exception entry
+    pub fpca: bool,
 }
 
 impl From<Control> for u8 {
     fn from(control: Control) -> Self {
-        control.n_priv as Self + ((control.sp_sel as Self) << 1)
+        control.n_priv as Self + ((control.sp_sel as Self) << 1) + ((control.fpca as Self) << 2)
     }
 }
 
@@ -1175,7 +1334,7 @@ impl From<u8> for DoubleReg {
 
 impl From<u8> for SingleReg {
     fn from(value: u8) -> Self {
-        match value & 0xf {
+        match value & 0x1f {
             0 => Self::S0,
             1 => Self::S1,
             2 => Self::S2,
@@ -1212,3 +1371,90 @@ impl From<u8> for SingleReg {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_reg_from_covers_all_registers() {
+        assert_eq!(SingleReg::from(15), SingleReg::S15);
+        assert_eq!(SingleReg::from(16), SingleReg::S16);
+        assert_eq!(SingleReg::from(31), SingleReg::S31);
+    }
+
+    #[test]
+    fn test_read_write_register_by_name() {
+        // Arrange
+        let mut processor = Processor::new();
+
+        // Act
+        processor.write_register(Reg::R3, 0x1234);
+        processor.write_register(Reg::PC, 0x100);
+
+        // Assert
+        assert_eq!(processor.read_register(Reg::R3), 0x1234);
+        assert_eq!(processor.read_register(Reg::PC), 0x100);
+        assert_eq!(processor.get_r(Reg::PC), 0x104);
+    }
+
+    #[test]
+    fn test_write_psp_by_name() {
+        // Arrange
+        let mut processor = Processor::new();
+
+        // Act
+        processor.write_special_register(SpecialReg::PSP, 0x2000_0800);
+        processor.write_special_register(SpecialReg::CONTROL, 0b10);
+
+        // Assert
+        assert_eq!(
+            processor.read_special_register(SpecialReg::PSP),
+            0x2000_0800
+        );
+        assert_eq!(processor.read_special_register(SpecialReg::CONTROL), 0b10);
+        assert_eq!(processor.read_register(Reg::SP), 0x2000_0800);
+    }
+
+    #[test]
+    fn test_write_special_registers_by_name() {
+        // Arrange
+        let mut processor = Processor::new();
+        processor.write_special_register(SpecialReg::XPSR, 0x0100_0003);
+
+        // Act
+        processor.write_special_register(SpecialReg::PRIMASK, 1);
+        processor.write_special_register(SpecialReg::APSR, 0xffff_ffff);
+
+        // Assert
+        assert_eq!(processor.read_special_register(SpecialReg::PRIMASK), 1);
+        assert_eq!(processor.get_execution_priority(), 0);
+        assert_eq!(
+            processor.read_special_register(SpecialReg::APSR),
+            0xf80f_0000
+        );
+        assert_eq!(processor.read_special_register(SpecialReg::IPSR), 3);
+        assert_eq!(
+            processor.read_special_register(SpecialReg::XPSR),
+            0xf90f_0003
+        );
+    }
+
+    #[test]
+    fn test_psr_display() {
+        let psr = PSR {
+            value: 0b1111_0001_0000_0000_0000_0000_0000_1111,
+        };
+        assert_eq!(format!("{}", psr), "NZCVq, IT=0b00000000, T=1, ISR=SysTick");
+
+        let psr = PSR {
+            value: 0b0010_0101_0000_0000_0011_1000_0000_0000,
+        };
+        assert_eq!(format!("{}", psr), "nzCvq, IT=0b00111010, T=1, ISR=Thread");
+
+        let psr = PSR {
+            value: 0b0000_1001_0000_0000_0000_0000_0001_0011,
+        };
+        assert_eq!(format!("{}", psr), "nzcvQ, IT=0b00000000, T=1, ISR=IRQ3");
+    }
+}
@@ -3,9 +3,12 @@
 //!
 
 use crate::bus::Bus;
-use crate::core::exception::ExceptionHandling;
+use crate::core::bits::Bits;
+use crate::core::exception::{Exception, ExceptionHandling, Lockup};
 use crate::core::fault::Fault;
 use crate::core::register::{BaseReg, PSR};
+use crate::peripheral::mpu::MPU_REGIONS;
+use crate::peripheral::scb::CCR_STKALIGN;
 use crate::Processor;
 use crate::ProcessorMode;
 
@@ -15,6 +18,21 @@ pub trait Reset {
     /// Reset Processor
     ///
     fn reset(&mut self) -> Result<(), Fault>;
+
+    ///
+    /// Reset Processor and the system peripherals
+    ///
+    fn system_reset(&mut self) -> Result<(), Fault>;
+
+    ///
+    /// Request a system reset to be done when the current instruction completes
+    ///
+    fn request_system_reset(&mut self);
+
+    ///
+    /// Do a system reset if one has been requested
+    ///
+    fn check_reset_request(&mut self);
 }
 
 impl Reset for Processor {
@@ -32,11 +50,14 @@ impl Reset for Processor {
         // Process stack pointer to zero
         self.set_psp(0);
 
-        // Link Register
-        self.lr = 0;
+        // Link Register, preset to an illegal exception return value
+        self.lr = 0xffff_ffff;
 
         // Mode
         self.mode = ProcessorMode::ThreadMode;
+        self.lockup = None;
+        self.breakpoint_hit = None;
+        self.run_breakpoint = None;
 
         // Apsr, ipsr
         self.psr = PSR { value: 0 };
@@ -49,6 +70,7 @@ impl Reset for Processor {
         self.basepri = 0;
         self.control.sp_sel = false;
         self.control.n_priv = false;
+        self.control.fpca = false;
 
         //TODO self.scs.reset();
         self.exceptions_reset();
@@ -58,8 +80,215 @@ impl Reset for Processor {
         self.itstate = 0;
         self.execution_priority = self.get_execution_priority();
 
-        let reset_vector = self.read32(vtor + 4)?;
+        // EPSR.T is taken from bit 0 of the reset vector
+        let reset_vector = self.read32(vtor.wrapping_add(4))?;
         self.blx_write_pc(reset_vector);
         Ok(())
     }
+
+    fn system_reset(&mut self) -> Result<(), Fault> {
+        // System control block
+        self.vtor = self.vtor_reset;
+        self.aircr = 0;
+        self.scr = 0;
+        self.ccr = CCR_STKALIGN;
+        self.shcsr = 0;
+        self.cfsr = 0;
+        self.hfsr = 0;
+        self.mmfar = 0;
+        self.bfar = 0;
+        self.afsr = 0;
+        self.cpacr = 0;
+
+        // Floating point context control
+        #[cfg(armv7em)]
+        {
+            self.fpccr = 0xc000_0000;
+        }
+        #[cfg(not(armv7em))]
+        {
+            self.fpccr = 0;
+        }
+        self.fpcar = 0;
+        self.fpdscr = 0;
+
+        // MPU
+        self.mpu_ctrl = 0;
+        self.mpu_rnr = 0;
+        self.mpu_rbar = [0; MPU_REGIONS];
+        self.mpu_rasr = [0; MPU_REGIONS];
+
+        // NVIC
+        self.nvic_interrupt_enabled = [0; 16];
+        self.nvic_interrupt_pending = [0; 16];
+
+        // SysTick
+        self.syst_rvr = 0;
+        self.syst_cvr = 0;
+        self.syst_csr = 0;
+
+        // Registered peripherals
+        for (_, peripheral) in &mut self.peripherals {
+            peripheral.reset();
+        }
+
+        self.state.set_bit(1, false); // sleeping == false
+        self.state.set_bit(2, false); // reset requested == false
+
+        self.reset()
+    }
+
+    fn request_system_reset(&mut self) {
+        self.state.set_bit(2, true); // reset requested == true
+    }
+
+    fn check_reset_request(&mut self) {
+        if self.state.get_bit(2) && self.system_reset().is_err() {
+            // the initial stack pointer or the reset vector could not be read
+            self.enter_lockup(Lockup::InvalidVector {
+                exception: Exception::Reset,
+                vector: None,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::exception::LOCKUP_ADDRESS;
+    use crate::core::executor::Executor;
+    use crate::core::register::{Epsr, Reg};
+    use crate::peripheral::scb::SystemControlBlock;
+    use crate::peripheral::systick::SysTick;
+    use crate::test_fixture::handler_test_processor;
+
+    fn vector_table(offset: usize, sp: u32, pc: u32) -> Vec<u8> {
+        let mut code = vec![0; offset + 0x100];
+        code[offset..offset + 4].copy_from_slice(&sp.to_le_bytes());
+        code[offset + 4..offset + 8].copy_from_slice(&pc.to_le_bytes());
+        code
+    }
+
+    #[test]
+    fn test_reset_loads_sp_and_pc_from_vector_table() {
+        // Arrange
+        let code = vector_table(0, 0x2000_0400, 0x0000_0041);
+        let mut processor = Processor::new();
+        processor.flash_memory(code.len(), &code);
+        processor.set_r(Reg::R3, 0x1234);
+        processor.primask = true;
+
+        // Act
+        processor.reset().unwrap();
+
+        // Assert
+        assert_eq!(processor.get_r(Reg::SP), 0x2000_0400);
+        assert_eq!(processor.get_msp(), 0x2000_0400);
+        assert_eq!(processor.get_pc(), 0x40);
+        assert!(processor.psr.get_t());
+        assert_eq!(processor.get_r(Reg::R3), 0);
+        assert_eq!(processor.get_r(Reg::LR), 0xffff_ffff);
+        assert!(!processor.primask);
+        assert_eq!(processor.mode, ProcessorMode::ThreadMode);
+    }
+
+    #[test]
+    fn test_reset_uses_vtor() {
+        // Arrange
+        let code = vector_table(0x80, 0x2000_0800, 0x0000_0061);
+        let mut processor = Processor::new();
+        processor.flash_memory(code.len(), &code);
+        processor.write_vtor(0x80);
+
+        // Act
+        processor.reset().unwrap();
+
+        // Assert
+        assert_eq!(processor.get_msp(), 0x2000_0800);
+        assert_eq!(processor.get_pc(), 0x60);
+    }
+
+    fn aircr_test_processor(aircr: u32) -> Processor {
+        let mut processor = handler_test_processor(
+            &[0x6001, 0xe7fe], // str r1, [r0], b .
+            &[],
+            &[],
+            0x2000_0400,
+        );
+        processor.set_r(Reg::R0, 0xe000_ed0c);
+        processor.set_r(Reg::R1, aircr);
+        processor.syst_write_csr(1);
+        processor
+    }
+
+    #[test]
+    fn test_sysresetreq_resets_system() {
+        // Arrange
+        let mut processor = aircr_test_processor(0x05fa_0004);
+
+        // Act
+        processor.step_instruction();
+
+        // Assert
+        assert_eq!(processor.get_pc(), 0x100);
+        assert_eq!(processor.get_r(Reg::R0), 0);
+        assert_eq!(processor.get_r(Reg::R1), 0);
+        assert_eq!(processor.syst_read_csr(), 0);
+        assert_eq!(processor.read_aircr(), 0xfa05_0000);
+    }
+
+    #[test]
+    fn test_sysresetreq_without_vectkey_is_ignored() {
+        // Arrange
+        let mut processor = aircr_test_processor(0x0000_0004);
+
+        // Act
+        processor.step_instruction();
+
+        // Assert
+        assert_eq!(processor.get_pc(), 0x102);
+        assert_eq!(processor.get_r(Reg::R0), 0xe000_ed0c);
+        assert_eq!(processor.get_r(Reg::R1), 4);
+        assert_eq!(processor.syst_read_csr(), 1);
+    }
+
+    #[test]
+    fn test_sysresetreq_keeps_configured_vtor() {
+        // Arrange
+        let mut code = vector_table(0x80, 0x2000_0800, 0x0000_0101);
+        code[0x100..0x104].copy_from_slice(&[0x01, 0x60, 0xfe, 0xe7]);
+        let mut processor = Processor::new();
+        processor.flash_memory(code.len(), &code);
+        processor.vtor_reset(0x80);
+        processor.reset().unwrap();
+        processor.set_r(Reg::R0, 0xe000_ed0c);
+        processor.set_r(Reg::R1, 0x05fa_0004);
+
+        // Act
+        processor.step_instruction();
+
+        // Assert
+        assert_eq!(processor.read_vtor(), 0x80);
+        assert_eq!(processor.get_msp(), 0x2000_0800);
+        assert_eq!(processor.get_pc(), 0x100);
+    }
+
+    #[test]
+    fn test_sysresetreq_with_unreadable_vector_table_locks_up() {
+        // Arrange
+        let mut processor = aircr_test_processor(0x05fa_0004);
+        processor.vtor_reset(0x6000_0000);
+
+        // Act
+        processor.step_instruction();
+
+        // Assert
+        let reason = Lockup::InvalidVector {
+            exception: Exception::Reset,
+            vector: None,
+        };
+        assert_eq!(processor.lockup(), Some(reason));
+        assert_eq!(processor.get_pc(), LOCKUP_ADDRESS);
+    }
 }
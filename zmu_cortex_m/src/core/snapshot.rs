@@ -0,0 +1,163 @@
+//!
+//! Snapshot and restore of the processor register state
+//!
+
+use crate::core::exception::ExceptionHandling;
+use crate::core::register::Control;
+use crate::Processor;
+use crate::ProcessorMode;
+
+///
+/// Copy of the core registers: general purpose and floating point
+/// registers, banked stack pointers, xPSR, the special purpose registers
+/// and the IT state. Memory contents are not included.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoreState {
+    r0_12: [u32; 13],
+    fp_regs: [u32; 32],
+    fpscr: u32,
+    msp: u32,
+    psp: u32,
+    lr: u32,
+    pc: u32,
+    psr: u32,
+    primask: bool,
+    #[cfg(any(armv7m, armv7em))]
+    faultmask: bool,
+    basepri: u8,
+    control: Control,
+    mode: ProcessorMode,
+    itstate: u8,
+}
+
+/// Trait for taking and restoring snapshots of the core registers
+pub trait Snapshot {
+    ///
+    /// Take a snapshot of the core registers
+    ///
+    fn snapshot(&self) -> CoreState;
+
+    ///
+    /// Restore the core registers from a snapshot
+    ///
+    fn restore(&mut self, state: &CoreState);
+}
+
+impl Snapshot for Processor {
+    fn snapshot(&self) -> CoreState {
+        CoreState {
+            r0_12: self.r0_12,
+            fp_regs: self.fp_regs,
+            fpscr: self.fpscr,
+            msp: self.msp,
+            psp: self.psp,
+            lr: self.lr,
+            pc: self.pc,
+            psr: self.psr.value,
+            primask: self.primask,
+            #[cfg(any(armv7m, armv7em))]
+            faultmask: self.faultmask,
+            basepri: self.basepri,
+            control: self.control,
+            mode: self.mode,
+            itstate: self.itstate,
+        }
+    }
+
+    fn restore(&mut self, state: &CoreState) {
+        self.r0_12 = state.r0_12;
+        self.fp_regs = state.fp_regs;
+        self.fpscr = state.fpscr;
+        self.msp = state.msp;
+        self.psp = state.psp;
+        self.lr = state.lr;
+        self.pc = state.pc;
+        self.psr.value = state.psr;
+        self.primask = state.primask;
+        #[cfg(any(armv7m, armv7em))]
+        {
+            self.faultmask = state.faultmask;
+        }
+        self.basepri = state.basepri;
+        self.control = state.control;
+        self.mode = state.mode;
+        self.itstate = state.itstate;
+
+        // masks may have changed which exceptions can preempt
+        self.execution_priority = self.get_execution_priority();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::exception::Exception;
+    use crate::core::executor::Executor;
+    use crate::core::instruction::Instruction;
+    use crate::core::register::{BaseReg, Ipsr};
+
+    #[test]
+    fn test_restore_returns_to_snapshot() {
+        // Arrange
+        let code = [
+            0x05, 0x20, // movs r0, #5
+            0x00, 0x21, // movs r1, #0
+            0x41, 0x18, // loop: adds r1, r0, r1
+            0x01, 0x38, // subs r0, #1
+            0xfc, 0xd1, // bne loop
+            0xfe, 0xe7, // b .
+        ];
+        let mut core = Processor::new();
+        core.flash_memory(code.len(), &code);
+        core.set_pc(0);
+        for _ in 0..5 {
+            core.step_instruction();
+        }
+        let snapshot = core.snapshot();
+
+        // Act
+        for _ in 0..7 {
+            core.step_instruction();
+        }
+        let later = core.snapshot();
+        core.restore(&snapshot);
+        let restored = core.snapshot();
+        for _ in 0..7 {
+            core.step_instruction();
+        }
+
+        // Assert
+        assert_ne!(later, snapshot);
+        assert_eq!(restored, snapshot);
+        assert_eq!(core.snapshot(), later);
+    }
+
+    #[test]
+    fn test_restore_updates_interrupt_masking() {
+        // Arrange
+        let mut core = Processor::new();
+        core.set_msp(0x2000_1000);
+        let unmasked = core.snapshot();
+        core.execute(
+            &Instruction::CPS {
+                im: true,
+                #[cfg(any(armv7m, armv7em))]
+                affect_pri: true,
+                #[cfg(any(armv7m, armv7em))]
+                affect_fault: false,
+            },
+            2,
+        );
+        core.set_exception_pending(Exception::SVCall);
+        core.check_exceptions();
+        assert_eq!(core.psr.get_isr_number(), 0);
+
+        // Act
+        core.restore(&unmasked);
+        core.check_exceptions();
+
+        // Assert
+        assert_eq!(core.psr.get_isr_number(), Exception::SVCall.into());
+    }
+}
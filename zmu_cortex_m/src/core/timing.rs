@@ -0,0 +1,376 @@
+//!
+//! Instruction timing models
+//!
+
+use crate::core::executor::ExecuteResult;
+use crate::core::instruction::Instruction;
+
+///
+/// Model of the number of clock cycles instructions burn.
+///
+/// The model is consulted after each executed instruction. Without a
+/// configured model the `DefaultTiming` of the simulated core is used.
+///
+pub trait TimingModel {
+    ///
+    /// Number of clock cycles burn by `instruction`.
+    ///
+    /// `result` tells if the instruction was taken, skipped or branched.
+    /// `wait_states` is the number of extra cycles the memory accesses of the
+    /// instruction spent waiting on the bus.
+    ///
+    fn cycles(&self, instruction: &Instruction, result: &ExecuteResult, wait_states: u32) -> u32;
+}
+
+///
+/// Number of registers moved by a load or store multiple instruction
+///
+fn transfer_count(instruction: &Instruction) -> Option<u32> {
+    match instruction {
+        Instruction::LDM { registers, .. }
+        | Instruction::LDMDB { registers, .. }
+        | Instruction::POP { registers, .. }
+        | Instruction::PUSH { registers, .. }
+        | Instruction::STM { registers, .. }
+        | Instruction::STMDB { registers, .. } => Some(registers.len() as u32),
+        Instruction::LDRD_imm { .. } | Instruction::STRD_imm { .. } => Some(2),
+        _ => None,
+    }
+}
+
+///
+/// Loads a single register, PC included, from memory
+///
+fn is_load(instruction: &Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::LDR_imm { .. }
+            | Instruction::LDR_lit { .. }
+            | Instruction::LDR_reg { .. }
+            | Instruction::LDRB_imm { .. }
+            | Instruction::LDRB_reg { .. }
+            | Instruction::LDRH_imm { .. }
+            | Instruction::LDRH_reg { .. }
+            | Instruction::LDRSB_imm { .. }
+            | Instruction::LDRSB_reg { .. }
+            | Instruction::LDRSH_imm { .. }
+            | Instruction::LDRSH_reg { .. }
+            | Instruction::LDREX { .. }
+            | Instruction::LDREXB { .. }
+            | Instruction::LDREXH { .. }
+    )
+}
+
+///
+/// Stores a single register to memory
+///
+fn is_store(instruction: &Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::STR_imm { .. }
+            | Instruction::STR_reg { .. }
+            | Instruction::STRB_imm { .. }
+            | Instruction::STRB_reg { .. }
+            | Instruction::STRH_imm { .. }
+            | Instruction::STRH_reg { .. }
+            | Instruction::STREX { .. }
+            | Instruction::STREXB { .. }
+            | Instruction::STREXH { .. }
+    )
+}
+
+fn is_barrier(instruction: &Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::DMB | Instruction::DSB | Instruction::ISB
+    )
+}
+
+///
+/// Timing model of the core the simulator is built for
+///
+#[cfg(armv6m)]
+pub type DefaultTiming = CortexM0Timing;
+
+///
+/// Timing model of the core the simulator is built for
+///
+#[cfg(any(armv7m, armv7em))]
+pub type DefaultTiming = CortexM3Timing;
+
+///
+/// Timing of the Cortex-M0 (ARMv6-M) core, as listed in the Cortex-M0
+/// Technical Reference Manual, table 3-1:
+///
+/// | instruction                      | cycles   |
+/// |----------------------------------|----------|
+/// | data processing, `MUL`           | 1        |
+/// | conditional branch not taken     | 1        |
+/// | `B`, `BX`, `BLX`, write to PC    | 3        |
+/// | `BL`                             | 4        |
+/// | single load or store             | 2        |
+/// | `LDM`, `STM`, `PUSH`, `POP`      | 1 + N    |
+/// | `POP` with PC                    | 3 + N    |
+/// | `MRS`, `MSR`                     | 3        |
+/// | `DMB`, `DSB`, `ISB`              | 4        |
+///
+/// N is the number of registers transferred, PC included.
+///
+#[derive(Default, Debug, Copy, Clone)]
+pub struct CortexM0Timing;
+
+impl TimingModel for CortexM0Timing {
+    fn cycles(&self, instruction: &Instruction, result: &ExecuteResult, wait_states: u32) -> u32 {
+        let cycles = match result {
+            ExecuteResult::NotTaken => 1,
+            ExecuteResult::Branched { .. } => match instruction {
+                Instruction::BL { .. } => 4,
+                Instruction::POP { registers, .. } => 3 + registers.len() as u32,
+                _ => 3,
+            },
+            _ => {
+                if let Some(count) = transfer_count(instruction) {
+                    1 + count
+                } else if is_load(instruction) || is_store(instruction) {
+                    2
+                } else if is_barrier(instruction) {
+                    4
+                } else {
+                    match instruction {
+                        Instruction::MRS { .. } | Instruction::MSR_reg { .. } => 3,
+                        _ => 1,
+                    }
+                }
+            }
+        };
+        cycles + wait_states
+    }
+}
+
+///
+/// Timing of the Cortex-M3 and Cortex-M4 (ARMv7-M) cores, as listed in the
+/// Cortex-M3 Technical Reference Manual, table 18-1:
+///
+/// | instruction                           | cycles    |
+/// |---------------------------------------|-----------|
+/// | data processing, `MUL`                | 1         |
+/// | conditional branch not taken          | 1         |
+/// | branches, write to PC                 | 1 + P     |
+/// | `TBB`, `TBH`                          | 2 + P     |
+/// | single load or store                  | 2         |
+/// | load to PC                            | 2 + P     |
+/// | `LDM`, `STM`, `PUSH`, `POP`, `LDRD`   | 1 + N     |
+/// | `LDM` or `POP` to PC                  | 1 + N + P |
+/// | `MLA`, `MLS`                          | 2         |
+/// | `UMULL`, `SMULL`                      | 5         |
+/// | `UMLAL`, `SMLAL`                      | 7         |
+/// | `UDIV`, `SDIV`                        | 12        |
+/// | `DMB`, `DSB`                          | 1         |
+/// | `ISB`                                 | 1 + P     |
+///
+/// Pipeline refills (P) are counted with their minimum of one cycle,
+/// barriers without the time waiting for outstanding memory accesses and
+/// data dependent instructions, such as divides, with their worst case.
+///
+#[derive(Default, Debug, Copy, Clone)]
+pub struct CortexM3Timing;
+
+/// Cycles to refill the pipeline after a branch
+const PIPELINE_REFILL: u32 = 1;
+
+impl TimingModel for CortexM3Timing {
+    fn cycles(&self, instruction: &Instruction, result: &ExecuteResult, wait_states: u32) -> u32 {
+        let cycles = match result {
+            ExecuteResult::NotTaken => 1,
+            ExecuteResult::Branched { .. } => {
+                if let Some(count) = transfer_count(instruction) {
+                    1 + count + PIPELINE_REFILL
+                } else if is_load(instruction) {
+                    2 + PIPELINE_REFILL
+                } else {
+                    match instruction {
+                        Instruction::TBB { .. } | Instruction::TBH { .. } => 2 + PIPELINE_REFILL,
+                        _ => 1 + PIPELINE_REFILL,
+                    }
+                }
+            }
+            _ => {
+                if let Some(count) = transfer_count(instruction) {
+                    1 + count
+                } else if is_load(instruction) || is_store(instruction) {
+                    2
+                } else {
+                    match instruction {
+                        Instruction::ISB => 1 + PIPELINE_REFILL,
+                        Instruction::MLA { .. } | Instruction::MLS { .. } => 2,
+                        Instruction::UMULL { .. } | Instruction::SMULL { .. } => 5,
+                        Instruction::UMLAL { .. } | Instruction::SMLAL { .. } => 7,
+                        Instruction::UDIV { .. } | Instruction::SDIV { .. } => 12,
+                        _ => 1,
+                    }
+                }
+            }
+        };
+        cycles + wait_states
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::executor::Executor;
+    use crate::core::register::{BaseReg, Reg};
+    use crate::Processor;
+    use enum_set::EnumSet;
+
+    fn run_countdown_loop(model: Option<Box<dyn TimingModel>>, flash_wait_states: u32) -> u64 {
+        let code = [
+            0x03, 0x20, // movs r0, #3
+            0x01, 0x38, // loop: subs r0, #1
+            0xfd, 0xd1, // bne loop
+            0xfe, 0xe7, // b .
+        ];
+        let mut core = Processor::new();
+        core.flash_memory(code.len(), &code);
+        core.timing_model(model);
+        core.flash_wait_states(flash_wait_states);
+        core.set_pc(0);
+
+        for _ in 0..7 {
+            core.step_instruction();
+        }
+        assert_eq!(core.get_r(Reg::R0), 0);
+        assert_eq!(core.get_pc(), 6);
+        core.cycle_count
+    }
+
+    #[test]
+    fn test_loop_cycles_depend_on_timing_model() {
+        // Arrange & Act
+        let m0_cycles = run_countdown_loop(Some(Box::new(CortexM0Timing)), 0);
+        let m3_cycles = run_countdown_loop(Some(Box::new(CortexM3Timing)), 0);
+
+        // Assert
+        // movs + 3 * subs + 2 * taken bne + not taken bne
+        assert_eq!(m0_cycles, 1 + 3 + 2 * 3 + 1);
+        assert_eq!(m3_cycles, 1 + 3 + 2 * 2 + 1);
+    }
+
+    #[test]
+    fn test_loop_cycles_depend_on_flash_wait_states() {
+        // Arrange & Act
+        let fast = run_countdown_loop(Some(Box::new(CortexM3Timing)), 0);
+        let slow = run_countdown_loop(Some(Box::new(CortexM3Timing)), 5);
+        let no_model_fast = run_countdown_loop(None, 0);
+        let no_model_slow = run_countdown_loop(None, 5);
+
+        // Assert
+        // every instruction fetch waits for the flash
+        assert_eq!(slow, fast + 7 * 5);
+        assert_eq!(no_model_slow, no_model_fast + 7 * 5);
+    }
+
+    #[test]
+    fn test_flash_data_reads_add_wait_states() {
+        // Arrange
+        let code = [
+            0x00, 0x48, // ldr r0, [pc, #0]
+            0xfe, 0xe7, // b .
+            0x78, 0x56, 0x34, 0x12, // .word 0x12345678
+        ];
+        let mut core = Processor::new();
+        core.flash_memory(code.len(), &code);
+        core.timing_model(Some(Box::new(CortexM3Timing)));
+        core.flash_wait_states(2);
+        core.set_pc(0);
+
+        // Act
+        let result = core.step_instruction();
+
+        // Assert
+        assert_eq!(core.get_r(Reg::R0), 0x1234_5678);
+        // load + instruction fetch wait + data read wait
+        assert_eq!(result.cycles(), 2 + 2 + 2);
+    }
+
+    #[test]
+    fn test_wait_states_are_added() {
+        // Arrange
+        let mut registers = EnumSet::new();
+        registers.insert(Reg::R4);
+        registers.insert(Reg::PC);
+        let pop = Instruction::POP {
+            registers,
+            thumb32: false,
+        };
+        let load = Instruction::LDR_imm {
+            rt: Reg::R0,
+            rn: Reg::R1,
+            imm32: 0,
+            index: true,
+            add: true,
+            wback: false,
+            thumb32: false,
+        };
+        let taken = ExecuteResult::Taken { cycles: 1 };
+        let branched = ExecuteResult::Branched { cycles: 1 };
+
+        // Act & Assert
+        assert_eq!(CortexM0Timing.cycles(&load, &taken, 0), 2);
+        assert_eq!(CortexM0Timing.cycles(&load, &taken, 3), 5);
+        assert_eq!(CortexM0Timing.cycles(&pop, &branched, 2), 7);
+        assert_eq!(CortexM3Timing.cycles(&pop, &branched, 2), 6);
+    }
+
+    #[test]
+    fn test_cycles_follow_trm_tables() {
+        // Arrange
+        let mut registers = EnumSet::new();
+        registers.insert(Reg::R4);
+        registers.insert(Reg::PC);
+        let pop = Instruction::POP {
+            registers,
+            thumb32: false,
+        };
+        let bl = Instruction::BL { imm32: 0 };
+        let mrs = Instruction::MRS {
+            rd: Reg::R0,
+            sysm: 0,
+        };
+        let taken = ExecuteResult::Taken { cycles: 1 };
+        let branched = ExecuteResult::Branched { cycles: 1 };
+
+        // Act & Assert
+        assert_eq!(CortexM0Timing.cycles(&bl, &branched, 0), 4);
+        assert_eq!(CortexM3Timing.cycles(&bl, &branched, 0), 2);
+        assert_eq!(CortexM0Timing.cycles(&pop, &branched, 0), 5);
+        assert_eq!(CortexM3Timing.cycles(&pop, &branched, 0), 4);
+        assert_eq!(CortexM0Timing.cycles(&mrs, &taken, 0), 3);
+        assert_eq!(CortexM3Timing.cycles(&mrs, &taken, 0), 1);
+        assert_eq!(CortexM0Timing.cycles(&Instruction::ISB, &taken, 0), 4);
+        assert_eq!(CortexM3Timing.cycles(&Instruction::ISB, &taken, 0), 2);
+        assert_eq!(CortexM3Timing.cycles(&Instruction::DMB, &taken, 0), 1);
+    }
+
+    #[test]
+    fn test_default_timing_follows_core() {
+        // Arrange
+        let code = [
+            0x00, 0xf0, 0x00, 0xf8, // bl 0x4
+            0xfe, 0xe7, // b .
+        ];
+        let mut core = Processor::new();
+        core.flash_memory(code.len(), &code);
+        core.set_pc(0);
+
+        // Act
+        let result = core.step_instruction();
+
+        // Assert
+        assert_eq!(core.get_pc(), 4);
+        #[cfg(armv6m)]
+        assert_eq!(result.cycles(), 4);
+        #[cfg(any(armv7m, armv7em))]
+        assert_eq!(result.cycles(), 2);
+    }
+}
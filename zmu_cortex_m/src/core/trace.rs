@@ -0,0 +1,179 @@
+//!
+//! Diagnostic events of the simulation
+//!
+//! The simulator reports what happens inside the processor to a
+//! `TraceSink` instead of printing, so that a host application decides
+//! what to log and where.
+//!
+
+use crate::core::exception::Exception;
+use crate::core::instruction::Instruction;
+use crate::semihosting::SemihostingCommand;
+
+///
+/// Verbosity of a trace event, from the most to the least frequent
+///
+#[derive(PartialEq, PartialOrd, Eq, Ord, Debug, Copy, Clone)]
+pub enum TraceLevel {
+    /// once per instruction
+    Trace,
+    /// exceptions and device accesses
+    Debug,
+    /// requests made by the program
+    Info,
+}
+
+///
+/// Something that happened during the simulation
+///
+#[derive(PartialEq, Debug)]
+pub enum TraceEvent<'a> {
+    /// An instruction completed without faulting
+    InstructionRetired {
+        /// address of the instruction
+        address: u32,
+        /// the instruction
+        instruction: &'a Instruction,
+        /// number of clock cycles burn
+        cycles: u32,
+    },
+    /// The processor entered an exception handler
+    ExceptionEntered {
+        /// the exception taken
+        exception: Exception,
+        /// address execution returns to from the handler
+        return_address: u32,
+    },
+    /// The program made a semihosting request
+    Semihosting {
+        /// the decoded request
+        command: &'a SemihostingCommand,
+    },
+    /// The device model handled a memory access
+    DeviceAccess {
+        /// accessed address
+        address: u32,
+        /// access size in bytes
+        size: usize,
+        /// value read or written
+        value: u32,
+        /// `true` for writes
+        is_write: bool,
+    },
+}
+
+impl TraceEvent<'_> {
+    ///
+    /// Verbosity of the event
+    ///
+    pub fn level(&self) -> TraceLevel {
+        match self {
+            Self::InstructionRetired { .. } => TraceLevel::Trace,
+            Self::ExceptionEntered { .. } | Self::DeviceAccess { .. } => TraceLevel::Debug,
+            Self::Semihosting { .. } => TraceLevel::Info,
+        }
+    }
+}
+
+///
+/// Receiver of trace events
+///
+/// Events may be reported via shared references to the processor, so the
+/// sink gets a shared reference too; use interior mutability to record.
+///
+pub trait TraceSink {
+    ///
+    /// Handle one event
+    ///
+    fn event(&self, event: &TraceEvent);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::exception::ExceptionHandling;
+    use crate::core::executor::Executor;
+    use crate::core::register::BaseReg;
+    use crate::Processor;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[derive(Clone, Default)]
+    struct RecordingSink {
+        events: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl TraceSink for RecordingSink {
+        fn event(&self, event: &TraceEvent) {
+            let text = match event {
+                TraceEvent::InstructionRetired { address, .. } => {
+                    format!("retired 0x{:x}", address)
+                }
+                TraceEvent::ExceptionEntered { exception, .. } => {
+                    format!("entered {}", exception)
+                }
+                TraceEvent::Semihosting { command } => format!("semihosting {:?}", command),
+                TraceEvent::DeviceAccess { address, .. } => format!("device 0x{:x}", address),
+            };
+            self.events.borrow_mut().push(text);
+        }
+    }
+
+    #[test]
+    fn test_trace_sink_records_events() {
+        // Arrange
+        let mut code = vec![0_u8; 0x110];
+        code[44..48].copy_from_slice(&0x109_u32.to_le_bytes()); // SVCall
+        code[0x100..0x102].copy_from_slice(&0x2001_u16.to_le_bytes()); // movs r0, #1
+        code[0x102..0x104].copy_from_slice(&0xdf00_u16.to_le_bytes()); // svc #0
+        code[0x108..0x10a].copy_from_slice(&0xbf00_u16.to_le_bytes()); // nop
+        let sink = RecordingSink::default();
+        let mut processor = Processor::new();
+        processor.flash_memory(code.len(), &code);
+        processor.trace_sink(Some(Box::new(sink.clone())));
+        processor.set_msp(0x2000_1000);
+        processor.set_pc(0x100);
+        processor.execution_priority = processor.get_execution_priority();
+
+        // Act
+        for _ in 0..3 {
+            processor.step_instruction();
+        }
+
+        // Assert
+        assert_eq!(
+            *sink.events.borrow(),
+            vec![
+                "retired 0x100",
+                "retired 0x102",
+                "entered SVCall",
+                "retired 0x108",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_trace_levels() {
+        // Arrange
+        let nop = Instruction::NOP { thumb32: false };
+
+        // Act & Assert
+        assert!(
+            TraceEvent::InstructionRetired {
+                address: 0,
+                instruction: &nop,
+                cycles: 1,
+            }
+            .level()
+                < TraceLevel::Info
+        );
+        assert_eq!(
+            TraceEvent::ExceptionEntered {
+                exception: Exception::HardFault,
+                return_address: 0,
+            }
+            .level(),
+            TraceLevel::Debug
+        );
+    }
+}
@@ -0,0 +1,184 @@
+//!
+//! Processor core variants and the instructions they implement
+//!
+
+use crate::core::instruction::Instruction;
+use crate::core::thumb::ThumbCode;
+
+///
+/// Cortex-M core being simulated
+///
+/// Instructions the core does not implement decode as UNDEFINED.
+///
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum CoreVariant {
+    /// ARMv6-M
+    CortexM0,
+    /// ARMv6-M
+    CortexM0Plus,
+    /// ARMv7-M
+    CortexM3,
+    /// ARMv7E-M, DSP extension
+    CortexM4,
+    /// ARMv7E-M, DSP extension and single precision floating point
+    CortexM4F,
+}
+
+impl CoreVariant {
+    ///
+    /// Check if the core implements an instruction decoded from `code`
+    ///
+    pub fn supports(self, code: ThumbCode, instruction: &Instruction) -> bool {
+        match self {
+            Self::CortexM0 | Self::CortexM0Plus => is_armv6m(code, instruction),
+            Self::CortexM3 => !is_dsp(instruction) && !is_fp(instruction),
+            Self::CortexM4 => !is_fp(instruction),
+            Self::CortexM4F => true,
+        }
+    }
+}
+
+///
+/// ARMv6-M has a handful of 32-bit instructions and lacks CBZ and IT
+///
+fn is_armv6m(code: ThumbCode, instruction: &Instruction) -> bool {
+    match code {
+        ThumbCode::Thumb32 { .. } => matches!(
+            instruction,
+            Instruction::BL { .. }
+                | Instruction::MSR_reg { .. }
+                | Instruction::MRS { .. }
+                | Instruction::DMB
+                | Instruction::DSB
+                | Instruction::ISB
+                | Instruction::UDF { .. }
+        ),
+        ThumbCode::Thumb16 { .. } => !matches!(
+            instruction,
+            Instruction::CBZ { .. } | Instruction::IT { .. }
+        ),
+    }
+}
+
+///
+/// Instructions of the ARMv7E-M DSP extension
+///
+fn is_dsp(instruction: &Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::SEL { .. }
+            | Instruction::UADD8 { .. }
+            | Instruction::SMUL { .. }
+            | Instruction::SMLA { .. }
+            | Instruction::UXTAB { .. }
+    )
+}
+
+///
+/// Floating point extension instructions
+///
+fn is_fp(instruction: &Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::VADD_f32 { .. }
+            | Instruction::VCVT_f32_to_int { .. }
+            | Instruction::VCVT_int_to_f32 { .. }
+            | Instruction::VDIV_f32 { .. }
+            | Instruction::VLDR { .. }
+            | Instruction::VMOV_cr_sp { .. }
+            | Instruction::VMOV_cr2_dp { .. }
+            | Instruction::VMOV_reg_f32 { .. }
+            | Instruction::VMOV_reg_f64 { .. }
+            | Instruction::VMUL_f32 { .. }
+            | Instruction::VSTR { .. }
+            | Instruction::VSUB_f32 { .. }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::executor::{Executor, StepResult};
+    use crate::core::fault::Fault;
+    use crate::core::register::{BaseReg, Reg};
+    use crate::decoder::Decoder;
+    use crate::test_fixture::handler_test_processor;
+    use crate::Processor;
+
+    fn sdiv_test_processor(variant: CoreVariant) -> Processor {
+        // HardFault handler at 0x200
+        let mut processor = handler_test_processor(
+            &[0xfb91, 0xf0f2], // sdiv r0, r1, r2
+            &[0xe7fe],         // b .
+            &[3],
+            0x2000_1000,
+        );
+        processor.core_variant(Some(variant));
+        processor.set_r(Reg::R1, -42_i32 as u32);
+        processor.set_r(Reg::R2, 2);
+        processor
+    }
+
+    #[test]
+    fn test_sdiv_is_undefined_on_cortex_m0() {
+        // Arrange
+        let mut processor = sdiv_test_processor(CoreVariant::CortexM0);
+
+        // Act
+        let result = processor.step_instruction();
+
+        // Assert
+        assert!(matches!(
+            result,
+            StepResult::Faulted {
+                fault: Fault::UndefInstr,
+                ..
+            }
+        ));
+        assert_eq!(processor.get_pc(), 0x200);
+    }
+
+    #[test]
+    fn test_sdiv_runs_on_cortex_m3() {
+        // Arrange
+        let mut processor = sdiv_test_processor(CoreVariant::CortexM3);
+
+        // Act
+        let result = processor.step_instruction();
+
+        // Assert
+        assert!(matches!(result, StepResult::Executed { .. }));
+        assert_eq!(processor.get_r(Reg::R0), -21_i32 as u32);
+        assert_eq!(processor.get_pc(), 0x104);
+    }
+
+    #[test]
+    fn test_instructions_available_per_variant() {
+        // Arrange
+        let mut processor = Processor::new();
+        let cbz = ThumbCode::Thumb16 { opcode: 0xb100 }; // cbz r0, .+4
+        let uadd8 = ThumbCode::Thumb32 {
+            opcode: 0xfa81_f042, // uadd8 r0, r1, r2
+        };
+        let vadd = ThumbCode::Thumb32 {
+            opcode: 0xee30_0a81, // vadd.f32 s0, s1, s2
+        };
+        let available = |processor: &Processor, code| {
+            !matches!(processor.decode(code), Instruction::UDF { .. })
+        };
+
+        // Act & Assert
+        processor.core_variant(Some(CoreVariant::CortexM0Plus));
+        assert!(!available(&processor, cbz));
+        processor.core_variant(Some(CoreVariant::CortexM3));
+        assert!(available(&processor, cbz));
+        assert!(!available(&processor, uadd8));
+        processor.core_variant(Some(CoreVariant::CortexM4));
+        assert!(available(&processor, uadd8));
+        assert!(!available(&processor, vadd));
+        processor.core_variant(Some(CoreVariant::CortexM4F));
+        assert!(available(&processor, vadd));
+        processor.core_variant(None);
+        assert!(available(&processor, vadd));
+    }
+}
@@ -64,6 +64,27 @@ pub fn decode_ADD_reg_sp_t2(opcode: u16) -> Instruction {
     }
 }
 
+#[allow(non_snake_case)]
+pub fn decode_ADD_reg_sp_t3(opcode: u32) -> Instruction {
+    let rm: u8 = opcode.get_bits(0..4) as u8;
+    let rd: u8 = opcode.get_bits(8..12) as u8;
+
+    let imm3: u8 = opcode.get_bits(12..15) as u8;
+    let imm2: u8 = opcode.get_bits(6..8) as u8;
+    let type_: u8 = opcode.get_bits(4..6) as u8;
+
+    let (shift_t, shift_n) = decode_imm_shift(type_, (imm3 << 2) + imm2);
+
+    Instruction::ADD_sp_reg {
+        rd: Reg::from(rd),
+        rm: Reg::from(rm),
+        setflags: opcode.get_bit(20),
+        shift_t,
+        shift_n,
+        thumb32: true,
+    }
+}
+
 #[allow(non_snake_case)]
 pub fn decode_ADD_reg_t3(opcode: u32) -> Instruction {
     let rn: u8 = opcode.get_bits(16..20) as u8;
@@ -8,6 +8,7 @@ pub fn decode_ADR_t1(command: u16) -> Instruction {
     Instruction::ADR {
         rd: From::from(command.get_bits(8..11)),
         imm32: u32::from(command.get_bits(0..8)) << 2,
+        add: true,
         thumb32: false,
     }
 }
@@ -15,9 +16,17 @@ pub fn decode_ADR_t1(command: u16) -> Instruction {
 #[allow(non_snake_case)]
 #[inline(always)]
 pub fn decode_ADR_t2(opcode: u32) -> Instruction {
-    Instruction::UDF {
-        imm32: 0,
-        opcode: opcode.into(),
+    let i: u8 = opcode.get_bit(26) as u8;
+    let imm3: u8 = opcode.get_bits(12..15) as u8;
+    let rd: u8 = opcode.get_bits(8..12) as u8;
+    let imm8: u8 = opcode.get_bits(0..8) as u8;
+
+    let params = [i, imm3, imm8];
+    let lengths = [1, 3, 8];
+    Instruction::ADR {
+        rd: Reg::from(rd),
+        imm32: zero_extend(&params, &lengths),
+        add: false,
         thumb32: true,
     }
 }
@@ -35,6 +44,7 @@ pub fn decode_ADR_t3(opcode: u32) -> Instruction {
     Instruction::ADR {
         rd: Reg::from(rd),
         imm32: zero_extend(&params, &lengths),
+        add: true,
         thumb32: true,
     }
 }
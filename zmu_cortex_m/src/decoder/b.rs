@@ -15,7 +15,7 @@ pub fn decode_B_t1_SVC_t1(opcode: u16) -> Instruction {
     }
     if cond == 0b1110 {
         return Instruction::UDF {
-            imm32: 0,
+            imm32: u32::from(opcode.get_bits(0..8)),
             opcode: opcode.into(),
             thumb32: false,
         };
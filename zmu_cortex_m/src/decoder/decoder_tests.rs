@@ -1,6 +1,6 @@
-use crate::core::instruction::Imm32Carry;
+use crate::core::instruction::{instruction_size, Imm32Carry};
 use crate::core::instruction::{SRType, SetFlags};
-use crate::core::register::{DoubleReg, ExtensionReg, Reg};
+use crate::core::register::{DoubleReg, ExtensionReg, Reg, SingleReg};
 
 use super::*;
 
@@ -460,6 +460,116 @@ fn test_decode_pop() {
     }
 }
 
+#[test]
+fn test_decode_push_pop_malformed_lists() {
+    // PUSH {} and POP {}
+    assert_eq!(
+        decode_16(0xb400),
+        Instruction::UDF {
+            imm32: 0,
+            opcode: 0xb400_u16.into(),
+            thumb32: false,
+        }
+    );
+    assert_eq!(
+        decode_16(0xbc00),
+        Instruction::UDF {
+            imm32: 0,
+            opcode: 0xbc00_u16.into(),
+            thumb32: false,
+        }
+    );
+    // PUSH.W {R4, PC}
+    assert_eq!(
+        decode_32(0xe92d8010),
+        Instruction::UDF {
+            imm32: 0,
+            opcode: 0xe92d8010_u32.into(),
+            thumb32: true,
+        }
+    );
+    // PUSH.W {R4, SP}
+    assert_eq!(
+        decode_32(0xe92d2010),
+        Instruction::UDF {
+            imm32: 0,
+            opcode: 0xe92d2010_u32.into(),
+            thumb32: true,
+        }
+    );
+    // POP.W {R4, LR, PC}
+    assert_eq!(
+        decode_32(0xe8bdc010),
+        Instruction::UDF {
+            imm32: 0,
+            opcode: 0xe8bdc010_u32.into(),
+            thumb32: true,
+        }
+    );
+    // STR PC, [SP, #-4]!
+    assert_eq!(
+        decode_32(0xf84dfd04),
+        Instruction::UDF {
+            imm32: 0,
+            opcode: 0xf84dfd04_u32.into(),
+            thumb32: true,
+        }
+    );
+}
+
+#[test]
+fn test_decode_narrow_literal_loads() {
+    // LDRB.W R0, [PC, #12]
+    assert_eq!(
+        decode_32(0xf89f000c),
+        Instruction::LDRB_lit {
+            rt: Reg::R0,
+            imm32: 12,
+            add: true,
+        }
+    );
+    // LDRH.W R1, [PC, #-4]
+    assert_eq!(
+        decode_32(0xf83f1004),
+        Instruction::LDRH_lit {
+            rt: Reg::R1,
+            imm32: 4,
+            add: false,
+        }
+    );
+    // LDRSB.W R2, [PC, #4]
+    assert_eq!(
+        decode_32(0xf99f2004),
+        Instruction::LDRSB_lit {
+            rt: Reg::R2,
+            imm32: 4,
+            add: true,
+        }
+    );
+    // LDRSH.W R3, [PC, #0x800]
+    assert_eq!(
+        decode_32(0xf9bf3800),
+        Instruction::LDRSH_lit {
+            rt: Reg::R3,
+            imm32: 0x800,
+            add: true,
+        }
+    );
+    // Rt == PC is a memory hint
+    assert_eq!(decode_32(0xf83ff004), Instruction::NOP { thumb32: true });
+    assert_eq!(decode_32(0xf9bff004), Instruction::NOP { thumb32: true });
+    // LDR.W R1, [PC, #20]
+    assert_eq!(
+        decode_32(0xf8df1014),
+        Instruction::LDR_lit {
+            rt: Reg::R1,
+            imm32: 20,
+            add: true,
+            thumb32: true,
+        }
+    );
+}
+
 #[test]
 fn test_decode_ldr() {
     // LDR.N R1, [PC, 0x1c]
@@ -567,6 +677,82 @@ fn test_decode_add_reg_sp() {
     }
 }
 
+#[test]
+fn test_decode_sp_adjust() {
+    // SUB SP, SP, #16
+    assert_eq!(
+        decode_16(0xb084),
+        Instruction::SUB_imm {
+            rd: Reg::SP,
+            rn: Reg::SP,
+            imm32: 16,
+            setflags: SetFlags::False,
+            thumb32: false,
+        }
+    );
+    // ADD SP, SP, #16
+    assert_eq!(
+        decode_16(0xb004),
+        Instruction::ADD_imm {
+            rd: Reg::SP,
+            rn: Reg::SP,
+            imm32: 16,
+            setflags: SetFlags::False,
+            thumb32: false,
+        }
+    );
+    // SUB SP, SP, #508
+    assert_eq!(
+        decode_16(0xb0ff),
+        Instruction::SUB_imm {
+            rd: Reg::SP,
+            rn: Reg::SP,
+            imm32: 508,
+            setflags: SetFlags::False,
+            thumb32: false,
+        }
+    );
+    // ADD SP, SP, #508
+    assert_eq!(
+        decode_16(0xb07f),
+        Instruction::ADD_imm {
+            rd: Reg::SP,
+            rn: Reg::SP,
+            imm32: 508,
+            setflags: SetFlags::False,
+            thumb32: false,
+        }
+    );
+}
+
+#[test]
+fn test_decode_add_sp_reg() {
+    // ADD R0, SP, R0
+    assert_eq!(
+        decode_16(0x4468),
+        Instruction::ADD_sp_reg {
+            rd: Reg::R0,
+            rm: Reg::R0,
+            setflags: false,
+            shift_t: SRType::LSL,
+            shift_n: 0,
+            thumb32: false,
+        }
+    );
+    // ADD.W R0, SP, R1, LSL #2
+    assert_eq!(
+        decode_32(0xeb0d0081),
+        Instruction::ADD_sp_reg {
+            rd: Reg::R0,
+            rm: Reg::R1,
+            setflags: false,
+            shift_t: SRType::LSL,
+            shift_n: 2,
+            thumb32: true,
+        }
+    );
+}
+
 #[test]
 fn test_decode_sub() {
     // SUB SP,SP, #0x8
@@ -722,9 +908,15 @@ fn test_decode_lsls() {
 fn test_decode_adr() {
     // ADR R0, PC, #(7<<2)
     match decode_16(0xa007) {
-        Instruction::ADR { rd, imm32, thumb32 } => {
+        Instruction::ADR {
+            rd,
+            imm32,
+            add,
+            thumb32,
+        } => {
             assert!(rd == Reg::R0);
             assert!(imm32 == 7 << 2);
+            assert!(add);
             assert!(!thumb32);
         }
         _ => {
@@ -733,6 +925,20 @@ fn test_decode_adr() {
     }
 }
 
+#[test]
+fn test_decode_adr_sub() {
+    // SUB R1, PC, #0x123 (ADR.W R1, <label before the instruction>)
+    assert_eq!(
+        decode_32(0xf2af_1123),
+        Instruction::ADR {
+            rd: Reg::R1,
+            imm32: 0x123,
+            add: false,
+            thumb32: true,
+        }
+    );
+}
+
 #[test]
 fn test_decode_bkpt() {
     // BKPT #0xab
@@ -788,6 +994,35 @@ fn test_decode_nop() {
     }
 }
 
+#[test]
+fn test_decode_hints() {
+    assert_eq!(decode_16(0xbf00), Instruction::NOP { thumb32: false });
+    assert_eq!(decode_16(0xbf10), Instruction::YIELD { thumb32: false });
+    assert_eq!(decode_16(0xbf20), Instruction::WFE { thumb32: false });
+    assert_eq!(decode_16(0xbf30), Instruction::WFI { thumb32: false });
+    assert_eq!(decode_16(0xbf40), Instruction::SEV { thumb32: false });
+
+    assert_eq!(decode_32(0xf3af8000), Instruction::NOP { thumb32: true });
+    assert_eq!(decode_32(0xf3af8001), Instruction::YIELD { thumb32: true });
+    assert_eq!(decode_32(0xf3af8002), Instruction::WFE { thumb32: true });
+    assert_eq!(decode_32(0xf3af8003), Instruction::WFI { thumb32: true });
+    assert_eq!(decode_32(0xf3af8004), Instruction::SEV { thumb32: true });
+}
+
+#[test]
+fn test_decode_unallocated_hints_as_nop() {
+    assert_eq!(decode_16(0xbf50), Instruction::NOP { thumb32: false });
+    assert_eq!(decode_16(0xbff0), Instruction::NOP { thumb32: false });
+    assert_eq!(decode_32(0xf3af8005), Instruction::NOP { thumb32: true });
+    assert_eq!(decode_32(0xf3af80ef), Instruction::NOP { thumb32: true });
+
+    // a non-zero mask makes it an IT instruction instead
+    match decode_16(0xbf18) {
+        Instruction::IT { mask, .. } => assert_eq!(mask, 0b1000),
+        _ => panic!("expected IT"),
+    }
+}
+
 #[test]
 fn test_decode_mul() {
     // MULS R4, R0, R4
@@ -1006,9 +1241,11 @@ fn test_decode_ldm() {
         Instruction::LDM {
             rn,
             registers,
+            wback,
             thumb32,
         } => {
             assert!(rn == Reg::R2);
+            assert!(wback);
             let elems: Vec<_> = registers.iter().collect();
             assert_eq!(vec![Reg::R0, Reg::R1], elems);
             assert!(!thumb32);
@@ -1026,9 +1263,11 @@ fn test_decode_ldm2() {
         Instruction::LDM {
             rn,
             registers,
+            wback,
             thumb32,
         } => {
             assert!(rn == Reg::R1);
+            assert!(wback);
             let elems: Vec<_> = registers.iter().collect();
             assert_eq!(vec![Reg::R3], elems);
             assert!(!thumb32);
@@ -1046,9 +1285,11 @@ fn test_decode_ldm3() {
         Instruction::LDM {
             rn,
             registers,
+            wback,
             thumb32,
         } => {
             assert!(rn == Reg::R4);
+            assert!(wback);
             let elems: Vec<_> = registers.iter().collect();
             assert_eq!(vec![Reg::R0, Reg::R1, Reg::R2], elems);
             assert!(!thumb32);
@@ -1377,6 +1618,33 @@ fn test_decode_cpsid() {
             affect_fault: false
         }
     );
+    // CPSIE i
+    assert_eq!(
+        decode_16(0xB662),
+        Instruction::CPS {
+            im: false,
+            affect_pri: true,
+            affect_fault: false
+        }
+    );
+    // CPSID f
+    assert_eq!(
+        decode_16(0xB671),
+        Instruction::CPS {
+            im: true,
+            affect_pri: false,
+            affect_fault: true
+        }
+    );
+    // CPSIE if
+    assert_eq!(
+        decode_16(0xB663),
+        Instruction::CPS {
+            im: false,
+            affect_pri: true,
+            affect_fault: true
+        }
+    );
 }
 
 #[test]
@@ -1403,6 +1671,110 @@ fn test_decode_bl_t1() {
     assert_eq!(decode_32(0xf7fefce1), Instruction::BL { imm32: -5694 });
 }
 
+#[test]
+fn test_decode_bl_t1_range() {
+    // BL +4
+    assert_eq!(decode_32(0xf000f802), Instruction::BL { imm32: 4 });
+
+    // BL -4
+    assert_eq!(decode_32(0xf7fffffe), Instruction::BL { imm32: -4 });
+
+    // BL +16777214, largest forward offset (S=0, J1=J2=0)
+    assert_eq!(decode_32(0xf3ffd7ff), Instruction::BL { imm32: 16_777_214 });
+
+    // BL -16777216, largest backward offset (S=1, J1=J2=0)
+    assert_eq!(
+        decode_32(0xf400d000),
+        Instruction::BL { imm32: -16_777_216 }
+    );
+
+    // BL +8388608, only I1 set (S=0, J1=0, J2=1)
+    assert_eq!(decode_32(0xf000d800), Instruction::BL { imm32: 0x0080_0000 });
+
+    // BL -12582912, only I2 set (S=1, J1=0, J2=1)
+    assert_eq!(decode_32(0xf400d800), Instruction::BL { imm32: -0x00c0_0000 });
+}
+
+#[test]
+fn test_decode_blx_imm_is_undefined() {
+    // BLX -132 (ARM state target) is UNDEFINED on M-profile
+    assert_eq!(
+        decode_32(0xf7ffefbe),
+        Instruction::UDF {
+            imm32: 0,
+            opcode: 0xf7ffefbe_u32.into(),
+            thumb32: true,
+        }
+    );
+}
+
+#[test]
+fn test_decode_udf() {
+    // UDF #0x12
+    assert_eq!(
+        decode_16(0xde12),
+        Instruction::UDF {
+            imm32: 0x12,
+            opcode: 0xde12_u16.into(),
+            thumb32: false,
+        }
+    );
+    // UDF.W #0x1234
+    assert_eq!(
+        decode_32(0xf7f1a234),
+        Instruction::UDF {
+            imm32: 0x1234,
+            opcode: 0xf7f1a234_u32.into(),
+            thumb32: true,
+        }
+    );
+}
+
+#[test]
+fn test_decode_reserved_is_undefined() {
+    // unallocated miscellaneous 16 bit encoding
+    let instruction = decode_16(0xb800);
+    assert_eq!(
+        instruction,
+        Instruction::UDF {
+            imm32: 0,
+            opcode: 0xb800_u16.into(),
+            thumb32: false,
+        }
+    );
+    assert_eq!(instruction_size(&instruction), 2);
+
+    // SRS, an ARM-profile only load/store multiple encoding
+    let instruction = decode_32(0xe80dc000);
+    assert_eq!(
+        instruction,
+        Instruction::UDF {
+            imm32: 0,
+            opcode: 0xe80dc000_u32.into(),
+            thumb32: true,
+        }
+    );
+    assert_eq!(instruction_size(&instruction), 4);
+}
+
+#[test]
+fn test_instruction_len_follows_encoding() {
+    // every narrow encoding is 2 bytes long
+    for opcode in 0..=0xffff_u16 {
+        if !is_thumb32(opcode) {
+            assert_eq!(decode_16(opcode).len(), 2, "{:#06x}", opcode);
+        }
+    }
+
+    // every wide encoding is 4 bytes long
+    for hw1 in 0xe800..=0xffff_u32 {
+        for hw2 in &[0x0000_u32, 0x0fff, 0x8f00, 0xffff] {
+            let opcode = (hw1 << 16) | hw2;
+            assert_eq!(decode_32(opcode).len(), 4, "{:#010x}", opcode);
+        }
+    }
+}
+
 #[test]
 fn test_decode_ldrw_imm() {
     // LDR.W R1, [R0], #0x4
@@ -1450,6 +1822,19 @@ fn test_decode_cbz() {
     );
 }
 
+#[test]
+fn test_decode_cbnz_max_offset() {
+    // CBNZ R0, <pc + 126>
+    assert_eq!(
+        decode_16(0xbbf8),
+        Instruction::CBZ {
+            rn: Reg::R0,
+            imm32: 126,
+            nonzero: true,
+        }
+    );
+}
+
 #[test]
 fn test_decode_it() {
     // ITT MI
@@ -2536,6 +2921,28 @@ fn test_decode_ror_imm_w() {
     );
 }
 
+#[test]
+fn test_decode_ldmdb() {
+    // LDMDB R0!, {R7, R8}
+    assert_eq!(
+        decode_32(0xe9300180),
+        Instruction::LDMDB {
+            rn: Reg::R0,
+            registers: [Reg::R7, Reg::R8].iter().cloned().collect(),
+            wback: true,
+        }
+    );
+    // LDMDB R0, {R4, R5}
+    assert_eq!(
+        decode_32(0xe9100030),
+        Instruction::LDMDB {
+            rn: Reg::R0,
+            registers: [Reg::R4, Reg::R5].iter().cloned().collect(),
+            wback: false,
+        }
+    );
+}
+
 #[test]
 fn test_decode_ldm_t2_w() {
     // 0xe8b11008 -> LDM R1!, {R3, R12}
@@ -2544,9 +2951,11 @@ fn test_decode_ldm_t2_w() {
         Instruction::LDM {
             rn,
             registers,
+            wback,
             thumb32,
         } => {
             assert!(rn == Reg::R1);
+            assert!(wback);
             let elems: Vec<_> = registers.iter().collect();
             assert_eq!(vec![Reg::R3, Reg::R12], elems);
             assert!(thumb32);
@@ -2942,6 +3351,166 @@ fn test_decode_vldr() {
 }
 
 
+#[test]
+fn test_decode_vldr_vstr_single() {
+    // vldr s1, [r0, #4]
+    assert_eq!(
+        decode_32(0xedd00a01),
+        Instruction::VLDR {
+            dd: ExtensionReg::Single {
+                reg: SingleReg::S1
+            },
+            rn: Reg::R0,
+            add: true,
+            imm32: 4,
+            single_reg: true
+        }
+    );
+    // vstr s3, [sp, #-8]
+    assert_eq!(
+        decode_32(0xed4d1a02),
+        Instruction::VSTR {
+            dd: ExtensionReg::Single {
+                reg: SingleReg::S3
+            },
+            rn: Reg::SP,
+            add: false,
+            imm32: 8,
+            single_reg: true
+        }
+    );
+}
+
+#[test]
+fn test_decode_vfp_arithmetic() {
+    // vadd.f32 s0, s1, s2
+    assert_eq!(
+        decode_32(0xee300a81),
+        Instruction::VADD_f32 {
+            sd: SingleReg::S0,
+            sn: SingleReg::S1,
+            sm: SingleReg::S2
+        }
+    );
+    // vsub.f32 s3, s4, s5
+    assert_eq!(
+        decode_32(0xee721a62),
+        Instruction::VSUB_f32 {
+            sd: SingleReg::S3,
+            sn: SingleReg::S4,
+            sm: SingleReg::S5
+        }
+    );
+    // vmul.f32 s0, s0, s1
+    assert_eq!(
+        decode_32(0xee200a20),
+        Instruction::VMUL_f32 {
+            sd: SingleReg::S0,
+            sn: SingleReg::S0,
+            sm: SingleReg::S1
+        }
+    );
+    // vdiv.f32 s2, s0, s1
+    assert_eq!(
+        decode_32(0xee801a20),
+        Instruction::VDIV_f32 {
+            sd: SingleReg::S2,
+            sn: SingleReg::S0,
+            sm: SingleReg::S1
+        }
+    );
+}
+
+#[test]
+fn test_decode_vcvt() {
+    // vcvt.s32.f32 s0, s1
+    assert_eq!(
+        decode_32(0xeebd0ae0),
+        Instruction::VCVT_f32_to_int {
+            sd: SingleReg::S0,
+            sm: SingleReg::S1,
+            unsigned: false,
+            round_zero: true
+        }
+    );
+    // vcvtr.u32.f32 s4, s5
+    assert_eq!(
+        decode_32(0xeebc2a62),
+        Instruction::VCVT_f32_to_int {
+            sd: SingleReg::S4,
+            sm: SingleReg::S5,
+            unsigned: true,
+            round_zero: false
+        }
+    );
+    // vcvt.f32.s32 s2, s0
+    assert_eq!(
+        decode_32(0xeeb81ac0),
+        Instruction::VCVT_int_to_f32 {
+            sd: SingleReg::S2,
+            sm: SingleReg::S0,
+            unsigned: false
+        }
+    );
+    // vcvt.f32.u32 s6, s4
+    assert_eq!(
+        decode_32(0xeeb83a42),
+        Instruction::VCVT_int_to_f32 {
+            sd: SingleReg::S6,
+            sm: SingleReg::S4,
+            unsigned: true
+        }
+    );
+}
+
+#[test]
+fn test_decode_vmov() {
+    // vmov s3, r1
+    assert_eq!(
+        decode_32(0xee011a90),
+        Instruction::VMOV_cr_sp {
+            to_arm_register: false,
+            rt: Reg::R1,
+            sn: SingleReg::S3,
+        }
+    );
+    // vmov r2, s3
+    assert_eq!(
+        decode_32(0xee112a90),
+        Instruction::VMOV_cr_sp {
+            to_arm_register: true,
+            rt: Reg::R2,
+            sn: SingleReg::S3,
+        }
+    );
+    // vmov r4, r5, d1
+    assert_eq!(
+        decode_32(0xec554b11),
+        Instruction::VMOV_cr2_dp {
+            to_arm_registers: true,
+            rt: Reg::R4,
+            rt2: Reg::R5,
+            dm: DoubleReg::D1,
+        }
+    );
+    // vmov.f32 s5, s3
+    assert_eq!(
+        decode_32(0xeef02a61),
+        Instruction::VMOV_reg_f32 {
+            sd: SingleReg::S5,
+            sm: SingleReg::S3,
+        }
+    );
+    // vmov.f64 d2, d1
+    assert_eq!(
+        decode_32(0xeeb02b41),
+        Instruction::VMOV_reg_f64 {
+            dd: DoubleReg::D2,
+            dm: DoubleReg::D1,
+        }
+    );
+}
+
 #[test]
 fn test_decode_vstr() {
     //250:       ed8d 7b12       vstr    d7, [sp, #72]   ; 0x48
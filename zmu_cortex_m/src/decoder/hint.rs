@@ -0,0 +1,39 @@
+use crate::core::bits::Bits;
+use crate::core::instruction::Instruction;
+
+use super::{
+    dbg::decode_DBG_t1,
+    nop::{decode_NOP_t1, decode_NOP_t2},
+    sev::{decode_SEV_t1, decode_SEV_t2},
+    wfe::{decode_WFE_t1, decode_WFE_t2},
+    wfi::{decode_WFI_t1, decode_WFI_t2},
+    yield_::{decode_YIELD_t1, decode_YIELD_t2},
+};
+
+///
+/// Decode the narrow hint space, IT with a zero mask.
+/// Unallocated hints execute as NOP.
+///
+pub fn decode_hint_t1(opcode: u16) -> Instruction {
+    match opcode.get_bits(4..8) {
+        0b0001 => decode_YIELD_t1(opcode),
+        0b0010 => decode_WFE_t1(opcode),
+        0b0011 => decode_WFI_t1(opcode),
+        0b0100 => decode_SEV_t1(opcode),
+        _ => decode_NOP_t1(opcode),
+    }
+}
+
+///
+/// Decode the wide hint space. Unallocated hints execute as NOP.
+///
+pub fn decode_hint_t2(opcode: u32) -> Instruction {
+    match opcode.get_bits(0..8) {
+        0b0000_0001 => decode_YIELD_t2(opcode),
+        0b0000_0010 => decode_WFE_t2(opcode),
+        0b0000_0011 => decode_WFI_t2(opcode),
+        0b0000_0100 => decode_SEV_t2(opcode),
+        0b1111_0000..=0b1111_1111 => decode_DBG_t1(opcode),
+        _ => decode_NOP_t2(opcode),
+    }
+}
@@ -8,20 +8,24 @@ use crate::core::register::Reg;
 #[inline(always)]
 pub fn decode_LDM_t1(opcode: u16) -> Instruction {
     let regs = get_reglist(opcode & 0b_1111_1111);
+    let rn = Reg::from(opcode.get_bits(8..11) as u8);
 
     Instruction::LDM {
         registers: regs,
-        rn: Reg::from(opcode.get_bits(8..11) as u8),
+        rn,
+        wback: !regs.contains(&rn),
         thumb32: false,
     }
 }
 
 #[allow(non_snake_case)]
 pub fn decode_LDMDB_t1(opcode: u32) -> Instruction {
-    Instruction::UDF {
-        imm32: 0,
-        opcode: opcode.into(),
-        thumb32: true,
+    let regs = get_reglist((opcode & 0b1101_1111_1111_1111) as u16);
+
+    Instruction::LDMDB {
+        registers: regs,
+        rn: Reg::from(opcode.get_bits(16..20) as u8),
+        wback: opcode.get_bit(21),
     }
 }
 
@@ -32,6 +36,7 @@ pub fn decode_LDM_t2(opcode: u32) -> Instruction {
     Instruction::LDM {
         registers: regs,
         rn: Reg::from(opcode.get_bits(16..20) as u8),
+        wback: opcode.get_bit(21),
         thumb32: true,
     }
 }
@@ -77,9 +77,9 @@ pub fn decode_LDRB_imm_t3(opcode: u32) -> Instruction {
 
 #[allow(non_snake_case)]
 pub fn decode_LDRB_lit_t1(opcode: u32) -> Instruction {
-    Instruction::UDF {
-        imm32: 0,
-        opcode: opcode.into(),
-        thumb32: true,
+    Instruction::LDRB_lit {
+        rt: Reg::from(opcode.get_bits(12..16) as u8),
+        imm32: opcode.get_bits(0..12),
+        add: opcode.get_bit(23),
     }
 }
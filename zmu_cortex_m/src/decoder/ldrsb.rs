@@ -62,9 +62,9 @@ pub fn decode_LDRSB_imm_t2(opcode: u32) -> Instruction {
 
 #[allow(non_snake_case)]
 pub fn decode_LDRSB_lit_t1(opcode: u32) -> Instruction {
-    Instruction::UDF {
-        imm32: 0,
-        opcode: opcode.into(),
-        thumb32: true,
+    Instruction::LDRSB_lit {
+        rt: Reg::from(opcode.get_bits(12..16) as u8),
+        imm32: opcode.get_bits(0..12),
+        add: opcode.get_bit(23),
     }
 }
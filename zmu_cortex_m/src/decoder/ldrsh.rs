@@ -62,9 +62,16 @@ pub fn decode_LDRSH_imm_t2(opcode: u32) -> Instruction {
 
 #[allow(non_snake_case)]
 pub fn decode_LDRSH_lit_t1(opcode: u32) -> Instruction {
-    Instruction::UDF {
-        imm32: 0,
-        opcode: opcode.into(),
-        thumb32: true,
+    let rt = opcode.get_bits(12..16);
+
+    // Rt == PC encodes an unallocated memory hint, executed as NOP
+    if rt == 15 {
+        return Instruction::NOP { thumb32: true };
+    }
+
+    Instruction::LDRSH_lit {
+        rt: Reg::from(rt as u8),
+        imm32: opcode.get_bits(0..12),
+        add: opcode.get_bit(23),
     }
 }
@@ -50,6 +50,8 @@ mod dsb;
 
 mod eor;
 
+mod hint;
+
 mod isb;
 mod it;
 
@@ -115,8 +117,14 @@ mod umull;
 mod uxt;
 mod uxtab;
 
+mod vadd;
+mod vcvt;
+mod vdiv;
 mod vldr;
+mod vmov;
+mod vmul;
 mod vstr;
+mod vsub;
 
 use {
     crate::decoder::str::{
@@ -130,7 +138,7 @@ use {
     add::{
         decode_ADD_SP_imm_t1, decode_ADD_SP_imm_t2, decode_ADD_imm_t1, decode_ADD_imm_t2,
         decode_ADD_imm_t3, decode_ADD_imm_t4, decode_ADD_reg_sp_t1, decode_ADD_reg_sp_t2,
-        decode_ADD_reg_t1, decode_ADD_reg_t2, decode_ADD_reg_t3,
+        decode_ADD_reg_sp_t3, decode_ADD_reg_t1, decode_ADD_reg_t2, decode_ADD_reg_t3,
     },
     adr::{decode_ADR_t1, decode_ADR_t2, decode_ADR_t3},
     and::{decode_AND_imm_t1, decode_AND_reg_t1, decode_AND_reg_t2},
@@ -153,10 +161,10 @@ use {
     },
     cpd::{decode_CDP2_t2, decode_CDP_t1},
     cps::decode_CPS_t1,
-    dbg::decode_DBG_t1,
     dmb::decode_DMB_t1,
     dsb::decode_DSB_t1,
     eor::{decode_EOR_imm_t1, decode_EOR_reg_t1, decode_EOR_reg_t2},
+    hint::{decode_hint_t1, decode_hint_t2},
     isb::decode_ISB_t1,
     it::decode_IT_t1,
     ldc::{decode_LDC2_imm_t2, decode_LDC2_lit_t2, decode_LDC_imm_t1, decode_LDC_lit_t1},
@@ -200,7 +208,6 @@ use {
     msr::decode_MSR_reg_t1,
     mul::{decode_MUL_t1, decode_MUL_t2},
     mvn::{decode_MVN_imm_t1, decode_MVN_reg_t1, decode_MVN_reg_t2},
-    nop::{decode_NOP_t1, decode_NOP_t2},
     orn::{decode_ORN_imm_t1, decode_ORN_reg_t1},
     orr::{decode_ORR_imm_t1, decode_ORR_reg_t1, decode_ORR_reg_t2},
     pld::{decode_PLD_imm_t1, decode_PLD_imm_t2, decode_PLD_lit_t1, decode_PLD_reg_t1},
@@ -219,7 +226,6 @@ use {
     sbfx::decode_SBFX_t1,
     sdiv::decode_SDIV_t1,
     sel::decode_SEL_t1,
-    sev::{decode_SEV_t1, decode_SEV_t2},
     smla::decode_SMLA_t1,
     smlal::decode_SMLAL_t1,
     smul::decode_SMUL_t1,
@@ -246,11 +252,14 @@ use {
     usat::decode_USAT_t1,
     uxt::{decode_UXTB_t1, decode_UXTB_t2, decode_UXTH_t1, decode_UXTH_t2},
     uxtab::decode_UXTAB_t1,
+    vadd::decode_VADD_f32_t1,
+    vcvt::decode_VCVT_t1,
+    vdiv::decode_VDIV_f32_t1,
     vldr::{decode_VLDR_t1, decode_VLDR_t2},
+    vmov::{decode_VMOV_cr2_dp_t1, decode_VMOV_cr_sp_t1, decode_VMOV_reg_t2},
+    vmul::decode_VMUL_f32_t1,
     vstr::{decode_VSTR_t1, decode_VSTR_t2},
-    wfe::{decode_WFE_t1, decode_WFE_t2},
-    wfi::{decode_WFI_t1, decode_WFI_t2},
-    yield_::{decode_YIELD_t1, decode_YIELD_t2},
+    vsub::decode_VSUB_f32_t1,
 };
 
 use crate::core::thumb::ThumbCode;
@@ -268,9 +277,17 @@ pub trait Decoder {
 
 impl Decoder for Processor {
     fn decode(&self, code: ThumbCode) -> Instruction {
-        match code {
+        let instruction = match code {
             ThumbCode::Thumb32 { opcode } => decode_32(opcode),
             ThumbCode::Thumb16 { opcode } => decode_16(opcode),
+        };
+        match self.core_variant {
+            Some(variant) if !variant.supports(code, &instruction) => Instruction::UDF {
+                imm32: 0,
+                opcode: code,
+                thumb32: matches!(code, ThumbCode::Thumb32 { .. }),
+            },
+            _ => instruction,
         }
     }
 }
@@ -283,8 +300,21 @@ pub fn is_thumb32(word: u16) -> bool {
     }
 }
 
-#[allow(non_snake_case)]
+///
+/// Undefined or reserved 16 bit encoding, decoded as UDF carrying the raw opcode
+///
 fn decode_undefined(opcode: u16) -> Instruction {
+    Instruction::UDF {
+        imm32: 0,
+        opcode: opcode.into(),
+        thumb32: false,
+    }
+}
+
+///
+/// Undefined or reserved 32 bit encoding, decoded as UDF carrying the raw opcode
+///
+fn decode_undefined_32(opcode: u32) -> Instruction {
     Instruction::UDF {
         imm32: 0,
         opcode: opcode.into(),
@@ -294,8 +324,10 @@ fn decode_undefined(opcode: u16) -> Instruction {
 
 #[allow(non_snake_case)]
 fn decode_UDF_t2(opcode: u32) -> Instruction {
+    let imm4 = opcode.get_bits(16..20);
+    let imm12 = opcode.get_bits(0..12);
     Instruction::UDF {
-        imm32: 0,
+        imm32: (imm4 << 12) | imm12,
         opcode: opcode.into(),
         thumb32: true,
     }
@@ -307,17 +339,9 @@ fn decode_UDF_t2(opcode: u32) -> Instruction {
 /// Decode 16 bit thumb opcode into an instruction
 ///
 pub fn decode_16(opcode: u16) -> Instruction {
-    if opcode == 0xbf20 {
-        decode_WFE_t1(opcode)
-    } else if opcode == 0xbf40 {
-        decode_SEV_t1(opcode)
-    } else if opcode == 0xbf00 {
-        decode_NOP_t1(opcode)
-    } else if opcode == 0xbf10 {
-        decode_YIELD_t1(opcode)
-    } else if opcode == 0xbf30 {
-        decode_WFI_t1(opcode)
-    } else if (opcode & 0xffef) == 0xb662 {
+    if (opcode & 0xff0f) == 0xbf00 {
+        decode_hint_t1(opcode)
+    } else if (opcode & 0xffec) == 0xb660 {
         decode_CPS_t1(opcode)
     } else if (opcode & 0xff87) == 0x4485 {
         decode_ADD_reg_sp_t2(opcode)
@@ -472,18 +496,8 @@ pub fn decode_16(opcode: u16) -> Instruction {
 /// Decode 32 bit thumb opcode into an instruction
 ///
 pub fn decode_32(opcode: u32) -> Instruction {
-    if opcode == 0xf3af8002 {
-        decode_WFE_t2(opcode)
-    } else if opcode == 0xf3af8003 {
-        decode_WFI_t2(opcode)
-    } else if opcode == 0xf3af80f0 {
-        decode_DBG_t1(opcode)
-    } else if opcode == 0xf3af8004 {
-        decode_SEV_t2(opcode)
-    } else if opcode == 0xf3af8001 {
-        decode_YIELD_t2(opcode)
-    } else if opcode == 0xf3af8000 {
-        decode_NOP_t2(opcode)
+    if (opcode & 0xffffff00) == 0xf3af8000 {
+        decode_hint_t2(opcode)
     } else if (opcode & 0xfffffff0) == 0xf3bf8f20 {
         decode_CLREX_t1(opcode)
     } else if (opcode & 0xfffffff0) == 0xf3bf8f50 {
@@ -558,6 +572,16 @@ pub fn decode_32(opcode: u32) -> Instruction {
         decode_ROR_reg_t2(opcode)
     } else if (opcode & 0xff7ff000) == 0xf81ff000 {
         decode_PLD_lit_t1(opcode)
+    } else if (opcode & 0xff7f0000) == 0xf85f0000 {
+        decode_LDR_lit_t2(opcode)
+    } else if (opcode & 0xff7f0000) == 0xf81f0000 {
+        decode_LDRB_lit_t1(opcode)
+    } else if (opcode & 0xff7f0000) == 0xf91f0000 {
+        decode_LDRSB_lit_t1(opcode)
+    } else if (opcode & 0xff7f0000) == 0xf83f0000 {
+        decode_LDRH_lit_t1(opcode)
+    } else if (opcode & 0xff7f0000) == 0xf93f0000 {
+        decode_LDRSH_lit_t1(opcode)
     } else if (opcode & 0xffe0f0f0) == 0xfa00f000 {
         decode_LSL_reg_t2(opcode)
     } else if (opcode & 0xffe0f0f0) == 0xfa20f000 {
@@ -654,26 +678,32 @@ pub fn decode_32(opcode: u32) -> Instruction {
         decode_LDRT_t1(opcode)
     } else if (opcode & 0xfbef8000) == 0xf04f0000 {
         decode_MOV_imm_t2(opcode)
-    } else if (opcode & 0xff7f0000) == 0xf85f0000 {
-        decode_LDR_lit_t2(opcode)
-    } else if (opcode & 0xff7f0000) == 0xf81f0000 {
-        decode_LDRB_lit_t1(opcode)
-    } else if (opcode & 0xff7f0000) == 0xf91f0000 {
-        decode_LDRSB_lit_t1(opcode)
-    } else if (opcode & 0xff7f0000) == 0xf83f0000 {
-        decode_LDRH_lit_t1(opcode)
     } else if (opcode & 0xfbef8000) == 0xf06f0000 {
         decode_MVN_imm_t1(opcode)
     } else if (opcode & 0xfbef8000) == 0xf1ad0000 {
         decode_SUB_SP_imm_t2(opcode)
-    } else if (opcode & 0xff7f0000) == 0xf93f0000 {
-        decode_LDRSH_lit_t1(opcode)
     } else if (opcode & 0xff300f00) == 0xed100a00 {
         decode_VLDR_t2(opcode)
     } else if (opcode & 0xff300f00) == 0xed100b00 {
         decode_VLDR_t1(opcode)
     } else if (opcode & 0xff300f00) == 0xed000a00 {
         decode_VSTR_t2(opcode)
+    } else if (opcode & 0xffe00f7f) == 0xee000a10 {
+        decode_VMOV_cr_sp_t1(opcode)
+    } else if (opcode & 0xffe00fd0) == 0xec400b10 {
+        decode_VMOV_cr2_dp_t1(opcode)
+    } else if (opcode & 0xffbf0ed0) == 0xeeb00a40 {
+        decode_VMOV_reg_t2(opcode)
+    } else if (opcode & 0xffb00f50) == 0xee300a00 {
+        decode_VADD_f32_t1(opcode)
+    } else if (opcode & 0xffb00f50) == 0xee300a40 {
+        decode_VSUB_f32_t1(opcode)
+    } else if (opcode & 0xffb00f50) == 0xee200a00 {
+        decode_VMUL_f32_t1(opcode)
+    } else if (opcode & 0xffb00f50) == 0xee800a00 {
+        decode_VDIV_f32_t1(opcode)
+    } else if (opcode & 0xffbf0f50) == 0xeeb80a40 || (opcode & 0xffbe0f50) == 0xeebc0a40 {
+        decode_VCVT_t1(opcode)
     } else if (opcode & 0xfff000c0) == 0xfb100000 {
         decode_SMLA_t1(opcode)
     } else if (opcode & 0xfff08020) == 0xf3c00000 {
@@ -740,7 +770,9 @@ pub fn decode_32(opcode: u32) -> Instruction {
         decode_SUB_imm_t4(opcode)
     } else if (opcode & 0xfe1f0000) == 0xec1f0000 {
         decode_LDC_lit_t1(opcode)
-    } else if (opcode & 0xffe08000) == 0xeb000000 {
+    } else if (opcode & 0xffef8000) == 0xeb0d0000 {
+        decode_ADD_reg_sp_t3(opcode)
+  } else if (opcode & 0xffe08000) == 0xeb000000 {
         decode_ADD_reg_t3(opcode)
     } else if (opcode & 0xfff00000) == 0xf8c00000 {
         decode_STR_imm_t3(opcode)
@@ -806,6 +838,9 @@ pub fn decode_32(opcode: u32) -> Instruction {
         decode_STRD_imm_t1(opcode)
     } else if (opcode & 0xf800d000) == 0xf000d000 {
         decode_BL_t1(opcode)
+    } else if (opcode & 0xf800d000) == 0xf000c000 {
+        // BLX (immediate) switches to ARM state, which M-profile does not have
+        decode_undefined_32(opcode)
     } else if (opcode & 0xfe100000) == 0xec000000 {
         decode_STC_t1(opcode)
     } else if (opcode & 0xf800d000) == 0xf0009000 {
@@ -819,7 +854,7 @@ pub fn decode_32(opcode: u32) -> Instruction {
     } else if (opcode & 0xfe100000) == 0xfc000000 {
         decode_STC2_t2(opcode)
     } else {
-        decode_UDF_t2(opcode)
+        decode_undefined_32(opcode)
     }
 }
 
@@ -3,6 +3,7 @@ use crate::core::bits::Bits;
 use crate::core::instruction::Instruction;
 use crate::core::operation::get_reglist;
 use crate::core::register::Reg;
+use crate::decoder::{decode_undefined, decode_undefined_32};
 use enum_set::EnumSet;
 
 #[allow(non_snake_case)]
@@ -14,6 +15,11 @@ pub fn decode_POP_reg_t1(opcode: u16) -> Instruction {
         regs.insert(Reg::PC);
     }
 
+    // an empty list is UNPREDICTABLE
+    if regs.is_empty() {
+        return decode_undefined(opcode);
+    }
+
     Instruction::POP {
         registers: regs,
         thumb32: false,
@@ -22,6 +28,15 @@ pub fn decode_POP_reg_t1(opcode: u16) -> Instruction {
 
 #[allow(non_snake_case)]
 pub fn decode_POP_t2(opcode: u32) -> Instruction {
+    // fewer than two registers, SP or both LR and PC in the list are
+    // UNPREDICTABLE
+    if (opcode & 0xffff).count_ones() < 2
+        || opcode.get_bit(13)
+        || (opcode.get_bit(14) && opcode.get_bit(15))
+    {
+        return decode_undefined_32(opcode);
+    }
+
     let regs = get_reglist((opcode & 0b1101_1111_1111_1111) as u16);
 
     Instruction::POP {
@@ -33,6 +48,12 @@ pub fn decode_POP_t2(opcode: u32) -> Instruction {
 #[allow(non_snake_case)]
 pub fn decode_POP_t3(opcode: u32) -> Instruction {
     let reg = opcode.get_bits(12..16);
+
+    // popping SP is UNPREDICTABLE
+    if reg == 13 {
+        return decode_undefined_32(opcode);
+    }
+
     let mut regs: EnumSet<Reg> = EnumSet::new();
 
     regs.insert(Reg::from(reg as u8));
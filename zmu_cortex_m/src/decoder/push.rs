@@ -2,6 +2,7 @@ use crate::core::bits::Bits;
 use crate::core::instruction::Instruction;
 use crate::core::operation::get_reglist;
 use crate::core::register::Reg;
+use crate::decoder::{decode_undefined, decode_undefined_32};
 use enum_set::EnumSet;
 
 #[allow(non_snake_case)]
@@ -13,6 +14,11 @@ pub fn decode_PUSH_t1(opcode: u16) -> Instruction {
         regs.insert(Reg::LR);
     }
 
+    // an empty list is UNPREDICTABLE
+    if regs.is_empty() {
+        return decode_undefined(opcode);
+    }
+
     Instruction::PUSH {
         registers: regs,
         thumb32: false,
@@ -21,6 +27,11 @@ pub fn decode_PUSH_t1(opcode: u16) -> Instruction {
 
 #[allow(non_snake_case)]
 pub fn decode_PUSH_t2(opcode: u32) -> Instruction {
+    // fewer than two registers, SP or PC in the list are UNPREDICTABLE
+    if (opcode & 0xffff).count_ones() < 2 || opcode.get_bit(13) || opcode.get_bit(15) {
+        return decode_undefined_32(opcode);
+    }
+
     let regs = get_reglist((opcode & 0b0101_1111_1111_1111) as u16);
 
     Instruction::PUSH {
@@ -33,6 +44,11 @@ pub fn decode_PUSH_t2(opcode: u32) -> Instruction {
 pub fn decode_PUSH_t3(opcode: u32) -> Instruction {
     let rt = opcode.get_bits(12..16);
 
+    // pushing SP or PC is UNPREDICTABLE
+    if rt == 13 || rt == 15 {
+        return decode_undefined_32(opcode);
+    }
+
     let mut regs: EnumSet<Reg> = EnumSet::new();
 
     regs.insert(rt.into());
@@ -0,0 +1,25 @@
+use crate::core::bits::Bits;
+use crate::core::instruction::Instruction;
+use crate::core::register::SingleReg;
+
+#[allow(non_snake_case)]
+#[inline(always)]
+pub fn decode_VCVT_t1(opcode: u32) -> Instruction {
+    let sd = SingleReg::from(((opcode.get_bits(12..16) as u8) << 1) + opcode.get_bit(22) as u8);
+    let sm = SingleReg::from(((opcode.get_bits(0..4) as u8) << 1) + opcode.get_bit(5) as u8);
+
+    if opcode.get_bit(18) {
+        Instruction::VCVT_f32_to_int {
+            sd,
+            sm,
+            unsigned: !opcode.get_bit(16),
+            round_zero: opcode.get_bit(7),
+        }
+    } else {
+        Instruction::VCVT_int_to_f32 {
+            sd,
+            sm,
+            unsigned: !opcode.get_bit(7),
+        }
+    }
+}
@@ -0,0 +1,13 @@
+use crate::core::bits::Bits;
+use crate::core::instruction::Instruction;
+use crate::core::register::SingleReg;
+
+#[allow(non_snake_case)]
+#[inline(always)]
+pub fn decode_VDIV_f32_t1(opcode: u32) -> Instruction {
+    Instruction::VDIV_f32 {
+        sd: SingleReg::from(((opcode.get_bits(12..16) as u8) << 1) + opcode.get_bit(22) as u8),
+        sn: SingleReg::from(((opcode.get_bits(16..20) as u8) << 1) + opcode.get_bit(7) as u8),
+        sm: SingleReg::from(((opcode.get_bits(0..4) as u8) << 1) + opcode.get_bit(5) as u8),
+    }
+}
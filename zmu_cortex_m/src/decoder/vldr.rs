@@ -23,9 +23,7 @@ pub fn decode_VLDR_t1(opcode: u32) -> Instruction {
 pub fn decode_VLDR_t2(opcode: u32) -> Instruction {
     Instruction::VLDR {
         dd: ExtensionReg::Single {
-            reg: SingleReg::from(
-                opcode.get_bits(12..16) as u8 + ((opcode.get_bit(22) as u8) << 4) as u8,
-            ),
+            reg: SingleReg::from(((opcode.get_bits(12..16) as u8) << 1) + opcode.get_bit(22) as u8),
         },
         rn: Reg::from(opcode.get_bits(16..20) as u8),
         imm32: opcode.get_bits(0..8) << 2,
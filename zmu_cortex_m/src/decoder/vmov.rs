@@ -0,0 +1,45 @@
+use crate::core::bits::Bits;
+use crate::core::instruction::Instruction;
+use crate::core::register::{DoubleReg, Reg, SingleReg};
+
+#[allow(non_snake_case)]
+#[inline(always)]
+pub fn decode_VMOV_cr_sp_t1(opcode: u32) -> Instruction {
+    Instruction::VMOV_cr_sp {
+        to_arm_register: opcode.get_bit(20),
+        rt: Reg::from(opcode.get_bits(12..16) as u8),
+        sn: SingleReg::from(((opcode.get_bits(16..20) as u8) << 1) + opcode.get_bit(7) as u8),
+    }
+}
+
+#[allow(non_snake_case)]
+#[inline(always)]
+pub fn decode_VMOV_cr2_dp_t1(opcode: u32) -> Instruction {
+    Instruction::VMOV_cr2_dp {
+        to_arm_registers: opcode.get_bit(20),
+        rt: Reg::from(opcode.get_bits(12..16) as u8),
+        rt2: Reg::from(opcode.get_bits(16..20) as u8),
+        dm: DoubleReg::from(opcode.get_bits(0..4) as u8 + ((opcode.get_bit(5) as u8) << 4)),
+    }
+}
+
+#[allow(non_snake_case)]
+#[inline(always)]
+pub fn decode_VMOV_reg_t2(opcode: u32) -> Instruction {
+    let vd = opcode.get_bits(12..16) as u8;
+    let d = opcode.get_bit(22) as u8;
+    let vm = opcode.get_bits(0..4) as u8;
+    let m = opcode.get_bit(5) as u8;
+
+    if opcode.get_bit(8) {
+        Instruction::VMOV_reg_f64 {
+            dd: DoubleReg::from(vd + (d << 4)),
+            dm: DoubleReg::from(vm + (m << 4)),
+        }
+    } else {
+        Instruction::VMOV_reg_f32 {
+            sd: SingleReg::from((vd << 1) + d),
+            sm: SingleReg::from((vm << 1) + m),
+        }
+    }
+}
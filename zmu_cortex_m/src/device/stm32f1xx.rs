@@ -259,7 +259,6 @@ impl Device {
     ///
     #[allow(clippy::too_many_lines)]
     pub fn new() -> Self {
-        println!("initialize stm32f1xx");
         Self {
             afio: AFIORegisters {
                 EVCR: 0,
@@ -703,7 +702,7 @@ impl RCC for Device {
             0x1C => self.rcc.APB1ENR = value,
             0x20 => self.rcc.BDCR = value,
             0x24 => self.rcc.CSR = value,
-            _ => return Err(Fault::DAccViol),
+            _ => return Err(Fault::PreciseErr { address: offset }),
         }
 
         Ok(())
@@ -721,7 +720,7 @@ impl RCC for Device {
             0x1C => self.rcc.APB1ENR,
             0x20 => self.rcc.BDCR,
             0x24 => self.rcc.CSR,
-            _ => return Err(Fault::DAccViol),
+            _ => return Err(Fault::PreciseErr { address: offset }),
         };
 
         Ok(result)
@@ -739,7 +738,7 @@ impl AFIO for Device {
             0x14 => self.afio.EXTICR[3] = value,
             //0x18 => self.rcc.APB2ENR = value,
             0x1C => self.afio.MAPR = value,
-            _ => return Err(Fault::DAccViol),
+            _ => return Err(Fault::PreciseErr { address: offset }),
         }
 
         Ok(())
@@ -755,7 +754,7 @@ impl AFIO for Device {
             0x14 => self.afio.EXTICR[3],
             //0x18 => self.rcc.APB2ENR,
             0x1C => self.afio.MAPR2,
-            _ => return Err(Fault::DAccViol),
+            _ => return Err(Fault::PreciseErr { address: offset }),
         };
 
         Ok(result)
@@ -782,7 +781,7 @@ impl GPIO for Device {
                 self.gpio[index].ODR = (odr | odr_reset_bits) & !odr;
             }
             0x18 => self.gpio[index].LCKR = value & 0x1_ffff,
-            _ => return Err(Fault::DAccViol),
+            _ => return Err(Fault::PreciseErr { address: offset }),
         }
         Ok(())
     }
@@ -796,7 +795,7 @@ impl GPIO for Device {
             0x10 => 0,
             0x14 => 0,
             0x18 => self.gpio[index].LCKR,
-            _ => return Err(Fault::DAccViol),
+            _ => return Err(Fault::PreciseErr { address: offset }),
         };
 
         Ok(result)
@@ -818,7 +817,7 @@ impl FLASH for Device {
                 // PRFTBE -> PRFTBS
                 self.flash.ACR.set_bit(5, self.flash.ACR.get_bit(4));
             }
-            _ => return Err(Fault::DAccViol),
+            _ => return Err(Fault::PreciseErr { address: offset }),
         }
 
         Ok(())
@@ -827,7 +826,7 @@ impl FLASH for Device {
     fn flash_read32(&mut self, offset: u32) -> Result<u32, Fault> {
         let result = match offset {
             0x0 => self.flash.ACR,
-            _ => return Err(Fault::DAccViol),
+            _ => return Err(Fault::PreciseErr { address: offset }),
         };
 
         Ok(result)
@@ -848,7 +847,7 @@ impl TIM for Device {
 
         match offset {
             0x0 => self.tim1_8[index].gp.gp.min.CR1 = value,
-            _ => return Err(Fault::DAccViol),
+            _ => return Err(Fault::PreciseErr { address: offset }),
         }
 
         Ok(())
@@ -866,7 +865,7 @@ impl TIM for Device {
 
         let result = match offset {
             0x0 => self.tim1_8[index].gp.gp.min.CR1,
-            _ => return Err(Fault::DAccViol),
+            _ => return Err(Fault::PreciseErr { address: offset }),
         };
 
         Ok(result)
@@ -891,7 +890,7 @@ impl TIM for Device {
             0x1c => self.tim2_5[index].CCMR2 = value & 0xffff,
             0x28 => self.tim2_5[index].gp.min.PSC = value & 0xffff,
             0x2c => self.tim2_5[index].gp.min.ARR = value & 0xffff,
-            _ => return Err(Fault::DAccViol),
+            _ => return Err(Fault::PreciseErr { address: offset }),
         }
 
         Ok(())
@@ -915,7 +914,7 @@ impl TIM for Device {
             0x1c => self.tim2_5[index].CCMR2,
             0x28 => self.tim2_5[index].gp.min.PSC,
             0x2C => self.tim2_5[index].gp.min.ARR,
-            _ => return Err(Fault::DAccViol),
+            _ => return Err(Fault::PreciseErr { address: offset }),
         };
 
         Ok(result)
@@ -938,7 +937,7 @@ impl TIM for Device {
 
         match offset {
             0x0 => self.tim9_14[index].min.CR1 = value,
-            _ => return Err(Fault::DAccViol),
+            _ => return Err(Fault::PreciseErr { address: offset }),
         }
 
         Ok(())
@@ -960,7 +959,7 @@ impl TIM for Device {
 
         let result = match offset {
             0x0 => self.tim9_14[index].min.CR1,
-            _ => return Err(Fault::DAccViol),
+            _ => return Err(Fault::PreciseErr { address: offset }),
         };
 
         Ok(result)
@@ -979,7 +978,7 @@ impl TIM for Device {
 
         match offset {
             0x0 => self.tim6_7[index].min.CR1 = value,
-            _ => return Err(Fault::DAccViol),
+            _ => return Err(Fault::PreciseErr { address: offset }),
         }
 
         Ok(())
@@ -993,7 +992,7 @@ impl TIM for Device {
 
         let result = match offset {
             0x0 => self.tim6_7[index].min.CR1,
-            _ => return Err(Fault::DAccViol),
+            _ => return Err(Fault::PreciseErr { address: offset }),
         };
 
         Ok(result)
@@ -1001,19 +1000,16 @@ impl TIM for Device {
 }
 
 impl Bus for Device {
-    fn read8(&self, bus_addr: u32) -> Result<u8, Fault> {
-        println!("read8 0x{:x}", bus_addr);
+    fn read8(&self, _bus_addr: u32) -> Result<u8, Fault> {
         Ok(0)
     }
 
-    fn read16(&self, bus_addr: u32) -> Result<u16, Fault> {
-        println!("read16 0x{:x}", bus_addr);
+    fn read16(&self, _bus_addr: u32) -> Result<u16, Fault> {
         Ok(0)
     }
 
     fn read32(&mut self, bus_addr: u32) -> Result<u32, Fault> {
-        println!("read32 0x{:x}", bus_addr);
-        match bus_addr {
+        let result = match bus_addr {
             AFIO_BASE..=AFIO_BASE_END => self.afio_read32(bus_addr - AFIO_BASE),
             RCC_BASE..=RCC_BASE_END => self.rcc_read32(bus_addr - RCC_BASE),
             GPIOA_BASE..=GPIOA_BASE_END => self.gpio_read32(0, bus_addr - GPIOA_BASE),
@@ -1078,13 +1074,14 @@ impl Bus for Device {
                 GeneralPurposeTimer2Type::TIM14,
                 bus_addr - TIM14_BASE,
             ),
-            _ => Err(Fault::DAccViol),
-        }
+            _ => Err(Fault::PreciseErr { address: bus_addr }),
+        };
+        // peripheral handlers only know the register offset
+        result.map_err(|_| Fault::PreciseErr { address: bus_addr })
     }
 
     fn write32(&mut self, addr: u32, value: u32) -> Result<(), Fault> {
-        println!("write32 0x{:x}=0x{:x}", addr, value);
-        match addr {
+        let result = match addr {
             AFIO_BASE..=AFIO_BASE_END => self.afio_write32(addr - AFIO_BASE, value),
             RCC_BASE..=RCC_BASE_END => self.rcc_write32(addr - RCC_BASE, value),
             GPIOA_BASE..=GPIOA_BASE_END => self.gpio_write32(0, addr - GPIOA_BASE, value),
@@ -1161,17 +1158,17 @@ impl Bus for Device {
                 addr - TIM14_BASE,
                 value,
             ),
-            _ => Err(Fault::DAccViol),
-        }
+            _ => Err(Fault::PreciseErr { address: addr }),
+        };
+        // peripheral handlers only know the register offset
+        result.map_err(|_| Fault::PreciseErr { address: addr })
     }
 
-    fn write16(&mut self, addr: u32, value: u16) -> Result<(), Fault> {
-        println!("write16 0x{:x}=0x{:x}", addr, value);
+    fn write16(&mut self, _addr: u32, _value: u16) -> Result<(), Fault> {
         Ok(())
     }
 
-    fn write8(&mut self, addr: u32, value: u8) -> Result<(), Fault> {
-        println!("write8 0x{:x}=0x{:x}", addr, value);
+    fn write8(&mut self, _addr: u32, _value: u8) -> Result<(), Fault> {
         Ok(())
     }
 
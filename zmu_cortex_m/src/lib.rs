@@ -29,35 +29,54 @@ pub mod bus;
 pub mod core;
 pub mod decoder;
 pub mod device;
+pub mod loader;
 pub mod memory;
 pub mod peripheral;
 pub mod semihosting;
 pub mod system;
+#[cfg(test)]
+mod test_fixture;
 
-use crate::core::instruction::instruction_size;
-
+use crate::bus::{Bus, Endianness, Peripheral, Watchpoint};
 use crate::core::exception::Exception;
-use crate::core::fetch::Fetch;
+use crate::core::executor::InstructionHook;
+use crate::core::executor::SvcHook;
+use crate::core::executor::TraceHook;
+use crate::core::fault::Fault;
 use crate::core::instruction::Instruction;
 use crate::core::register::{Apsr, BaseReg, Control, Reg, PSR};
+use crate::core::thumb::ThumbCode;
+use crate::core::timing::TimingModel;
+use crate::core::trace::{TraceEvent, TraceSink};
+use crate::core::variant::CoreVariant;
 
 use crate::memory::flash::FlashMemory;
-use crate::memory::map::MemoryMapConfig;
+use crate::memory::map::{MapMemory, MemoryMapConfig};
 use crate::memory::ram::RAM;
+use crate::peripheral::clock::Clocked;
+use crate::peripheral::itm::ITM_TCR_ITMENA;
+use crate::peripheral::mpu::MPU_REGIONS;
+use crate::peripheral::scb::{
+    SystemControlBlock, CCR_STKALIGN, DEMCR_TRCENA, DHCSR_C_DEBUGEN, DHCSR_C_HALT, DHCSR_DBGKEY,
+};
+use crate::peripheral::test_exit::TestExitConfig;
+use crate::semihosting::host_io::HostIo;
 use crate::semihosting::SemihostingCommand;
 use crate::semihosting::SemihostingResponse;
 
-use crate::core::exception::ExceptionState;
+use crate::core::exception::{ExceptionState, Lockup};
+use std::cell::Cell;
 use std::collections::HashMap;
 use std::fmt;
 use std::io;
+use std::ops::Range;
 
 #[cfg(feature = "stm32f103")]
 use crate::device::stm32f1xx::Device;
 
 #[cfg(feature = "generic-device")]
 use crate::device::generic::Device;
-use decoder::Decoder;
+use decoder::{is_thumb32, Decoder};
 
 #[derive(PartialEq, Debug, Copy, Clone)]
 /// Main execution mode of the processor
@@ -79,10 +98,17 @@ pub struct Processor {
     /// 32 of 32-bit floating pointer registers
     pub fp_regs: [u32; 32],
 
+    /// Floating-point Status and Control Register
+    pub fpscr: u32,
+
     /// MSP, virtual reg r[13]
     pub msp: u32,
     /// PSP, virtual reg r[13]
     pub psp: u32,
+    /// Lowest allowed MSP value, like MSPLIM of ARMv8-M
+    pub msp_limit: Option<u32>,
+    /// Lowest allowed PSP value, like PSPLIM of ARMv8-M
+    pub psp_limit: Option<u32>,
     pub lr: u32,
     pc: u32,
 
@@ -126,6 +152,13 @@ pub struct Processor {
     /// bit 1 : 1= processor sleeping, 0 : processor awake
     pub state: u32,
 
+    ///
+    /// Debug Halting Control and Status Register, the control bits
+    ///
+    /// Like on hardware, a system reset keeps the core halted.
+    ///
+    pub dhcsr: u32,
+
     ///
     /// lookup table for exceptions and their states
     ///
@@ -156,6 +189,10 @@ pub struct Processor {
     pub cpuid: u32,
     pub icsr: u32,
     pub vtor: u32,
+    ///
+    /// VTOR after a system reset, the load address of a relocated image
+    ///
+    pub vtor_reset: u32,
     pub aircr: u32,
     pub scr: u32,
     pub ccr: u32,
@@ -182,29 +219,158 @@ pub struct Processor {
     pub nvic_interrupt_enabled: [u32; 16],
     pub nvic_interrupt_pending: [u32; 16],
 
+    ///
+    /// MPU control, region number and region registers
+    ///
+    pub mpu_ctrl: u32,
+    pub mpu_rnr: u32,
+    pub mpu_rbar: [u32; MPU_REGIONS],
+    pub mpu_rasr: [u32; MPU_REGIONS],
+
     pub dwt_ctrl: u32,
     pub dwt_cyccnt: u32,
+    pub dwt_comp: [u32; 4],
+    pub dwt_mask: [u32; 4],
+    pub dwt_function: [u32; 4],
+
+    ///
+    /// comparators matched since their `DWT_FUNCTION` was last read
+    ///
+    dwt_matched: Cell<u32>,
+
+    ///
+    /// a watchpoint matched during the current instruction
+    ///
+    dwt_trap: Cell<bool>,
+
+    ///
+    /// Debug Exception and Monitor Control Register
+    ///
+    pub demcr: u32,
 
     pub syst_rvr: u32,
     pub syst_cvr: u32,
     pub syst_csr: u32,
 
+
+    ///
+    /// placement of the test exit device, `None` when it is not present
+    ///
+    test_exit: Option<TestExitConfig>,
+
     ///
     /// file handle to which to write ITM data
     ///
     pub itm_file: Option<Box<dyn io::Write + 'static>>,
 
+    ///
+    /// ITM trace enable, trace privilege and trace control registers
+    ///
+    pub itm_ter: u32,
+    pub itm_tpr: u32,
+    pub itm_tcr: u32,
+
+    ///
+    /// console to which characters of ITM stimulus port 0 are forwarded
+    ///
+    itm_console: Option<Box<dyn HostIo>>,
+
     ///
     /// semihosting plug
     ///
     semihost_func: Option<Box<dyn FnMut(&SemihostingCommand) -> SemihostingResponse>>,
 
-    instruction_cache: Vec<(Instruction, usize)>,
+    ///
+    /// console receiving semihosting writes to the console handles
+    ///
+    semihost_console: Option<Box<dyn HostIo>>,
+
+    ///
+    /// custom instruction handler
+    ///
+    instruction_hook: Option<InstructionHook>,
+
+    ///
+    /// instruction trace handler
+    ///
+    trace_hook: Option<TraceHook>,
+
+    ///
+    /// receiver of diagnostic events, `None` discards them
+    ///
+    trace_sink: Option<Box<dyn TraceSink>>,
+
+    ///
+    /// supervisor call handler
+    ///
+    svc_hook: Option<SvcHook>,
+
+    ///
+    /// instruction timing model, the default timing of the core is used when not set
+    ///
+    timing_model: Option<Box<dyn TimingModel>>,
+
+    ///
+    /// simulated core variant, all implemented instructions are available when not set
+    ///
+    core_variant: Option<CoreVariant>,
+
+    ///
+    /// bus wait states accumulated by the instruction being executed
+    ///
+    wait_states: Cell<u32>,
+
+    ///
+    /// decoded instructions and their sizes by halfword of the code region,
+    /// when enabled
+    ///
+    decode_cache: Option<Vec<Option<(Instruction, usize)>>>,
 
     pub last_pc: u32,
 
     mem_map: Option<MemoryMapConfig>,
 
+    ///
+    /// byte order of data accesses, fixed at reset
+    ///
+    endianness: Endianness,
+
+    ///
+    /// user registered memory mapped peripherals
+    ///
+    peripherals: Vec<(Range<u32>, Box<dyn Peripheral>)>,
+
+    ///
+    /// user registered peripherals advanced with the core clock
+    ///
+    clocked_peripherals: Vec<Box<dyn Clocked>>,
+
+    ///
+    /// memory watchpoints
+    ///
+    watchpoints: Vec<Watchpoint>,
+
+    ///
+    /// exit code reported by the program via semihosting exit
+    ///
+    exit_code: Option<u32>,
+
+    ///
+    /// reason of the lockup, when the processor is locked up
+    ///
+    lockup: Option<Lockup>,
+
+    ///
+    /// immediate of the BKPT instruction that halted the processor
+    ///
+    breakpoint_hit: Option<u8>,
+
+    ///
+    /// address of the breakpoint the last `run` stopped at, stepped over
+    /// when the next `run` resumes from it
+    ///
+    run_breakpoint: Option<u32>,
+
     pub device: Device,
 }
 
@@ -271,10 +437,12 @@ impl Processor {
     ///
     /// Create processor with default data
     ///
+    #[allow(clippy::too_many_lines)]
     pub fn new() -> Self {
         Self {
             mode: ProcessorMode::ThreadMode,
             vtor: 0,
+            vtor_reset: 0,
             psr: PSR { value: 0 },
             primask: false,
             #[cfg(any(armv7m, armv7em))]
@@ -283,18 +451,26 @@ impl Processor {
             control: Control {
                 n_priv: false,
                 sp_sel: false,
+                fpca: false,
             },
             r0_12: [0; 13],
             fp_regs: [0; 32],
+            fpscr: 0,
             pc: 0,
             msp: 0,
             psp: 0,
+            msp_limit: None,
+            psp_limit: None,
             lr: 0,
             code: FlashMemory::new(65536, &[0; 65536]),
-            // TODO make RAM size configurable
             sram: RAM::new_with_fill(0x2000_0000, 128 * 1024, 0xcd),
             itm_file: None,
-            state: 0,
+            itm_ter: 0,
+            itm_tpr: 0,
+            itm_tcr: 0,
+            itm_console: None,
+            state: 1,
+            dhcsr: 0,
             cycle_count: 0,
             instruction_count: 0,
             exceptions: make_default_exception_priorities(),
@@ -302,11 +478,19 @@ impl Processor {
             pending_exception_count: 0,
             itstate: 0,
             semihost_func: None,
+            semihost_console: None,
+            instruction_hook: None,
+            trace_hook: None,
+            trace_sink: None,
+            svc_hook: None,
+            timing_model: None,
+            core_variant: None,
+            wait_states: Cell::new(0),
             cpuid: 0,
             icsr: 0,
             aircr: 0,
             scr: 0,
-            ccr: 0,
+            ccr: CCR_STKALIGN,
             shcsr: 0,
             cfsr: 0,
             dfsr: 0,
@@ -316,6 +500,10 @@ impl Processor {
             afsr: 0,
             cpacr: 0,
 
+            // automatic and lazy state preservation enabled on reset
+            #[cfg(armv7em)]
+            fpccr: 0xc000_0000,
+            #[cfg(not(armv7em))]
             fpccr: 0,
             fpcar: 0,
             fpdscr: 0,
@@ -326,17 +514,36 @@ impl Processor {
             ictr: 0,
             actlr: 0,
 
+            mpu_ctrl: 0,
+            mpu_rnr: 0,
+            mpu_rbar: [0; MPU_REGIONS],
+            mpu_rasr: [0; MPU_REGIONS],
             dwt_ctrl: 0x4000_0000,
             dwt_cyccnt: 0,
+            dwt_comp: [0; 4],
+            dwt_mask: [0; 4],
+            dwt_function: [0; 4],
+            dwt_matched: Cell::new(0),
+            dwt_trap: Cell::new(false),
+            demcr: 0,
 
             nvic_interrupt_enabled: [0; 16],
             nvic_interrupt_pending: [0; 16],
             syst_rvr: 0,
             syst_cvr: 0,
             syst_csr: 0,
-            instruction_cache: Vec::new(),
+            test_exit: None,
+            decode_cache: None,
             last_pc: 0,
             mem_map: None,
+            endianness: Endianness::Little,
+            peripherals: Vec::new(),
+            clocked_peripherals: Vec::new(),
+            watchpoints: Vec::new(),
+            exit_code: None,
+            lockup: None,
+            breakpoint_hit: None,
+            run_breakpoint: None,
             device: Device::new(),
         }
     }
@@ -344,21 +551,135 @@ impl Processor {
     /// Configure flash memory
     pub fn flash_memory<'a>(&'a mut self, flash_size: usize, code: &[u8]) -> &'a mut Self {
         self.code = FlashMemory::new(flash_size, code);
+        self.flush_decode_cache();
+        self
+    }
+
+    ///
+    /// Program `data` to memory at `address`, like a flash programmer would.
+    /// Flash is written regardless of its write protection.
+    ///
+    pub fn load_memory(&mut self, address: u32, data: &[u8]) -> Result<(), Fault> {
+        self.flush_decode_cache();
+        let addr = self.map_address(address);
+        if self.code.in_range(addr) {
+            return self.code.load(addr, data).map_err(|fault| match fault {
+                Fault::PreciseErr { address: end } => Fault::PreciseErr {
+                    address: address.wrapping_add(end - addr),
+                },
+                _ => fault,
+            });
+        }
+        for (offset, byte) in data.iter().enumerate() {
+            self.write8(address.wrapping_add(offset as u32), *byte)?;
+        }
+        Ok(())
+    }
+
+    /// Configure flash memory located at given start address
+    pub fn flash_memory_at(
+        &mut self,
+        start_address: u32,
+        flash_size: usize,
+        code: &[u8],
+    ) -> &mut Self {
+        self.flash_memory(flash_size, code);
+        self.memory_map(if start_address == 0 {
+            None
+        } else {
+            Some(MemoryMapConfig::new(start_address, 0, flash_size))
+        })
+    }
+
+    /// Configure the number of wait states of flash memory reads
+    pub fn flash_wait_states(&mut self, wait_states: u32) -> &mut Self {
+        self.code.set_wait_states(wait_states);
+        self
+    }
+
+    /// Configure the number of wait states of RAM accesses
+    pub fn ram_wait_states(&mut self, wait_states: u32) -> &mut Self {
+        self.sram.set_wait_states(wait_states);
+        self
+    }
+
+    /// Allow writes to flash memory, which otherwise cause a bus fault
+    pub fn flash_writable(&mut self, writable: bool) -> &mut Self {
+        self.code.set_writable(writable);
+        self
+    }
+
+    /// Configure ram memory
+    pub fn ram_memory(&mut self, start_address: u32, ram_size: usize) -> &mut Self {
+        self.sram = RAM::new_with_fill(start_address, ram_size, 0xcd);
+        self.flush_decode_cache();
         self
     }
 
     /// Configure memory mapping
     pub fn memory_map(&mut self, map: Option<MemoryMapConfig>) -> &mut Self {
         self.mem_map = map;
+        self.flush_decode_cache();
+        self
+    }
+
+    /// Configure the byte order of data accesses, AIRCR.ENDIANNESS
+    pub fn endianness(&mut self, endianness: Endianness) -> &mut Self {
+        self.endianness = endianness;
+        self
+    }
+
+    ///
+    /// Configure a soft stack guard.
+    ///
+    /// Pushes, `SUB SP` and exception stacking that would move the stack
+    /// pointer below its limit raise a usage fault instead. `None` disables
+    /// the check.
+    ///
+    pub fn stack_limits(&mut self, msp_limit: Option<u32>, psp_limit: Option<u32>) -> &mut Self {
+        self.msp_limit = msp_limit;
+        self.psp_limit = psp_limit;
         self
     }
 
     /// Configure itm output file
+    ///
+    /// Like an attached trace probe, this enables the trace unit and all
+    /// stimulus ports. Firmware may still disable them.
     pub fn itm<'a>(&'a mut self, file: Option<Box<dyn io::Write + 'static>>) -> &'a mut Self {
+        if file.is_some() {
+            self.demcr |= DEMCR_TRCENA;
+            self.itm_tcr |= ITM_TCR_ITMENA;
+            self.itm_ter = 0xffff_ffff;
+        }
         self.itm_file = file;
         self
     }
 
+    /// Configure console to which characters written to ITM stimulus port 0 are forwarded
+    pub fn itm_console(&mut self, console: Option<Box<dyn HostIo>>) -> &mut Self {
+        self.itm_console = console;
+        self
+    }
+
+    ///
+    /// Set the initial vector table location, which VTOR also returns to
+    /// on a system reset
+    ///
+    pub fn vtor_reset(&mut self, vtor: u32) -> &mut Self {
+        self.write_vtor(vtor);
+        self.vtor_reset = self.vtor;
+        self
+    }
+
+    ///
+    /// Map the test exit device. `None` removes the peripheral.
+    ///
+    pub fn test_exit(&mut self, config: Option<TestExitConfig>) -> &mut Self {
+        self.test_exit = config;
+        self
+    }
+
     /// Configure semihosting
     pub fn semihost<'a>(
         &'a mut self,
@@ -369,22 +690,223 @@ impl Processor {
     }
 
     ///
-    /// Pre cache (decode) instructions to speed up simulation
+    /// Configure console for semihosting writes to the console handles
+    /// (`TT_HANDLE_STDOUT` and `TT_HANDLE_STDERR`). Other commands still go
+    /// to the semihosting function.
     ///
-    pub fn cache_instructions(&mut self) {
-        // pre-cache the decoded instructions
-        {
-            let mut pc = 0;
-
-            while pc < (self.code.len() as u32) {
-                let thumb = self.fetch(pc).unwrap();
-                let instruction = self.decode(thumb);
-                self.instruction_cache
-                    .push((instruction, instruction_size(&instruction)));
-                pc += 2;
+    pub fn semihost_console(&mut self, console: Option<Box<dyn HostIo>>) -> &mut Self {
+        self.semihost_console = console;
+        self
+    }
+
+    /// Configure custom instruction handler
+    pub fn instruction_hook(&mut self, func: Option<InstructionHook>) -> &mut Self {
+        self.instruction_hook = func;
+        self
+    }
+
+    /// Configure instruction trace handler
+    pub fn trace_hook(&mut self, func: Option<TraceHook>) -> &mut Self {
+        self.trace_hook = func;
+        self
+    }
+
+    /// Configure receiver of diagnostic events
+    pub fn trace_sink(&mut self, sink: Option<Box<dyn TraceSink>>) -> &mut Self {
+        self.trace_sink = sink;
+        self
+    }
+
+    ///
+    /// Report an event to the trace sink
+    ///
+    #[inline(always)]
+    pub(crate) fn trace(&self, event: &TraceEvent) {
+        if let Some(sink) = &self.trace_sink {
+            sink.event(event);
+        }
+    }
+
+    /// Configure supervisor call handler, which replaces taking the `SVCall` exception
+    pub fn svc_hook(&mut self, func: Option<SvcHook>) -> &mut Self {
+        self.svc_hook = func;
+        self
+    }
+
+    /// Configure instruction timing model
+    pub fn timing_model(&mut self, model: Option<Box<dyn TimingModel>>) -> &mut Self {
+        self.timing_model = model;
+        self
+    }
+
+    ///
+    /// Configure the simulated core variant.
+    ///
+    /// Instructions the variant does not implement decode as UNDEFINED, for
+    /// example divides on a Cortex-M0. Without a variant all instructions
+    /// the simulator implements are available.
+    ///
+    pub fn core_variant(&mut self, variant: Option<CoreVariant>) -> &mut Self {
+        self.core_variant = variant;
+        self.flush_decode_cache();
+        self
+    }
+
+    /// Register a memory mapped peripheral to handle accesses in given address range
+    pub fn register_peripheral(
+        &mut self,
+        range: Range<u32>,
+        handler: Box<dyn Peripheral>,
+    ) -> &mut Self {
+        self.peripherals.push((range, handler));
+        self
+    }
+
+    /// Register a peripheral to advance in lockstep with the core clock
+    pub fn register_clocked(&mut self, clocked: Box<dyn Clocked>) -> &mut Self {
+        self.clocked_peripherals.push(clocked);
+        self
+    }
+
+    /// Add a memory watchpoint
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) -> &mut Self {
+        self.watchpoints.push(watchpoint);
+        self
+    }
+
+    ///
+    /// Enable or disable caching of decoded instructions.
+    ///
+    /// With the cache enabled, instructions executed from the code region
+    /// by `step` and `step_instruction` are decoded once and then looked up
+    /// by address. The cache is flushed whenever memory contents or layout
+    /// is reconfigured.
+    ///
+    pub fn decode_cache(&mut self, enabled: bool) -> &mut Self {
+        self.decode_cache = if enabled {
+            Some(vec![None; self.code.len() / 2])
+        } else {
+            None
+        };
+        self
+    }
+
+    fn flush_decode_cache(&mut self) {
+        if self.decode_cache.is_some() {
+            self.decode_cache(true);
+        }
+    }
+
+    ///
+    /// Drop cached decodes of instructions overlapping the `size` bytes
+    /// written at `address`. A 32-bit instruction may start at the
+    /// halfword before the written range.
+    ///
+    fn invalidate_decode_cache(&mut self, address: u32, size: u32) {
+        if self.decode_cache.is_none() {
+            return;
+        }
+        let addr = self.map_address(address);
+        let first = ((addr >> 1) as usize).saturating_sub(1);
+        let last = (addr.wrapping_add(size - 1) >> 1) as usize;
+        if let Some(cache) = self.decode_cache.as_mut() {
+            for entry in cache.iter_mut().take(last + 1).skip(first) {
+                *entry = None;
             }
         }
     }
+
+    ///
+    /// Exit code of the program, once it has exited via semihosting
+    ///
+    pub fn exit_code(&self) -> Option<u32> {
+        self.exit_code
+    }
+
+    ///
+    /// Reason of the lockup, when the processor has entered the lockup state
+    ///
+    pub fn lockup(&self) -> Option<Lockup> {
+        self.lockup
+    }
+
+    ///
+    /// Halt the core like a debugger does. No instructions execute and the
+    /// peripherals do not advance until `resume`.
+    ///
+    pub fn halt(&mut self) -> &mut Self {
+        self.debug_write_dhcsr(DHCSR_DBGKEY | DHCSR_C_DEBUGEN | DHCSR_C_HALT);
+        self
+    }
+
+    ///
+    /// Resume execution after `halt`
+    ///
+    pub fn resume(&mut self) -> &mut Self {
+        self.debug_write_dhcsr(DHCSR_DBGKEY | DHCSR_C_DEBUGEN);
+        self
+    }
+
+    ///
+    /// Check if the core is halted for debugging
+    ///
+    pub fn is_halted(&self) -> bool {
+        self.dhcsr & DHCSR_C_HALT != 0
+    }
+
+    ///
+    /// Immediate of the BKPT instruction the processor halted on, if any
+    ///
+    pub fn breakpoint_hit(&self) -> Option<u8> {
+        self.breakpoint_hit
+    }
+
+    ///
+    /// Cache decoded instructions to speed up simulation, the same as
+    /// `decode_cache(true)`. Writes to cached code invalidate its decodes.
+    ///
+    pub fn cache_instructions(&mut self) {
+        if self.decode_cache.is_none() {
+            self.decode_cache(true);
+        }
+    }
+
+    ///
+    /// Disassemble the instructions in range `start..start + len`.
+    /// Returns the address, raw bytes and text of each instruction.
+    /// A range running past the end of the address space is clamped to it.
+    ///
+    pub fn disassemble_range(
+        &self,
+        start: u32,
+        len: u32,
+    ) -> Result<Vec<(u32, Vec<u8>, String)>, Fault> {
+        let mut listing = Vec::new();
+        let len = len.min((u32::MAX - start).saturating_add(1));
+        let mut offset = 0;
+
+        while offset < len {
+            let address = start.wrapping_add(offset);
+            // read like a debugger, without wait states or watchpoints
+            let mut bytes = self.read_slice(address, 2)?;
+            let halfword = u16::from_le_bytes([bytes[0], bytes[1]]);
+            let thumb = if is_thumb32(halfword) {
+                bytes.extend(self.read_slice(address.wrapping_add(2), 2)?);
+                ThumbCode::Thumb32 {
+                    opcode: u32::from(halfword) << 16
+                        | u32::from(u16::from_le_bytes([bytes[2], bytes[3]])),
+                }
+            } else {
+                ThumbCode::Thumb16 { opcode: halfword }
+            };
+            let size = bytes.len() as u32;
+            let instruction = self.decode(thumb);
+
+            listing.push((address, bytes, instruction.to_string()));
+            offset = offset.saturating_add(size);
+        }
+        Ok(listing)
+    }
 }
 
 impl fmt::Display for Processor {
@@ -414,3 +936,110 @@ impl fmt::Display for Processor {
                  self.get_r(Reg::LR))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_separate_flash_and_ram_regions() {
+        // Arrange
+        let code = [0u8; 0x400];
+        let mut processor = Processor::new();
+        processor
+            .flash_memory_at(0x0800_0000, code.len(), &code)
+            .ram_memory(0x2000_0000, 0x1000);
+
+        // Act & Assert
+        assert_eq!(
+            processor.write32(0x0800_0010, 42),
+            Err(Fault::PreciseErr {
+                address: 0x0800_0010
+            })
+        );
+        processor.write32(0x2000_0010, 42).unwrap();
+        assert_eq!(processor.read32(0x2000_0010).unwrap(), 42);
+        assert_eq!(
+            processor.write32(0x2000_1000, 42),
+            Err(Fault::PreciseErr {
+                address: 0x2000_1000
+            })
+        );
+    }
+
+    #[test]
+    fn test_writable_flash() {
+        // Arrange
+        let code = [0u8; 0x400];
+        let mut processor = Processor::new();
+        processor
+            .flash_memory_at(0x0800_0000, code.len(), &code)
+            .flash_writable(true);
+
+        // Act
+        processor.write32(0x0800_0010, 42).unwrap();
+
+        // Assert
+        assert_eq!(processor.read32(0x0800_0010).unwrap(), 42);
+    }
+
+    #[test]
+    fn test_disassemble_range_mixed_width() {
+        // Arrange
+        let code = [
+            0x01, 0x20, // movs r0, #1
+            0x4f, 0xf0, 0x00, 0x01, // mov.w r1, #0
+            0x00, 0xbf, // nop
+        ];
+        let mut processor = Processor::new();
+        processor.flash_memory(code.len(), &code);
+
+        // Act
+        let listing = processor.disassemble_range(0, code.len() as u32).unwrap();
+
+        // Assert
+        let addresses: Vec<u32> = listing.iter().map(|(address, _, _)| *address).collect();
+        assert_eq!(addresses, vec![0, 2, 6]);
+        assert_eq!(listing[0].1, vec![0x01, 0x20]);
+        assert_eq!(listing[1].1, vec![0x4f, 0xf0, 0x00, 0x01]);
+        assert_eq!(listing[2].1, vec![0x00, 0xbf]);
+        assert!(listing[0].2.starts_with("mov"));
+        assert!(listing[2].2.starts_with("nop"));
+    }
+
+    #[test]
+    fn test_disassemble_range_does_not_fire_watchpoints() {
+        // Arrange
+        let hits = Rc::new(Cell::new(0));
+        let recorded = hits.clone();
+        let mut processor = Processor::new();
+        processor.flash_memory(4, &[0x00, 0xbf, 0x00, 0xbf]);
+        processor.add_watchpoint(Watchpoint::new(
+            0..4,
+            true,
+            true,
+            Box::new(move |_, _, _, _| recorded.set(recorded.get() + 1)),
+        ));
+
+        // Act
+        let listing = processor.disassemble_range(0, 4).unwrap();
+
+        // Assert
+        assert_eq!(listing.len(), 2);
+        assert_eq!(hits.get(), 0);
+    }
+    #[test]
+    fn test_disassemble_range_wrapping_address_space() {
+        // Arrange
+        let processor = Processor::new();
+
+        // Act
+        let listing = processor.disassemble_range(0xffff_fffe, 4);
+
+        // Assert
+        // the range is clamped to the top of the address space instead of
+        // wrapping to an empty range, the unmapped address faults
+        assert!(listing.is_err());
+    }
+}
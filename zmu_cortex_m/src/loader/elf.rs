@@ -0,0 +1,219 @@
+//!
+//! ELF image loader
+//!
+
+use crate::loader::LoaderError;
+use crate::Processor;
+use goblin::elf::header::EM_ARM;
+use goblin::elf::program_header::PT_LOAD;
+use goblin::elf::Elf;
+use std::ops::Range;
+
+fn parse_elf(image: &[u8]) -> Result<Elf<'_>, LoaderError> {
+    let elf = Elf::parse(image).map_err(|e| LoaderError::InvalidImage(e.to_string()))?;
+
+    if elf.is_64 || elf.header.e_machine != EM_ARM {
+        return Err(LoaderError::InvalidImage(
+            "not a 32-bit ARM ELF image".to_string(),
+        ));
+    }
+    Ok(elf)
+}
+
+///
+/// Physical addresses and file contents of the `PT_LOAD` segments that
+/// have data in the file
+///
+fn load_segments<'a>(elf: &Elf<'_>, image: &'a [u8]) -> Result<Vec<(u32, &'a [u8])>, LoaderError> {
+    let mut segments = Vec::new();
+    for ph in &elf.program_headers {
+        if ph.p_type == PT_LOAD && ph.p_filesz > 0 {
+            let start = ph.p_offset as usize;
+            let data = start
+                .checked_add(ph.p_filesz as usize)
+                .and_then(|end| image.get(start..end))
+                .ok_or_else(|| {
+                    LoaderError::InvalidImage("segment data outside of the file".to_string())
+                })?;
+            segments.push((ph.p_paddr as u32, data));
+        }
+    }
+    Ok(segments)
+}
+
+///
+/// Load the `PT_LOAD` segments of an ELF image to their physical (load)
+/// addresses moved by `offset`. Returns the entry point of the image, moved
+/// by the same offset.
+///
+/// Memory for the segments must be configured beforehand.
+///
+pub fn load_elf(processor: &mut Processor, image: &[u8], offset: u32) -> Result<u32, LoaderError> {
+    let elf = parse_elf(image)?;
+
+    for (address, data) in load_segments(&elf, image)? {
+        processor.load_memory(address.wrapping_add(offset), data)?;
+    }
+
+    Ok((elf.entry as u32).wrapping_add(offset))
+}
+
+///
+/// Address range `load_elf` writes to for the image and `offset`, from the
+/// lowest to the highest address of the segments.
+///
+pub fn elf_load_range(image: &[u8], offset: u32) -> Result<Range<u32>, LoaderError> {
+    let elf = parse_elf(image)?;
+
+    let mut range: Option<Range<u32>> = None;
+    for (address, data) in load_segments(&elf, image)? {
+        let start = address.wrapping_add(offset);
+        let end = start.checked_add(data.len() as u32).ok_or_else(|| {
+            LoaderError::InvalidImage("segment past the end of the address space".to_string())
+        })?;
+        range = Some(match range {
+            Some(range) => range.start.min(start)..range.end.max(end),
+            None => start..end,
+        });
+    }
+    range.ok_or_else(|| LoaderError::InvalidImage("no loadable segments".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::Bus;
+
+    // segments as (paddr, vaddr, data, memsz)
+    fn make_elf(entry: u32, segments: &[(u32, u32, &[u8], u32)]) -> Vec<u8> {
+        let phoff = 52;
+        let mut data_offset = phoff + 32 * segments.len() as u32;
+
+        let mut image = vec![0x7f, b'E', b'L', b'F', 1, 1, 1, 0];
+        image.extend_from_slice(&[0; 8]);
+        image.extend_from_slice(&2_u16.to_le_bytes()); // ET_EXEC
+        image.extend_from_slice(&EM_ARM.to_le_bytes());
+        image.extend_from_slice(&1_u32.to_le_bytes());
+        image.extend_from_slice(&entry.to_le_bytes());
+        image.extend_from_slice(&phoff.to_le_bytes());
+        image.extend_from_slice(&0_u32.to_le_bytes()); // no section headers
+        image.extend_from_slice(&0x0500_0200_u32.to_le_bytes());
+        image.extend_from_slice(&52_u16.to_le_bytes());
+        image.extend_from_slice(&32_u16.to_le_bytes());
+        image.extend_from_slice(&(segments.len() as u16).to_le_bytes());
+        image.extend_from_slice(&40_u16.to_le_bytes());
+        image.extend_from_slice(&0_u16.to_le_bytes());
+        image.extend_from_slice(&0_u16.to_le_bytes());
+
+        for (paddr, vaddr, data, memsz) in segments {
+            for value in &[
+                PT_LOAD,
+                data_offset,
+                *vaddr,
+                *paddr,
+                data.len() as u32,
+                *memsz,
+                5,
+                4,
+            ] {
+                image.extend_from_slice(&value.to_le_bytes());
+            }
+            data_offset += data.len() as u32;
+        }
+        for (_, _, data, _) in segments {
+            image.extend_from_slice(data);
+        }
+        image
+    }
+
+    #[test]
+    fn test_load_elf_segments() {
+        // Arrange
+        let text = [
+            0x00, 0x04, 0x00, 0x20, // initial sp
+            0x09, 0x00, 0x00, 0x08, // reset vector
+            0x01, 0x20, // movs r0, #1
+            0xfe, 0xe7, // b .
+        ];
+        let data = [0xef, 0xbe, 0xad, 0xde];
+        let image = make_elf(
+            0x0800_0009,
+            &[
+                (0x0800_0000, 0x0800_0000, &text, text.len() as u32),
+                (0x0800_0100, 0x2000_0000, &data, data.len() as u32),
+                (0x2000_0004, 0x2000_0004, &[], 0x10),
+            ],
+        );
+        let mut processor = Processor::new();
+        processor.flash_memory_at(0x0800_0000, 0x1000, &[]);
+
+        // Act
+        let entry = load_elf(&mut processor, &image, 0);
+
+        // Assert
+        assert_eq!(entry, Ok(0x0800_0009));
+        assert_eq!(processor.read16(0x0800_0008).unwrap(), 0x2001);
+        assert_eq!(processor.read32(0x0800_0004).unwrap(), 0x0800_0009);
+        assert_eq!(processor.read32(0x0800_0100).unwrap(), 0xdead_beef);
+    }
+
+    #[test]
+    fn test_load_elf_at_offset() {
+        // Arrange: image linked at 0, loaded behind a bootloader
+        let text = [
+            0x00, 0x04, 0x00, 0x20, // initial sp
+            0x09, 0x00, 0x00, 0x00, // reset vector
+            0x01, 0x20, // movs r0, #1
+            0xfe, 0xe7, // b .
+        ];
+        let data = [0xef, 0xbe, 0xad, 0xde];
+        let image = make_elf(
+            0x0000_0009,
+            &[
+                (0x0000_0000, 0x0000_0000, &text, text.len() as u32),
+                (0x0000_0100, 0x2000_0000, &data, data.len() as u32),
+            ],
+        );
+        let mut processor = Processor::new();
+        processor.flash_memory_at(0x0800_0000, 0x1000, &[]);
+
+        // Act
+        let range = elf_load_range(&image, 0x0800_0000);
+        let entry = load_elf(&mut processor, &image, 0x0800_0000);
+
+        // Assert
+        assert_eq!(range, Ok(0x0800_0000..0x0800_0104));
+        assert_eq!(entry, Ok(0x0800_0009));
+        assert_eq!(processor.read16(0x0800_0008).unwrap(), 0x2001);
+        assert_eq!(processor.read32(0x0800_0100).unwrap(), 0xdead_beef);
+    }
+
+    #[test]
+    fn test_load_elf_outside_memory() {
+        // Arrange
+        let image = make_elf(0, &[(0x0800_0ffe, 0x0800_0ffe, &[1, 2, 3, 4], 4)]);
+        let mut processor = Processor::new();
+        processor.flash_memory_at(0x0800_0000, 0x1000, &[]);
+
+        // Act
+        let result = load_elf(&mut processor, &image, 0);
+
+        // Assert
+        assert_eq!(
+            result,
+            Err(LoaderError::NotMapped {
+                address: 0x0800_1000
+            })
+        );
+    }
+
+    #[test]
+    fn test_load_invalid_elf() {
+        let mut processor = Processor::new();
+
+        assert!(matches!(
+            load_elf(&mut processor, b"not an elf file", 0),
+            Err(LoaderError::InvalidImage(_))
+        ));
+    }
+}
@@ -0,0 +1,169 @@
+//!
+//! Intel HEX image loader
+//!
+
+use crate::loader::LoaderError;
+use crate::Processor;
+
+const DATA: u8 = 0x00;
+const END_OF_FILE: u8 = 0x01;
+const EXTENDED_SEGMENT_ADDRESS: u8 = 0x02;
+const START_SEGMENT_ADDRESS: u8 = 0x03;
+const EXTENDED_LINEAR_ADDRESS: u8 = 0x04;
+const START_LINEAR_ADDRESS: u8 = 0x05;
+
+fn invalid_record(line: usize) -> LoaderError {
+    LoaderError::InvalidImage(format!("invalid record on line {}", line))
+}
+
+///
+/// Parse a record line into its bytes and validate the checksum.
+///
+fn parse_record(record: &str, line: usize) -> Result<Vec<u8>, LoaderError> {
+    let hex = record
+        .strip_prefix(':')
+        .ok_or_else(|| invalid_record(line))?;
+    if !hex.is_ascii() || hex.len() % 2 != 0 || hex.len() < 10 {
+        return Err(invalid_record(line));
+    }
+
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+        .collect::<Result<Vec<u8>, _>>()
+        .map_err(|_| invalid_record(line))?;
+
+    // byte count, address, type and checksum surround the data
+    if bytes.len() != bytes[0] as usize + 5 {
+        return Err(invalid_record(line));
+    }
+    if bytes.iter().fold(0_u8, |sum, b| sum.wrapping_add(*b)) != 0 {
+        return Err(LoaderError::Checksum { line });
+    }
+    Ok(bytes)
+}
+
+///
+/// Load an Intel HEX image to memory. Returns the start address of the
+/// image, if the image gives one.
+///
+/// Memory for the image must be configured beforehand.
+///
+pub fn load_ihex(processor: &mut Processor, image: &str) -> Result<Option<u32>, LoaderError> {
+    let mut base: u32 = 0;
+    let mut start_address = None;
+
+    for (index, record) in image.lines().enumerate() {
+        let line = index + 1;
+        let record = record.trim();
+        if record.is_empty() {
+            continue;
+        }
+
+        let bytes = parse_record(record, line)?;
+        let address = u32::from(bytes[1]) << 8 | u32::from(bytes[2]);
+        let data = &bytes[4..bytes.len() - 1];
+
+        match bytes[3] {
+            DATA => processor.load_memory(base.wrapping_add(address), data)?,
+            END_OF_FILE => break,
+            EXTENDED_SEGMENT_ADDRESS if data.len() == 2 => {
+                base = (u32::from(data[0]) << 8 | u32::from(data[1])) << 4;
+            }
+            EXTENDED_LINEAR_ADDRESS if data.len() == 2 => {
+                base = (u32::from(data[0]) << 8 | u32::from(data[1])) << 16;
+            }
+            START_SEGMENT_ADDRESS | START_LINEAR_ADDRESS if data.len() == 4 => {
+                start_address = Some(
+                    u32::from(data[0]) << 24
+                        | u32::from(data[1]) << 16
+                        | u32::from(data[2]) << 8
+                        | u32::from(data[3]),
+                );
+            }
+            _ => return Err(invalid_record(line)),
+        }
+    }
+
+    Ok(start_address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::Bus;
+
+    const IMAGE: &str = "\
+:020000040800F2
+:0C00000000040020090000080120FEE7B9
+:020000040801F1
+:02FFFE00EFBE54
+:0400000508000009E6
+:00000001FF
+";
+
+    #[test]
+    fn test_load_ihex() {
+        // Arrange
+        let mut processor = Processor::new();
+        processor.flash_memory_at(0x0800_0000, 0x2_0000, &[]);
+
+        // Act
+        let start = load_ihex(&mut processor, IMAGE);
+
+        // Assert
+        assert_eq!(start, Ok(Some(0x0800_0009)));
+        assert_eq!(processor.read32(0x0800_0000).unwrap(), 0x2000_0400);
+        assert_eq!(processor.read32(0x0800_0004).unwrap(), 0x0800_0009);
+        assert_eq!(processor.read16(0x0800_0008).unwrap(), 0x2001);
+        // placed by the extended linear address record
+        assert_eq!(processor.read16(0x0801_fffe).unwrap(), 0xbeef);
+        assert_eq!(processor.read16(0x0800_fffe).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_load_ihex_extended_segment_address() {
+        // Arrange
+        let mut processor = Processor::new();
+        processor.flash_memory(0x2_0000, &[]);
+
+        // Act
+        let start = load_ihex(&mut processor, ":020000021000EC\n:02001000AA55EF\n");
+
+        // Assert
+        assert_eq!(start, Ok(None));
+        assert_eq!(processor.read16(0x1_0010).unwrap(), 0x55aa);
+    }
+
+    #[test]
+    fn test_load_ihex_checksum_error() {
+        // Arrange
+        let mut processor = Processor::new();
+        processor.flash_memory_at(0x0800_0000, 0x2_0000, &[]);
+        let corrupted = IMAGE.replace(":02FFFE00EFBE54", ":02FFFE00EFBF54");
+
+        // Act
+        let result = load_ihex(&mut processor, &corrupted);
+
+        // Assert
+        assert_eq!(result, Err(LoaderError::Checksum { line: 4 }));
+    }
+
+    #[test]
+    fn test_load_ihex_malformed_record() {
+        let mut processor = Processor::new();
+
+        assert!(matches!(
+            load_ihex(&mut processor, ":0C000000000400"),
+            Err(LoaderError::InvalidImage(_))
+        ));
+        assert!(matches!(
+            load_ihex(&mut processor, "020000040800F2"),
+            Err(LoaderError::InvalidImage(_))
+        ));
+        assert!(matches!(
+            load_ihex(&mut processor, ":0é000004080F2"),
+            Err(LoaderError::InvalidImage(_))
+        ));
+    }
+}
@@ -0,0 +1,131 @@
+//!
+//! Loading of firmware images to the simulated memory
+//!
+
+use crate::bus::Bus;
+use crate::core::fault::Fault;
+use crate::memory::map::MapMemory;
+use crate::Processor;
+
+pub mod elf;
+pub mod ihex;
+
+#[derive(PartialEq, Debug, Clone)]
+///
+/// Reasons for an image to fail loading
+///
+pub enum LoaderError {
+    ///
+    /// The image is malformed or of unsupported kind
+    ///
+    InvalidImage(String),
+
+    ///
+    /// The image does not fit the configured memory, `address` is the first
+    /// address that could not be written
+    ///
+    NotMapped {
+        /// the address that could not be written
+        address: u32,
+    },
+
+    ///
+    /// A record of a hex image has an invalid checksum
+    ///
+    Checksum {
+        /// line number of the record, starting from 1
+        line: usize,
+    },
+}
+
+impl From<Fault> for LoaderError {
+    fn from(fault: Fault) -> Self {
+        match fault {
+            Fault::PreciseErr { address } | Fault::DAccViol { address } => {
+                Self::NotMapped { address }
+            }
+            _ => Self::InvalidImage(format!("{:?} fault while loading the image", fault)),
+        }
+    }
+}
+
+///
+/// Load a flat binary image to flash, starting from `load_address`.
+///
+/// The whole image must fit in the configured flash region.
+///
+pub fn load_bin(
+    processor: &mut Processor,
+    image: &[u8],
+    load_address: u32,
+) -> Result<(), LoaderError> {
+    if image.is_empty() {
+        return Ok(());
+    }
+    if !processor.code.in_range(processor.map_address(load_address)) {
+        return Err(LoaderError::NotMapped {
+            address: load_address,
+        });
+    }
+    processor.load_memory(load_address, image)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_bin() {
+        // Arrange
+        let mut processor = Processor::new();
+        processor.flash_memory_at(0x0800_0000, 0x100, &[]);
+
+        // Act
+        let result = load_bin(&mut processor, &[0x00, 0x04, 0x00, 0x20, 0x09], 0x0800_0000);
+
+        // Assert
+        assert_eq!(result, Ok(()));
+        assert_eq!(processor.read32(0x0800_0000).unwrap(), 0x2000_0400);
+        assert_eq!(processor.read8(0x0800_0004).unwrap(), 0x09);
+    }
+
+    #[test]
+    fn test_load_bin_does_not_fit_flash() {
+        // Arrange
+        let mut processor = Processor::new();
+        processor.flash_memory_at(0x0800_0000, 0x100, &[]);
+
+        // Act
+        let too_large = load_bin(&mut processor, &[0xff; 0x10], 0x0800_00f8);
+        let outside = load_bin(&mut processor, &[0xff; 0x10], 0x2000_0000);
+
+        // Assert
+        assert_eq!(
+            too_large,
+            Err(LoaderError::NotMapped {
+                address: 0x0800_0100
+            })
+        );
+        assert_eq!(
+            outside,
+            Err(LoaderError::NotMapped {
+                address: 0x2000_0000
+            })
+        );
+        assert_eq!(processor.read8(0x0800_00f8).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_fault_keeps_address() {
+        // Act & Assert
+        assert_eq!(
+            LoaderError::from(Fault::DAccViol {
+                address: 0x2000_0010
+            }),
+            LoaderError::NotMapped {
+                address: 0x2000_0010
+            }
+        );
+    }
+}
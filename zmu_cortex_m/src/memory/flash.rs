@@ -11,15 +11,51 @@ use byteorder::{ByteOrder, LittleEndian};
 /// Flash memory with configurable start address and data content
 pub struct FlashMemory {
     data: Box<[u8]>,
+    writable: bool,
+    wait_states: u32,
 }
 
 impl FlashMemory {
-    /// make a flash data instance with given start address, size and data content
+    /// make a flash data instance with given size and data content,
+    /// the rest of the flash is zero filled
     pub fn new(size: usize, new_data: &[u8]) -> Self {
         let mut data = vec![0_u8; size].into_boxed_slice();
-        data.copy_from_slice(new_data);
+        data[..new_data.len()].copy_from_slice(new_data);
 
-        Self { data }
+        Self {
+            data,
+            writable: false,
+            wait_states: 0,
+        }
+    }
+
+    /// Allow or disallow writes to the flash contents
+    pub fn set_writable(&mut self, writable: bool) {
+        self.writable = writable;
+    }
+
+    /// Set the number of wait states each read access takes
+    pub fn set_wait_states(&mut self, wait_states: u32) {
+        self.wait_states = wait_states;
+    }
+
+    /// Number of wait states each read access takes
+    pub fn wait_states(&self) -> u32 {
+        self.wait_states
+    }
+
+    /// Program flash contents at given offset, regardless of write protection
+    pub fn load(&mut self, addr: u32, new_data: &[u8]) -> Result<(), Fault> {
+        let a = addr as usize;
+        let len = self.data.len();
+        let bytes = self
+            .data
+            .get_mut(a..a + new_data.len())
+            .ok_or(Fault::PreciseErr {
+                address: len.max(a) as u32,
+            })?;
+        bytes.copy_from_slice(new_data);
+        Ok(())
     }
 
     ///
@@ -36,28 +72,65 @@ impl FlashMemory {
 impl Bus for FlashMemory {
     fn read8(&self, addr: u32) -> Result<u8, Fault> {
         let a = addr as usize;
-        Ok(self.data[a])
+        self.data
+            .get(a)
+            .copied()
+            .ok_or(Fault::PreciseErr { address: addr })
     }
     fn read16(&self, addr: u32) -> Result<u16, Fault> {
         let a = addr as usize;
 
-        Ok(LittleEndian::read_u16(&self.data[a..a + 2]))
+        let bytes = self
+            .data
+            .get(a..a + 2)
+            .ok_or(Fault::PreciseErr { address: addr })?;
+        Ok(LittleEndian::read_u16(bytes))
     }
 
     fn read32(&mut self, addr: u32) -> Result<u32, Fault> {
         let a = addr as usize;
-        Ok(LittleEndian::read_u32(&self.data[a..a + 4]))
+        let bytes = self
+            .data
+            .get(a..a + 4)
+            .ok_or(Fault::PreciseErr { address: addr })?;
+        Ok(LittleEndian::read_u32(bytes))
     }
 
-    fn write32(&mut self, _addr: u32, _value: u32) -> Result<(), Fault> {
-        Err(Fault::DAccViol)
+    fn write32(&mut self, addr: u32, value: u32) -> Result<(), Fault> {
+        if !self.writable {
+            return Err(Fault::PreciseErr { address: addr });
+        }
+        let a = addr as usize;
+        let bytes = self
+            .data
+            .get_mut(a..a + 4)
+            .ok_or(Fault::PreciseErr { address: addr })?;
+        LittleEndian::write_u32(bytes, value);
+        Ok(())
     }
 
-    fn write16(&mut self, _addr: u32, _value: u16) -> Result<(), Fault> {
-        Err(Fault::DAccViol)
+    fn write16(&mut self, addr: u32, value: u16) -> Result<(), Fault> {
+        if !self.writable {
+            return Err(Fault::PreciseErr { address: addr });
+        }
+        let a = addr as usize;
+        let bytes = self
+            .data
+            .get_mut(a..a + 2)
+            .ok_or(Fault::PreciseErr { address: addr })?;
+        LittleEndian::write_u16(bytes, value);
+        Ok(())
     }
-    fn write8(&mut self, _addr: u32, _value: u8) -> Result<(), Fault> {
-        Err(Fault::DAccViol)
+    fn write8(&mut self, addr: u32, value: u8) -> Result<(), Fault> {
+        if !self.writable {
+            return Err(Fault::PreciseErr { address: addr });
+        }
+        let a = addr as usize;
+        *self
+            .data
+            .get_mut(a)
+            .ok_or(Fault::PreciseErr { address: addr })? = value;
+        Ok(())
     }
 
     fn in_range(&self, addr: u32) -> bool {
@@ -94,3 +167,16 @@ fn test_in_range() {
         assert!(!mem.in_range(0xFFFF_FFFF));
     }
 }
+
+#[test]
+fn test_write() {
+    let mut mem = FlashMemory::new(1024, &vec![0u8; 1024]);
+    assert_eq!(mem.write32(0, 42), Err(Fault::PreciseErr { address: 0 }));
+
+    mem.set_writable(true);
+    mem.write32(0, 0x1234_5678).unwrap();
+    mem.write16(4, 0x9abc).unwrap();
+    mem.write8(6, 0xde).unwrap();
+    assert_eq!(mem.read32(0).unwrap(), 0x1234_5678);
+    assert_eq!(mem.read32(4).unwrap(), 0x00de_9abc);
+}
@@ -12,6 +12,7 @@ use byteorder::{ByteOrder, LittleEndian};
 pub struct RAM {
     start_address: u32,
     data: Box<[u8]>,
+    wait_states: u32,
 }
 
 impl RAM {
@@ -22,6 +23,7 @@ impl RAM {
         Self {
             start_address,
             data,
+            wait_states: 0,
         }
     }
     ///
@@ -31,43 +33,76 @@ impl RAM {
         Self {
             start_address,
             data,
+            wait_states: 0,
         }
     }
+
+    /// Set the number of wait states each access takes
+    pub fn set_wait_states(&mut self, wait_states: u32) {
+        self.wait_states = wait_states;
+    }
+
+    /// Number of wait states each access takes
+    pub fn wait_states(&self) -> u32 {
+        self.wait_states
+    }
 }
 
 impl Bus for RAM {
     fn read8(&self, addr: u32) -> Result<u8, Fault> {
-        let a = addr - self.start_address;
-        Ok(self.data[a as usize])
+        let a = (addr - self.start_address) as usize;
+        self.data
+            .get(a)
+            .copied()
+            .ok_or(Fault::PreciseErr { address: addr })
     }
 
     fn read16(&self, addr: u32) -> Result<u16, Fault> {
         let a = (addr - self.start_address) as usize;
 
-        Ok(LittleEndian::read_u16(&self.data[a..a + 2]))
+        let bytes = self
+            .data
+            .get(a..a + 2)
+            .ok_or(Fault::PreciseErr { address: addr })?;
+        Ok(LittleEndian::read_u16(bytes))
     }
 
     fn read32(&mut self, addr: u32) -> Result<u32, Fault> {
         let a = (addr - self.start_address) as usize;
-        Ok(LittleEndian::read_u32(&self.data[a..a + 4]))
+        let bytes = self
+            .data
+            .get(a..a + 4)
+            .ok_or(Fault::PreciseErr { address: addr })?;
+        Ok(LittleEndian::read_u32(bytes))
     }
 
     fn write8(&mut self, addr: u32, value: u8) -> Result<(), Fault> {
-        let a = addr - self.start_address;
-        self.data[a as usize] = value;
+        let a = (addr - self.start_address) as usize;
+        *self
+            .data
+            .get_mut(a)
+            .ok_or(Fault::PreciseErr { address: addr })? = value;
         Ok(())
     }
 
     fn write16(&mut self, addr: u32, value: u16) -> Result<(), Fault> {
         let a = (addr - self.start_address) as usize;
 
-        LittleEndian::write_u16(&mut self.data[a..a + 2], value);
+        let bytes = self
+            .data
+            .get_mut(a..a + 2)
+            .ok_or(Fault::PreciseErr { address: addr })?;
+        LittleEndian::write_u16(bytes, value);
         Ok(())
     }
 
     fn write32(&mut self, addr: u32, value: u32) -> Result<(), Fault> {
         let a = (addr - self.start_address) as usize;
-        LittleEndian::write_u32(&mut self.data[a..a + 4], value);
+        let bytes = self
+            .data
+            .get_mut(a..a + 4)
+            .ok_or(Fault::PreciseErr { address: addr })?;
+        LittleEndian::write_u32(bytes, value);
         Ok(())
     }
 
@@ -132,3 +167,32 @@ fn test_write_read() {
         assert_eq!(mem.read8(1022).unwrap(), 0xCC);
     }
 }
+
+#[test]
+fn test_access_past_end() {
+    let mut mem = RAM::new(0x2000_0000, 1024);
+    assert_eq!(
+        mem.read32(0x2000_03fe),
+        Err(Fault::PreciseErr {
+            address: 0x2000_03fe
+        })
+    );
+    assert_eq!(
+        mem.read16(0x2000_03ff),
+        Err(Fault::PreciseErr {
+            address: 0x2000_03ff
+        })
+    );
+    assert_eq!(
+        mem.write32(0x2000_03fd, 0),
+        Err(Fault::PreciseErr {
+            address: 0x2000_03fd
+        })
+    );
+    assert_eq!(
+        mem.write16(0x2000_03ff, 0),
+        Err(Fault::PreciseErr {
+            address: 0x2000_03ff
+        })
+    );
+}
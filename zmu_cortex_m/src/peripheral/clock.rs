@@ -0,0 +1,182 @@
+//!
+//! Core clock distribution to the peripherals
+//!
+//! The processor advances all peripherals from a single `tick`, so that
+//! they see the same number of cycles as the core whether it is running
+//! or sleeping.
+//!
+//! The peripherals built into the processor (DWT and system tick) are
+//! stepped directly by the `Clocked` implementation of `Processor`.
+//! Peripherals registered to the bus are advanced through
+//! `Peripheral::tick`, other host models through `Clocked`. Requests the
+//! bus peripherals make, like the interrupt of a timer or the system reset
+//! of a watchdog, are carried out after all peripherals have been advanced.
+//!
+
+use crate::bus::PeripheralRequest;
+use crate::core::reset::Reset;
+use crate::peripheral::{dwt::Dwt, nvic::NVIC, systick::SysTick};
+use crate::Processor;
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::rc::Rc;
+
+///
+/// Something that advances with the core clock
+///
+pub trait Clocked {
+    ///
+    /// Advance ```cycles``` core clock cycles
+    ///
+    fn tick(&mut self, cycles: u64);
+}
+
+impl<T: Clocked> Clocked for Rc<RefCell<T>> {
+    fn tick(&mut self, cycles: u64) {
+        self.borrow_mut().tick(cycles);
+    }
+}
+
+impl Clocked for Processor {
+    #[inline]
+    fn tick(&mut self, cycles: u64) {
+        self.cycle_count += cycles;
+
+        // a single instruction never takes more than 32 bits worth of cycles,
+        // only long sleeps need to be split
+        if let Ok(count) = u32::try_from(cycles) {
+            self.dwt_tick(count);
+            self.syst_step(count);
+        } else {
+            let mut remaining = cycles;
+            while remaining > 0 {
+                let count = remaining.min(u64::from(u32::MAX)) as u32;
+                remaining -= u64::from(count);
+
+                self.dwt_tick(count);
+                self.syst_step(count);
+            }
+        }
+
+        if !self.peripherals.is_empty() || !self.clocked_peripherals.is_empty() {
+            self.tick_peripherals(cycles);
+        }
+    }
+}
+
+impl Processor {
+    fn tick_peripherals(&mut self, cycles: u64) {
+        let mut requests = Vec::new();
+        for (_, peripheral) in &mut self.peripherals {
+            peripheral.tick(cycles);
+            while let Some(request) = peripheral.take_request() {
+                requests.push(request);
+            }
+        }
+        for clocked in &mut self.clocked_peripherals {
+            clocked.tick(cycles);
+        }
+
+        for request in requests {
+            match request {
+                PeripheralRequest::Interrupt(irq) => {
+                    self.nvic_write_ispr(irq / 32, 1 << (irq % 32));
+                }
+                PeripheralRequest::SystemReset => self.request_system_reset(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::{Bus, Peripheral};
+    use crate::core::executor::{Executor, RunExit};
+    use crate::core::fault::Fault;
+    use crate::core::register::BaseReg;
+    use crate::peripheral::scb::DEMCR_TRCENA;
+    use crate::peripheral::timer::{Timer, TIMER_CR1_CEN, TIMER_REGISTERS_SIZE};
+    use std::collections::HashSet;
+
+    #[derive(Default)]
+    struct CycleCounter {
+        cycles: u64,
+    }
+
+    impl Clocked for CycleCounter {
+        fn tick(&mut self, cycles: u64) {
+            self.cycles += cycles;
+        }
+    }
+
+    impl Peripheral for CycleCounter {
+        fn read32(&mut self, _offset: u32) -> Result<u32, Fault> {
+            Ok(self.cycles as u32)
+        }
+        fn read16(&self, _offset: u32) -> Result<u16, Fault> {
+            Ok(self.cycles as u16)
+        }
+        fn read8(&self, _offset: u32) -> Result<u8, Fault> {
+            Ok(self.cycles as u8)
+        }
+        fn write32(&mut self, _offset: u32, _value: u32) -> Result<(), Fault> {
+            Ok(())
+        }
+        fn write16(&mut self, _offset: u32, _value: u16) -> Result<(), Fault> {
+            Ok(())
+        }
+        fn write8(&mut self, _offset: u32, _value: u8) -> Result<(), Fault> {
+            Ok(())
+        }
+        fn tick(&mut self, cycles: u64) {
+            self.cycles += cycles;
+        }
+    }
+
+    #[test]
+    fn test_peripherals_stay_in_sync() {
+        // Arrange
+        let code = [
+            0x00, 0xbf, // nop
+            0x30, 0xbf, // wfi
+            0xfd, 0xe7, // b 0
+        ];
+        let counter = Rc::new(RefCell::new(CycleCounter::default()));
+        let bus_counter = Rc::new(RefCell::new(CycleCounter::default()));
+        let mut processor = Processor::new();
+        processor.flash_memory(code.len(), &code);
+        processor.register_peripheral(
+            0x4000_0000..0x4000_0000 + TIMER_REGISTERS_SIZE,
+            Box::new(Timer::new(28, 16)),
+        );
+        processor.register_clocked(Box::new(counter.clone()));
+        processor.register_peripheral(0x5000_0000..0x5000_0004, Box::new(bus_counter.clone()));
+        processor.set_pc(0);
+
+        // SysTick and the timer both wrap every 100 cycles
+        processor.write32(0xE000_E014, 99).unwrap();
+        processor.write32(0xE000_E010, 1).unwrap();
+        processor.write32(0x4000_002c, 99).unwrap();
+        processor.write32(0x4000_0000, TIMER_CR1_CEN).unwrap();
+        processor.write32(0xE000_EDFC, DEMCR_TRCENA).unwrap();
+        processor.write32(0xE000_1000, 1).unwrap(); // CYCCNTENA
+
+        // Act
+        let exit = processor.run(1234, &HashSet::new());
+
+        // Assert
+        assert_eq!(exit, RunExit::CycleBudget);
+        let cycles = processor.cycle_count;
+        assert!(cycles >= 1234);
+        assert_eq!(counter.borrow().cycles, cycles);
+        assert_eq!(bus_counter.borrow().cycles, cycles);
+        assert_eq!(u64::from(processor.dwt_cyccnt), cycles);
+        assert_eq!(
+            u64::from(processor.read32(0x4000_0024).unwrap()),
+            cycles % 100
+        );
+        // SysTick loads the reload value on the first cycle, then counts down
+        assert_eq!(u64::from(processor.syst_cvr), 99 - (cycles - 1) % 100);
+    }
+}
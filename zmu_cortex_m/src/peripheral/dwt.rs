@@ -3,6 +3,8 @@
 //!
 
 use crate::core::bits::Bits;
+use crate::core::exception::{Exception, ExceptionHandling};
+use crate::peripheral::scb::DEMCR_MON_EN;
 use crate::Processor;
 
 /// Register API to Debug and Trace peripheral
@@ -17,15 +19,39 @@ pub trait Dwt {
     ///
     fn dwt_write_cyccnt(&mut self, value: u32);
 
+    ///
+    /// Read comparator register at `offset` from `DWT_COMP0`
+    ///
+    fn dwt_read_comparator(&mut self, offset: u32) -> u32;
+
+    ///
+    /// Write comparator register at `offset` from `DWT_COMP0`
+    ///
+    fn dwt_write_comparator(&mut self, offset: u32, value: u32);
+
+    ///
+    /// Match a data access of `size` bytes against the watchpoint comparators
+    ///
+    fn dwt_watch_access(&self, addr: u32, size: usize, is_write: bool);
+
     ///
     /// Clock dwt block ```cycles```.
     ///
+    /// Watchpoint matches of the previous instruction are reported to
+    /// `DFSR` and pend the debug monitor exception when it is enabled.
     ///
     fn dwt_tick(&mut self, cycles: u32);
 }
 
 const DWT_CTRL_CYCCNTENA: u32 = 1;
 
+/// `DWT_FUNCTION` value to watch reads of the compared address
+pub const DWT_FUNCTION_WATCH_READ: u32 = 0b0101;
+/// `DWT_FUNCTION` value to watch writes of the compared address
+pub const DWT_FUNCTION_WATCH_WRITE: u32 = 0b0110;
+/// `DWT_FUNCTION` value to watch reads and writes of the compared address
+pub const DWT_FUNCTION_WATCH_READ_WRITE: u32 = 0b0111;
+
 impl Dwt for Processor {
     fn dwt_write_ctrl(&mut self, value: u32) {
         self.dwt_ctrl.set_bits(16..23, value.get_bits(16..23));
@@ -36,18 +62,143 @@ impl Dwt for Processor {
         self.dwt_cyccnt = value;
     }
 
+    fn dwt_read_comparator(&mut self, offset: u32) -> u32 {
+        let n = (offset >> 4) as usize;
+        match offset.get_bits(2..4) {
+            0 => self.dwt_comp[n],
+            1 => self.dwt_mask[n],
+            2 => {
+                // MATCHED is cleared by the read
+                let matched = self.dwt_matched.get();
+                self.dwt_matched.set(matched & !(1 << n));
+                self.dwt_function[n] | (matched.get_bits(n..n + 1) << 24)
+            }
+            _ => 0,
+        }
+    }
+
+    fn dwt_write_comparator(&mut self, offset: u32, value: u32) {
+        let n = (offset >> 4) as usize;
+        match offset.get_bits(2..4) {
+            0 => self.dwt_comp[n] = value,
+            1 => self.dwt_mask[n] = value.get_bits(0..5),
+            2 => self.dwt_function[n] = value.get_bits(0..4),
+            _ => (),
+        }
+    }
+
+    fn dwt_watch_access(&self, addr: u32, size: usize, is_write: bool) {
+        for n in 0..self.dwt_function.len() {
+            let watched = match self.dwt_function[n] {
+                DWT_FUNCTION_WATCH_READ => !is_write,
+                DWT_FUNCTION_WATCH_WRITE => is_write,
+                DWT_FUNCTION_WATCH_READ_WRITE => true,
+                _ => false,
+            };
+            if !watched {
+                continue;
+            }
+
+            // MASK tells the number of low address bits ignored by the compare
+            let mask = u32::MAX.checked_shl(self.dwt_mask[n]).unwrap_or(0);
+            let compare = self.dwt_comp[n] & mask;
+            if (0..size as u32)
+                .map(|i| addr.wrapping_add(i))
+                .any(|address| address & mask == compare)
+            {
+                self.dwt_matched.set(self.dwt_matched.get() | (1 << n));
+                self.dwt_trap.set(true);
+            }
+        }
+    }
+
     #[inline(always)]
     fn dwt_tick(&mut self, cycles: u32) {
         self.dwt_cyccnt = self
             .dwt_cyccnt
             .wrapping_add(cycles * (self.dwt_ctrl & DWT_CTRL_CYCCNTENA));
+
+        if self.dwt_trap.get() {
+            self.dwt_trap.set(false);
+            self.dfsr.set_bit(2, true); // DWTTRAP
+            if self.demcr & DEMCR_MON_EN != 0 {
+                self.set_exception_pending(Exception::DebugMonitor);
+            }
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::bus::Bus;
+    use crate::core::executor::Executor;
+    use crate::core::register::BaseReg;
+    use crate::core::register::Ipsr;
     use crate::core::reset::Reset;
+    use crate::peripheral::scb::DEMCR_TRCENA;
+
+    #[test]
+    fn test_comparator_mask_and_function() {
+        // Arrange
+        let mut processor = Processor::new();
+        processor.dwt_write_comparator(0x10, 0x2000_0104);
+        processor.dwt_write_comparator(0x14, 2);
+        processor.dwt_write_comparator(0x18, DWT_FUNCTION_WATCH_READ);
+
+        // Act
+        processor.dwt_watch_access(0x2000_0100, 4, false);
+        processor.dwt_watch_access(0x2000_0108, 4, true);
+        let unmatched = processor.dwt_read_comparator(0x18);
+        processor.dwt_watch_access(0x2000_0107, 1, false);
+        let matched = processor.dwt_read_comparator(0x18);
+
+        // Assert
+        assert_eq!(unmatched, DWT_FUNCTION_WATCH_READ);
+        assert_eq!(matched, DWT_FUNCTION_WATCH_READ | 1 << 24);
+        assert_eq!(processor.dwt_read_comparator(0x18), DWT_FUNCTION_WATCH_READ);
+    }
+
+    #[cfg(any(armv7m, armv7em))]
+    #[test]
+    fn test_write_watchpoint_pends_debug_monitor() {
+        // Arrange
+        let mut code = vec![0u8; 0x110];
+        code[0..4].copy_from_slice(&0x2000_1000u32.to_le_bytes());
+        code[4..8].copy_from_slice(&0x101u32.to_le_bytes());
+        code[0x30..0x34].copy_from_slice(&0x109u32.to_le_bytes()); // DebugMonitor
+        code[0x100..0x102].copy_from_slice(&0x4902u16.to_le_bytes()); // ldr r1, [pc, #8]
+        code[0x102..0x104].copy_from_slice(&0x6008u16.to_le_bytes()); // str r0, [r1]
+        code[0x104..0x106].copy_from_slice(&0xbf00u16.to_le_bytes()); // nop
+        code[0x106..0x108].copy_from_slice(&0xe7feu16.to_le_bytes()); // b .
+        code[0x108..0x10a].copy_from_slice(&0xe7feu16.to_le_bytes()); // handler: b .
+        code[0x10c..0x110].copy_from_slice(&0x2000_0100u32.to_le_bytes());
+
+        let mut processor = Processor::new();
+        processor.flash_memory(code.len(), &code);
+        processor.reset().unwrap();
+
+        processor
+            .write32(0xE000_EDFC, DEMCR_TRCENA | DEMCR_MON_EN)
+            .unwrap();
+        processor.write32(0xE000_1020, 0x2000_0100).unwrap();
+        processor.write32(0xE000_1024, 0).unwrap();
+        processor
+            .write32(0xE000_1028, DWT_FUNCTION_WATCH_WRITE)
+            .unwrap();
+
+        // Act
+        processor.step_instruction();
+        let pc_after_load = processor.get_pc();
+        processor.step_instruction();
+
+        // Assert
+        assert_eq!(pc_after_load, 0x102);
+        assert_eq!(processor.get_pc(), 0x108);
+        assert_eq!(processor.psr.get_isr_number(), 12);
+        assert!(processor.dfsr.get_bit(2));
+        assert!(processor.read32(0xE000_1028).unwrap().get_bit(24));
+    }
 
     #[test]
     fn test_dwt_tick() {
@@ -66,4 +217,47 @@ mod tests {
         // Act
         assert_eq!(processor.dwt_cyccnt, 42);
     }
+
+    fn run_program(enable_cyccnt: bool) -> Processor {
+        let code = [
+            0x01, 0x20, // movs r0, #1
+            0x02, 0x21, // movs r1, #2
+            0x40, 0x18, // adds r0, r0, r1
+            0x00, 0xbf, // nop
+            0xfe, 0xe7, // b .
+        ];
+        let mut processor = Processor::new();
+        processor.flash_memory(code.len(), &code);
+        processor.set_pc(0);
+        if enable_cyccnt {
+            processor.write32(0xE000_1000, DWT_CTRL_CYCCNTENA).unwrap();
+        }
+        for _ in 0..5 {
+            processor.step_instruction();
+        }
+        processor
+    }
+
+    #[test]
+    fn test_cyccnt_counts_executed_cycles() {
+        // Arrange & Act
+        let mut processor = run_program(true);
+
+        // Assert
+        assert!(processor.cycle_count > 0);
+        assert_eq!(
+            u64::from(processor.read32(0xE000_1004).unwrap()),
+            processor.cycle_count
+        );
+    }
+
+    #[test]
+    fn test_cyccnt_stopped_when_disabled() {
+        // Arrange & Act
+        let mut processor = run_program(false);
+
+        // Assert
+        assert!(processor.cycle_count > 0);
+        assert_eq!(processor.read32(0xE000_1004).unwrap(), 0);
+    }
 }
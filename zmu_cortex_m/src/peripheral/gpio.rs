@@ -0,0 +1,238 @@
+//!
+//! GPIO Port Simulation
+//!
+//! Modeled after a 16 pin STM32F4 GPIO port. Pins configured as outputs
+//! follow `GPIOx_ODR`, the other pins are driven by the host.
+//!
+//! The port is a bus `Peripheral`. Register a shared handle to it with
+//! `Processor::register_peripheral` and keep another to drive the inputs
+//! and watch the pins. The port keeps its state over a processor reset.
+//!
+
+use crate::bus::Peripheral;
+use crate::core::bits::Bits;
+use crate::core::fault::Fault;
+
+/// Default base address of the GPIO port, GPIOA
+pub const GPIO_BASE: u32 = 0x4002_0000;
+
+/// Number of pins of the port
+pub const GPIO_PINS: usize = 16;
+
+/// Size of the register block of the port
+pub const GPIO_REGISTERS_SIZE: u32 = 0x1c;
+
+/// Offset of `GPIOx_BSRR`, the bit set/reset register
+const GPIO_BSRR: u32 = 0x18;
+
+///
+/// State of a GPIO port
+///
+#[derive(Default, Debug, Clone)]
+pub struct GpioPort {
+    moder: u32,
+    otyper: u32,
+    ospeedr: u32,
+    pupdr: u32,
+    odr: u32,
+    /// pin levels driven by the host
+    input: u32,
+}
+
+impl GpioPort {
+    ///
+    /// Port in its reset state, with no pin driven by the host
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Set the levels the host drives on the pins, bit n for pin n
+    ///
+    pub fn set_input(&mut self, levels: u16) {
+        self.input = u32::from(levels);
+    }
+
+    ///
+    /// Current levels of the pins, outputs follow the program
+    ///
+    pub fn pins(&self) -> Vec<bool> {
+        let idr = self.read_idr();
+        (0..GPIO_PINS).map(|pin| idr.get_bit(pin)).collect()
+    }
+
+    ///
+    /// Read `GPIOx_IDR`, the current levels of the pins
+    ///
+    pub fn read_idr(&self) -> u32 {
+        let mut idr = 0;
+        for pin in 0..GPIO_PINS {
+            let level = match self.moder.get_bits(pin * 2..pin * 2 + 2) {
+                0b01 => self.odr.get_bit(pin),
+                // the input schmitt trigger is off in analog mode
+                0b11 => false,
+                // input, or alternate function driven outside of the simulation
+                _ => self.input.get_bit(pin),
+            };
+            idr.set_bit(pin, level);
+        }
+        idr
+    }
+
+    fn read(&self, offset: u32) -> u32 {
+        match offset {
+            0x00 => self.moder,
+            0x04 => self.otyper,
+            0x08 => self.ospeedr,
+            0x0c => self.pupdr,
+            0x10 => self.read_idr(),
+            0x14 => self.odr,
+            // BSRR is write only
+            _ => 0,
+        }
+    }
+
+    fn write(&mut self, offset: u32, value: u32) {
+        match offset {
+            0x00 => self.moder = value,
+            0x04 => self.otyper = value.get_bits(0..16),
+            0x08 => self.ospeedr = value,
+            0x0c => self.pupdr = value,
+            0x14 => self.odr = value.get_bits(0..16),
+            GPIO_BSRR => {
+                // set has priority over reset of the same pin
+                let set = value.get_bits(0..16);
+                let reset = value.get_bits(16..32);
+                self.odr = (self.odr & !reset) | set;
+            }
+            _ => {}
+        }
+    }
+
+    ///
+    /// Write the `width` bit wide lane at `offset` of a register
+    ///
+    fn write_lane(&mut self, offset: u32, value: u32, width: u32) {
+        let register = offset & !3;
+        let shift = (offset & 3) * 8;
+        let mask = (u32::MAX >> (32 - width)) << shift;
+
+        // BSRR acts on the bits written as one, the other lanes must not
+        // set or reset pins
+        let old = if register == GPIO_BSRR {
+            0
+        } else {
+            self.read(register)
+        };
+        self.write(register, (old & !mask) | ((value << shift) & mask));
+    }
+}
+
+impl Peripheral for GpioPort {
+    fn read32(&mut self, offset: u32) -> Result<u32, Fault> {
+        Ok(self.read(offset))
+    }
+
+    fn read16(&self, offset: u32) -> Result<u16, Fault> {
+        Ok((self.read(offset & !3) >> ((offset & 3) * 8)) as u16)
+    }
+
+    fn read8(&self, offset: u32) -> Result<u8, Fault> {
+        Ok((self.read(offset & !3) >> ((offset & 3) * 8)) as u8)
+    }
+
+    fn write32(&mut self, offset: u32, value: u32) -> Result<(), Fault> {
+        self.write(offset, value);
+        Ok(())
+    }
+
+    fn write16(&mut self, offset: u32, value: u16) -> Result<(), Fault> {
+        self.write_lane(offset, u32::from(value), 16);
+        Ok(())
+    }
+
+    fn write8(&mut self, offset: u32, value: u8) -> Result<(), Fault> {
+        self.write_lane(offset, u32::from(value), 8);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::Bus;
+    use crate::Processor;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn gpio_test_processor() -> (Processor, Rc<RefCell<GpioPort>>) {
+        let port = Rc::new(RefCell::new(GpioPort::new()));
+        let mut processor = Processor::new();
+        processor.register_peripheral(
+            GPIO_BASE..GPIO_BASE + GPIO_REGISTERS_SIZE,
+            Box::new(port.clone()),
+        );
+        processor.write32(GPIO_BASE, 0b01 << 10).unwrap(); // PA5 output
+        (processor, port)
+    }
+
+    #[test]
+    fn test_gpio_output_via_odr_and_bsrr() {
+        // Arrange
+        let (mut processor, port) = gpio_test_processor();
+
+        // Act & Assert
+        processor.write32(GPIO_BASE + 0x14, 1 << 5).unwrap();
+        assert!(port.borrow().pins()[5]);
+        processor.write32(GPIO_BASE + 0x14, 0).unwrap();
+        assert!(!port.borrow().pins()[5]);
+
+        processor.write32(GPIO_BASE + 0x18, 1 << 5).unwrap();
+        assert!(port.borrow().pins()[5]);
+        assert_eq!(processor.read32(GPIO_BASE + 0x14).unwrap(), 1 << 5);
+        processor.write32(GPIO_BASE + 0x18, 1 << 21).unwrap();
+        assert!(!port.borrow().pins()[5]);
+        processor
+            .write32(GPIO_BASE + 0x18, 1 << 21 | 1 << 5)
+            .unwrap();
+        assert!(port.borrow().pins()[5]);
+        assert_eq!(processor.read32(GPIO_BASE + 0x18).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_gpio_halfword_bsrr() {
+        // Arrange
+        let (mut processor, port) = gpio_test_processor();
+        processor.write32(GPIO_BASE + 0x14, 1 << 3).unwrap();
+
+        // Act & Assert
+        processor.write16(GPIO_BASE + 0x18, 1 << 5).unwrap();
+        assert_eq!(processor.read32(GPIO_BASE + 0x14).unwrap(), 1 << 5 | 1 << 3);
+        assert!(port.borrow().pins()[5]);
+
+        processor.write16(GPIO_BASE + 0x1a, 1 << 5).unwrap();
+        assert_eq!(processor.read32(GPIO_BASE + 0x14).unwrap(), 1 << 3);
+        assert!(!port.borrow().pins()[5]);
+
+        processor.write8(GPIO_BASE + 0x14, 1 << 5).unwrap();
+        assert_eq!(processor.read16(GPIO_BASE + 0x14).unwrap(), 1 << 5);
+    }
+
+    #[test]
+    fn test_gpio_input_levels() {
+        // Arrange
+        let (mut processor, port) = gpio_test_processor();
+        processor.write32(GPIO_BASE + 0x14, 0xffff).unwrap();
+
+        // Act
+        port.borrow_mut().set_input(1 << 0 | 1 << 2);
+
+        // Assert
+        assert_eq!(processor.read32(GPIO_BASE + 0x10).unwrap(), 0b10_0101);
+        assert_eq!(
+            port.borrow().pins()[..6],
+            [true, false, true, false, false, true]
+        );
+    }
+}
@@ -2,6 +2,8 @@
 //! Cortex Instruction Trace Macrocell simulation
 //!
 
+use crate::core::bits::Bits;
+use crate::peripheral::scb::DEMCR_TRCENA;
 use crate::Processor;
 
 ///
@@ -36,10 +38,29 @@ pub trait InstrumentationTraceMacrocell {
     /// Value of 0xC5ACCE55 unlocks the access to debug registers.
     ///
     fn itm_write_lar_u32(&mut self, value: u32);
+
+    ///
+    /// write value to TER register, stimulus port enable bits
+    ///
+    fn itm_write_ter(&mut self, value: u32);
+
+    ///
+    /// write value to TPR register, unprivileged access bits of ports
+    ///
+    fn itm_write_tpr(&mut self, value: u32);
+
+    ///
+    /// write value to TCR register, trace control
+    ///
+    fn itm_write_tcr(&mut self, value: u32);
 }
 
+/// `ITM_TCR.ITMENA`, enable the ITM
+pub const ITM_TCR_ITMENA: u32 = 1;
+
 trait InstrumentationTraceMacrocellHelper {
     fn write_itm_packet(&mut self, packet: Vec<u8>);
+    fn write_stim(&mut self, port: u8, payload: &[u8]);
 }
 
 fn make_header(port: u8, payload_size: usize) -> u8 {
@@ -74,6 +95,23 @@ impl InstrumentationTraceMacrocellHelper for Processor {
             f.flush().unwrap();
         }
     }
+
+    fn write_stim(&mut self, port: u8, payload: &[u8]) {
+        // writes to disabled ports are ignored
+        if self.demcr & DEMCR_TRCENA == 0
+            || self.itm_tcr & ITM_TCR_ITMENA == 0
+            || !self.itm_ter.get_bit(usize::from(port))
+        {
+            return;
+        }
+
+        self.write_itm_packet(make_instrumentation_packet(port, payload));
+        if port == 0 {
+            if let Some(console) = &mut self.itm_console {
+                let _ = console.write_stdout(payload);
+            }
+        }
+    }
 }
 
 impl InstrumentationTraceMacrocell for Processor {
@@ -89,18 +127,90 @@ impl InstrumentationTraceMacrocell for Processor {
             ((value & 0xff_0000) >> 16) as u8,
             ((value & 0xff00_0000) >> 24) as u8,
         ];
-        self.write_itm_packet(make_instrumentation_packet(port, &payload));
+        self.write_stim(port, &payload);
     }
 
     fn itm_write_lar_u32(&mut self, _value: u32) {}
 
     fn write_stim_u16(&mut self, port: u8, value: u16) {
         let payload: [u8; 2] = [(value & 0xff) as u8, ((value & 0xff00) >> 8) as u8];
-        self.write_itm_packet(make_instrumentation_packet(port, &payload));
+        self.write_stim(port, &payload);
     }
 
     fn write_stim_u8(&mut self, port: u8, value: u8) {
         let payload: [u8; 1] = [value];
-        self.write_itm_packet(make_instrumentation_packet(port, &payload));
+        self.write_stim(port, &payload);
+    }
+
+    fn itm_write_ter(&mut self, value: u32) {
+        self.itm_ter = value;
+    }
+
+    fn itm_write_tpr(&mut self, value: u32) {
+        self.itm_tpr = value.get_bits(0..4);
+    }
+
+    fn itm_write_tcr(&mut self, value: u32) {
+        // BUSY is read only
+        self.itm_tcr = value & 0x007f_0f1f;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::Bus;
+    use crate::semihosting::host_io::CapturingHostIo;
+
+    fn send_chars(processor: &mut Processor, text: &str) {
+        // like CMSIS ITM_SendChar
+        for c in text.bytes() {
+            while processor.read32(0xE000_0000).unwrap() == 0 {}
+            processor.write8(0xE000_0000, c).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_stimulus_port_0_output() {
+        // Arrange
+        let trace = CapturingHostIo::default();
+        let console = CapturingHostIo::default();
+        let mut processor = Processor::new();
+        processor.itm(Some(Box::new(trace.clone())));
+        processor.itm_console(Some(Box::new(console.clone())));
+
+        // Act
+        send_chars(&mut processor, "hi\n");
+        processor.write16(0xE000_0004, 0x1234).unwrap();
+
+        // Assert
+        assert_eq!(*console.stdout.borrow(), b"hi\n");
+        assert_eq!(
+            *trace.stdout.borrow(),
+            vec![0x01, b'h', 0x01, b'i', 0x01, b'\n', 0x0a, 0x34, 0x12]
+        );
+    }
+
+    #[test]
+    fn test_disabled_stimulus_ports_are_ignored() {
+        // Arrange
+        let console = CapturingHostIo::default();
+        let mut processor = Processor::new();
+        processor.itm_console(Some(Box::new(console.clone())));
+
+        // Act & Assert
+        send_chars(&mut processor, "a");
+        processor.write32(0xE000_EDFC, DEMCR_TRCENA).unwrap();
+        send_chars(&mut processor, "b");
+        processor.write32(0xE000_0E80, ITM_TCR_ITMENA).unwrap();
+        send_chars(&mut processor, "c");
+        processor.write32(0xE000_0E00, 0b10).unwrap();
+        send_chars(&mut processor, "d");
+        processor.write32(0xE000_0E00, 0b01).unwrap();
+        send_chars(&mut processor, "e");
+
+        assert_eq!(*console.stdout.borrow(), b"e");
+        assert_eq!(processor.read32(0xE000_0E80).unwrap(), ITM_TCR_ITMENA);
+        assert_eq!(processor.read32(0xE000_0E00).unwrap(), 0b01);
     }
 }
@@ -0,0 +1,281 @@
+//!
+//! Independent Watchdog Simulation
+//!
+//! Modeled after the STM32 IWDG. The counter runs on the core clock
+//! divided by the prescaler and requests a system reset when it reaches
+//! zero.
+//!
+//! The watchdog is a bus `Peripheral`. Register it with
+//! `Processor::register_peripheral`, usually at `IWDG_BASE`.
+//!
+
+use crate::bus::{Peripheral, PeripheralRequest};
+use crate::core::bits::Bits;
+use crate::core::fault::Fault;
+use crate::peripheral::clock::Clocked;
+
+/// Default base address of the watchdog registers
+pub const IWDG_BASE: u32 = 0x4000_3000;
+
+/// Size of the register block of the watchdog
+pub const IWDG_REGISTERS_SIZE: u32 = 0x10;
+
+/// Reload the counter from `IWDG_RLR`
+pub const IWDG_KEY_RELOAD: u32 = 0xAAAA;
+/// Enable writes to `IWDG_PR` and `IWDG_RLR`
+pub const IWDG_KEY_ACCESS: u32 = 0x5555;
+/// Start the watchdog
+pub const IWDG_KEY_START: u32 = 0xCCCC;
+
+/// Reset value of `IWDG_RLR`
+pub const IWDG_RLR_RESET: u32 = 0xfff;
+
+/// Offset of `IWDG_KR`, the key register
+const IWDG_KR: u32 = 0;
+
+///
+/// State of the independent watchdog
+///
+#[derive(Debug, Clone)]
+pub struct Iwdg {
+    running: bool,
+    key: u32,
+    pr: u32,
+    rlr: u32,
+    counter: u32,
+    prescale_count: u32,
+    reset_requested: bool,
+}
+
+impl Default for Iwdg {
+    fn default() -> Self {
+        Self {
+            running: false,
+            key: 0,
+            pr: 0,
+            rlr: IWDG_RLR_RESET,
+            counter: IWDG_RLR_RESET,
+            prescale_count: 0,
+            reset_requested: false,
+        }
+    }
+}
+
+impl Iwdg {
+    ///
+    /// Watchdog in its reset state, stopped
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// write to `IWDG_KR`, key register
+    ///
+    fn write_kr(&mut self, value: u32) {
+        // any key other than IWDG_KEY_ACCESS write protects PR and RLR again
+        self.key = value & 0xffff;
+        match self.key {
+            IWDG_KEY_RELOAD => self.counter = self.rlr,
+            IWDG_KEY_START if !self.running => {
+                self.running = true;
+                self.counter = self.rlr;
+                self.prescale_count = 0;
+            }
+            _ => {}
+        }
+    }
+
+    fn read(&self, offset: u32) -> u32 {
+        match offset {
+            4 => self.pr,
+            8 => self.rlr,
+            // prescaler and reload updates take effect immediately, SR
+            // stays zero. The key register is write only.
+            _ => 0,
+        }
+    }
+
+    fn write(&mut self, offset: u32, value: u32) {
+        match offset {
+            IWDG_KR => self.write_kr(value),
+            4 if self.key == IWDG_KEY_ACCESS => self.pr = value.get_bits(0..3),
+            8 if self.key == IWDG_KEY_ACCESS => self.rlr = value.get_bits(0..12),
+            _ => {}
+        }
+    }
+
+    ///
+    /// Write the `width` bit wide lane at `offset` of a register
+    ///
+    fn write_lane(&mut self, offset: u32, value: u32, width: u32) {
+        let register = offset & !3;
+        let shift = (offset & 3) * 8;
+        let mask = (u32::MAX >> (32 - width)) << shift;
+        let old = self.read(register);
+        self.write(register, (old & !mask) | ((value << shift) & mask));
+    }
+}
+
+impl Clocked for Iwdg {
+    fn tick(&mut self, cycles: u64) {
+        if !self.running {
+            return;
+        }
+
+        // divider is 4 << PR, saturating at 256
+        let divider = 4 << u64::from(self.pr.min(6));
+
+        let count = u64::from(self.prescale_count) + cycles;
+        self.prescale_count = (count % divider) as u32;
+
+        // the counter expires one period after reaching zero
+        let periods = count / divider;
+        if periods > u64::from(self.counter) {
+            self.counter = 0;
+            self.reset_requested = true;
+        } else {
+            self.counter -= periods as u32;
+        }
+    }
+}
+
+impl Peripheral for Iwdg {
+    fn read32(&mut self, offset: u32) -> Result<u32, Fault> {
+        Ok(self.read(offset))
+    }
+
+    fn read16(&self, offset: u32) -> Result<u16, Fault> {
+        Ok((self.read(offset & !3) >> ((offset & 3) * 8)) as u16)
+    }
+
+    fn read8(&self, offset: u32) -> Result<u8, Fault> {
+        Ok((self.read(offset & !3) >> ((offset & 3) * 8)) as u8)
+    }
+
+    fn write32(&mut self, offset: u32, value: u32) -> Result<(), Fault> {
+        self.write(offset, value);
+        Ok(())
+    }
+
+    fn write16(&mut self, offset: u32, value: u16) -> Result<(), Fault> {
+        self.write_lane(offset, u32::from(value), 16);
+        Ok(())
+    }
+
+    fn write8(&mut self, offset: u32, value: u8) -> Result<(), Fault> {
+        self.write_lane(offset, u32::from(value), 8);
+        Ok(())
+    }
+
+    fn tick(&mut self, cycles: u64) {
+        Clocked::tick(self, cycles);
+    }
+
+    fn take_request(&mut self) -> Option<PeripheralRequest> {
+        if self.reset_requested {
+            self.reset_requested = false;
+            Some(PeripheralRequest::SystemReset)
+        } else {
+            None
+        }
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::Bus;
+    use crate::core::executor::Executor;
+    use crate::core::register::{BaseReg, Reg};
+    use crate::core::reset::Reset;
+    use crate::test_fixture::handler_test_processor;
+    use crate::Processor;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn watchdog_test_processor() -> (Processor, Rc<RefCell<Iwdg>>) {
+        let iwdg = Rc::new(RefCell::new(Iwdg::new()));
+        let mut processor = handler_test_processor(
+            &[0xbf00, 0xe7fd], // nop, b 0x100
+            &[],
+            &[],
+            0x2000_1000,
+        );
+        processor.register_peripheral(
+            IWDG_BASE..IWDG_BASE + IWDG_REGISTERS_SIZE,
+            Box::new(iwdg.clone()),
+        );
+        processor.reset().unwrap();
+
+        // timeout after (1 + 1) * 4 cycles
+        processor.write32(IWDG_BASE, IWDG_KEY_ACCESS).unwrap();
+        processor.write32(IWDG_BASE + 4, 0).unwrap();
+        processor.write32(IWDG_BASE + 8, 1).unwrap();
+        processor.write32(IWDG_BASE, IWDG_KEY_START).unwrap();
+        (processor, iwdg)
+    }
+
+    #[test]
+    fn test_iwdg_registers_are_write_protected() {
+        // Arrange
+        let mut iwdg = Iwdg::new();
+
+        // Act
+        iwdg.write32(8, 0x10).unwrap();
+        iwdg.write32(IWDG_KR, IWDG_KEY_ACCESS).unwrap();
+        iwdg.write32(4, 0xff).unwrap();
+        iwdg.write8(9, 0x3).unwrap();
+        iwdg.write32(IWDG_KR, IWDG_KEY_RELOAD).unwrap();
+        iwdg.write32(8, 0x20).unwrap();
+
+        // Assert
+        assert_eq!(iwdg.read32(4).unwrap(), 0b111);
+        assert_eq!(iwdg.read32(8).unwrap(), 0x3ff);
+    }
+
+    #[test]
+    fn test_iwdg_timeout_resets() {
+        // Arrange
+        let (mut processor, iwdg) = watchdog_test_processor();
+        processor.set_r(Reg::R0, 42);
+
+        // Act
+        processor.tick(7);
+
+        // Assert
+        assert_eq!(processor.get_r(Reg::R0), 42);
+
+        // Act
+        processor.step_instruction();
+
+        // Assert
+        assert_eq!(processor.get_r(Reg::R0), 0);
+        assert_eq!(processor.get_pc(), 0x100);
+        assert!(!iwdg.borrow().running);
+    }
+
+    #[test]
+    fn test_iwdg_refresh_prevents_reset() {
+        // Arrange
+        let (mut processor, iwdg) = watchdog_test_processor();
+        processor.set_r(Reg::R0, 42);
+
+        // Act
+        for _ in 0..10 {
+            processor.step_instruction();
+            processor.step_instruction();
+            processor
+                .write16(IWDG_BASE, IWDG_KEY_RELOAD as u16)
+                .unwrap();
+        }
+
+        // Assert
+        assert_eq!(processor.get_r(Reg::R0), 42);
+        assert!(iwdg.borrow().running);
+    }
+}
@@ -2,8 +2,16 @@
 //! Cortex Core Peripherals simulation
 //!
 
+pub mod clock;
 pub mod dwt;
+pub mod gpio;
 pub mod itm;
+pub mod iwdg;
+pub mod mpu;
 pub mod nvic;
+pub mod rng;
 pub mod scb;
 pub mod systick;
+pub mod test_exit;
+pub mod timer;
+pub mod uart;
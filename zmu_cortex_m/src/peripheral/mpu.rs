@@ -0,0 +1,396 @@
+//!
+//! Memory Protection Unit simulation
+//!
+//! Models the ARMv7-M protected memory system MPU with 8 regions. When
+//! enabled, data accesses and instruction fetches are checked against the
+//! region permissions.
+//!
+
+use crate::core::bits::Bits;
+use crate::core::fault::Fault;
+use crate::Processor;
+
+///
+/// Kind of memory access checked by the MPU
+///
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum MpuAccess {
+    /// data read
+    Read,
+    /// data write
+    Write,
+    /// instruction fetch
+    Execute,
+}
+
+///
+/// Register API for the memory protection unit
+///
+pub trait Mpu {
+    ///
+    /// Read `MPU_TYPE`, number of supported regions
+    ///
+    fn mpu_read_type(&self) -> u32;
+
+    ///
+    /// Read `MPU_RBAR` of the region selected by `MPU_RNR`
+    ///
+    fn mpu_read_rbar(&self) -> u32;
+
+    ///
+    /// Read `MPU_RASR` of the region selected by `MPU_RNR`
+    ///
+    fn mpu_read_rasr(&self) -> u32;
+
+    ///
+    /// Write `MPU_CTRL`, control register
+    ///
+    fn mpu_write_ctrl(&mut self, value: u32);
+
+    ///
+    /// Write `MPU_RNR`, region number register
+    ///
+    fn mpu_write_rnr(&mut self, value: u32);
+
+    ///
+    /// Write `MPU_RBAR`, region base address register
+    ///
+    fn mpu_write_rbar(&mut self, value: u32);
+
+    ///
+    /// Write `MPU_RASR`, region attribute and size register
+    ///
+    fn mpu_write_rasr(&mut self, value: u32);
+
+    ///
+    /// Check if an access of `size` bytes at `addr` is permitted, returning
+    /// the memory management fault to raise if it is not.
+    ///
+    fn mpu_check(&self, addr: u32, size: u32, access: MpuAccess) -> Result<(), Fault>;
+}
+
+/// Number of MPU regions
+pub const MPU_REGIONS: usize = 8;
+
+/// `MPU_CTRL.ENABLE`
+pub const MPU_CTRL_ENABLE: u32 = 1;
+/// `MPU_CTRL.HFNMIENA`, keep the MPU enabled for hard fault and NMI handlers
+pub const MPU_CTRL_HFNMIENA: u32 = 1 << 1;
+/// `MPU_CTRL.PRIVDEFENA`, use the default memory map as a background region
+/// for privileged accesses
+pub const MPU_CTRL_PRIVDEFENA: u32 = 1 << 2;
+
+trait MpuHelper {
+    fn mpu_region(&self, addr: u32) -> Option<usize>;
+    fn mpu_permitted(&self, addr: u32, access: MpuAccess) -> bool;
+}
+
+impl MpuHelper for Processor {
+    fn mpu_region(&self, addr: u32) -> Option<usize> {
+        // the highest numbered matching region takes priority
+        (0..MPU_REGIONS).rev().find(|&n| {
+            let rasr = self.mpu_rasr[n];
+            if !rasr.get_bit(0) {
+                return false;
+            }
+
+            // region size is 2^(SIZE + 1) bytes, 32 bytes at minimum
+            let size_bits = (rasr.get_bits(1..6) + 1).max(5);
+            let mask = u32::MAX.checked_shl(size_bits).unwrap_or(0);
+            if addr & mask != self.mpu_rbar[n] & mask {
+                return false;
+            }
+
+            // regions of 256 bytes or more have 8 subregions that can be disabled
+            if size_bits >= 8 {
+                let subregion = ((addr & !mask) >> (size_bits - 3)) as usize;
+                return !rasr.get_bit(8 + subregion);
+            }
+            true
+        })
+    }
+
+    fn mpu_permitted(&self, addr: u32, access: MpuAccess) -> bool {
+        // the private peripheral bus always uses the default memory map
+        if (0xE000_0000..0xE010_0000).contains(&addr) {
+            return true;
+        }
+
+        let privileged = self.current_mode_is_privileged();
+        match self.mpu_region(addr) {
+            Some(n) => {
+                let rasr = self.mpu_rasr[n];
+                let (read, write) = match (rasr.get_bits(24..27), privileged) {
+                    (0b001 | 0b010, true) | (0b011, _) => (true, true),
+                    (0b010, false) | (0b110 | 0b111, _) | (0b101, true) => (true, false),
+                    _ => (false, false),
+                };
+                match access {
+                    MpuAccess::Read => read,
+                    MpuAccess::Write => write,
+                    MpuAccess::Execute => read && !rasr.get_bit(28),
+                }
+            }
+            None => privileged && self.mpu_ctrl & MPU_CTRL_PRIVDEFENA != 0,
+        }
+    }
+}
+
+impl Mpu for Processor {
+    fn mpu_read_type(&self) -> u32 {
+        // DREGION, unified regions
+        (MPU_REGIONS as u32) << 8
+    }
+
+    fn mpu_read_rbar(&self) -> u32 {
+        let n = self.mpu_rnr as usize;
+        (self.mpu_rbar[n] & 0xffff_ffe0) | self.mpu_rnr
+    }
+
+    fn mpu_read_rasr(&self) -> u32 {
+        self.mpu_rasr[self.mpu_rnr as usize]
+    }
+
+    fn mpu_write_ctrl(&mut self, value: u32) {
+        self.mpu_ctrl = value.get_bits(0..3);
+    }
+
+    fn mpu_write_rnr(&mut self, value: u32) {
+        self.mpu_rnr = value.get_bits(0..3);
+    }
+
+    fn mpu_write_rbar(&mut self, value: u32) {
+        // VALID selects the region to update from the REGION field
+        if value.get_bit(4) {
+            self.mpu_rnr = value.get_bits(0..3);
+        }
+        self.mpu_rbar[self.mpu_rnr as usize] = value & 0xffff_ffe0;
+    }
+
+    fn mpu_write_rasr(&mut self, value: u32) {
+        self.mpu_rasr[self.mpu_rnr as usize] = value & 0x173f_ff3f;
+    }
+
+    fn mpu_check(&self, addr: u32, size: u32, access: MpuAccess) -> Result<(), Fault> {
+        if self.mpu_ctrl & MPU_CTRL_ENABLE == 0
+            || (self.execution_priority < 0 && self.mpu_ctrl & MPU_CTRL_HFNMIENA == 0)
+        {
+            return Ok(());
+        }
+
+        // regions are at least 32 bytes and aligned to their size, so an
+        // access can only cross into the region of its last byte
+        let last = addr.wrapping_add(size.max(1) - 1);
+        if self.mpu_permitted(addr, access) && self.mpu_permitted(last, access) {
+            Ok(())
+        } else if access == MpuAccess::Execute {
+            Err(Fault::IAccViol)
+        } else {
+            Err(Fault::DAccViol { address: addr })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::Bus;
+    use crate::core::exception::{Exception, ExceptionHandling};
+    use crate::core::executor::Executor;
+    use crate::core::register::{BaseReg, Ipsr, Reg};
+    use crate::test_fixture::handler_test_processor;
+
+    // AP field values
+    const AP_FULL_ACCESS: u32 = 0b011 << 24;
+    const AP_READ_ONLY: u32 = 0b111 << 24;
+
+    fn rasr(size_bits: u32, attributes: u32) -> u32 {
+        attributes | ((size_bits - 1) << 1) | 1
+    }
+
+    fn mpu_test_processor(program: &[u16]) -> Processor {
+        // HardFault and MemManage handlers at 0x200
+        let mut processor = handler_test_processor(program, &[0xe7fe], &[3, 4], 0x2000_1000);
+
+        // region 0: flash, read only and executable
+        processor.write32(0xE000_ED9C, 0x0000_0010).unwrap();
+        processor
+            .write32(0xE000_EDA0, rasr(12, AP_READ_ONLY))
+            .unwrap();
+        // region 1: the first 8 kilobytes of ram, full access but not executable
+        processor.write32(0xE000_ED9C, 0x2000_0011).unwrap();
+        processor
+            .write32(0xE000_EDA0, rasr(13, AP_FULL_ACCESS | 1 << 28))
+            .unwrap();
+        processor.write32(0xE000_ED94, MPU_CTRL_ENABLE).unwrap();
+        processor
+    }
+
+    #[test]
+    fn test_mpu_region_registers() {
+        // Arrange
+        let mut processor = Processor::new();
+
+        // Act
+        processor.write32(0xE000_ED9C, 0x2000_0013).unwrap();
+        processor
+            .write32(0xE000_EDA0, rasr(10, AP_READ_ONLY))
+            .unwrap();
+
+        // Assert
+        assert_eq!(processor.read32(0xE000_ED90).unwrap(), 0x800);
+        assert_eq!(processor.read32(0xE000_ED98).unwrap(), 3);
+        assert_eq!(processor.read32(0xE000_ED9C).unwrap(), 0x2000_0003);
+        assert_eq!(
+            processor.read32(0xE000_EDA0).unwrap(),
+            rasr(10, AP_READ_ONLY)
+        );
+    }
+
+    #[test]
+    fn test_mpu_permissions() {
+        // Arrange
+        let processor = mpu_test_processor(&[]);
+
+        // Act & Assert
+        assert_eq!(processor.mpu_check(0x100, 2, MpuAccess::Execute), Ok(()));
+        assert_eq!(processor.mpu_check(0x100, 4, MpuAccess::Read), Ok(()));
+        assert_eq!(
+            processor.mpu_check(0x100, 4, MpuAccess::Write),
+            Err(Fault::DAccViol { address: 0x100 })
+        );
+        assert_eq!(
+            processor.mpu_check(0x2000_1ffc, 4, MpuAccess::Write),
+            Ok(())
+        );
+        assert_eq!(
+            processor.mpu_check(0x2000_0000, 2, MpuAccess::Execute),
+            Err(Fault::IAccViol)
+        );
+        assert_eq!(
+            processor.mpu_check(0x2000_2000, 4, MpuAccess::Read),
+            Err(Fault::DAccViol {
+                address: 0x2000_2000
+            })
+        );
+    }
+
+    #[test]
+    fn test_mpu_checks_whole_access() {
+        // Arrange
+        let processor = mpu_test_processor(&[]);
+
+        // Act & Assert: the last bytes of the word are past the ram region
+        assert_eq!(processor.mpu_check(0x2000_1ffe, 2, MpuAccess::Read), Ok(()));
+        assert_eq!(
+            processor.mpu_check(0x2000_1ffe, 4, MpuAccess::Read),
+            Err(Fault::DAccViol {
+                address: 0x2000_1ffe
+            })
+        );
+        assert_eq!(processor.mpu_check(0x0ffe, 2, MpuAccess::Execute), Ok(()));
+        assert_eq!(
+            processor.mpu_check(0x0ffe, 4, MpuAccess::Execute),
+            Err(Fault::IAccViol)
+        );
+    }
+
+    #[test]
+    fn test_step_checks_execute_permission() {
+        // Arrange
+        let code = [
+            0x4801, // ldr r0, [pc, #4]
+            0x4700, // bx r0
+            0xbf00, // nop
+            0xbf00, // nop
+            0x0001, 0x2000, // .word 0x20000001
+        ];
+        let mut processor = mpu_test_processor(&code);
+        processor.write16(0x2000_0000, 0xbf00).unwrap(); // nop
+        processor.cache_instructions();
+
+        // Act
+        for _ in 0..3 {
+            processor.step();
+        }
+
+        // Assert
+        assert_eq!(processor.get_pc(), 0x200);
+        #[cfg(any(armv7m, armv7em))]
+        assert_eq!(processor.cfsr.get_bit(0), true); // IACCVIOL
+    }
+
+    #[cfg(any(armv7m, armv7em))]
+    #[test]
+    fn test_stacking_into_read_only_region_is_mstkerr() {
+        // Arrange
+        let mut processor = mpu_test_processor(&[0xbf00]);
+        processor.write32(0xE000_ED24, 1 << 16).unwrap(); // MEMFAULTENA
+        processor.set_msp(0x200);
+
+        // Act
+        processor.set_exception_pending(Exception::HardFault);
+        processor.step_instruction();
+
+        // Assert
+        assert_eq!(processor.lockup(), None);
+        assert_eq!(processor.psr.get_isr_number(), 3);
+        assert_eq!(
+            processor.exception_pending(Exception::MemoryManagementFault),
+            true
+        );
+        assert_eq!(processor.cfsr.get_bits(0..8), 0b0001_0000); // MSTKERR
+    }
+
+    #[test]
+    fn test_access_outside_regions_faults() {
+        // Arrange
+        let code = [
+            0x4901, // ldr r1, [pc, #4]
+            0x6808, // ldr r0, [r1]
+            0xe7fe, // b .
+            0xbf00, // nop
+            0x3000, 0x2000, // .word 0x20003000
+        ];
+        let mut processor = mpu_test_processor(&code);
+
+        // Act
+        processor.step_instruction();
+        processor.step_instruction();
+
+        // Assert
+        assert_eq!(processor.get_r(Reg::R1), 0x2000_3000);
+        assert_eq!(processor.get_pc(), 0x200);
+        #[cfg(any(armv7m, armv7em))]
+        {
+            assert_eq!(processor.psr.get_isr_number(), 3); // escalated to HardFault
+            assert_eq!(processor.mmfar, 0x2000_3000);
+            assert_eq!(processor.cfsr.get_bits(0..8), 0b1000_0010); // MMARVALID, DACCVIOL
+        }
+    }
+
+    #[test]
+    fn test_write_to_read_only_region_faults() {
+        // Arrange
+        let code = [
+            0x2100, // movs r1, #0
+            0x6808, // ldr r0, [r1]
+            0x6008, // str r0, [r1]
+            0xe7fe, // b .
+        ];
+        let mut processor = mpu_test_processor(&code);
+        #[cfg(any(armv7m, armv7em))]
+        processor.write32(0xE000_ED24, 1 << 16).unwrap(); // MEMFAULTENA
+
+        // Act
+        for _ in 0..3 {
+            processor.step_instruction();
+        }
+
+        // Assert
+        assert_eq!(processor.get_r(Reg::R0), 0x2000_1000);
+        assert_eq!(processor.get_pc(), 0x200);
+        #[cfg(any(armv7m, armv7em))]
+        assert_eq!(processor.psr.get_isr_number(), 4);
+    }
+}
@@ -0,0 +1,254 @@
+//!
+//! Random Number Generator Simulation
+//!
+//! Modeled after the STM32 RNG. The numbers come from a seeded xorshift32
+//! generator instead of an entropy source, so that firmware polling the
+//! RNG runs the same way on every simulation.
+//!
+//! The generator is a bus `Peripheral`. Register it with
+//! `Processor::register_peripheral` at `RNG_BASE`. A system reset
+//! restarts the sequence from the seed.
+//!
+
+use crate::bus::Peripheral;
+use crate::core::bits::Bits;
+use crate::core::fault::Fault;
+use std::cell::Cell;
+
+/// Base address of the RNG registers
+pub const RNG_BASE: u32 = 0x5006_0800;
+
+/// Size of the register block of the RNG
+pub const RNG_REGISTERS_SIZE: u32 = 0xc;
+
+/// `RNG_CR.RNGEN`, generator enable
+pub const RNG_CR_RNGEN: u32 = 1 << 2;
+/// `RNG_CR.IE`, interrupt enable. Stored only, no interrupt is raised.
+pub const RNG_CR_IE: u32 = 1 << 3;
+
+/// `RNG_SR.DRDY`, data ready
+pub const RNG_SR_DRDY: u32 = 1;
+
+/// Clock cycles to generate a number after enabling or reading `RNG_DR`
+pub const RNG_GENERATION_CYCLES: u32 = 40;
+
+///
+/// Next state of the xorshift32 generator
+///
+pub fn xorshift32(state: u32) -> u32 {
+    let mut x = state;
+    x ^= x << 13;
+    x ^= x >> 17;
+    x ^= x << 5;
+    x
+}
+
+///
+/// Initial generator state for `seed`, xorshift32 gets stuck at zero
+///
+pub fn rng_initial_state(seed: u32) -> u32 {
+    if seed == 0 {
+        1
+    } else {
+        seed
+    }
+}
+
+///
+/// State of the random number generator
+///
+#[derive(Debug, Clone)]
+pub struct Rng {
+    seed: u32,
+    state: u32,
+    cr: u32,
+    dr: u32,
+    // reading `RNG_DR` consumes the number, also through a shared reference
+    sr: Cell<u32>,
+    countdown: Cell<u32>,
+}
+
+impl Rng {
+    ///
+    /// Generator in its reset state, generating numbers from `seed`. The
+    /// same seed yields the same sequence on every run.
+    ///
+    pub fn new(seed: u32) -> Self {
+        Self {
+            seed,
+            state: rng_initial_state(seed),
+            cr: 0,
+            dr: 0,
+            sr: Cell::new(0),
+            countdown: Cell::new(0),
+        }
+    }
+
+    fn write_cr(&mut self, value: u32) {
+        let was_enabled = self.cr.get_bit(2);
+        self.cr = value & (RNG_CR_RNGEN | RNG_CR_IE);
+
+        if !self.cr.get_bit(2) {
+            self.sr.set(0);
+        } else if !was_enabled && self.sr.get() & RNG_SR_DRDY == 0 {
+            self.countdown.set(RNG_GENERATION_CYCLES);
+        }
+    }
+
+    fn read_dr(&self) -> u32 {
+        if self.sr.get() & RNG_SR_DRDY != 0 {
+            self.sr.set(0);
+            self.countdown.set(RNG_GENERATION_CYCLES);
+        }
+        self.dr
+    }
+
+    fn read(&self, offset: u32) -> u32 {
+        match offset {
+            0 => self.cr,
+            // the seeded generator never reports clock or seed errors
+            4 => self.sr.get(),
+            _ => self.read_dr(),
+        }
+    }
+
+    ///
+    /// Write the `width` bit wide lane at `offset` of a register
+    ///
+    fn write_lane(&mut self, offset: u32, value: u32, width: u32) {
+        // only the control register is writable
+        if offset & !3 == 0 {
+            let shift = (offset & 3) * 8;
+            let mask = (u32::MAX >> (32 - width)) << shift;
+            self.write_cr((self.cr & !mask) | ((value << shift) & mask));
+        }
+    }
+}
+
+impl Peripheral for Rng {
+    fn read32(&mut self, offset: u32) -> Result<u32, Fault> {
+        Ok(self.read(offset))
+    }
+
+    fn read16(&self, offset: u32) -> Result<u16, Fault> {
+        Ok((self.read(offset & !3) >> ((offset & 3) * 8)) as u16)
+    }
+
+    fn read8(&self, offset: u32) -> Result<u8, Fault> {
+        Ok((self.read(offset & !3) >> ((offset & 3) * 8)) as u8)
+    }
+
+    fn write32(&mut self, offset: u32, value: u32) -> Result<(), Fault> {
+        self.write_lane(offset, value, 32);
+        Ok(())
+    }
+
+    fn write16(&mut self, offset: u32, value: u16) -> Result<(), Fault> {
+        self.write_lane(offset, u32::from(value), 16);
+        Ok(())
+    }
+
+    fn write8(&mut self, offset: u32, value: u8) -> Result<(), Fault> {
+        self.write_lane(offset, u32::from(value), 8);
+        Ok(())
+    }
+
+    fn tick(&mut self, cycles: u64) {
+        if !self.cr.get_bit(2) || self.sr.get() & RNG_SR_DRDY != 0 {
+            return;
+        }
+
+        let cycles = cycles.min(u64::from(u32::MAX)) as u32;
+        let countdown = self.countdown.get().saturating_sub(cycles);
+        self.countdown.set(countdown);
+        if countdown == 0 {
+            self.state = xorshift32(self.state);
+            self.dr = self.state;
+            self.sr.set(RNG_SR_DRDY);
+        }
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.seed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::Bus;
+    use crate::core::reset::Reset;
+    use crate::peripheral::clock::Clocked;
+    use crate::Processor;
+
+    fn rng_test_processor() -> Processor {
+        let mut processor = Processor::new();
+        processor.register_peripheral(
+            RNG_BASE..RNG_BASE + RNG_REGISTERS_SIZE,
+            Box::new(Rng::new(0x1234_5678)),
+        );
+        processor
+    }
+
+    #[test]
+    fn test_rng_sequence_is_deterministic() {
+        // Arrange
+        let mut processor = rng_test_processor();
+        processor.write32(RNG_BASE, RNG_CR_RNGEN).unwrap();
+
+        // Act
+        let not_ready = processor.read32(RNG_BASE + 4).unwrap();
+        processor.tick(u64::from(RNG_GENERATION_CYCLES));
+        let ready = processor.read32(RNG_BASE + 4).unwrap();
+        let first = processor.read32(RNG_BASE + 8).unwrap();
+        let consumed = processor.read32(RNG_BASE + 4).unwrap();
+        processor.tick(u64::from(RNG_GENERATION_CYCLES));
+        let second = processor.read32(RNG_BASE + 8).unwrap();
+
+        // Assert
+        assert_eq!(not_ready & RNG_SR_DRDY, 0);
+        assert_eq!(ready & RNG_SR_DRDY, RNG_SR_DRDY);
+        assert_eq!(consumed & RNG_SR_DRDY, 0);
+        assert_eq!(first, 0x8798_5aa5);
+        assert_eq!(second, 0x155b_24a3);
+        assert_eq!(first, xorshift32(0x1234_5678));
+        assert_eq!(second, xorshift32(first));
+    }
+
+    #[test]
+    fn test_rng_sub_word_access() {
+        // Arrange
+        let mut processor = rng_test_processor();
+
+        // Act
+        processor.write8(RNG_BASE, RNG_CR_RNGEN as u8).unwrap();
+        processor.write16(RNG_BASE + 2, 0).unwrap();
+        processor.tick(u64::from(RNG_GENERATION_CYCLES));
+        let ready = processor.read16(RNG_BASE + 4).unwrap();
+        let low_byte = processor.read8(RNG_BASE + 8).unwrap();
+        let consumed = processor.read8(RNG_BASE + 4).unwrap();
+
+        // Assert
+        assert_eq!(processor.read32(RNG_BASE).unwrap(), RNG_CR_RNGEN);
+        assert_eq!(u32::from(ready), RNG_SR_DRDY);
+        assert_eq!(low_byte, 0xa5);
+        assert_eq!(consumed, 0);
+    }
+
+    #[test]
+    fn test_rng_restarts_sequence_on_system_reset() {
+        // Arrange
+        let mut processor = rng_test_processor();
+        processor.write32(RNG_BASE, RNG_CR_RNGEN).unwrap();
+        processor.tick(u64::from(RNG_GENERATION_CYCLES));
+        let first = processor.read32(RNG_BASE + 8).unwrap();
+
+        // Act
+        processor.system_reset().unwrap();
+        processor.write32(RNG_BASE, RNG_CR_RNGEN).unwrap();
+        processor.tick(u64::from(RNG_GENERATION_CYCLES));
+
+        // Assert
+        assert_eq!(processor.read32(RNG_BASE + 8).unwrap(), first);
+    }
+}
@@ -2,9 +2,14 @@
 //! Cortex System Control Block Simulation
 //!
 
+use crate::bus::Endianness;
 use crate::core::bits::Bits;
 use crate::core::exception::Exception;
 use crate::core::exception::ExceptionHandling;
+use crate::core::fault::Fault;
+use crate::core::reset::Reset;
+#[cfg(any(armv7m, armv7em))]
+use crate::peripheral::nvic::NVIC;
 use crate::Processor;
 
 use crate::core::register::Ipsr;
@@ -23,6 +28,11 @@ pub trait SystemControlBlock {
     ///
     fn write_icsr(&mut self, value: u32);
 
+    ///
+    /// Write Application Interrupt and Reset Control Register
+    ///
+    fn write_aircr(&mut self, value: u32);
+
     ///
     /// Write Vector Table Offset
     ///
@@ -141,6 +151,36 @@ pub trait SystemControlBlock {
     ///
     fn write_scr(&mut self, value: u32);
 
+    ///
+    /// Write Configuration and Control Register
+    ///
+    fn write_ccr(&mut self, value: u32);
+
+    ///
+    /// Write System Handler Control and State Register
+    ///
+    #[cfg(any(armv7m, armv7em))]
+    fn write_shcsr(&mut self, value: u32);
+
+    ///
+    /// Write Configurable Fault Status Register (write one to clear)
+    ///
+    #[cfg(any(armv7m, armv7em))]
+    fn write_cfsr(&mut self, value: u32);
+
+    ///
+    /// Write Hard Fault Status Register (write one to clear)
+    ///
+    #[cfg(any(armv7m, armv7em))]
+    fn write_hfsr(&mut self, value: u32);
+
+    ///
+    /// Record the cause of a fault to the fault status and address
+    /// registers, before the fault is taken as `exception`.
+    ///
+    #[cfg(any(armv7m, armv7em))]
+    fn set_fault_status(&mut self, fault: Fault, exception: Exception);
+
     ///
     /// Write Debug Exception and Monitor Control Register
     ///
@@ -151,6 +191,23 @@ pub trait SystemControlBlock {
     ///
     fn read_demcr(&self) -> u32;
 
+    ///
+    /// Write Debug Halting Control and Status Register from the bus.
+    /// Software cannot change `C_DEBUGEN`, so the program can only halt or
+    /// step the core after a debugger has enabled halting debug.
+    ///
+    fn write_dhcsr(&mut self, value: u32);
+
+    ///
+    /// Write Debug Halting Control and Status Register from the debug port
+    ///
+    fn debug_write_dhcsr(&mut self, value: u32);
+
+    ///
+    /// Read Debug Halting Control and Status Register
+    ///
+    fn read_dhcsr(&self) -> u32;
+
     ///
     /// Read Vector Table Offset
     ///
@@ -161,6 +218,11 @@ pub trait SystemControlBlock {
     ///
     fn read_scr(&self) -> u32;
 
+    ///
+    /// Read Application Interrupt and Reset Control Register
+    ///
+    fn read_aircr(&self) -> u32;
+
     ///
     /// Write "Software Triggered Interrupt Register"
     ///
@@ -168,6 +230,40 @@ pub trait SystemControlBlock {
     fn write_stir(&mut self, value: u32);
 }
 
+/// `DEMCR.MON_EN`, enable the debug monitor exception
+pub const DEMCR_MON_EN: u32 = 1 << 16;
+/// `DEMCR.TRCENA`, enable the DWT and ITM units
+pub const DEMCR_TRCENA: u32 = 1 << 24;
+
+/// `CCR.STKALIGN`, align the exception stack frame to 8 bytes
+pub const CCR_STKALIGN: u32 = 1 << 9;
+
+/// `DHCSR.C_DEBUGEN`, enable halting debug
+pub const DHCSR_C_DEBUGEN: u32 = 1;
+/// `DHCSR.C_HALT`, halt the core
+pub const DHCSR_C_HALT: u32 = 1 << 1;
+/// `DHCSR.C_STEP`, halt again after each instruction
+pub const DHCSR_C_STEP: u32 = 1 << 2;
+/// `DHCSR.S_REGRDY`, core register transfer complete
+pub const DHCSR_S_REGRDY: u32 = 1 << 16;
+/// `DHCSR.S_HALT`, the core is halted
+pub const DHCSR_S_HALT: u32 = 1 << 17;
+/// `DHCSR.S_SLEEP`, the core is sleeping
+pub const DHCSR_S_SLEEP: u32 = 1 << 18;
+/// `DHCSR.S_LOCKUP`, the core is locked up
+pub const DHCSR_S_LOCKUP: u32 = 1 << 19;
+/// `DHCSR.DBGKEY`, writes without the key are ignored
+pub const DHCSR_DBGKEY: u32 = 0xa05f << 16;
+
+// vector catch bits, and the monitor bits on ARMv7-M
+#[cfg(any(armv7m, armv7em))]
+const DEMCR_WRITABLE: u32 = DEMCR_TRCENA | DEMCR_MON_EN | 0x7f1;
+#[cfg(armv6m)]
+const DEMCR_WRITABLE: u32 = DEMCR_TRCENA | 0x401;
+
+const AIRCR_VECTKEY: u32 = 0x05fa;
+const AIRCR_VECTKEYSTAT: u32 = 0xfa05;
+
 impl SystemControlBlock for Processor {
     fn read_icsr(&self) -> u32 {
         let mut value: u32 = 0;
@@ -178,6 +274,10 @@ impl SystemControlBlock for Processor {
             value.set_bits(12..21, usize::from(exception) as u32);
         }
 
+        value.set_bit(26, self.exception_pending(Exception::SysTick)); // PENDSTSET
+        value.set_bit(28, self.exception_pending(Exception::PendSV)); // PENDSVSET
+        value.set_bit(31, self.exception_pending(Exception::NMI)); // NMIPENDSET
+
         value
     }
 
@@ -198,7 +298,8 @@ impl SystemControlBlock for Processor {
     }
 
     fn write_vtor(&mut self, value: u32) {
-        self.vtor = value
+        // TBLOFF: the table is aligned to at least 128 bytes
+        self.vtor = value & 0xffff_ff80;
     }
 
     #[cfg(any(armv7m, armv7em))]
@@ -288,11 +389,114 @@ impl SystemControlBlock for Processor {
         }
     }
 
+    fn write_aircr(&mut self, value: u32) {
+        // writes without the key are ignored
+        if value.get_bits(16..32) != AIRCR_VECTKEY {
+            return;
+        }
+
+        #[cfg(any(armv7m, armv7em))]
+        {
+            self.aircr.set_bits(8..11, value.get_bits(8..11)); // PRIGROUP
+            self.execution_priority = self.get_execution_priority();
+        }
+
+        if value.get_bit(2) {
+            self.request_system_reset(); // SYSRESETREQ
+        }
+    }
+
     fn write_scr(&mut self, value: u32) {
         self.scr = value;
     }
 
-    fn write_demcr(&mut self, _value: u32) {}
+    fn write_ccr(&mut self, value: u32) {
+        self.ccr = value;
+        // ARMv6-M always aligns the stack frame
+        #[cfg(armv6m)]
+        {
+            self.ccr |= CCR_STKALIGN;
+        }
+    }
+
+    #[cfg(any(armv7m, armv7em))]
+    fn write_shcsr(&mut self, value: u32) {
+        // only the fault handler enable bits are writable
+        self.shcsr.set_bits(16..19, value.get_bits(16..19));
+    }
+
+    #[cfg(any(armv7m, armv7em))]
+    fn write_cfsr(&mut self, value: u32) {
+        self.cfsr &= !value;
+    }
+
+    #[cfg(any(armv7m, armv7em))]
+    fn write_hfsr(&mut self, value: u32) {
+        self.hfsr &= !value;
+    }
+
+    #[cfg(any(armv7m, armv7em))]
+    fn set_fault_status(&mut self, fault: Fault, exception: Exception) {
+        match fault {
+            Fault::VectorTable => self.hfsr.set_bit(1, true),
+            Fault::Forced => self.hfsr.set_bit(30, true),
+            // MMFSR
+            Fault::IAccViol => self.cfsr.set_bit(0, true),
+            Fault::DAccViol { address } => {
+                self.cfsr.set_bit(1, true);
+                self.cfsr.set_bit(7, true); // MMARVALID
+                self.mmfar = address;
+            }
+            Fault::Msunskerr => self.cfsr.set_bit(3, true),
+            Fault::Mstkerr => self.cfsr.set_bit(4, true),
+            // BFSR
+            Fault::PreciseErr { address } => {
+                self.cfsr.set_bit(9, true);
+                self.cfsr.set_bit(15, true); // BFARVALID
+                self.bfar = address;
+            }
+            Fault::Stkerr => self.cfsr.set_bit(12, true),
+            // UFSR
+            Fault::UndefInstr => self.cfsr.set_bit(16, true),
+            Fault::Invstate => self.cfsr.set_bit(17, true),
+            Fault::InvPc => self.cfsr.set_bit(18, true),
+            Fault::NoCp => self.cfsr.set_bit(19, true),
+            Fault::StkOf => self.cfsr.set_bit(20, true), // STKOF as on ARMv8-M
+            Fault::Unaligned => self.cfsr.set_bit(24, true),
+            Fault::DivByZero => self.cfsr.set_bit(25, true),
+        }
+
+        if exception == Exception::HardFault && Exception::from(fault) != exception {
+            // HFSR.FORCED: configurable fault escalated to hardfault
+            self.hfsr.set_bit(30, true);
+        }
+    }
+
+    fn write_demcr(&mut self, value: u32) {
+        self.demcr = value & DEMCR_WRITABLE;
+    }
+
+    fn write_dhcsr(&mut self, value: u32) {
+        let debugen = self.dhcsr & DHCSR_C_DEBUGEN;
+        self.debug_write_dhcsr((value & !DHCSR_C_DEBUGEN) | debugen);
+    }
+
+    fn debug_write_dhcsr(&mut self, value: u32) {
+        if value & 0xffff_0000 != DHCSR_DBGKEY {
+            return;
+        }
+
+        // the core halts and steps only while halting debug is enabled
+        if value & DHCSR_C_DEBUGEN == 0 {
+            self.dhcsr = 0;
+            return;
+        }
+
+        if value & DHCSR_C_HALT != 0 && self.dhcsr & DHCSR_C_HALT == 0 {
+            self.dfsr.set_bit(0, true); // HALTED
+        }
+        self.dhcsr = value & (DHCSR_C_DEBUGEN | DHCSR_C_HALT | DHCSR_C_STEP);
+    }
 
     #[cfg(any(armv7m, armv7em))]
     fn read_shpr1(&self) -> u32 {
@@ -382,19 +586,39 @@ impl SystemControlBlock for Processor {
     fn read_scr(&self) -> u32 {
         0
     }
+
+    fn read_aircr(&self) -> u32 {
+        let mut value = (AIRCR_VECTKEYSTAT << 16) | self.aircr;
+        value.set_bit(15, self.endianness == Endianness::Big); // ENDIANNESS
+        value
+    }
     fn read_vtor(&self) -> u32 {
         self.vtor
     }
 
     fn read_demcr(&self) -> u32 {
-        0
+        self.demcr
+    }
+
+    fn read_dhcsr(&self) -> u32 {
+        let mut dhcsr = self.dhcsr | DHCSR_S_REGRDY;
+        if self.dhcsr & DHCSR_C_HALT != 0 {
+            dhcsr |= DHCSR_S_HALT;
+        }
+        if self.state.get_bit(1) {
+            dhcsr |= DHCSR_S_SLEEP;
+        }
+        if self.lockup().is_some() {
+            dhcsr |= DHCSR_S_LOCKUP;
+        }
+        dhcsr
     }
 
     #[cfg(any(armv7m, armv7em))]
     fn write_stir(&mut self, value: u32) {
-        self.set_exception_pending(Exception::Interrupt {
-            n: value.get_bits(0..9) as usize,
-        });
+        // INTID is pended like a write to the NVIC ISPR would
+        let irqn = value.get_bits(0..9) as usize;
+        self.nvic_write_ispr(irqn / 32, 1 << (irqn % 32));
     }
 }
 
@@ -402,8 +626,11 @@ impl SystemControlBlock for Processor {
 #[cfg(any(armv7m, armv7em))]
 mod tests {
     use super::*;
+    use crate::bus::Bus;
     use crate::core::exception::Exception;
     use crate::core::exception::ExceptionHandling;
+    use crate::core::register::BaseReg;
+    use crate::core::reset::Reset;
 
     #[test]
     #[cfg(any(armv7m, armv7em))]
@@ -490,4 +717,75 @@ mod tests {
         assert_eq!(processor.read_shpr3_u16(0), 0x0033);
         assert_eq!(processor.read_shpr3_u16(1), 0x6655);
     }
+
+    #[test]
+    fn test_stir_pends_interrupt_through_nvic() {
+        // Arrange
+        let mut processor = Processor::new();
+        processor.reset().unwrap();
+        processor.set_msp(0x2000_1000);
+        processor.write32(0xE000_ED08, 0x2000_0200).unwrap();
+        processor
+            .write32(0x2000_0200 + (16 + 5) * 4, 0x0000_0501)
+            .unwrap();
+
+        // Act
+        processor.write32(0xE000_EF00, 5).unwrap();
+        processor.check_exceptions();
+
+        // Assert: pending, but not taken while disabled
+        assert_eq!(processor.nvic_read_ispr(0), 1 << 5);
+        assert_eq!(processor.psr.get_isr_number(), 0);
+
+        // Act
+        processor.nvic_write_iser(0, 1 << 5);
+        processor.check_exceptions();
+
+        // Assert
+        assert_eq!(processor.psr.get_isr_number(), 16 + 5);
+        assert_eq!(processor.get_pc(), 0x500);
+        assert_eq!(processor.nvic_read_ispr(0), 0);
+    }
+
+    #[test]
+    fn test_icsr_pend_set_and_clear() {
+        // Arrange
+        let mut processor = Processor::new();
+
+        // Act
+        processor.write_icsr(1 << 28 | 1 << 26);
+
+        // Assert
+        assert_eq!(
+            processor.read_icsr() & (1 << 28 | 1 << 26),
+            1 << 28 | 1 << 26
+        );
+        assert!(processor.exception_pending(Exception::PendSV));
+        assert!(processor.exception_pending(Exception::SysTick));
+
+        // Act
+        processor.write_icsr(1 << 27 | 1 << 25);
+
+        // Assert
+        assert_eq!(processor.read_icsr() & (1 << 28 | 1 << 26), 0);
+        assert!(!processor.exception_pending(Exception::PendSV));
+        assert!(!processor.exception_pending(Exception::SysTick));
+    }
+
+    #[test]
+    #[cfg(any(armv7m, armv7em))]
+    fn test_fault_status_write_one_to_clear() {
+        // Arrange
+        let mut processor = Processor::new();
+        processor.set_fault_status(Fault::DivByZero, Exception::HardFault);
+        processor.set_fault_status(Fault::NoCp, Exception::HardFault);
+
+        // Act
+        processor.write_cfsr(1 << 25);
+        processor.write_hfsr(1 << 30);
+
+        // Assert
+        assert_eq!(processor.cfsr, 1 << 19);
+        assert_eq!(processor.hfsr, 0);
+    }
 }
@@ -0,0 +1,117 @@
+//!
+//! Test Exit Device
+//!
+//! A single write only register that ends the simulation, so that bare
+//! metal test programs can report pass or fail without semihosting. A word
+//! whose upper half matches the configured magic ends the run with the
+//! lower half as the exit code, other writes are ignored.
+//!
+
+use crate::core::bits::Bits;
+use crate::Processor;
+
+///
+/// Placement of the test exit device
+///
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct TestExitConfig {
+    /// address of the exit register
+    address: u32,
+    /// value expected in the upper half of the written word
+    magic: u16,
+}
+
+impl TestExitConfig {
+    ///
+    /// Exit register at `address`, writes must carry `magic` in bits 16..32
+    ///
+    pub fn new(address: u32, magic: u16) -> Self {
+        Self { address, magic }
+    }
+}
+
+///
+/// Register API for the test exit device
+///
+pub trait TestExit {
+    ///
+    /// Write the exit register, `magic << 16 | code` stops the processor
+    ///
+    fn test_exit_write(&mut self, value: u32);
+
+    ///
+    /// `true` if the exit register is mapped at `addr`
+    ///
+    fn test_exit_at(&self, addr: u32) -> bool;
+}
+
+impl TestExit for Processor {
+    fn test_exit_write(&mut self, value: u32) {
+        if let Some(config) = self.test_exit {
+            if value.get_bits(16..32) == u32::from(config.magic) {
+                self.exit_code = Some(value.get_bits(0..16));
+                self.state.set_bit(0, false);
+            }
+        }
+    }
+
+    fn test_exit_at(&self, addr: u32) -> bool {
+        self.test_exit.is_some_and(|config| config.address == addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::Bus;
+    use crate::core::executor::{Executor, RunExit};
+    use crate::core::register::BaseReg;
+    use crate::test_fixture::handler_test_processor;
+    use std::collections::HashSet;
+
+    const TEST_EXIT: u32 = 0x4000_f000;
+
+    fn test_exit_processor(value: u32) -> Processor {
+        let program = [
+            0x4801, // ldr r0, [pc, #4]
+            0x4902, // ldr r1, [pc, #8]
+            0x6008, // str r0, [r1]
+            0xe7fe, // b .
+            value as u16,
+            (value >> 16) as u16,
+            TEST_EXIT as u16,
+            (TEST_EXIT >> 16) as u16,
+        ];
+        let mut processor = handler_test_processor(&program, &[], &[], 0x2000_1000);
+        processor.test_exit(Some(TestExitConfig::new(TEST_EXIT, 0x5a5a)));
+        processor
+    }
+
+    #[test]
+    fn test_exit_device_stops_run() {
+        // Arrange
+        let mut processor = test_exit_processor(0x5a5a_0007);
+
+        // Act
+        let exit = processor.run(1000, &HashSet::new());
+
+        // Assert
+        assert_eq!(exit, RunExit::Exited { code: 7 });
+        assert_eq!(processor.exit_code(), Some(7));
+        assert_eq!(processor.get_pc(), 0x106);
+    }
+
+    #[test]
+    fn test_exit_device_ignores_other_values() {
+        // Arrange
+        let mut processor = test_exit_processor(0x0000_0007);
+
+        // Act
+        let exit = processor.run(1000, &HashSet::new());
+
+        // Assert
+        assert_eq!(exit, RunExit::CycleBudget);
+        assert_eq!(processor.exit_code(), None);
+        assert_eq!(processor.read32(TEST_EXIT).unwrap(), 0);
+    }
+}
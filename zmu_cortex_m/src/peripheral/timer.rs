@@ -0,0 +1,303 @@
+//!
+//! General Purpose Timer Simulation
+//!
+//! Modeled after the up counting mode of the STM32 general purpose timers.
+//! The timer counts core clock cycles divided by the prescaler and raises
+//! an update event when the counter wraps from the auto-reload value. As on
+//! the hardware, the counter is blocked while the auto-reload value is zero.
+//!
+//! The timer is a bus `Peripheral`. Register it with
+//! `Processor::register_peripheral` at the base address of the timer.
+//!
+
+use crate::bus::{Peripheral, PeripheralRequest};
+use crate::core::bits::Bits;
+use crate::core::fault::Fault;
+
+/// Size of the register block of the timer
+pub const TIMER_REGISTERS_SIZE: u32 = 0x30;
+
+/// `TIMx_CR1.CEN`, counter enable
+pub const TIMER_CR1_CEN: u32 = 1;
+/// `TIMx_CR1.URS`, only counter overflow raises update events
+pub const TIMER_CR1_URS: u32 = 1 << 2;
+/// `TIMx_DIER.UIE`, update interrupt enable
+pub const TIMER_DIER_UIE: u32 = 1;
+/// `TIMx_SR.UIF`, update interrupt flag
+pub const TIMER_SR_UIF: u32 = 1;
+/// `TIMx_EGR.UG`, update generation
+pub const TIMER_EGR_UG: u32 = 1;
+
+/// Offset of `TIMx_EGR`, the event generation register
+const TIMER_EGR: u32 = 0x14;
+
+///
+/// State of a general purpose timer
+///
+#[derive(Debug, Clone)]
+pub struct Timer {
+    /// NVIC line pended on update events
+    irq: usize,
+    /// mask of the counter and auto-reload register width
+    mask: u32,
+    cr1: u32,
+    dier: u32,
+    sr: u32,
+    cnt: u32,
+    psc: u32,
+    arr: u32,
+    prescale_count: u32,
+    interrupt_requested: bool,
+}
+
+impl Timer {
+    ///
+    /// Timer in its reset state with a 16 or 32 bit counter that pends
+    /// interrupt `irq`
+    ///
+    pub fn new(irq: usize, counter_bits: u32) -> Self {
+        let mask = if counter_bits >= 32 {
+            u32::MAX
+        } else {
+            (1 << counter_bits) - 1
+        };
+        Self {
+            irq,
+            mask,
+            cr1: 0,
+            dier: 0,
+            sr: 0,
+            cnt: 0,
+            psc: 0,
+            // the auto-reload register resets to the largest count
+            arr: mask,
+            prescale_count: 0,
+            interrupt_requested: false,
+        }
+    }
+
+    fn update_event(&mut self) {
+        self.sr |= TIMER_SR_UIF;
+        if self.dier & TIMER_DIER_UIE != 0 {
+            self.interrupt_requested = true;
+        }
+    }
+
+    fn read(&self, offset: u32) -> u32 {
+        match offset {
+            0x00 => self.cr1,
+            0x0c => self.dier,
+            0x10 => self.sr,
+            0x24 => self.cnt,
+            0x28 => self.psc,
+            0x2c => self.arr,
+            _ => 0,
+        }
+    }
+
+    fn write(&mut self, offset: u32, value: u32) {
+        match offset {
+            0x00 => self.cr1 = value.get_bits(0..10),
+            0x0c => self.dier = value & TIMER_DIER_UIE,
+            // flags are cleared by writing zero
+            0x10 => self.sr &= value,
+            TIMER_EGR if value & TIMER_EGR_UG != 0 => {
+                self.cnt = 0;
+                self.prescale_count = 0;
+                if self.cr1 & TIMER_CR1_URS == 0 {
+                    self.update_event();
+                }
+            }
+            0x24 => self.cnt = value & self.mask,
+            0x28 => self.psc = value.get_bits(0..16),
+            0x2c => self.arr = value & self.mask,
+            _ => {}
+        }
+    }
+
+    ///
+    /// Write the `width` bit wide lane at `offset` of a register
+    ///
+    fn write_lane(&mut self, offset: u32, value: u32, width: u32) {
+        let register = offset & !3;
+        let shift = (offset & 3) * 8;
+        let mask = (u32::MAX >> (32 - width)) << shift;
+
+        // the lanes not written keep their value, also the flags in SR
+        let old = self.read(register);
+        self.write(register, (old & !mask) | ((value << shift) & mask));
+    }
+}
+
+impl Peripheral for Timer {
+    fn read32(&mut self, offset: u32) -> Result<u32, Fault> {
+        Ok(self.read(offset))
+    }
+
+    fn read16(&self, offset: u32) -> Result<u16, Fault> {
+        Ok((self.read(offset & !3) >> ((offset & 3) * 8)) as u16)
+    }
+
+    fn read8(&self, offset: u32) -> Result<u8, Fault> {
+        Ok((self.read(offset & !3) >> ((offset & 3) * 8)) as u8)
+    }
+
+    fn write32(&mut self, offset: u32, value: u32) -> Result<(), Fault> {
+        self.write(offset, value);
+        Ok(())
+    }
+
+    fn write16(&mut self, offset: u32, value: u16) -> Result<(), Fault> {
+        self.write_lane(offset, u32::from(value), 16);
+        Ok(())
+    }
+
+    fn write8(&mut self, offset: u32, value: u8) -> Result<(), Fault> {
+        self.write_lane(offset, u32::from(value), 8);
+        Ok(())
+    }
+
+    fn tick(&mut self, cycles: u64) {
+        if self.cr1 & TIMER_CR1_CEN == 0 || self.arr == 0 {
+            return;
+        }
+
+        // the counter ticks every PSC + 1 cycles
+        let divider = u64::from(self.psc) + 1;
+        let count = u64::from(self.prescale_count) + cycles;
+        self.prescale_count = (count % divider) as u32;
+
+        let mut ticks = count / divider;
+        if ticks == 0 {
+            return;
+        }
+
+        // a counter at or above ARR wraps to zero on the next tick
+        if self.cnt >= self.arr {
+            self.cnt = 0;
+            self.update_event();
+            ticks -= 1;
+        }
+
+        let counter = u64::from(self.cnt) + ticks;
+        if counter > u64::from(self.arr) {
+            self.update_event();
+        }
+        self.cnt = (counter % (u64::from(self.arr) + 1)) as u32;
+    }
+
+    fn take_request(&mut self) -> Option<PeripheralRequest> {
+        if self.interrupt_requested {
+            self.interrupt_requested = false;
+            Some(PeripheralRequest::Interrupt(self.irq))
+        } else {
+            None
+        }
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.irq, self.mask.count_ones());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::Bus;
+    use crate::core::exception::{Exception, ExceptionHandling};
+    use crate::peripheral::clock::Clocked;
+    use crate::Processor;
+
+    const TIM2: u32 = 0x4000_0000;
+
+    fn timer_test_processor(counter_bits: u32) -> Processor {
+        let mut processor = Processor::new();
+        processor.register_peripheral(
+            TIM2..TIM2 + TIMER_REGISTERS_SIZE,
+            Box::new(Timer::new(28, counter_bits)),
+        );
+        processor
+    }
+
+    #[test]
+    fn test_timer_update_interrupt() {
+        // Arrange
+        let mut processor = timer_test_processor(32);
+        processor.write32(0xE000_E100, 1 << 28).unwrap(); // enable IRQ28
+        processor.write32(TIM2 + 0x28, 3).unwrap(); // divide by 4
+        processor.write32(TIM2 + 0x2c, 9).unwrap(); // 10 ticks per period
+        processor.write32(TIM2 + 0x0c, TIMER_DIER_UIE).unwrap();
+        processor.write32(TIM2, TIMER_CR1_CEN).unwrap();
+
+        // Act
+        processor.tick(39);
+
+        // Assert
+        assert_eq!(processor.read32(TIM2 + 0x24).unwrap(), 9);
+        assert_eq!(processor.read32(TIM2 + 0x10).unwrap(), 0);
+        assert!(!processor.exception_pending(Exception::Interrupt { n: 28 }));
+
+        // Act
+        processor.tick(1);
+
+        // Assert
+        assert_eq!(processor.read32(TIM2 + 0x24).unwrap(), 0);
+        assert_eq!(processor.read32(TIM2 + 0x10).unwrap(), TIMER_SR_UIF);
+        assert_eq!(processor.read32(0xE000_E200).unwrap(), 1 << 28);
+        assert!(processor.exception_pending(Exception::Interrupt { n: 28 }));
+
+        // Act: the handler clears the flag
+        processor.write32(TIM2 + 0x10, !TIMER_SR_UIF).unwrap();
+
+        // Assert
+        assert_eq!(processor.read32(TIM2 + 0x10).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_timer_counter_width() {
+        // Arrange
+        let mut processor = timer_test_processor(16);
+
+        // Act
+        processor.write32(TIM2 + 0x2c, 0x1_2345).unwrap();
+
+        // Assert
+        assert_eq!(processor.read32(TIM2 + 0x2c).unwrap(), 0x2345);
+    }
+
+    #[test]
+    fn test_timer_blocked_while_arr_is_zero() {
+        // Arrange
+        let mut processor = timer_test_processor(32);
+        processor.write32(TIM2 + 0x2c, 0).unwrap();
+        processor.write32(TIM2 + 0x0c, TIMER_DIER_UIE).unwrap();
+        processor.write32(TIM2, TIMER_CR1_CEN).unwrap();
+
+        // Act
+        processor.tick(100);
+
+        // Assert
+        assert_eq!(processor.read32(TIM2 + 0x24).unwrap(), 0);
+        assert_eq!(processor.read32(TIM2 + 0x10).unwrap(), 0);
+        assert!(!processor.exception_pending(Exception::Interrupt { n: 28 }));
+    }
+
+    #[test]
+    fn test_timer_sub_word_access() {
+        // Arrange
+        let mut processor = timer_test_processor(16);
+
+        // Act
+        processor.write16(TIM2 + 0x2c, 0x1234).unwrap();
+        processor.write8(TIM2 + 0x28, 7).unwrap();
+        let arr = processor.read16(TIM2 + 0x2c).unwrap();
+        let arr_high_byte = processor.read8(TIM2 + 0x2d).unwrap();
+        processor.write8(TIM2 + 0x2d, 0x56).unwrap();
+
+        // Assert
+        assert_eq!(arr, 0x1234);
+        assert_eq!(arr_high_byte, 0x12);
+        assert_eq!(processor.read32(TIM2 + 0x28).unwrap(), 7);
+        assert_eq!(processor.read32(TIM2 + 0x2c).unwrap(), 0x5634);
+    }
+}
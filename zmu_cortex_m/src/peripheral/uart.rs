@@ -0,0 +1,312 @@
+//!
+//! UART Simulation
+//!
+//! Modeled after the STM32F1 USART1 in asynchronous mode. Transmitted
+//! bytes are written to the console of the host. Received bytes come from
+//! a buffer the host fills with `Uart::input`, so polling the status
+//! register never blocks the simulation.
+//!
+//! The UART is a bus `Peripheral`. Register a shared handle to it with
+//! `Processor::register_peripheral` at `UART_BASE` and keep another to
+//! send it input.
+//!
+
+use crate::bus::Peripheral;
+use crate::core::bits::Bits;
+use crate::core::fault::Fault;
+use crate::semihosting::host_io::HostIo;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+/// Base address of the UART registers
+pub const UART_BASE: u32 = 0x4001_3800;
+
+/// Size of the UART register block
+pub const UART_REGISTERS_SIZE: u32 = 0x1c;
+
+/// Depth of the transmit FIFO
+pub const UART_FIFO_DEPTH: usize = 16;
+
+/// `USART_SR.TXE`, transmit FIFO not full
+pub const UART_SR_TXE: u32 = 1 << 7;
+/// `USART_SR.TC`, transmission complete
+pub const UART_SR_TC: u32 = 1 << 6;
+/// `USART_SR.RXNE`, received data ready to be read
+pub const UART_SR_RXNE: u32 = 1 << 5;
+
+/// `USART_CR1.UE`, UART enable
+pub const UART_CR1_UE: u32 = 1 << 13;
+/// `USART_CR1.TE`, transmitter enable
+pub const UART_CR1_TE: u32 = 1 << 3;
+/// `USART_CR1.RE`, receiver enable
+pub const UART_CR1_RE: u32 = 1 << 2;
+
+/// Offset of `USART_DR`, the data register
+const UART_DR: u32 = 4;
+
+///
+/// State of the UART
+///
+pub struct Uart {
+    cr1: u32,
+    brr: u32,
+    tx_pending: u32,
+    tx_countdown: u32,
+    /// bytes sent by the host, not yet read by the program
+    input: RefCell<VecDeque<u8>>,
+    console: Box<dyn HostIo>,
+}
+
+impl Uart {
+    ///
+    /// UART in its reset state, transmitting to `console`
+    ///
+    pub fn new(console: Box<dyn HostIo>) -> Self {
+        Self {
+            cr1: 0,
+            brr: 0,
+            tx_pending: 0,
+            tx_countdown: 0,
+            input: RefCell::new(VecDeque::new()),
+            console,
+        }
+    }
+
+    ///
+    /// Send bytes to the UART, the program receives them in order
+    ///
+    pub fn input(&mut self, data: &[u8]) {
+        self.input.borrow_mut().extend(data);
+    }
+
+    fn char_cycles(&self) -> u32 {
+        // BRR divides the core clock down to the baud rate, a frame is 10 bits
+        self.brr.max(1) * 10
+    }
+
+    fn receiving(&self) -> bool {
+        self.cr1 & (UART_CR1_UE | UART_CR1_RE) == UART_CR1_UE | UART_CR1_RE
+    }
+
+    ///
+    /// Read `USART_SR`, status register
+    ///
+    /// RXNE is set while the receiver is enabled and host input is queued.
+    ///
+    fn read_sr(&self) -> u32 {
+        let mut sr = 0;
+        sr.set_bit(7, (self.tx_pending as usize) < UART_FIFO_DEPTH);
+        sr.set_bit(6, self.tx_pending == 0);
+        sr.set_bit(5, self.receiving() && !self.input.borrow().is_empty());
+        sr
+    }
+
+    ///
+    /// Read `USART_DR`, the next received byte
+    ///
+    fn read_dr(&self) -> u32 {
+        if !self.receiving() {
+            return 0;
+        }
+        self.input.borrow_mut().pop_front().map_or(0, u32::from)
+    }
+
+    ///
+    /// Write `USART_DR`, queue a byte for transmission
+    ///
+    fn write_dr(&mut self, value: u32) {
+        if self.cr1 & (UART_CR1_UE | UART_CR1_TE) != UART_CR1_UE | UART_CR1_TE
+            || self.tx_pending as usize >= UART_FIFO_DEPTH
+        {
+            return;
+        }
+
+        // the byte reaches the host right away, so that nothing is lost when
+        // the program stops with bytes still queued. Only the status bits
+        // follow the transmission time.
+        let _ = self.console.write_stdout(&[value as u8]);
+        if self.tx_pending == 0 {
+            self.tx_countdown = self.char_cycles();
+        }
+        self.tx_pending += 1;
+    }
+
+    fn read(&self, offset: u32) -> u32 {
+        match offset {
+            0 => self.read_sr(),
+            UART_DR => self.read_dr(),
+            8 => self.brr,
+            0xc => self.cr1,
+            _ => 0,
+        }
+    }
+
+    fn write(&mut self, offset: u32, value: u32) {
+        match offset {
+            UART_DR => self.write_dr(value),
+            8 => self.brr = value.get_bits(0..16),
+            0xc => self.cr1 = value.get_bits(0..14),
+            _ => {}
+        }
+    }
+
+    ///
+    /// Write the `width` bit wide lane at `offset` of a register
+    ///
+    fn write_lane(&mut self, offset: u32, value: u32, width: u32) {
+        let register = offset & !3;
+        let shift = (offset & 3) * 8;
+        let mask = (u32::MAX >> (32 - width)) << shift;
+
+        // reading DR would consume a received byte
+        let old = if register == UART_DR {
+            0
+        } else {
+            self.read(register)
+        };
+        self.write(register, (old & !mask) | ((value << shift) & mask));
+    }
+}
+
+impl Peripheral for Uart {
+    fn read32(&mut self, offset: u32) -> Result<u32, Fault> {
+        Ok(self.read(offset))
+    }
+
+    fn read16(&self, offset: u32) -> Result<u16, Fault> {
+        Ok((self.read(offset & !3) >> ((offset & 3) * 8)) as u16)
+    }
+
+    fn read8(&self, offset: u32) -> Result<u8, Fault> {
+        Ok((self.read(offset & !3) >> ((offset & 3) * 8)) as u8)
+    }
+
+    fn write32(&mut self, offset: u32, value: u32) -> Result<(), Fault> {
+        self.write(offset, value);
+        Ok(())
+    }
+
+    fn write16(&mut self, offset: u32, value: u16) -> Result<(), Fault> {
+        self.write_lane(offset, u32::from(value), 16);
+        Ok(())
+    }
+
+    fn write8(&mut self, offset: u32, value: u8) -> Result<(), Fault> {
+        self.write_lane(offset, u32::from(value), 8);
+        Ok(())
+    }
+
+    fn tick(&mut self, cycles: u64) {
+        let mut cycles = cycles;
+        while self.tx_pending > 0 && cycles >= u64::from(self.tx_countdown) {
+            cycles -= u64::from(self.tx_countdown);
+            self.tx_pending -= 1;
+            self.tx_countdown = self.char_cycles();
+        }
+        if self.tx_pending > 0 {
+            // less than a character time remains
+            self.tx_countdown -= cycles as u32;
+        }
+    }
+
+    fn reset(&mut self) {
+        // bytes sent by the host stay queued
+        self.cr1 = 0;
+        self.brr = 0;
+        self.tx_pending = 0;
+        self.tx_countdown = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::Bus;
+    use crate::peripheral::clock::Clocked;
+    use crate::semihosting::host_io::CapturingHostIo;
+    use crate::Processor;
+    use std::rc::Rc;
+
+    fn uart_test_processor(console: &CapturingHostIo) -> (Processor, Rc<RefCell<Uart>>) {
+        let uart = Rc::new(RefCell::new(Uart::new(Box::new(console.clone()))));
+        let mut processor = Processor::new();
+        processor.register_peripheral(
+            UART_BASE..UART_BASE + UART_REGISTERS_SIZE,
+            Box::new(uart.clone()),
+        );
+        processor.write32(UART_BASE + 8, 4).unwrap(); // 40 cycles per byte
+        processor
+            .write32(UART_BASE + 0xc, UART_CR1_UE | UART_CR1_TE | UART_CR1_RE)
+            .unwrap();
+        (processor, uart)
+    }
+
+    #[test]
+    fn test_uart_transmit() {
+        // Arrange
+        let console = CapturingHostIo::default();
+        let (mut processor, _) = uart_test_processor(&console);
+        let text = "hello over the uart\n";
+
+        // Act
+        let mut polls = 0;
+        for c in text.bytes() {
+            while processor.read32(UART_BASE).unwrap() & UART_SR_TXE == 0 {
+                processor.tick(1);
+                polls += 1;
+            }
+            processor.write32(UART_BASE + 4, u32::from(c)).unwrap();
+        }
+        let busy = processor.read32(UART_BASE).unwrap();
+        processor.tick(40 * UART_FIFO_DEPTH as u64);
+        let idle = processor.read32(UART_BASE).unwrap();
+
+        // Assert
+        assert_eq!(*console.stdout.borrow(), text.as_bytes());
+        assert_eq!(polls, 40 * (text.len() - UART_FIFO_DEPTH));
+        assert_eq!(busy & UART_SR_TC, 0);
+        assert_eq!(idle & (UART_SR_TXE | UART_SR_TC), UART_SR_TXE | UART_SR_TC);
+    }
+
+    #[test]
+    fn test_uart_receive() {
+        // Arrange
+        let console = CapturingHostIo::default();
+        let (mut processor, uart) = uart_test_processor(&console);
+        uart.borrow_mut().input(b"ok");
+
+        // Act
+        let mut received = Vec::new();
+        while processor.read32(UART_BASE).unwrap() & UART_SR_RXNE != 0 {
+            received.push(processor.read32(UART_BASE + 4).unwrap() as u8);
+        }
+
+        // Assert
+        assert_eq!(received, b"ok");
+        assert_eq!(processor.read32(UART_BASE).unwrap() & UART_SR_RXNE, 0);
+    }
+
+    #[test]
+    fn test_uart_sub_word_access() {
+        // Arrange
+        let console = CapturingHostIo::default();
+        let (mut processor, uart) = uart_test_processor(&console);
+        uart.borrow_mut().input(b"z");
+
+        // Act
+        processor.write8(UART_BASE + 4, b'a').unwrap();
+        processor.write16(UART_BASE + 4, u16::from(b'b')).unwrap();
+        processor
+            .write8(UART_BASE + 0xd, (UART_CR1_UE >> 8) as u8)
+            .unwrap();
+        let status = processor.read16(UART_BASE).unwrap();
+        let received = processor.read8(UART_BASE + 4).unwrap();
+        let cr1 = processor.read16(UART_BASE + 0xc).unwrap();
+
+        // Assert
+        assert_eq!(*console.stdout.borrow(), b"ab");
+        assert_ne!(u32::from(status) & UART_SR_RXNE, 0);
+        assert_eq!(received, b'z');
+        assert_eq!(u32::from(cr1), UART_CR1_UE | UART_CR1_TE | UART_CR1_RE);
+    }
+}
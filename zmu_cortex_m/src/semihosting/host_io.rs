@@ -0,0 +1,142 @@
+//!
+//! Host side console I/O used by semihosting handlers
+//!
+
+use std::cell::RefCell;
+use std::io;
+use std::io::{Read, Write};
+use std::rc::Rc;
+
+///
+/// Console streams of the host, as seen by the simulated program.
+///
+/// Semihosting handlers route console traffic through this trait instead of
+/// printing directly, so that the output can be captured or redirected.
+///
+pub trait HostIo {
+    ///
+    /// Write bytes to the standard output of the host.
+    ///
+    fn write_stdout(&mut self, data: &[u8]) -> io::Result<()>;
+
+    ///
+    /// Write bytes to the standard error of the host.
+    ///
+    fn write_stderr(&mut self, data: &[u8]) -> io::Result<()>;
+
+    ///
+    /// Read bytes from the standard input of the host.
+    /// Returns the number of bytes read, zero at end of input.
+    ///
+    fn read_stdin(&mut self, buf: &mut [u8]) -> io::Result<usize>;
+}
+
+///
+/// `HostIo` backed by the process standard streams.
+///
+#[derive(Default, Debug)]
+pub struct StdHostIo;
+
+impl HostIo for StdHostIo {
+    fn write_stdout(&mut self, data: &[u8]) -> io::Result<()> {
+        let mut stdout = io::stdout();
+        stdout.write_all(data)?;
+        stdout.flush()
+    }
+
+    fn write_stderr(&mut self, data: &[u8]) -> io::Result<()> {
+        let mut stderr = io::stderr();
+        stderr.write_all(data)?;
+        stderr.flush()
+    }
+
+    fn read_stdin(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        io::stdin().read(buf)
+    }
+}
+
+///
+/// `HostIo` that keeps the console traffic in memory, so that the output of
+/// a program can be inspected and its input given up front. Clones share the
+/// same buffers.
+///
+#[derive(Default, Debug, Clone)]
+pub struct CapturingHostIo {
+    /// bytes written to the standard output
+    pub stdout: Rc<RefCell<Vec<u8>>>,
+    /// bytes written to the standard error
+    pub stderr: Rc<RefCell<Vec<u8>>>,
+    /// bytes not yet read from the standard input
+    pub stdin: Rc<RefCell<Vec<u8>>>,
+}
+
+impl HostIo for CapturingHostIo {
+    fn write_stdout(&mut self, data: &[u8]) -> io::Result<()> {
+        self.stdout.borrow_mut().extend_from_slice(data);
+        Ok(())
+    }
+
+    fn write_stderr(&mut self, data: &[u8]) -> io::Result<()> {
+        self.stderr.borrow_mut().extend_from_slice(data);
+        Ok(())
+    }
+
+    fn read_stdin(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut stdin = self.stdin.borrow_mut();
+        let count = stdin.len().min(buf.len());
+        buf[..count].copy_from_slice(&stdin[..count]);
+        stdin.drain(..count);
+        Ok(count)
+    }
+}
+
+/// Writes go to the captured standard output
+impl io::Write for CapturingHostIo {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stdout.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::Bus;
+    use crate::core::executor::Executor;
+    use crate::core::register::{BaseReg, Reg};
+    use crate::semihosting::TT_HANDLE_STDOUT;
+    use crate::Processor;
+
+    #[test]
+    fn test_program_output_is_captured() {
+        // Arrange
+        let code = [
+            0x05, 0x20, // movs r0, #5 (SYS_WRITE)
+            0xab, 0xbe, // bkpt 0xab
+            0xfe, 0xe7, // b .
+        ];
+        let host_io = CapturingHostIo::default();
+        let mut core = Processor::new();
+        core.flash_memory(code.len(), &code);
+        core.semihost_console(Some(Box::new(host_io.clone())));
+        core.write32(0x2000_0000, TT_HANDLE_STDOUT).unwrap();
+        core.write32(0x2000_0004, 0x2000_0010).unwrap();
+        core.write32(0x2000_0008, 3).unwrap();
+        core.write32(0x2000_0010, 0x000a_6968).unwrap(); // "hi\n"
+        core.set_r(Reg::R1, 0x2000_0000);
+        core.set_pc(0);
+
+        // Act
+        core.step_instruction();
+        core.step_instruction();
+
+        // Assert
+        assert_eq!(*host_io.stdout.borrow(), b"hi\n");
+        assert!(host_io.stderr.borrow().is_empty());
+        assert_eq!(core.get_r(Reg::R0), 0);
+    }
+}
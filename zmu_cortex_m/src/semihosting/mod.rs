@@ -7,7 +7,11 @@ use crate::core::bits::Bits;
 use crate::core::fault::Fault;
 use crate::core::register::BaseReg;
 use crate::core::register::Reg;
+use crate::semihosting::host_io::HostIo;
 use crate::Processor;
+use std::cmp::min;
+
+pub mod host_io;
 
 #[derive(PartialEq, Debug, Copy, Clone)]
 #[allow(missing_docs)]
@@ -137,6 +141,10 @@ pub enum SemihostingCommand {
         len: u32,
     },
     ///
+    /// Read a character from the debug console
+    ///
+    SysReadc,
+    ///
     /// Trigger an exception
     ///
     SysException {
@@ -160,6 +168,32 @@ pub enum SemihostingCommand {
     /// Get the value of errno
     ///
     SysErrno,
+    ///
+    /// Get the command line used to start the program
+    ///
+    SysGetCmdline,
+}
+
+impl SemihostingCommand {
+    ///
+    /// Exit code requested by an exit command, `None` for other commands.
+    ///
+    /// Application exit reports the given status (0 for `SYS_EXIT` which
+    /// carries no status), any other stop reason is reported as 1.
+    ///
+    pub fn exit_code(&self) -> Option<u32> {
+        match *self {
+            Self::SysException { reason } => match reason {
+                SysExceptionReason::ADPStoppedApplicationExit => Some(0),
+                _ => Some(1),
+            },
+            Self::SysExitExtended { reason, subcode } => match reason {
+                SysExceptionReason::ADPStoppedApplicationExit => Some(subcode),
+                _ => Some(1),
+            },
+            _ => None,
+        }
+    }
 }
 
 #[derive(PartialEq, Debug, Clone)]
@@ -194,14 +228,19 @@ pub enum SemihostingResponse {
     },
     /// syswrite command response
     SysWrite {
-        /// result Ok = bytes written, Err = error code
+        /// result Ok = bytes written, Err = number of bytes not written
         result: Result<u32, i32>,
     },
     /// sysread command response
     SysRead {
-        /// result Ok = data, Err = error code
+        /// result Ok = (memory pointer, data, number of bytes not read), Err = error code
         result: Result<(u32, Vec<u8>, u32), i32>,
     },
+    /// sysreadc command response
+    SysReadc {
+        /// result Ok = character read, Err = error code
+        result: Result<u8, i32>,
+    },
     /// sysexception command response
     SysException {
         /// result
@@ -226,20 +265,58 @@ pub enum SemihostingResponse {
         /// result
         result: u32,
     },
+    /// sysgetcmdline command response
+    SysGetCmdline {
+        /// result Ok = command line, Err = error code
+        result: Result<String, i32>,
+    },
 }
 
 const SYS_OPEN: u32 = 0x01;
 const SYS_CLOSE: u32 = 0x02;
 const SYS_WRITE: u32 = 0x05;
 const SYS_READ: u32 = 0x06;
+const SYS_READC: u32 = 0x07;
 const SYS_ISTTY: u32 = 0x09;
 const SYS_SEEK: u32 = 0x0a;
 const SYS_FLEN: u32 = 0x0c;
 const SYS_CLOCK: u32 = 0x10;
 const SYS_ERRNO: u32 = 0x13;
+const SYS_GET_CMDLINE: u32 = 0x15;
 const SYS_EXIT: u32 = 0x18;
 const SYS_EXIT_EXTENDED: u32 = 0x20;
 
+/// Handle of the console input stream, as opened with `:tt`
+pub const TT_HANDLE_STDIN: u32 = 1;
+/// Handle of the console output stream
+pub const TT_HANDLE_STDOUT: u32 = 2;
+/// Handle of the console error stream
+pub const TT_HANDLE_STDERR: u32 = 3;
+
+///
+/// Write the data of a `SYS_WRITE` command to the console handles to `console`.
+/// Returns `None` for other commands and handles.
+///
+pub fn console_write(
+    console: &mut dyn HostIo,
+    command: &SemihostingCommand,
+) -> Option<SemihostingResponse> {
+    let (handle, data) = match command {
+        SemihostingCommand::SysWrite { handle, data } => (*handle, data),
+        _ => return None,
+    };
+    let written = match handle {
+        TT_HANDLE_STDOUT => console.write_stdout(data),
+        TT_HANDLE_STDERR => console.write_stderr(data),
+        _ => return None,
+    };
+    Some(SemihostingResponse::SysWrite {
+        result: written
+            .map(|()| data.len() as u32)
+            .map_err(|_| data.len() as i32),
+    })
+}
+
 ///
 /// Decode semihosting command based on register values
 ///
@@ -259,12 +336,12 @@ pub fn decode_semihostcmd(
 
             while filename_len > 0 {
                 string_bytes.push(processor.read8(string_ptr)?);
-                string_ptr += 1;
+                string_ptr = string_ptr.wrapping_add(1);
                 filename_len -= 1;
             }
 
             SemihostingCommand::SysOpen {
-                name: String::from_utf8(string_bytes).unwrap(),
+                name: String::from_utf8_lossy(&string_bytes).into_owned(),
                 mode,
             }
         }
@@ -284,7 +361,7 @@ pub fn decode_semihostcmd(
             // :tt console output
             while len > 0 {
                 data.push(processor.read8(memoryptr)?);
-                memoryptr += 1;
+                memoryptr = memoryptr.wrapping_add(1);
                 len -= 1;
             }
             SemihostingCommand::SysWrite { handle, data }
@@ -301,6 +378,7 @@ pub fn decode_semihostcmd(
                 len,
             }
         }
+        SYS_READC => SemihostingCommand::SysReadc,
         SYS_FLEN => {
             let params_ptr = r1;
             let handle = processor.read32(params_ptr)?;
@@ -322,6 +400,7 @@ pub fn decode_semihostcmd(
         }
         SYS_CLOCK => SemihostingCommand::SysClock,
         SYS_ERRNO => SemihostingCommand::SysErrno,
+        SYS_GET_CMDLINE => SemihostingCommand::SysGetCmdline,
         SYS_EXIT_EXTENDED => {
             let params_ptr = r1;
             let reason = SysExceptionReason::from_u32(processor.read32(params_ptr)?);
@@ -379,12 +458,16 @@ pub fn semihost_return(processor: &mut Processor, response: &SemihostingResponse
                 let mut addr = *memoryptr;
                 for x in data {
                     processor.write8(addr, *x);
-                    addr += 1;
+                    addr = addr.wrapping_add(1);
                 }
                 processor.set_r(Reg::R0, *diff);
             }
             Err(error_code) => processor.set_r(Reg::R0, *error_code as u32),
         },
+        SemihostingResponse::SysReadc { result } => match result {
+            Ok(character) => processor.set_r(Reg::R0, u32::from(character)),
+            Err(error_code) => processor.set_r(Reg::R0, error_code as u32),
+        },
         SemihostingResponse::SysClock { result } => match result {
             Ok(centiseconds) => processor.set_r(Reg::R0, centiseconds),
             Err(error_code) => processor.set_r(Reg::R0, error_code as u32),
@@ -392,5 +475,289 @@ pub fn semihost_return(processor: &mut Processor, response: &SemihostingResponse
         SemihostingResponse::SysErrno { result } => {
             processor.set_r(Reg::R0, result);
         }
+        SemihostingResponse::SysGetCmdline { ref result } => match result {
+            Ok(cmdline) => {
+                // the argument block holds the buffer address and size, the
+                // size is updated to the length of the null terminated string
+                let params_ptr = processor.get_r(Reg::R1);
+                if let (Ok(buffer), Ok(size)) = (
+                    processor.read32(params_ptr),
+                    processor.read32(params_ptr.wrapping_add(4)),
+                ) {
+                    if size == 0 {
+                        processor.set_r(Reg::R0, (-1_i32) as u32);
+                        return;
+                    }
+                    let bytes = cmdline.as_bytes();
+                    let len = min(bytes.len(), size as usize - 1);
+                    let mut addr = buffer;
+                    for x in &bytes[..len] {
+                        processor.write8(addr, *x);
+                        addr = addr.wrapping_add(1);
+                    }
+                    processor.write8(addr, 0);
+                    processor.write32(params_ptr.wrapping_add(4), len as u32);
+                    processor.set_r(Reg::R0, 0);
+                } else {
+                    processor.set_r(Reg::R0, (-1_i32) as u32);
+                }
+            }
+            Err(error_code) => processor.set_r(Reg::R0, *error_code as u32),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::executor::Executor;
+    use crate::core::instruction::Instruction;
+    use std::cell::RefCell;
+    use std::io::Read;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_sys_write_stdout() {
+        // Arrange
+        const STDOUT: u32 = 2;
+        let output = Rc::new(RefCell::new(Vec::new()));
+        let stream = output.clone();
+
+        let mut processor = Processor::new();
+        processor.semihost(Some(Box::new(move |cmd: &SemihostingCommand| match cmd {
+            SemihostingCommand::SysWrite { handle, data } if *handle == STDOUT => {
+                stream.borrow_mut().extend_from_slice(data);
+                SemihostingResponse::SysWrite {
+                    result: Ok(data.len() as u32),
+                }
+            }
+            _ => panic!("unexpected semihosting command {:?}", cmd),
+        })));
+
+        let text = b"Hello, world!\n";
+        for (i, byte) in text.iter().enumerate() {
+            processor.write8(0x2000_0100 + i as u32, *byte).unwrap();
+        }
+        processor.write32(0x2000_0000, STDOUT).unwrap();
+        processor.write32(0x2000_0004, 0x2000_0100).unwrap();
+        processor.write32(0x2000_0008, text.len() as u32).unwrap();
+        processor.set_r(Reg::R0, SYS_WRITE);
+        processor.set_r(Reg::R1, 0x2000_0000);
+
+        // Act
+        processor.execute(&Instruction::BKPT { imm32: 0xab }, 2);
+
+        // Assert
+        assert_eq!(&output.borrow()[..], &text[..]);
+        assert_eq!(processor.get_r(Reg::R0), 0);
+    }
+
+    #[test]
+    fn test_sys_write_failure_returns_unwritten_count() {
+        // Arrange
+        let mut processor = Processor::new();
+
+        // Act
+        semihost_return(
+            &mut processor,
+            &SemihostingResponse::SysWrite { result: Err(5) },
+        );
+
+        // Assert
+        assert_eq!(processor.get_r(Reg::R0), 5);
+    }
+
+    #[test]
+    fn test_sys_open_decodes_name_and_mode() {
+        // Arrange
+        let opened = Rc::new(RefCell::new(None));
+        let request = opened.clone();
+
+        let mut processor = Processor::new();
+        processor.semihost(Some(Box::new(move |cmd: &SemihostingCommand| match cmd {
+            SemihostingCommand::SysOpen { name, mode } => {
+                *request.borrow_mut() = Some((name.clone(), *mode));
+                SemihostingResponse::SysOpen { result: Ok(5) }
+            }
+            _ => panic!("unexpected semihosting command {:?}", cmd),
+        })));
+
+        let name = b"out.txt";
+        for (i, byte) in name.iter().enumerate() {
+            processor.write8(0x2000_0100 + i as u32, *byte).unwrap();
+        }
+        processor.write32(0x2000_0000, 0x2000_0100).unwrap();
+        processor.write32(0x2000_0004, 4).unwrap();
+        processor.write32(0x2000_0008, name.len() as u32).unwrap();
+        processor.set_r(Reg::R0, SYS_OPEN);
+        processor.set_r(Reg::R1, 0x2000_0000);
+
+        // Act
+        processor.execute(&Instruction::BKPT { imm32: 0xab }, 2);
+
+        // Assert
+        assert_eq!(*opened.borrow(), Some(("out.txt".to_string(), 4)));
+        assert_eq!(processor.get_r(Reg::R0), 5);
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        assert_eq!(
+            SemihostingCommand::SysException {
+                reason: SysExceptionReason::ADPStoppedApplicationExit
+            }
+            .exit_code(),
+            Some(0)
+        );
+        assert_eq!(
+            SemihostingCommand::SysException {
+                reason: SysExceptionReason::ADPStoppedRunTimeErrorUnknown
+            }
+            .exit_code(),
+            Some(1)
+        );
+        assert_eq!(
+            SemihostingCommand::SysExitExtended {
+                reason: SysExceptionReason::ADPStoppedApplicationExit,
+                subcode: 42
+            }
+            .exit_code(),
+            Some(42)
+        );
+        assert_eq!(SemihostingCommand::SysClock.exit_code(), None);
+    }
+
+    #[test]
+    fn test_sys_exit_reports_exit_code() {
+        // Arrange
+        let mut processor = Processor::new();
+        processor.semihost(Some(Box::new(|_: &SemihostingCommand| {
+            SemihostingResponse::SysException {
+                success: true,
+                stop: true,
+            }
+        })));
+        processor.state.set_bit(0, true);
+        processor.set_r(Reg::R0, SYS_EXIT);
+        processor.set_r(Reg::R1, 0x20026);
+
+        // Act
+        processor.execute(&Instruction::BKPT { imm32: 0xab }, 2);
+
+        // Assert
+        assert_eq!(processor.state & 1, 0);
+        assert_eq!(processor.exit_code(), Some(0));
+    }
+
+    fn get_cmdline(cmdline: &'static str, size: u32) -> (Processor, Vec<u8>) {
+        let mut processor = Processor::new();
+        processor.semihost(Some(Box::new(move |cmd: &SemihostingCommand| match cmd {
+            SemihostingCommand::SysGetCmdline => SemihostingResponse::SysGetCmdline {
+                result: Ok(cmdline.to_string()),
+            },
+            _ => panic!("unexpected semihosting command {:?}", cmd),
+        })));
+        processor.write32(0x2000_0000, 0x2000_0100).unwrap();
+        processor.write32(0x2000_0004, size).unwrap();
+        processor.set_r(Reg::R0, SYS_GET_CMDLINE);
+        processor.set_r(Reg::R1, 0x2000_0000);
+
+        processor.execute(&Instruction::BKPT { imm32: 0xab }, 2);
+
+        let len = processor.read32(0x2000_0004).unwrap();
+        let text = (0..=len)
+            .map(|i| processor.read8(0x2000_0100 + i).unwrap())
+            .collect();
+        (processor, text)
+    }
+
+    #[test]
+    fn test_sys_get_cmdline() {
+        // Arrange & Act
+        let (processor, text) = get_cmdline("test.elf -v 3", 80);
+
+        // Assert
+        assert_eq!(processor.get_r(Reg::R0), 0);
+        assert_eq!(&text[..], b"test.elf -v 3\0");
+    }
+
+    #[test]
+    fn test_sys_get_cmdline_truncates_to_buffer() {
+        // Arrange & Act
+        let (processor, text) = get_cmdline("test.elf -v 3", 9);
+
+        // Assert
+        assert_eq!(processor.get_r(Reg::R0), 0);
+        assert_eq!(&text[..], b"test.elf\0");
+    }
+
+    fn canned_input_semihost(
+        text: &'static [u8],
+    ) -> Box<dyn FnMut(&SemihostingCommand) -> SemihostingResponse> {
+        let mut input = std::io::Cursor::new(text);
+        Box::new(move |cmd: &SemihostingCommand| match cmd {
+            SemihostingCommand::SysRead { memoryptr, len, .. } => {
+                let mut data = vec![0; *len as usize];
+                let count = input.read(&mut data).unwrap();
+                data.truncate(count);
+                SemihostingResponse::SysRead {
+                    result: Ok((*memoryptr, data, *len - count as u32)),
+                }
+            }
+            SemihostingCommand::SysReadc => {
+                let mut character = [0; 1];
+                match input.read(&mut character).unwrap() {
+                    1 => SemihostingResponse::SysReadc {
+                        result: Ok(character[0]),
+                    },
+                    _ => SemihostingResponse::SysReadc { result: Err(-1) },
+                }
+            }
+            _ => panic!("unexpected semihosting command {:?}", cmd),
+        })
+    }
+
+    #[test]
+    fn test_sys_read_stdin() {
+        // Arrange
+        const STDIN: u32 = 1;
+        let mut processor = Processor::new();
+        processor.semihost(Some(canned_input_semihost(b"abc")));
+
+        processor.write8(0x2000_0103, 0x55).unwrap();
+        processor.write32(0x2000_0000, STDIN).unwrap();
+        processor.write32(0x2000_0004, 0x2000_0100).unwrap();
+        processor.write32(0x2000_0008, 8).unwrap();
+        processor.set_r(Reg::R0, SYS_READ);
+        processor.set_r(Reg::R1, 0x2000_0000);
+
+        // Act
+        processor.execute(&Instruction::BKPT { imm32: 0xab }, 2);
+
+        // Assert
+        assert_eq!(processor.read8(0x2000_0100).unwrap(), b'a');
+        assert_eq!(processor.read8(0x2000_0101).unwrap(), b'b');
+        assert_eq!(processor.read8(0x2000_0102).unwrap(), b'c');
+        assert_eq!(processor.read8(0x2000_0103).unwrap(), 0x55);
+        assert_eq!(processor.get_r(Reg::R0), 5);
+    }
+
+    #[test]
+    fn test_sys_readc() {
+        // Arrange
+        let mut processor = Processor::new();
+        processor.semihost(Some(canned_input_semihost(b"x")));
+
+        // Act
+        processor.set_r(Reg::R0, SYS_READC);
+        processor.execute(&Instruction::BKPT { imm32: 0xab }, 2);
+        let first = processor.get_r(Reg::R0);
+        processor.set_r(Reg::R0, SYS_READC);
+        processor.execute(&Instruction::BKPT { imm32: 0xab }, 2);
+        let second = processor.get_r(Reg::R0);
+
+        // Assert
+        assert_eq!(first, u32::from(b'x'));
+        assert_eq!(second, 0xffff_ffff);
     }
 }
@@ -3,10 +3,12 @@
 //!
 
 use crate::core::bits::Bits;
+use crate::core::exception::Lockup;
 use crate::core::executor::Executor;
 use crate::core::fault::Fault;
 use crate::core::register::BaseReg;
 use crate::core::reset::Reset;
+use crate::semihosting::host_io::StdHostIo;
 use crate::semihosting::SemihostingCommand;
 use crate::semihosting::SemihostingResponse;
 use crate::MemoryMapConfig;
@@ -23,6 +25,32 @@ pub enum SimulationError {
     /// A fault was triggered and escalated to stop the simulation
     ///
     FaultTrap,
+
+    ///
+    /// The processor locked up, for example on an invalid vector
+    ///
+    Lockup {
+        /// why the processor locked up
+        reason: Lockup,
+    },
+
+    ///
+    /// A BKPT instruction halted the processor
+    ///
+    BreakpointHit {
+        /// address of the BKPT instruction
+        address: u32,
+        /// the BKPT immediate
+        imm: u8,
+    },
+
+    ///
+    /// The core was halted for debugging
+    ///
+    DebugHalt {
+        /// address of the next instruction
+        address: u32,
+    },
 }
 
 ///
@@ -44,6 +72,11 @@ pub struct SimulationStatistics {
     /// Wallclock time spent for the simulation
     ///
     pub duration: Duration,
+
+    ///
+    /// Exit code reported by the program via semihosting, if any
+    ///
+    pub exit_code: Option<u32>,
 }
 
 impl From<Fault> for SimulationError {
@@ -55,19 +88,25 @@ impl From<Fault> for SimulationError {
 ///
 /// Run simulation until processing gets terminated
 ///
+/// `vtor` gives the initial vector table location, which is the load
+/// address of the image when it is relocated away from its link address.
+///
 pub fn simulate(
     code: &[u8],
     semihost_func: Box<dyn FnMut(&SemihostingCommand) -> SemihostingResponse + 'static>,
     itm_file: Option<Box<dyn io::Write + 'static>>,
     map: Option<MemoryMapConfig>,
     flash_size: usize,
+    vtor: u32,
 ) -> Result<SimulationStatistics, SimulationError> {
     let mut processor = Processor::new();
 
     processor.itm(itm_file);
     processor.semihost(Some(semihost_func));
+    processor.semihost_console(Some(Box::new(StdHostIo)));
     processor.memory_map(map);
     processor.flash_memory(flash_size, code);
+    processor.vtor_reset(vtor);
     //processor.ram_memory(ram_size);
 
     processor.cache_instructions();
@@ -76,23 +115,40 @@ pub fn simulate(
     processor.reset()?;
     processor.state.set_bit(0, true); // running
 
-    while processor.state & 1 == 1 {
-        while processor.state == 0b01 {
+    // a halted core does not execute, stop instead of spinning on it
+    while processor.state & 1 == 1 && !processor.is_halted() {
+        while processor.state == 0b01 && !processor.is_halted() {
             //running, !sleeping
             processor.step();
         }
 
-        while processor.state == 0b11 {
+        while processor.state == 0b11 && !processor.is_halted() {
             //running, sleeping
             processor.step_sleep();
         }
     }
     let end = Instant::now();
 
+    if let Some(reason) = processor.lockup() {
+        return Err(SimulationError::Lockup { reason });
+    }
+    if let Some(imm) = processor.breakpoint_hit() {
+        return Err(SimulationError::BreakpointHit {
+            address: processor.get_pc(),
+            imm,
+        });
+    }
+    if processor.is_halted() {
+        return Err(SimulationError::DebugHalt {
+            address: processor.get_pc(),
+        });
+    }
+
     Ok(SimulationStatistics {
         instruction_count: processor.instruction_count,
         cycle_count: processor.cycle_count,
         duration: end.duration_since(start),
+        exit_code: processor.exit_code(),
     })
 }
 
@@ -106,6 +162,7 @@ pub fn simulate_trace<F>(
     itm_file: Option<Box<dyn io::Write + 'static>>,
     map: Option<MemoryMapConfig>,
     flash_size: usize,
+    vtor: u32,
 ) -> Result<SimulationStatistics, SimulationError>
 where
     F: FnMut(&Processor),
@@ -113,8 +170,10 @@ where
     let mut processor = Processor::new();
     processor.itm(itm_file);
     processor.semihost(Some(semihost_func));
+    processor.semihost_console(Some(Box::new(StdHostIo)));
     processor.memory_map(map);
     processor.flash_memory(flash_size, code);
+    processor.vtor_reset(vtor);
     processor.cache_instructions();
 
     let start = Instant::now();
@@ -122,15 +181,16 @@ where
     processor.reset().unwrap();
     processor.state.set_bit(0, true); // running
 
-    while processor.state & 1 == 1 {
-        while processor.state == 0b01 {
+    // a halted core does not execute, stop instead of spinning on it
+    while processor.state & 1 == 1 && !processor.is_halted() {
+        while processor.state == 0b01 && !processor.is_halted() {
             //running, !sleeping
             processor.last_pc = processor.get_pc();
             processor.step();
             trace_func(&processor);
         }
         processor.last_pc = processor.get_pc();
-        while processor.state == 0b11 {
+        while processor.state == 0b11 && !processor.is_halted() {
             //running, sleeping
             processor.step_sleep();
         }
@@ -138,9 +198,63 @@ where
 
     let end = Instant::now();
 
+    if let Some(reason) = processor.lockup() {
+        return Err(SimulationError::Lockup { reason });
+    }
+    if let Some(imm) = processor.breakpoint_hit() {
+        return Err(SimulationError::BreakpointHit {
+            address: processor.get_pc(),
+            imm,
+        });
+    }
+    if processor.is_halted() {
+        return Err(SimulationError::DebugHalt {
+            address: processor.get_pc(),
+        });
+    }
+
     Ok(SimulationStatistics {
         instruction_count: processor.instruction_count,
         cycle_count: processor.cycle_count,
         duration: end.duration_since(start),
+        exit_code: processor.exit_code(),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulate_relocated_image() {
+        // arrange: image linked at 0 but loaded at 0x8000, with a reset
+        // handler that immediately exits via semihosting
+        let load_address: u32 = 0x8000;
+        let mut code = [0u8; 0x80];
+        code[0..4].copy_from_slice(&0x2000_0400_u32.to_le_bytes());
+        code[4..8].copy_from_slice(&(load_address + 0x41).to_le_bytes());
+        code[0x40..0x42].copy_from_slice(&0x2018_u16.to_le_bytes()); // movs r0, #0x18
+        code[0x42..0x44].copy_from_slice(&0xbeab_u16.to_le_bytes()); // bkpt 0xab
+
+        let semihost_func = Box::new(|cmd: &SemihostingCommand| {
+            assert!(matches!(cmd, SemihostingCommand::SysException { .. }));
+            SemihostingResponse::SysException {
+                success: true,
+                stop: true,
+            }
+        });
+
+        // act
+        let statistics = simulate(
+            &code,
+            semihost_func,
+            None,
+            Some(MemoryMapConfig::new(load_address, 0, code.len())),
+            code.len(),
+            load_address,
+        );
+
+        // assert
+        assert_eq!(statistics.ok().unwrap().instruction_count, 2);
+    }
+}
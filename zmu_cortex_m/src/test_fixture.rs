@@ -0,0 +1,41 @@
+//!
+//! Processor fixtures shared by the unit tests
+//!
+
+use crate::core::exception::ExceptionHandling;
+use crate::core::register::BaseReg;
+use crate::Processor;
+
+///
+/// Processor running `program` from 0x100 on `msp`, with `handler` at
+/// 0x200 as the handler of the exceptions numbered in `vectors`.
+///
+/// The vector table holds `msp` and the reset vector to 0x100, so a reset
+/// returns the processor to where it starts.
+///
+pub fn handler_test_processor(
+    program: &[u16],
+    handler: &[u16],
+    vectors: &[usize],
+    msp: u32,
+) -> Processor {
+    let mut code = vec![0_u8; 0x300];
+    code[0..4].copy_from_slice(&msp.to_le_bytes());
+    code[4..8].copy_from_slice(&0x0000_0101_u32.to_le_bytes());
+    for vector in vectors {
+        code[vector * 4..vector * 4 + 4].copy_from_slice(&0x0000_0201_u32.to_le_bytes());
+    }
+    for (i, halfword) in program.iter().enumerate() {
+        code[0x100 + 2 * i..0x102 + 2 * i].copy_from_slice(&halfword.to_le_bytes());
+    }
+    for (i, halfword) in handler.iter().enumerate() {
+        code[0x200 + 2 * i..0x202 + 2 * i].copy_from_slice(&halfword.to_le_bytes());
+    }
+
+    let mut processor = Processor::new();
+    processor.flash_memory(code.len(), &code);
+    processor.set_msp(msp);
+    processor.set_pc(0x100);
+    processor.execution_priority = processor.get_execution_priority();
+    processor
+}